@@ -107,22 +107,34 @@ const DonationsStats: React.FC<StatsProps> = ({ owner, application }) => {
         const fetchDonations = async () => {
             setLoading(true);
             try {
-                const query = `
-                  query {
-                    donationsViewByRecipient(owner: "${owner}") {
-                      id
-                      timestamp
-                      amount
-                      fromOwner
-                      message
-                    }
-                  }
-                `;
-                const result: any = await application.query(JSON.stringify({ query }));
-                const data = typeof result === 'string' ? JSON.parse(result) : result;
-
-                if (data.errors) throw new Error(data.errors[0].message);
-                setDonations(data.data?.donationsViewByRecipient || []);
+                // The donations query is paginated; stats aggregate over every donation
+                const all: any[] = [];
+                let cursor: number | null = null;
+                do {
+                    const afterArg: string = cursor === null ? '' : `, afterId: ${cursor}`;
+                    const query = `
+                      query {
+                        donationsViewByRecipient(owner: "${owner}", limit: 500${afterArg}) {
+                          donations {
+                            id
+                            timestamp
+                            amount
+                            fromOwner
+                            message
+                          }
+                          nextCursor
+                        }
+                      }
+                    `;
+                    const result: any = await application.query(JSON.stringify({ query }));
+                    const data = typeof result === 'string' ? JSON.parse(result) : result;
+
+                    if (data.errors) throw new Error(data.errors[0].message);
+                    const page = data.data?.donationsViewByRecipient;
+                    all.push(...(page?.donations || []));
+                    cursor = page?.nextCursor ?? null;
+                } while (cursor !== null);
+                setDonations(all);
             } catch (err: any) {
                 setError(err.message || 'Failed to fetch donations');
             } finally {
@@ -0,0 +1,132 @@
+//! Checked `Amount` arithmetic for aggregations and basis-point splits.
+//! Callers decide what to do on overflow; the contract saturates and reports it.
+
+use linera_sdk::linera_base_types::Amount;
+
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// `amount * bps / 10_000`, rounded down. Fails for shares above 100%.
+pub fn mul_bps(amount: Amount, bps: u16) -> Result<Amount, String> {
+    let bps = bps as u128;
+    if bps > BPS_DENOMINATOR {
+        return Err(format!("Basis points out of range: {}", bps));
+    }
+    // Split the multiplication so the intermediate never exceeds the original amount
+    let attos = amount.to_attos();
+    let whole = (attos / BPS_DENOMINATOR) * bps;
+    let part = (attos % BPS_DENOMINATOR) * bps / BPS_DENOMINATOR;
+    Ok(Amount::from_attos(whole + part))
+}
+
+/// `a + b`, or `Err` carrying the saturated sum on overflow
+pub fn add_tracking_overflow(a: Amount, b: Amount) -> Result<Amount, Amount> {
+    a.try_add(b).map_err(|_| Amount::MAX)
+}
+
+/// `a - b`, or `Err` carrying zero when `b` is larger
+pub fn sub_tracking_underflow(a: Amount, b: Amount) -> Result<Amount, Amount> {
+    a.try_sub(b).map_err(|_| Amount::ZERO)
+}
+
+/// `part / whole` in basis points, rounded down and capped at 10_000; a zero `whole` counts as reached
+pub fn ratio_bps(part: Amount, whole: Amount) -> u32 {
+    let (part, whole) = (part.to_attos(), whole.to_attos());
+    if part >= whole {
+        return BPS_DENOMINATOR as u32;
+    }
+    // Past u128::MAX / 10_000 the whole is large enough that dropping its last digits is invisible
+    let bps = match part.checked_mul(BPS_DENOMINATOR) {
+        Some(scaled) => scaled / whole,
+        None => part / (whole / BPS_DENOMINATOR),
+    };
+    bps.min(BPS_DENOMINATOR - 1) as u32
+}
+
+/// Sum of `amounts`, or `Err` carrying the saturated sum on overflow
+pub fn sum_tracking_overflow(amounts: impl IntoIterator<Item = Amount>) -> Result<Amount, Amount> {
+    let mut total = Amount::ZERO;
+    let mut overflowed = false;
+    for amount in amounts {
+        total = match add_tracking_overflow(total, amount) {
+            Ok(sum) => sum,
+            Err(saturated) => {
+                overflowed = true;
+                saturated
+            }
+        };
+    }
+    if overflowed { Err(total) } else { Ok(total) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn amount() -> impl Strategy<Value = Amount> {
+        prop_oneof![any::<u128>(), 0..=u64::MAX as u128].prop_map(Amount::from_attos)
+    }
+
+    proptest! {
+        #[test]
+        fn share_never_exceeds_the_amount_and_is_exact_in_whole_units(attos in any::<u128>(), bps in 0..=10_000u16) {
+            let share = mul_bps(Amount::from_attos(attos), bps).unwrap();
+            prop_assert!(share.to_attos() <= attos);
+            let whole = attos - attos % BPS_DENOMINATOR;
+            prop_assert_eq!(mul_bps(Amount::from_attos(whole), bps).unwrap().to_attos(), whole / BPS_DENOMINATOR * bps as u128);
+        }
+
+        #[test]
+        fn shares_above_one_hundred_percent_are_refused(attos in any::<u128>(), bps in 10_001..=u16::MAX) {
+            prop_assert!(mul_bps(Amount::from_attos(attos), bps).is_err());
+        }
+
+        #[test]
+        fn addition_is_exact_or_reports_saturation(a in amount(), b in amount()) {
+            match add_tracking_overflow(a, b) {
+                Ok(sum) => prop_assert_eq!(Some(sum.to_attos()), a.to_attos().checked_add(b.to_attos())),
+                Err(saturated) => {
+                    prop_assert_eq!(saturated, Amount::MAX);
+                    prop_assert!(a.to_attos().checked_add(b.to_attos()).is_none());
+                }
+            }
+        }
+
+        #[test]
+        fn subtraction_is_exact_or_reports_zero(a in amount(), b in amount()) {
+            match sub_tracking_underflow(a, b) {
+                Ok(rest) => prop_assert_eq!(rest.to_attos() + b.to_attos(), a.to_attos()),
+                Err(floor) => {
+                    prop_assert_eq!(floor, Amount::ZERO);
+                    prop_assert!(b > a);
+                }
+            }
+        }
+
+        #[test]
+        fn sum_matches_folding_the_additions(amounts in proptest::collection::vec(amount(), 0..20)) {
+            let exact = amounts.iter().try_fold(0u128, |total, a| total.checked_add(a.to_attos()));
+            match sum_tracking_overflow(amounts) {
+                Ok(total) => prop_assert_eq!(Some(total.to_attos()), exact),
+                Err(saturated) => {
+                    prop_assert_eq!(saturated, Amount::MAX);
+                    prop_assert!(exact.is_none());
+                }
+            }
+        }
+
+        #[test]
+        fn ratio_of_a_share_recovers_its_basis_points(attos in 10_000..=u128::MAX, bps in 0..=10_000u16) {
+            let whole = Amount::from_attos(attos);
+            let ratio = ratio_bps(mul_bps(whole, bps).unwrap(), whole);
+            prop_assert!(ratio <= bps as u32 && ratio + 1 >= bps as u32);
+        }
+
+        #[test]
+        fn ratio_is_complete_only_once_the_part_covers_the_whole(part in amount(), whole in amount()) {
+            let ratio = ratio_bps(part, whole);
+            prop_assert!(ratio <= 10_000);
+            prop_assert_eq!(ratio == 10_000, part >= whole);
+        }
+    }
+}
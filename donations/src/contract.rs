@@ -3,17 +3,63 @@
 mod state;
 
 use linera_sdk::{
-    abis::fungible::{Account as FungibleAccount, InitialState, Parameters},
-    linera_base_types::{Account, AccountOwner, WithContractAbi, StreamName, StreamUpdate},
-    views::{RootView, View},
+    abis::fungible::{Account as FungibleAccount, FungibleOperation, FungibleResponse, FungibleTokenAbi, InitialState},
+    linera_base_types::{Account, AccountOwner, Amount, ApplicationId, ChainId, WithContractAbi, StreamName, StreamUpdate},
+    views::RootView,
     Contract, ContractRuntime,
 };
-use donations::{Message, DonationsAbi, Operation, ResponseData, DonationsEvent, SocialLink};
-use state::DonationsState;
+use donations::{Message, DonationsAbi, DonationsParameters, Operation, OrderStatus, ResponseData, DonationsEvent, SocialLink, SocialLinkInput, EVENTS_STREAM, SubscriptionAccess};
+use state::{DonationsState, StateError};
+
+/// Unwrap a result where failing must abort the operation, with the error's user-facing message
+trait OrFail<T> {
+    fn or_fail(self, context: &str) -> T;
+}
+
+impl<T, E: std::fmt::Display> OrFail<T> for Result<T, E> {
+    fn or_fail(self, context: &str) -> T {
+        self.unwrap_or_else(|e| panic!("{}: {}", context, e))
+    }
+}
+
+/// Why an operation was refused, returned to the client as `ResponseData::Error`
+struct OperationError {
+    code: &'static str,
+    message: String,
+}
+
+impl OperationError {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        OperationError { code, message: message.into() }
+    }
+
+    fn unauthorized(message: impl Into<String>) -> Self { Self::new("unauthorized", message) }
+
+    fn invalid(message: impl Into<String>) -> Self { Self::new("invalid", message) }
+}
+
+impl From<StateError> for OperationError {
+    fn from(e: StateError) -> Self {
+        // Stable identifiers clients can match on
+        let code = match &e {
+            StateError::NotFound(_) => "not_found",
+            StateError::Unauthorized(_) => "unauthorized",
+            StateError::Validation(_) => "invalid",
+            StateError::Storage(_) => "storage",
+        };
+        OperationError::new(code, e.to_string())
+    }
+}
+
+impl std::fmt::Display for OperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.message) }
+}
 
 pub struct DonationsContract {
     state: DonationsState,
     runtime: ContractRuntime<Self>,
+    // Events the current operation emitted, charged to its signer's rate limit
+    events_emitted: u32,
 }
 
 linera_sdk::contract!(DonationsContract);
@@ -22,16 +68,18 @@ impl WithContractAbi for DonationsContract { type Abi = DonationsAbi; }
 
 impl Contract for DonationsContract {
     type Message = Message;
-    type Parameters = Parameters;
+    type Parameters = DonationsParameters;
     type InstantiationArgument = InitialState;
     type EventValue = DonationsEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = DonationsState::load(runtime.root_view_storage_context()).await.expect("load");
-        DonationsContract { state, runtime }
+        DonationsContract { state, runtime, events_emitted: 0 }
     }
 
     async fn instantiate(&mut self, state: Self::InstantiationArgument) {
+        // The account creating the application starts out as admin
+        self.state.admin.set(self.runtime.authenticated_signer());
         for (owner, amount) in state.accounts {
             let account = Account { chain_id: self.runtime.chain_id(), owner };
             self.runtime.transfer(AccountOwner::CHAIN, account, amount);
@@ -39,660 +87,504 @@ impl Contract for DonationsContract {
     }
 
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
-        match operation {
-            Operation::Transfer { owner, amount, target_account, text_message } => {
-                self.runtime.check_account_permission(owner).expect("perm");
-                let target_account_norm = self.normalize_account(target_account);
-                self.runtime.transfer(owner, target_account_norm, amount);
-                if target_account_norm.chain_id != self.runtime.chain_id() {
-                    let current_chain = self.runtime.chain_id();
-                    let current_chain_str = current_chain.to_string();
-                    let message = Message::TransferWithMessage { owner: target_account_norm.owner, amount, text_message: text_message.clone(), source_chain_id: current_chain, source_owner: owner };
-                    self.runtime.prepare_message(message).with_authentication().send_to(target_account_norm.chain_id);
-                    let ts = self.runtime.system_time().micros();
-                    if let Ok(id) = self.state.record_donation(owner, target_account_norm.owner, amount, text_message.clone(), Some(current_chain_str.clone()), Some(target_account_norm.chain_id.to_string()), ts).await {
-                        self.runtime.emit("donations_events".into(), &DonationsEvent::DonationSent { id, from: owner, to: target_account_norm.owner, amount, message: text_message, source_chain_id: Some(current_chain_str), to_chain_id: Some(target_account_norm.chain_id.to_string()), timestamp: ts });
-                    }
-                } else {
-                    let ts = self.runtime.system_time().micros();
-                    if let Ok(id) = self.state.record_donation(owner, target_account_norm.owner, amount, text_message.clone(), None, Some(target_account_norm.chain_id.to_string()), ts).await {
-                        self.runtime.emit("donations_events".into(), &DonationsEvent::DonationSent { id, from: owner, to: target_account_norm.owner, amount, message: text_message, source_chain_id: None, to_chain_id: Some(target_account_norm.chain_id.to_string()), timestamp: ts });
-                    }
+        // Send back payments refused and pay out income pledged while the signer wasn't around to sign
+        if let Some(signer) = self.runtime.authenticated_signer() {
+            self.settle_pending_refunds(signer).await;
+            self.settle_income_redirect(signer).await;
+        }
+        
+        match self.apply_rate_limited(operation).await {
+            Ok(response) => response,
+            Err(e) => ResponseData::Error { code: e.code.to_string(), message: e.message },
+        }
+    }
+
+    async fn execute_message(&mut self, message: Self::Message) {
+        match message {
+            Message::Notify => {}
+            Message::Tracked { outbox_id, message } => {
+                let Some(origin_chain) = self.runtime.message_origin_chain_id() else { return };
+                // A re-sent copy of something already applied is only acked again
+                if self.state.note_tracked_delivery(origin_chain, outbox_id).await.unwrap_or(false) {
+                    Box::pin(self.execute_message(*message)).await;
                 }
-                ResponseData::Ok
-            }
-            Operation::Withdraw => {
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let balance = self.runtime.owner_balance(owner);
-                let target_account = Account { chain_id: self.runtime.chain_id(), owner: AccountOwner::CHAIN };
-                self.runtime.transfer(owner, target_account, balance);
-                ResponseData::Ok
+                self.runtime.prepare_message(Message::OutboxAck { outbox_id }).send_to(origin_chain);
             }
-            Operation::Mint { owner, amount } => {
-                let target_account = Account { chain_id: self.runtime.chain_id(), owner };
-                self.runtime.transfer(AccountOwner::CHAIN, target_account, amount);
-                ResponseData::Ok
+            Message::OutboxAck { outbox_id } => {
+                // No signer needed: the origin chain is set by the system, not the sender, and entries
+                // are keyed by the chain they went to, so a chain can only ack what was sent to it
+                if let Some(origin_chain) = self.runtime.message_origin_chain_id() {
+                    let _ = self.state.remove_outbox(origin_chain, outbox_id).await;
+                }
             }
-            Operation::UpdateProfile { name, bio, socials, avatar_hash, header_hash } => {
-                let owner = self.runtime.authenticated_signer().unwrap();
+            Message::TransferWithMessage { owner, amount, text_message, source_chain_id, source_owner, source_donation_id } => {
                 let ts = self.runtime.system_time().micros();
-                if let Some(n) = name.clone() {
-                    let _ = self.state.set_name(owner, n.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileNameUpdated { owner, name: n, timestamp: ts });
-                }
-                if let Some(b) = bio.clone() {
-                    let _ = self.state.set_bio(owner, b.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileBioUpdated { owner, bio: b, timestamp: ts });
+                let current_chain_id = self.runtime.chain_id().to_string();
+                let payment_app = self.payment_app_label();
+                // The funds already arrived, so an oversized message is cut rather than refused
+                let (text_message, content_modified) = self.sanitize_inbound_message(text_message);
+                let recorded = self.state.record_donation(source_owner, owner, amount, text_message.clone(), Some(source_chain_id.to_string()), Some(source_donation_id), Some(current_chain_id.clone()), ts, payment_app).await;
+                if let (Ok(id), true) = (&recorded, content_modified) {
+                    let _ = self.state.mark_donation_modified(*id).await;
+                    let _ = self.state.record_content_adjustment("donation_message", ts).await;
                 }
-                for s in socials.into_iter() {
-                    let _ = self.state.set_social(owner, s.name.clone(), s.url.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileSocialUpdated { owner, name: s.name, url: s.url, timestamp: ts });
+                if self.state.is_holding_account(owner).await {
+                    if let Ok(id) = recorded {
+                        let _ = self.state.hold_donation(id).await;
+                    }
+                    return;
                 }
-                if let Some(hash) = avatar_hash {
-                    let _ = self.state.set_avatar(owner, hash.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: ts });
+                if let Ok(id) = recorded {
+                    self.emit_event(DonationsEvent::DonationSent { id, from: source_owner, to: owner, amount, message: text_message, source_chain_id: Some(source_chain_id.to_string()), source_donation_id: Some(source_donation_id), to_chain_id: Some(current_chain_id), timestamp: ts });
                 }
-                if let Some(hash) = header_hash {
-                    let _ = self.state.set_header(owner, hash.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: ts });
+                // Moving one's own funds between chains isn't income
+                if owner != source_owner {
+                    self.accrue_income_redirect(owner, amount).await;
                 }
-                ResponseData::Ok
             }
-            Operation::Register { main_chain_id, name, bio, socials, avatar_hash, header_hash } => {
-                // Send register message to main chain so it subscribes to our events
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let msg = Message::Register {
-                    source_chain_id: self.runtime.chain_id(),
-                    owner,
-                    name: name.clone(),
-                    bio: bio.clone(),
-                    socials: socials.iter().map(|s| SocialLink { name: s.name.clone(), url: s.url.clone() }).collect(),
-                };
-                self.runtime
-                    .prepare_message(msg)
-                    .with_authentication()
-                    .send_to(main_chain_id);
-                
-                // Save main_chain_id to subscriptions so we know where to send future messages
-                let _ = self.state.subscriptions.insert(&owner, main_chain_id.to_string());
-                
-                let ts = self.runtime.system_time().micros();
-                if let Some(n) = name.clone() {
-                    let _ = self.state.set_name(owner, n.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileNameUpdated { owner, name: n, timestamp: ts });
+            Message::Register { source_chain_id, owner, name, bio, socials } => {
+                if self.state.is_closed(owner).await {
+                    return;
                 }
-                if let Some(b) = bio.clone() {
-                    let _ = self.state.set_bio(owner, b.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileBioUpdated { owner, bio: b, timestamp: ts });
+                // A repeated registration may carry a stale payload: don't re-subscribe,
+                // and only fill profile fields that are still unset
+                let already_registered = matches!(self.state.subscriptions.get(&owner).await, Ok(Some(chain)) if chain == source_chain_id.to_string());
+                if !already_registered {
+                    // Subscribe this (main) chain to the source chain's donations_events stream
+                    let app_id = self.runtime.application_id().forget_abi();
+                    let stream = StreamName::from(EVENTS_STREAM);
+                    self.runtime.subscribe_to_events(source_chain_id, app_id, stream.clone());
+                    let _ = self.state.subscriptions.insert(&owner, source_chain_id.to_string());
+                    let _ = self.state.home_chains.insert(&owner, source_chain_id.to_string());
                 }
-                for s in socials.into_iter() {
-                    let _ = self.state.set_social(owner, s.name.clone(), s.url.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileSocialUpdated { owner, name: s.name, url: s.url, timestamp: ts });
+                let current = self.state.get_profile(owner).await.ok().flatten();
+                if let Some(n) = name {
+                    let unset = current.as_ref().is_none_or(|p| p.name == "anon");
+                    if current.as_ref().is_none_or(|p| p.name != n) && (!already_registered || unset) {
+                        let _ = self.state.set_name(owner, n).await;
+                    }
                 }
-                if let Some(hash) = avatar_hash {
-                    let _ = self.state.set_avatar(owner, hash.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: ts });
+                if let Some(b) = bio {
+                    let unset = current.as_ref().is_none_or(|p| p.bio.is_empty());
+                    if current.as_ref().is_none_or(|p| p.bio != b) && (!already_registered || unset) {
+                        let _ = self.state.set_bio(owner, b).await;
+                    }
                 }
-                if let Some(hash) = header_hash {
-                    let _ = self.state.set_header(owner, hash.clone()).await;
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: ts });
+                for s in socials {
+                    let existing = current.as_ref().and_then(|p| p.socials.iter().find(|e| e.name == s.name));
+                    if existing.is_none_or(|e| e.url != s.url) && (!already_registered || existing.is_none()) {
+                        let _ = self.state.set_social(owner, s.name, s.url).await;
+                    }
                 }
-                ResponseData::Ok
             }
-            Operation::SetAvatar { hash } => {
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let ts = self.runtime.system_time().micros();
-                let _ = self.state.set_avatar(owner, hash.clone()).await;
-                self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: ts });
-                ResponseData::Ok
+            Message::ProductCreated { product } => {
+                // Main chain stores product from other chains
+                self.mirror_product(product).await;
             }
-            Operation::SetHeader { hash } => {
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let ts = self.runtime.system_time().micros();
-                let _ = self.state.set_header(owner, hash.clone()).await;
-                self.runtime.emit("donations_events".into(), &DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: ts });
-                ResponseData::Ok
+            Message::ProductUpdated { product } => {
+                // Main chain updates product
+                self.mirror_product(product).await;
             }
-            Operation::GetProfile { owner } => {
-                match self.state.get_profile(owner).await { Ok(p) => ResponseData::Profile(p), Err(_) => ResponseData::Profile(None) }
+            Message::ProductMigrated { product } => {
+                // Only the author moves a product, and only to the chain receiving it
+                if self.runtime.authenticated_signer() == Some(product.author) && product.author_chain_id == self.runtime.chain_id().to_string() {
+                    self.mirror_product(product.clone()).await;
+                    let ts = self.runtime.system_time().micros();
+                    self.emit_event(DonationsEvent::ProductUpdated { product, timestamp: ts });
+                }
             }
-            Operation::GetDonationsByRecipient { owner } => {
-                match self.state.list_donations_by_recipient(owner).await { Ok(v) => ResponseData::Donations(v), Err(_) => ResponseData::Donations(Vec::new()) }
+            Message::SlugConflict { product_id, slug } => {
+                // Seller's chain drops a slug the main chain already has for another product,
+                // taking the conflict only from the main chain the seller registered with
+                if let Ok(Some(product)) = self.state.get_product(&product_id).await {
+                    let main_chain = self.state.subscriptions.get(&product.author).await.ok().flatten();
+                    if main_chain.is_none() || self.runtime.message_origin_chain_id().map(|c| c.to_string()) != main_chain {
+                        return;
+                    }
+                    if product.slug.as_deref() == Some(slug.as_str()) {
+                        let _ = self.state.clear_product_slug(&product_id).await;
+                        let ts = self.runtime.system_time().micros();
+                        self.emit_event(DonationsEvent::ProductSlugConflict { product_id, slug, timestamp: ts });
+                    }
+                }
             }
-            Operation::GetDonationsByDonor { owner } => {
-                match self.state.list_donations_by_donor(owner).await { Ok(v) => ResponseData::Donations(v), Err(_) => ResponseData::Donations(Vec::new()) }
+            Message::ProductDeleted { product_id, author } => {
+                // Main chain deletes product; only its author may
+                if self.runtime.authenticated_signer() != Some(author) {
+                    return;
+                }
+                let _ = self.state.delete_product(&product_id, author).await;
+                let _ = self.state.unfeature_product(author, &product_id).await;
+                let _ = self.state.unorder_product(author, &product_id).await;
             }
-            Operation::CreateProduct { public_data, price, private_data, success_message, order_form } => {
-                let owner = self.runtime.authenticated_signer().expect("Authentication required");
-                let ts = self.runtime.system_time().micros();
-                let chain_id = self.runtime.chain_id();
-                let product_id = format!("{}-{}", ts, chain_id);
-                
-                // Convert OrderFormFieldInput to OrderFormField
-                let order_form_fields: Vec<donations::OrderFormField> = order_form.into_iter().map(|f| donations::OrderFormField {
-                    key: f.key,
-                    label: f.label,
-                    field_type: f.field_type,
-                    required: f.required,
-                }).collect();
-                
-                let product = donations::Product {
-                    id: product_id.clone(),
-                    author: owner,
-                    author_chain_id: chain_id.to_string(),
-                    public_data,
-                    price,
-                    private_data,
-                    success_message,
-                    order_form: order_form_fields,
-                    created_at: ts,
-                };
-                
-                self.state.create_product(product.clone()).await.expect("Failed to create product");
-                self.runtime.emit("donations_events".into(), &DonationsEvent::ProductCreated { product: product.clone(), timestamp: ts });
-                
-                // Send to main chain if we're on a different chain
-                if let Ok(main_chain_str) = self.state.subscriptions.get(&owner).await {
-                    if let Some(main_chain_id_str) = main_chain_str {
-                        if let Ok(main_chain_id) = main_chain_id_str.parse() {
-                            if main_chain_id != chain_id {
-                                self.runtime.prepare_message(Message::ProductCreated { product }).with_authentication().send_to(main_chain_id);
-                            }
-                        }
-                    }
+            Message::FeaturedProductsUpdated { author, product_ids } => {
+                // Main chain mirrors the author's featured list, as only the author may set it
+                if self.runtime.authenticated_signer() != Some(author) {
+                    return;
                 }
-                
-                ResponseData::Ok
+                let _ = self.state.set_featured_products(author, product_ids).await;
             }
-            Operation::UpdateProduct { product_id, public_data, price, private_data, success_message, order_form } => {
-                let owner = self.runtime.authenticated_signer().expect("Authentication required");
-                
-                // Convert Option<Vec<OrderFormFieldInput>> to Option<Vec<OrderFormField>>
-                let order_form_fields = order_form.map(|fields| {
-                    fields.into_iter().map(|f| donations::OrderFormField {
-                        key: f.key,
-                        label: f.label,
-                        field_type: f.field_type,
-                        required: f.required,
-                    }).collect()
-                });
-                
-                self.state.update_product(&product_id, owner, public_data, price, private_data, success_message, order_form_fields).await.expect("Failed to update product");
-                
-                let product = self.state.get_product(&product_id).await.expect("Failed to get product").expect("Product not found");
-                let ts = self.runtime.system_time().micros();
-                self.runtime.emit("donations_events".into(), &DonationsEvent::ProductUpdated { product: product.clone(), timestamp: ts });
-                
-                // Send to main chain
-                if let Ok(main_chain_str) = self.state.subscriptions.get(&owner).await {
-                    if let Some(main_chain_id_str) = main_chain_str {
-                        if let Ok(main_chain_id) = main_chain_id_str.parse() {
-                            let chain_id = self.runtime.chain_id();
-                            if main_chain_id != chain_id {
-                                self.runtime.prepare_message(Message::ProductUpdated { product }).with_authentication().send_to(main_chain_id);
-                            }
-                        }
+            Message::ProductOrderUpdated { author, ordered_ids } => {
+                // Main chain mirrors the author's storefront order, as only the author may set it
+                if self.runtime.authenticated_signer() != Some(author) {
+                    return;
+                }
+                let _ = self.state.set_product_order(author, ordered_ids).await;
+            }
+            Message::ProductPurchased { purchase_id, product_id, buyer, buyer_chain_id, seller, amount } => {
+                // Main chain receives purchase notification and sends product data to buyer
+                self.handle_product_purchased(purchase_id, product_id, buyer, buyer_chain_id, seller, amount, true).await;
+            }
+            Message::DonationReplied { source_donation_id, to, text, replied_at } => {
+                // Only the recipient may reply to a donation
+                if self.runtime.authenticated_signer() == Some(to) {
+                    let current_chain = self.runtime.chain_id().to_string();
+                    let _ = self.state.mirror_donation_reply((current_chain.clone(), source_donation_id), &current_chain, to, text, replied_at).await;
+                }
+            }
+            Message::OrderNoteAdded { purchase_id, note } => {
+                // Only accept notes signed by the party they claim to be from
+                if self.runtime.authenticated_signer() == Some(note.from) {
+                    let _ = self.state.add_order_note(&purchase_id, note).await;
+                }
+            }
+            Message::SendProductData { buyer, purchase_id, product, entitlements } => {
+                // Buyer's chain receives full product data
+                self.receive_product_data(buyer, purchase_id, product, entitlements).await;
+            }
+            Message::ProductReviewed { review } => {
+                // Product's chain keeps reviews only from buyers it has a purchase on record for
+                if self.runtime.authenticated_signer() == Some(review.reviewer) {
+                    if let Ok(true) = self.state.has_purchased(review.reviewer, &review.product_id).await {
+                        let _ = self.state.put_review(review).await;
                     }
                 }
-                
-                ResponseData::Ok
             }
-            Operation::DeleteProduct { product_id } => {
-                let owner = self.runtime.authenticated_signer().expect("Authentication required");
-                self.state.delete_product(&product_id, owner).await.expect("Failed to delete product");
-                
-                let ts = self.runtime.system_time().micros();
-                self.runtime.emit("donations_events".into(), &DonationsEvent::ProductDeleted { product_id: product_id.clone(), author: owner, timestamp: ts });
-                
-                // Send to main chain
-                if let Ok(main_chain_str) = self.state.subscriptions.get(&owner).await {
-                    if let Some(main_chain_id_str) = main_chain_str {
-                        if let Ok(main_chain_id) = main_chain_id_str.parse() {
-                            let chain_id = self.runtime.chain_id();
-                            if main_chain_id != chain_id {
-                                self.runtime.prepare_message(Message::ProductDeleted { product_id, author: owner }).with_authentication().send_to(main_chain_id);
-                            }
-                        }
+            Message::OrderStatusChanged { purchase_id, status } => {
+                // Only the order's seller may move it along
+                if let Ok(Some(purchase)) = self.state.get_purchase(&purchase_id).await {
+                    if self.runtime.authenticated_signer() == Some(purchase.seller) {
+                        let _ = self.state.set_order_status(&purchase_id, status).await;
                     }
                 }
-                
-                ResponseData::Ok
             }
-            Operation::TransferToBuy { owner, product_id, amount, target_account, order_data } => {
-                self.runtime.check_account_permission(owner).expect("Permission denied");
-                
-                // Transfer full amount to author
-                let target_account_norm = self.normalize_account(target_account);
-                self.runtime.transfer(owner, target_account_norm, amount);
-                
-                // Generate purchase ID
-                let ts = self.runtime.system_time().micros();
-                let purchase_id = format!("purchase-{}-{}", ts, self.runtime.chain_id());
-                let buyer_chain_id = self.runtime.chain_id();
-                let seller = target_account_norm.owner;
-                
-                // Emit event
-                self.runtime.emit("donations_events".into(), &DonationsEvent::ProductPurchased {
-                    purchase_id: purchase_id.clone(),
-                    product_id: product_id.clone(),
-                    buyer: owner,
-                    seller,
-                    amount,
-                    timestamp: ts,
-                });
-                
-                // Send purchase message to main chain
-                if let Ok(main_chain_str) = self.state.subscriptions.get(&owner).await {
-                    if let Some(main_chain_id_str) = main_chain_str {
-                        if let Ok(main_chain_id) = main_chain_id_str.parse() {
-                            self.runtime.prepare_message(Message::ProductPurchased {
-                                purchase_id: purchase_id.clone(),
-                                product_id: product_id.clone(),
-                                buyer: owner,
-                                buyer_chain_id,
-                                seller,
-                                amount,
-                            }).with_authentication().send_to(main_chain_id);
-                        }
+            Message::OrderFailed { buyer, order } => {
+                // Relayed by the main chain on the buyer's behalf
+                if self.runtime.authenticated_signer() == Some(buyer) {
+                    self.record_order_failure(buyer, order).await;
+                }
+            }
+            Message::EntitlementsRevoked { buyer, purchase_id } => {
+                // Only the purchase's seller may revoke what it granted
+                if let Ok(Some(purchase)) = self.state.get_purchase(&purchase_id).await {
+                    if purchase.buyer == buyer && self.runtime.authenticated_signer() == Some(purchase.seller) {
+                        let _ = self.state.revoke_purchase_entitlements(buyer, &purchase_id).await;
                     }
                 }
-                
-                // NEW: Send order notification directly to seller's chain
-                // NEW: Send order notification directly to seller's chain
-                // We trust the target_account chain_id as it comes from the product metadata
-                // and we already transferred funds there.
-                let seller_chain_id = target_account_norm.chain_id;
+            }
+            Message::OrderReceived { purchase_id, product_id, buyer, buyer_chain_id, amount, order_data, timestamp } => {
+                // Seller's chain receives order notification with buyer's form data
+                // We must fetch the product to get the correct seller (author) and to record the purchase
+                if let Ok(Some(product)) = self.state.get_product(&product_id).await {
+                    let seller = product.author; // Correct seller is the product author
+                    // Answers are cleaned like donation messages, since the payment already arrived
+                    let (order_data, content_modified) = self.sanitize_inbound_order_data(order_data);
+                    if content_modified {
+                        let ts = self.runtime.system_time().micros();
+                        let _ = self.state.record_content_adjustment("order_data", ts).await;
+                    }
+                    
+                    // This chain's copy of the product is the real one: an order paid after the last
+                    // copy went, or with answers its form doesn't accept, is kept for the seller to
+                    // refund, and the buyer told why
+                    let rejection = if product.stock == Some(0) {
+                        Some((OrderStatus::SoldOut, "The product sold out before the order arrived".to_string()))
+                    } else {
+                        DonationsState::validate_order_data(&product.order_form, &order_data).err()
+                            .map(|error| (OrderStatus::Rejected, error.to_string()))
+                    };
 
-                if seller_chain_id != buyer_chain_id {
-                    self.runtime.prepare_message(Message::OrderReceived {
-                        purchase_id: purchase_id.clone(),
+                    // Record the full purchase so it shows up in "My Orders"
+                    let purchase = donations::Purchase {
+                        id: purchase_id.clone(),
                         product_id: product_id.clone(),
-                        buyer: owner,
-                        buyer_chain_id,
+                        buyer,
+                        buyer_chain_id: buyer_chain_id.to_string(),
+                        seller,
+                        seller_chain_id: product.author_chain_id.clone(),
                         amount,
+                        timestamp,
                         order_data: order_data.clone(),
-                        timestamp: ts,
-                    }).with_authentication().send_to(seller_chain_id);
-                } else {
-                    // Same chain: Record purchase immediately if product exists locally
-                    // This covers local purchases and self-purchases
-                    if let Ok(Some(product)) = self.state.get_product(&product_id).await {
-                         let purchase = donations::Purchase {
-                            id: purchase_id.clone(),
-                            product_id: product_id.clone(),
-                            buyer: owner,
-                            buyer_chain_id: buyer_chain_id.to_string(),
-                            seller,
-                            seller_chain_id: product.author_chain_id.clone(),
-                            // ...
-                            amount,
-                            timestamp: ts,
-                            order_data: order_data.clone(),
-                            product: product.clone(),
-                        };
-                        let _ = self.state.record_purchase(purchase).await;
+                        content_modified,
+                        payment_app: self.payment_app_label(),
+                        status: rejection.as_ref().map_or(OrderStatus::Pending, |(status, _)| *status),
+                        product: product.clone(),
+                    };
+                    
+                    if let Some((_, reason)) = rejection {
+                        if self.state.record_purchase_with_grants(purchase, Vec::new()).await.is_ok() {
+                            self.reject_order(purchase_id, product_id, buyer, buyer_chain_id, seller, amount, reason, timestamp).await;
+                        }
+                        return;
                     }
-                }
-                
-                ResponseData::Ok
-            }
-            Operation::ReadDataBlob { hash } => {
-                use linera_sdk::linera_base_types::{CryptoHash, DataBlobHash};
-                use std::str::FromStr;
-                
-                match CryptoHash::from_str(&hash) {
-                    Ok(crypto_hash) => {
-                        let blob_hash = DataBlobHash(crypto_hash);
-                        let data = self.runtime.read_data_blob(blob_hash);
-                        eprintln!("[READ_BLOB] Read {} bytes from blob {}", data.len(), hash);
+                    
+                    // The payment arrived either way; only a persisted order is announced
+                    let grants = DonationsState::purchase_grants(&product, &purchase_id, timestamp);
+                    let recorded = self.state.record_purchase_with_grants(purchase, grants).await;
+                    self.accrue_income_redirect(seller, amount).await;
+                    if recorded.is_err() {
+                        return;
                     }
-                    Err(e) => {
-                        eprintln!("[READ_BLOB] ERROR: Invalid blob hash format '{}': {:?}", hash, e);
+                    
+                    if product.stock.is_some() {
+                        if let Ok(product) = self.state.decrement_stock(&product_id).await {
+                            self.announce_product_updated(product).await;
+                        }
                     }
-                }
-                ResponseData::Ok
-            }
-            
-            // Content subscription operations
-            Operation::SetSubscriptionPrice { price, description } => {
-                let owner = self.runtime.authenticated_signer().unwrap();
-                self.state.set_subscription_price(owner, price, description.clone()).await.expect("Failed to set subscription price");
-                
-                let ts = self.runtime.system_time().micros();
-                self.runtime.emit("donations_events".into(), &DonationsEvent::SubscriptionPriceSet { 
-                    author: owner, 
-                    price,
-                    description,
-                    timestamp: ts 
-                });
-                
-                ResponseData::Ok
-            }
-            
-            Operation::DeleteSubscriptionPrice => {
-                let owner = self.runtime.authenticated_signer().unwrap();
-                self.state.delete_subscription_info(owner).await.expect("Failed to delete subscription info");
-                
-                let ts = self.runtime.system_time().micros();
-                self.runtime.emit("donations_events".into(), &DonationsEvent::SubscriptionPriceDeleted {
-                    author: owner,
-                    timestamp: ts,
-                });
-                
-                ResponseData::Ok
+
+                    self.emit_event(DonationsEvent::OrderPlaced {
+                        purchase_id,
+                        product_id,
+                        buyer,
+                        seller,
+                        amount,
+                        timestamp,
+                    });
+                }
             }
-            
-            Operation::SubscribeToAuthor { owner, amount, target_account } => {
-                let subscriber = self.runtime.authenticated_signer().unwrap();
-                let ts = self.runtime.system_time().micros();
-                
-                // Transfer payment to author
-                let target_account_norm = self.normalize_account(target_account);
-                let author = target_account_norm.owner;
-                let author_chain_id = target_account_norm.chain_id;
-                self.runtime.transfer(owner, target_account_norm, amount);
+            Message::SubscriptionPayment { subscriber, subscriber_chain_id, author, amount, duration_micros, timestamp } => {
+                // Author's chain receives subscription payment; one it refuses is refunded
+                let author_chain_id = self.runtime.chain_id();
+                let end_timestamp = timestamp + duration_micros;
+                let sub_id = format!("sub-{}-{}-{}", subscriber, author, timestamp);
+                let subscriber_chain_id = match donations::canonical_chain_id(&subscriber_chain_id) {
+                    Ok(chain_id) => chain_id,
+                    Err(error) => {
+                        // The payment still arrived: send it back to the chain it came from
+                        if let Some(origin_chain) = self.runtime.message_origin_chain_id() {
+                            self.reject_subscription_payment(sub_id, subscriber, origin_chain, author, amount, error).await;
+                        }
+                        return;
+                    }
+                };
+                let subscriber_chain = subscriber_chain_id.parse::<ChainId>().or_fail("Canonical chain id");
                 
-                // Subscription duration (30 days)
-                const THIRTY_DAYS_MICROS: u64 = 30 * 24 * 60 * 60 * 1_000_000;
-                let end_timestamp = ts + THIRTY_DAYS_MICROS;
-                let subscriber_chain_id = self.runtime.chain_id();
-                let sub_id = format!("sub-{}-{}-{}", subscriber, author, ts);
+                // Grandfathered renewals are accepted at the locked price, anything else must
+                // cover the current price
+                let now = self.runtime.system_time().micros();
+                self.apply_pending_subscription_price(author).await;
+                let (locked_price, renewals_remaining_at_locked_price) = match self.state.subscription_terms(author, subscriber, amount, now).await {
+                    Ok(terms) => terms,
+                    Err(error) => {
+                        // Underpaid, or the author doesn't offer subscriptions: no subscription,
+                        // the whole payment goes back
+                        self.reject_subscription_payment(sub_id, subscriber, subscriber_chain, author, amount, error.to_string()).await;
+                        return;
+                    }
+                };
                 
-                // Create local subscription (for mySubscriptions query)
                 let subscription = donations::ContentSubscription {
                     id: sub_id.clone(),
                     subscriber,
-                    subscriber_chain_id: subscriber_chain_id.to_string(),
+                    subscriber_chain_id: subscriber_chain_id.clone(),
                     author,
                     author_chain_id: author_chain_id.to_string(),
-                    start_timestamp: ts,
+                    start_timestamp: timestamp,
                     end_timestamp,
                     price: amount,
+                    locked_price,
+                    renewals_remaining_at_locked_price,
+                    auto_renew: false,
+                    auto_renew_failures: 0,
                 };
                 
-                self.state.create_subscription(subscription.clone()).await.expect("Failed to create subscription");
-                
-                // Notify author's chain about subscription payment
-                if author_chain_id != subscriber_chain_id {
-                    self.runtime.prepare_message(Message::SubscriptionPayment {
-                        subscriber,
-                        subscriber_chain_id: subscriber_chain_id.to_string(),
-                        author,
-                        amount,
-                        duration_micros: THIRTY_DAYS_MICROS,
-                        timestamp: ts,
-                    }).with_authentication().send_to(author_chain_id);
+                if let Err(error) = self.state.create_subscription(subscription).await {
+                    self.reject_subscription_payment(sub_id, subscriber, subscriber_chain, author, amount, error.to_string()).await;
+                    return;
                 }
+                self.accrue_income_redirect(author, amount).await;
                 
-                ResponseData::Ok
-            }
-            
-            Operation::CreatePost { title, content, image_hash, poll_options, poll_end_timestamp, giveaway_prize, giveaway_end_timestamp } => {
-                let author = self.runtime.authenticated_signer().unwrap();
-                let ts = self.runtime.system_time().micros();
-                // Generate 12-character hex ID from timestamp
-                let post_id = format!("{:012x}", ts % 0x1000000000000);
-                let author_chain_id = self.runtime.chain_id();
-                
-                // Create poll if options provided
-                let poll = if !poll_options.is_empty() {
-                    Some(donations::Poll {
-                        options: poll_options.into_iter().map(|text| donations::PollOption {
-                            text,
-                            votes_count: 0,
-                        }).collect(),
-                        end_timestamp: poll_end_timestamp.unwrap_or(0),
-                        voters: std::collections::BTreeMap::new(),
-                    })
-                } else {
-                    None
-                };
-                
-                // Create giveaway if prize provided
-                let giveaway = if let Some(prize_amount) = giveaway_prize {
-                    Some(donations::Giveaway {
-                        prize_amount,
-                        end_timestamp: giveaway_end_timestamp.unwrap_or(0),
-                        participants: Vec::new(),
-                        winner: None,
-                        is_resolved: false,
-                    })
-                } else {
-                    None
-                };
-                
-                let post = donations::Post {
-                    id: post_id.clone(),
+                // Let the subscriber's chain cache the terms for its next renewal
+                let renewal_price = self.state.effective_renewal_price(author, subscriber, now).await.ok().flatten().unwrap_or(locked_price);
+                self.runtime.prepare_message(Message::SubscriptionConfirmed {
+                    subscription_id: sub_id.clone(),
+                    subscriber,
                     author,
-                    author_chain_id: author_chain_id.to_string(),
-                    title,
-                    content,
-                    image_hash,
-                    created_at: ts,
-                    poll,
-                    giveaway,
-                };
-                
-                // Save post
-                self.state.create_post(post.clone()).await.expect("Failed to create post");
+                    locked_price,
+                    renewals_remaining_at_locked_price,
+                    renewal_price,
+                }).with_authentication().send_to(subscriber_chain);
                 
-                // Emit event
-                self.runtime.emit("donations_events".into(), &DonationsEvent::PostCreated { 
-                    post: post.clone(), 
-                    timestamp: ts 
+                // Emit event for indexing
+                self.emit_event(DonationsEvent::UserSubscribed {
+                    subscription_id: sub_id,
+                    subscriber,
+                    author,
+                    price: amount,
+                    end_timestamp,
+                    timestamp,
                 });
-                
-                // Get active subscriptions and clean up expired ones
-                let all_subs = self.state.subscriptions_by_author.get(&author).await
-                    .ok()
-                    .flatten()
-                    .unwrap_or_default();
-                
-                for sub_id in all_subs {
-                    if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
-                        if sub.end_timestamp < ts {
-                            // Subscription expired - unsubscribe
-                            let _ = self.state.remove_subscription(&sub_id, author, sub.subscriber).await;
-                            
-                            self.runtime.emit("donations_events".into(), &DonationsEvent::UserUnsubscribed {
-                                subscription_id: sub_id,
-                                subscriber: sub.subscriber,
-                                author,
-                                timestamp: ts,
-                            });
-                        } else {
-                            // Subscription active - send post to subscriber's chain
-                            if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
-                                if subscriber_chain_id != author_chain_id {
-                                    self.runtime.prepare_message(Message::PostPublished {
-                                        post: post.clone(),
-                                    }).with_authentication().send_to(subscriber_chain_id);
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                ResponseData::Ok
             }
-
-
-            
-            Operation::UpdatePost { post_id, title, content, image_hash } => {
-                let author = self.runtime.authenticated_signer().unwrap();
-                let ts = self.runtime.system_time().micros();
-                
-                // Update post
-                self.state.update_post(&post_id, title, content, image_hash).await
-                    .expect("Failed to update post");
-                
-                // Get updated post
-                let post = self.state.get_post(&post_id).await
-                    .expect("Failed to get post")
-                    .expect("Post not found");
-                
-                // Verify ownership
-                if post.author != author {
-                    panic!("Unauthorized: not post author");
-                }
-                
-                // Emit event
-                self.runtime.emit("donations_events".into(), &DonationsEvent::PostUpdated {
-                    post: post.clone(),
-                    timestamp: ts,
-                });
-                
-                // Send update to active subscribers
-                let all_subs = self.state.subscriptions_by_author.get(&author).await
-                    .ok()
-                    .flatten()
-                    .unwrap_or_default();
-                
-                let author_chain_id = self.runtime.chain_id();
-                for sub_id in all_subs {
-                    if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
-                        if sub.end_timestamp >= ts {
-                            // Active subscription - send update
-                            if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
-                                if subscriber_chain_id != author_chain_id {
-                                    self.runtime.prepare_message(Message::PostUpdated {
-                                        post: post.clone(),
-                                    }).with_authentication().send_to(subscriber_chain_id);
-                                }
-                            }
-                        }
-                    }
+            Message::PriceChangeNotice { subscriber, author, old_price, new_price, effective_at } => {
+                // Subscriber's chain files the notice in the subscriber's inbox; only the author announces it
+                if self.runtime.authenticated_signer() != Some(author) {
+                    return;
                 }
-                
-                ResponseData::Ok
+                let _ = self.state.push_notification(subscriber, donations::Notification {
+                    kind: "price_change".to_string(),
+                    from: author,
+                    text: format!("Subscription price changes from {} to {} at {}", old_price, new_price, effective_at),
+                    timestamp: self.runtime.system_time().micros(),
+                }).await;
             }
-            
-            Operation::DeletePost { post_id } => {
-                let author = self.runtime.authenticated_signer().unwrap();
+            Message::SubscriptionRejected { subscription_id, subscriber, author, amount, reason } => {
+                // Subscriber's chain removes the subscription the author's chain didn't accept
+                if self.runtime.authenticated_signer() != Some(subscriber) {
+                    return;
+                }
                 let ts = self.runtime.system_time().micros();
-                
-                // Delete post (will verify ownership inside)
-                self.state.delete_post(&post_id, author).await
-                    .expect("Failed to delete post");
-                
-                // Emit event
-                self.runtime.emit("donations_events".into(), &DonationsEvent::PostDeleted {
-                    post_id: post_id.clone(),
+                let _ = self.state.remove_subscription(&subscription_id, author, subscriber).await;
+                let _ = self.state.push_notification(subscriber, donations::Notification {
+                    kind: "subscription_rejected".to_string(),
+                    from: author,
+                    text: format!("Subscription rejected ({}). The {} paid is refunded when the author's chain next signs a block", reason, amount),
+                    timestamp: ts,
+                }).await;
+                self.emit_event(DonationsEvent::UserUnsubscribed {
+                    subscription_id,
+                    subscriber,
                     author,
                     timestamp: ts,
                 });
-                
-                // Send deletion to active subscribers
-                let all_subs = self.state.subscriptions_by_author.get(&author).await
-                    .ok()
-                    .flatten()
-                    .unwrap_or_default();
-                
-                let author_chain_id = self.runtime.chain_id();
-                for sub_id in all_subs {
-                    if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
-                        if sub.end_timestamp >= ts {
-                            // Active subscription - send deletion
-                            if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
-                                if subscriber_chain_id != author_chain_id {
-                                    self.runtime.prepare_message(Message::PostDeleted {
-                                        post_id: post_id.clone(),
-                                        author,
-                                    }).with_authentication().send_to(subscriber_chain_id);
-                                }
-                            }
-                        }
+            }
+            Message::Announcement { subscriber, author, text, timestamp } => {
+                // Subscriber's chain files the announcement in the subscriber's inbox and feed
+                if self.runtime.authenticated_signer() != Some(author) {
+                    return;
+                }
+                let _ = self.state.push_announcement(subscriber, donations::Notification {
+                    kind: "announcement".to_string(),
+                    from: author,
+                    text,
+                    timestamp,
+                }).await;
+            }
+            Message::SubscriptionConfirmed { subscription_id, subscriber, author, locked_price, renewals_remaining_at_locked_price, renewal_price } => {
+                // Subscriber's chain stores the terms granted by the author's chain. The confirmation
+                // is sent while the subscriber's payment is applied there, so it carries the
+                // subscriber's signature; the author's chain is what vouches for the terms.
+                let Ok(Some(sub)) = self.state.content_subscriptions.get(&subscription_id).await else { return };
+                if sub.author != author || sub.subscriber != subscriber
+                    || self.runtime.message_origin_chain_id().map(|c| c.to_string()) != Some(sub.author_chain_id) {
+                    return;
+                }
+                if !matches!(self.runtime.authenticated_signer(), Some(signer) if signer == author || signer == subscriber) {
+                    return;
+                }
+                let _ = self.state.confirm_subscription(&subscription_id, author, subscriber, locked_price, renewals_remaining_at_locked_price, renewal_price).await;
+            }
+            Message::Unsubscribed { subscription_id, subscriber } => {
+                // Author's chain drops its copy; only the subscriber may cancel
+                if self.runtime.authenticated_signer() != Some(subscriber) {
+                    return;
+                }
+                if let Ok(Some(sub)) = self.state.content_subscriptions.get(&subscription_id).await {
+                    if sub.subscriber == subscriber {
+                        let _ = self.state.remove_subscription(&subscription_id, sub.author, subscriber).await;
+                        let ts = self.runtime.system_time().micros();
+                        self.emit_event(DonationsEvent::UserUnsubscribed {
+                            subscription_id,
+                            subscriber,
+                            author: sub.author,
+                            timestamp: ts,
+                        });
                     }
                 }
-                
-                ResponseData::Ok
             }
-            
-            Operation::CastVote { author_chain_id, author, post_id, option_index } => {
-                let voter = self.runtime.authenticated_signer().unwrap();
+            Message::PostPublished { post } => {
+                // Subscriber's chain receives the post
+                let _ = self.state.create_post(post).await;
+            }
+            Message::PostUpdated { post } => {
+                // Subscriber's chain updates the post, mirroring a removed image too
+                let clear_image = post.image_hash.is_none();
+                let _ = self.state.update_post(&post.id, Some(post.title), Some(post.content), post.image_hash, clear_image).await;
+            }
+            Message::PostDeleted { post_id, author } => {
+                // Subscriber's chain deletes the post
+                let _ = self.state.delete_post(&post_id, author).await;
+            }
+            Message::VoteCasted { post_id, voter, voter_chain_id: _, option_index } => {
+                // Author's chain receives vote from subscriber
                 let ts = self.runtime.system_time().micros();
-                let voter_chain_id = self.runtime.chain_id();
                 
-                // If we're on the author's chain - vote directly
-                if author_chain_id == voter_chain_id {
-                    // Validate subscription (author can always vote on their own posts)
+                // Get post to find author
+                if let Ok(Some(post)) = self.state.get_post(&post_id).await {
+                    let author = post.author;
+                    
+                    // Validate subscription (author doesn't need subscription for their own posts)
                     if voter != author {
                         let is_valid = self.check_subscription_valid(voter, author, ts).await;
                         if !is_valid {
-                            panic!("Invalid or expired subscription");
+                            return; // Ignore invalid vote
                         }
                     }
                     
-                    // Check poll exists and hasn't ended
-                    if let Ok(Some(post)) = self.state.get_post(&post_id).await {
-                        if let Some(poll) = &post.poll {
-                            if ts > poll.end_timestamp && poll.end_timestamp > 0 {
-                                panic!("Poll has ended");
-                            }
-                        } else {
-                            panic!("Post has no poll");
+                    // Check poll hasn't ended
+                    if let Some(poll) = &post.poll {
+                        if ts > poll.end_timestamp && poll.end_timestamp > 0 {
+                            return; // Poll has ended
                         }
                     } else {
-                        panic!("Post not found");
+                        return; // No poll
                     }
                     
                     // Cast vote
                     let voter_id = voter.to_string();
-                    let updated_poll = self.state.cast_vote(&post_id, voter_id, option_index).await
-                        .expect("Failed to cast vote");
-                    
-                    // Emit event
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::VoteCasted {
-                        post_id: post_id.clone(),
-                        voter,
-                        option_index,
-                        timestamp: ts,
-                    });
-                    
-                    // Broadcast updated poll results to all active subscribers
-                    self.broadcast_poll_update(&post_id, &updated_poll, author).await;
-                } else {
-                    // Send vote message to author's chain
-                    self.runtime.prepare_message(Message::VoteCasted {
-                        post_id,
-                        voter,
-                        voter_chain_id,
-                        option_index,
-                    }).with_authentication().send_to(author_chain_id);
+                    if let Ok(updated_poll) = self.state.cast_vote(&post_id, voter_id, option_index).await {
+                        // Emit event
+                        self.emit_event(DonationsEvent::VoteCasted {
+                            post_id: post_id.clone(),
+                            voter,
+                            option_index,
+                            timestamp: ts,
+                        });
+                        
+                        // Broadcast updated poll results to all active subscribers
+                        self.broadcast_poll_update(&post_id, &updated_poll, author, false).await;
+                    }
                 }
-                
-                ResponseData::Ok
             }
-            
-            Operation::ParticipateInGiveaway { author_chain_id, author, post_id } => {
-                let participant = self.runtime.authenticated_signer().unwrap();
+            Message::PollResultsUpdated { post_id, poll } => {
+                // Subscriber's chain receives updated poll results
+                let _ = self.state.update_poll_results(&post_id, poll).await;
+            }
+            Message::GiveawayParticipation { post_id, participant, participant_chain_id } => {
+                // Author's chain receives giveaway participation from subscriber
                 let ts = self.runtime.system_time().micros();
-                let participant_chain_id = self.runtime.chain_id();
                 
-                // If we're on the author's chain - participate directly
-                if author_chain_id == participant_chain_id {
-                    // Validate subscription (author can participate in their own giveaway for testing)
+                // Get post to find author
+                if let Ok(Some(post)) = self.state.get_post(&post_id).await {
+                    let author = post.author;
+                    
+                    // Validate subscription
                     if participant != author {
                         let is_valid = self.check_subscription_valid(participant, author, ts).await;
                         if !is_valid {
-                            panic!("Invalid or expired subscription");
+                            return; // Ignore invalid participation
                         }
                     }
                     
                     // Check giveaway exists and hasn't ended
-                    if let Ok(Some(post)) = self.state.get_post(&post_id).await {
-                        if let Some(giveaway) = &post.giveaway {
-                            if ts > giveaway.end_timestamp && giveaway.end_timestamp > 0 {
-                                panic!("Giveaway has ended");
-                            }
-                            if giveaway.is_resolved {
-                                panic!("Giveaway already resolved");
-                            }
-                        } else {
-                            panic!("Post has no giveaway");
+                    if let Some(giveaway) = &post.giveaway {
+                        if ts > giveaway.end_timestamp && giveaway.end_timestamp > 0 {
+                            return; // Giveaway has ended
+                        }
+                        if giveaway.is_resolved {
+                            return; // Already resolved
                         }
                     } else {
-                        panic!("Post not found");
+                        return; // No giveaway
                     }
                     
                     // Add participant
@@ -702,508 +594,2621 @@ impl Contract for DonationsContract {
                         joined_at: ts,
                     };
                     
-                    let updated_giveaway = self.state.add_giveaway_participant(&post_id, giveaway_participant).await
-                        .expect("Failed to join giveaway");
-                    
-                    // Emit event
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::GiveawayParticipated {
-                        post_id: post_id.clone(),
-                        participant,
-                        timestamp: ts,
-                    });
-                    
-                    // Broadcast updated giveaway to all active subscribers
-                    self.broadcast_giveaway_update(&post_id, &updated_giveaway, author).await;
-                } else {
-                    // Send participation message to author's chain
-                    self.runtime.prepare_message(Message::GiveawayParticipation {
-                        post_id,
-                        participant,
-                        participant_chain_id,
-                    }).with_authentication().send_to(author_chain_id);
+                    if let Ok(updated_giveaway) = self.state.add_giveaway_participant(&post_id, giveaway_participant).await {
+                        // Emit event
+                        self.emit_event(DonationsEvent::GiveawayParticipated {
+                            post_id: post_id.clone(),
+                            participant,
+                            timestamp: ts,
+                        });
+                        
+                        // Broadcast updated giveaway to all active subscribers
+                        self.broadcast_giveaway_update(&post_id, &updated_giveaway, author).await;
+                    }
                 }
-                
-                ResponseData::Ok
             }
-            
-            Operation::ResolveGiveaway { post_id } => {
-                let author = self.runtime.authenticated_signer().unwrap();
-                let ts = self.runtime.system_time().micros();
-                
-                // Get post and verify ownership
-                let post = self.state.get_post(&post_id).await
-                    .expect("Failed to get post")
-                    .expect("Post not found");
-                
-                if post.author != author {
-                    panic!("Unauthorized: not post author");
+            Message::GiveawayUpdated { post_id, giveaway } => {
+                // Subscriber's chain receives updated giveaway
+                let _ = self.state.update_giveaway(&post_id, giveaway).await;
+            }
+            Message::CommentPosted { post_id, parent_id, commenter, commenter_chain_id, text } => {
+                // Author's chain receives comment from subscriber; only the commenter may post it
+                if self.runtime.authenticated_signer() != Some(commenter) {
+                    return;
                 }
+                let ts = self.runtime.system_time().micros();
                 
-                let giveaway = post.giveaway.as_ref().expect("Post has no giveaway");
-                
-                if giveaway.is_resolved {
-                    panic!("Giveaway already resolved");
+                if let Ok(Some(post)) = self.state.get_post(&post_id).await {
+                    let author = post.author;
+                    if !self.check_subscription_valid(commenter, author, ts).await {
+                        return; // Ignore comment without subscription
+                    }
+                    // The comment was already sent, so an oversized one is cut rather than refused
+                    let (text, cut) = self.sanitize_inbound_text(text);
+                    if let Ok(comment) = self.state.new_comment(&post_id, commenter, commenter_chain_id.to_string(), parent_id, text, cut, ts).await {
+                        if comment.content_modified {
+                            let _ = self.state.record_content_adjustment("comment", ts).await;
+                        }
+                        self.emit_event(DonationsEvent::CommentAdded { comment: comment.clone(), timestamp: ts });
+                        self.broadcast_comment(comment, author, true).await;
+                    }
                 }
-                
-                if giveaway.participants.is_empty() {
-                    panic!("No participants to pick winner from");
+            }
+            Message::CommentDeleteRequested { comment_id, requester } => {
+                // Author's chain deletes a comment on its author's request
+                if self.runtime.authenticated_signer() != Some(requester) {
+                    return;
                 }
-                
-                // Pick winner using pseudo-random selection
-                let participants_count = giveaway.participants.len();
-                let winner_index = (ts as usize + post_id.len() + participants_count) % participants_count;
-                
-                // Resolve and get winner
-                let winner = self.state.resolve_giveaway(&post_id, winner_index).await
-                    .expect("Failed to resolve giveaway");
-                
-                // Transfer prize to winner
-                let winner_chain_id: linera_sdk::linera_base_types::ChainId = winner.chain_id.parse()
-                    .expect("Invalid winner chain ID");
-                let winner_account = Account {
-                    chain_id: winner_chain_id,
-                    owner: winner.owner,
-                };
-                self.runtime.transfer(author, winner_account, giveaway.prize_amount);
-                
-                // Emit event
-                self.runtime.emit("donations_events".into(), &DonationsEvent::GiveawayResolved {
-                    post_id: post_id.clone(),
-                    winner: winner.owner,
-                    winner_chain_id: winner.chain_id.clone(),
-                    prize_amount: giveaway.prize_amount,
-                    timestamp: ts,
-                });
-                
-                // Broadcast resolved giveaway to all active subscribers
-                if let Ok(Some(updated_post)) = self.state.get_post(&post_id).await {
-                    if let Some(updated_giveaway) = &updated_post.giveaway {
-                        self.broadcast_giveaway_update(&post_id, updated_giveaway, author).await;
+                let ts = self.runtime.system_time().micros();
+                if let Ok(comment) = self.state.remove_comment(&comment_id, requester, false).await {
+                    if let Ok(Some(post)) = self.state.get_post(&comment.post_id).await {
+                        self.emit_event(DonationsEvent::CommentRemoved { comment: comment.clone(), timestamp: ts });
+                        self.broadcast_comment(comment, post.author, false).await;
                     }
                 }
-                
-                ResponseData::Ok
+            }
+            Message::CommentPublished { comment } | Message::CommentUpdated { comment } => {
+                // Subscriber's chain mirrors the comment tree, as broadcast by the post's chain
+                // on behalf of the commenter or the post author
+                let Ok(Some(post)) = self.state.get_post(&comment.post_id).await else { return };
+                if self.runtime.message_origin_chain_id().map(|c| c.to_string()) != Some(post.author_chain_id) {
+                    return;
+                }
+                let signer = self.runtime.authenticated_signer();
+                if signer != Some(comment.author) && signer != Some(post.author) {
+                    return;
+                }
+                let _ = self.state.upsert_comment(comment).await;
             }
         }
     }
 
+    async fn store(mut self) { self.state.save().await.expect("save") }
+}
 
-
-    async fn execute_message(&mut self, message: Self::Message) {
-        match message {
-            Message::Notify => {}
-            Message::TransferWithMessage { owner, amount, text_message, source_chain_id, source_owner } => {
-                let ts = self.runtime.system_time().micros();
-                let current_chain_id = self.runtime.chain_id().to_string();
-                if let Ok(id) = self.state.record_donation(source_owner, owner, amount, text_message.clone(), Some(source_chain_id.to_string()), Some(current_chain_id.clone()), ts).await {
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::DonationSent { id, from: source_owner, to: owner, amount, message: text_message, source_chain_id: Some(source_chain_id.to_string()), to_chain_id: Some(current_chain_id), timestamp: ts });
-                }
-            }
-            Message::Register { source_chain_id, owner, name, bio, socials } => {
-                // Subscribe this (main) chain to the source chain's donations_events stream
-                let app_id = self.runtime.application_id().forget_abi();
-                let stream = StreamName::from("donations_events");
-                self.runtime.subscribe_to_events(source_chain_id, app_id, stream.clone());
-                let _ = self.state.subscriptions.insert(&owner, source_chain_id.to_string());
-                if let Some(n) = name { let _ = self.state.set_name(owner, n).await; }
-                if let Some(b) = bio { let _ = self.state.set_bio(owner, b).await; }
-                for s in socials { let _ = self.state.set_social(owner, s.name, s.url).await; }
-            }
-            Message::ProductCreated { product } => {
-                // Main chain stores product from other chains
-                let _ = self.state.create_product(product).await;
-            }
-            Message::ProductUpdated { product } => {
-                // Main chain updates product
-                let product_id = product.id.clone();
-                let author = product.author;
-                let _ = self.state.delete_product(&product_id, author).await;
-                let _ = self.state.create_product(product).await;
-            }
-            Message::ProductDeleted { product_id, author } => {
-                // Main chain deletes product
-                let _ = self.state.delete_product(&product_id, author).await;
+impl DonationsContract {
+    /// Run one operation. Failures found before it changed anything come back as `Err`;
+    /// later ones panic, so a half-applied operation is never committed.
+    async fn apply_operation(&mut self, operation: Operation) -> Result<ResponseData, OperationError> {
+        // A closed account can still take its remaining funds out and flush its outbox
+        if let Some(signer) = self.runtime.authenticated_signer() {
+            if !matches!(operation, Operation::Withdraw { .. } | Operation::RetryOutbox) && self.state.is_closed(signer).await {
+                return Err(OperationError::new("account_closed", "Account is closed"));
             }
-            Message::ProductPurchased { purchase_id, product_id, buyer, buyer_chain_id, seller, amount } => {
-                // Main chain receives purchase notification and sends product data to buyer
-                if let Ok(Some(product)) = self.state.get_product(&product_id).await {
-                    // Validate that the paid amount matches the product price
-                    if amount == product.price {
-                        // Send product data to buyer's chain
-                        self.runtime.prepare_message(Message::SendProductData {
-                            buyer,
-                            purchase_id: purchase_id.clone(),
-                            product: product.clone(),
-                        }).with_authentication().send_to(buyer_chain_id);
-                        
-                        // Record purchase on main chain
-                        let ts = self.runtime.system_time().micros();
-                        let purchase = donations::Purchase {
-                            id: purchase_id.clone(),
-                            product_id: product_id.clone(),
-                            buyer,
-                            buyer_chain_id: buyer_chain_id.to_string(),
-                            seller,
-                            seller_chain_id: product.author_chain_id.clone(),
-                            amount,
-                            timestamp: ts,
-                            order_data: std::collections::BTreeMap::new(), // Main chain doesn't have order data
-                            product,
-                        };
-                        let _ = self.state.record_purchase(purchase).await;
-                        
-                        // Emit event so subscribers to Main Chain see the purchase
-                        self.runtime.emit("donations_events".into(), &DonationsEvent::ProductPurchased {
-                            purchase_id: purchase_id.clone(),
-                            product_id: product_id.clone(),
-                            buyer,
-                            seller,
-                            amount,
-                            timestamp: ts,
-                        });
+        }
+        Ok(match operation {
+            Operation::Transfer { owner, amount, target_account, text_message } => {
+                self.runtime.check_account_permission(owner).map_err(|_| OperationError::unauthorized("Permission denied"))?;
+                let target_account_norm = self.normalize_account(target_account);
+                self.check_transfer(owner, target_account_norm, amount)?;
+                let text_message = self.clean_message(text_message)?;
+                self.pay(owner, target_account_norm, amount).await?;
+                let payment_app = self.payment_app_label();
+                if target_account_norm.chain_id != self.runtime.chain_id() {
+                    self.announce_cross_chain_transfer(owner, target_account_norm, amount, text_message).await;
+                } else if self.state.is_holding_account(target_account_norm.owner).await {
+                    // Held until the admin routes it; mirrored only then
+                    let ts = self.runtime.system_time().micros();
+                    let id = self.state.record_donation(owner, target_account_norm.owner, amount, text_message, None, None, Some(target_account_norm.chain_id.to_string()), ts, payment_app).await.or_fail("Failed to record donation");
+                    self.state.hold_donation(id).await.or_fail("Failed to hold donation");
+                } else {
+                    let ts = self.runtime.system_time().micros();
+                    if let Ok(id) = self.state.record_donation(owner, target_account_norm.owner, amount, text_message.clone(), None, None, Some(target_account_norm.chain_id.to_string()), ts, payment_app).await {
+                        self.emit_event(DonationsEvent::DonationSent { id, from: owner, to: target_account_norm.owner, amount, message: text_message, source_chain_id: None, source_donation_id: None, to_chain_id: Some(target_account_norm.chain_id.to_string()), timestamp: ts });
+                    }
+                    if owner != target_account_norm.owner {
+                        self.accrue_income_redirect(target_account_norm.owner, amount).await;
                     }
                 }
+                ResponseData::Ok
             }
-            Message::SendProductData { buyer, purchase_id, product } => {
-                // Buyer's chain receives full product data
-                let ts = self.runtime.system_time().micros();
-                let purchase = donations::Purchase {
-                    id: purchase_id,
-                    product_id: product.id.clone(),
-                    buyer,
-                    buyer_chain_id: self.runtime.chain_id().to_string(),
-                    seller: product.author,
-                    seller_chain_id: product.author_chain_id.clone(),
-                    amount: product.price,
-                    timestamp: ts,
-                    order_data: std::collections::BTreeMap::new(), // Empty for now
-                    product,
+            Operation::Withdraw { target, amount } => {
+                let owner = self.signer()?;
+                let balance = self.balance_of(owner);
+                let amount = match amount {
+                    // Zero, like no amount at all, withdraws the whole balance
+                    None => balance,
+                    Some(amount) if amount == Amount::ZERO => balance,
+                    Some(amount) => amount,
                 };
-                let _ = self.state.record_purchase(purchase).await;
+                if amount > balance {
+                    return Err(OperationError::new("insufficient_balance", format!("Withdrawal of {} exceeds balance {}", amount, balance)));
+                }
+                let target = match target {
+                    Some(target) => self.normalize_account(target),
+                    None => Account { chain_id: self.runtime.chain_id(), owner: AccountOwner::CHAIN },
+                };
+                // Committed funds stay in the owner's account: asking for them fails rather than
+                // withdrawing only part of the request
+                self.pay(owner, target, amount).await?;
+                // Records on both chains follow the same path as a cross-chain Transfer
+                if target.chain_id != self.runtime.chain_id() {
+                    self.announce_cross_chain_transfer(owner, target, amount, None).await;
+                }
+                ResponseData::Ok
             }
-            Message::OrderReceived { purchase_id, product_id, buyer, buyer_chain_id, amount, order_data, timestamp } => {
-                // Seller's chain receives order notification with buyer's form data
-                // We must fetch the product to get the correct seller (author) and to record the purchase
-                if let Ok(Some(product)) = self.state.get_product(&product_id).await {
-                    let seller = product.author; // Correct seller is the product author
-
-                    // Record the full purchase so it shows up in "My Orders"
-                    let purchase = donations::Purchase {
-                        id: purchase_id.clone(),
-                        product_id: product_id.clone(),
-                        buyer,
-                        buyer_chain_id: buyer_chain_id.to_string(),
-                        seller,
-                        seller_chain_id: product.author_chain_id.clone(),
-                        amount,
-                        timestamp,
-                        order_data: order_data.clone(),
-                        product: product.clone(),
-                    };
-                    
-                    let _ = self.state.record_purchase(purchase).await;
-
-                    self.runtime.emit("donations_events".into(), &DonationsEvent::OrderPlaced {
-                        purchase_id,
-                        product_id,
-                        buyer,
-                        seller,
-                        amount,
-                        timestamp,
-                    });
+            Operation::Mint { owner, amount } => {
+                self.require_admin()?;
+                self.state.record_mint(amount)?;
+                let target_account = Account { chain_id: self.runtime.chain_id(), owner };
+                if self.payment_app().is_some() {
+                    // In token mode mints come out of this application's own token account
+                    let treasury = AccountOwner::from(self.runtime.application_id().forget_abi());
+                    self.pay(treasury, target_account, amount).await.or_fail("Failed to mint");
+                } else {
+                    self.runtime.transfer(AccountOwner::CHAIN, target_account, amount);
                 }
+                ResponseData::Ok
             }
-            Message::SubscriptionPayment { subscriber, subscriber_chain_id, author, amount, duration_micros, timestamp } => {
-                // Author's chain receives subscription payment
-                let author_chain_id = self.runtime.chain_id();
+            Operation::UpdateProfile { name, bio, socials, avatar_hash, header_hash } => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                // One cooldown covers every field, avatar, header and socials included
+                self.state.note_profile_update(owner, ts).await?;
+                self.apply_profile_fields(owner, name, bio, socials, avatar_hash, header_hash, ts).await;
+                ResponseData::Ok
+            }
+            Operation::Register { main_chain_id, name, bio, socials, avatar_hash, header_hash } => {
+                // Send register message to main chain so it subscribes to our events
+                let owner = self.signer()?;
+                let msg = Message::Register {
+                    source_chain_id: self.runtime.chain_id(),
+                    owner,
+                    name: name.clone(),
+                    bio: bio.clone(),
+                    socials: socials.iter().map(|s| SocialLink { name: s.name.clone(), url: s.url.clone() }).collect(),
+                };
+                self.send_tracked(msg, main_chain_id).await;
                 
-                let end_timestamp = timestamp + duration_micros;
-                let sub_id = format!("sub-{}-{}-{}", subscriber, author, timestamp);
+                // Save main_chain_id to subscriptions so we know where to send future messages
+                let _ = self.state.subscriptions.insert(&owner, main_chain_id.to_string());
+                let _ = self.state.home_chains.insert(&owner, self.runtime.chain_id().to_string());
                 
-                let subscription = donations::ContentSubscription {
-                    id: sub_id.clone(),
-                    subscriber,
-                    subscriber_chain_id,
-                    author,
-                    author_chain_id: author_chain_id.to_string(),
-                    start_timestamp: timestamp,
-                    end_timestamp,
-                    price: amount,
-                };
-                
-                let _ = self.state.create_subscription(subscription).await;
-                
-                // Emit event for indexing
-                self.runtime.emit("donations_events".into(), &DonationsEvent::UserSubscribed {
-                    subscription_id: sub_id,
-                    subscriber,
-                    author,
-                    price: amount,
-                    end_timestamp,
-                    timestamp,
-                });
+                let ts = self.runtime.system_time().micros();
+                self.apply_profile_fields(owner, name, bio, socials, avatar_hash, header_hash, ts).await;
+                ResponseData::Ok
             }
-            Message::PostPublished { post } => {
-                // Subscriber's chain receives the post
-                let _ = self.state.create_post(post).await;
+            Operation::SetAvatar { hash } => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                self.state.note_profile_update(owner, ts).await?;
+                if let Ok(true) = self.state.set_avatar(owner, hash.clone()).await {
+                    self.emit_event(DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: ts });
+                }
+                ResponseData::Ok
             }
-            Message::PostUpdated { post } => {
-                // Subscriber's chain updates the post
-                let _ = self.state.update_post(&post.id, Some(post.title), Some(post.content), post.image_hash).await;
+            Operation::SetHeader { hash } => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                self.state.note_profile_update(owner, ts).await?;
+                if let Ok(true) = self.state.set_header(owner, hash.clone()).await {
+                    self.emit_event(DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: ts });
+                }
+                ResponseData::Ok
             }
-            Message::PostDeleted { post_id, author } => {
-                // Subscriber's chain deletes the post
-                let _ = self.state.delete_post(&post_id, author).await;
+            Operation::GetProfile { owner } => {
+                match self.state.get_profile(owner).await { Ok(p) => ResponseData::Profile(p), Err(_) => ResponseData::Profile(None) }
             }
-            Message::VoteCasted { post_id, voter, voter_chain_id, option_index } => {
-                // Author's chain receives vote from subscriber
+            Operation::GetDonationsByRecipient { owner } => {
+                match self.state.list_donations_by_recipient(owner).await { Ok(v) => ResponseData::Donations(v), Err(_) => ResponseData::Donations(Vec::new()) }
+            }
+            Operation::GetDonationsByDonor { owner } => {
+                match self.state.list_donations_by_donor(owner).await { Ok(v) => ResponseData::Donations(v), Err(_) => ResponseData::Donations(Vec::new()) }
+            }
+            Operation::CreateProduct { public_data, price, private_data, encrypted, success_message, order_form, slug, category, entitlements, order_form_template, stock } => {
+                let owner = self.signer()?;
                 let ts = self.runtime.system_time().micros();
+                let chain_id = self.runtime.chain_id();
+                let product_id = format!("{}-{}", ts, chain_id);
                 
-                // Get post to find author
-                if let Ok(Some(post)) = self.state.get_post(&post_id).await {
-                    let author = post.author;
-                    
-                    // Validate subscription (author doesn't need subscription for their own posts)
-                    if voter != author {
-                        let is_valid = self.check_subscription_valid(voter, author, ts).await;
-                        if !is_valid {
-                            return; // Ignore invalid vote
-                        }
+                // Convert OrderFormFieldInput to OrderFormField
+                let mut order_form_fields: Vec<donations::OrderFormField> = order_form.into_iter().map(|f| donations::OrderFormField {
+                    key: f.key,
+                    label: f.label,
+                    field_type: f.field_type,
+                    required: f.required,
+                }).collect();
+                if let Some(name) = order_form_template {
+                    if !order_form_fields.is_empty() {
+                        return Err(OperationError::invalid("Give either an order form or a template, not both"));
                     }
-                    
-                    // Check poll hasn't ended
-                    if let Some(poll) = &post.poll {
-                        if ts > poll.end_timestamp && poll.end_timestamp > 0 {
-                            return; // Poll has ended
+                    order_form_fields = self.state.get_order_form_template(owner, &name).await?;
+                }
+                
+                let product = donations::Product {
+                    id: product_id.clone(),
+                    author: owner,
+                    author_chain_id: chain_id.to_string(),
+                    public_data,
+                    price,
+                    private_data,
+                    encrypted: encrypted.unwrap_or(false),
+                    success_message,
+                    order_form: order_form_fields,
+                    slug: slug.filter(|s| !s.is_empty()),
+                    category: category.filter(|c| !c.is_empty()),
+                    entitlements,
+                    content_revision: 0,
+                    form_revision: 0,
+                    created_at: ts,
+                    stock,
+                };
+                
+                self.state.create_product(product.clone()).await?;
+                self.emit_event(DonationsEvent::ProductCreated { product: product.clone(), timestamp: ts });
+                
+                // Send to main chain if we're on a different chain
+                if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&owner).await {
+                    if let Ok(main_chain_id) = main_chain_id_str.parse() {
+                        if main_chain_id != chain_id {
+                            self.send_tracked(Message::ProductCreated { product }, main_chain_id).await;
                         }
-                    } else {
-                        return; // No poll
-                    }
-                    
-                    // Cast vote
-                    let voter_id = voter.to_string();
-                    if let Ok(updated_poll) = self.state.cast_vote(&post_id, voter_id, option_index).await {
-                        // Emit event
-                        self.runtime.emit("donations_events".into(), &DonationsEvent::VoteCasted {
-                            post_id: post_id.clone(),
-                            voter,
-                            option_index,
-                            timestamp: ts,
-                        });
-                        
-                        // Broadcast updated poll results to all active subscribers
-                        self.broadcast_poll_update(&post_id, &updated_poll, author).await;
                     }
                 }
+                
+                ResponseData::Ok
             }
-            Message::PollResultsUpdated { post_id, poll } => {
-                // Subscriber's chain receives updated poll results
-                let _ = self.state.update_poll_results(&post_id, poll).await;
-            }
-            Message::GiveawayParticipation { post_id, participant, participant_chain_id } => {
-                // Author's chain receives giveaway participation from subscriber
+            Operation::UpdateProduct { product_id, public_data, price, private_data, encrypted, success_message, order_form, slug, category, entitlements, stock, clear_stock } => {
+                let owner = self.signer()?;
+                
+                // Convert Option<Vec<OrderFormFieldInput>> to Option<Vec<OrderFormField>>
+                let order_form_fields = order_form.map(|fields| {
+                    fields.into_iter().map(|f| donations::OrderFormField {
+                        key: f.key,
+                        label: f.label,
+                        field_type: f.field_type,
+                        required: f.required,
+                    }).collect()
+                });
+                
+                self.state.validate_product_edit(&product_id, owner, slug.as_deref(), category.as_deref()).await?;
+                self.state.update_product(&product_id, owner, public_data, price, private_data, encrypted, success_message, order_form_fields, entitlements).await?;
+                
                 let ts = self.runtime.system_time().micros();
+                if let Some(slug) = slug {
+                    let slug = if slug.is_empty() { None } else { Some(slug) };
+                    self.state.set_product_slug(&product_id, owner, slug, ts).await.or_fail("Failed to set product slug");
+                }
+                if let Some(category) = category {
+                    let category = if category.is_empty() { None } else { Some(category) };
+                    self.state.set_product_category(&product_id, owner, category).await.or_fail("Failed to set product category");
+                }
+                if clear_stock == Some(true) || stock.is_some() {
+                    let stock = if clear_stock == Some(true) { None } else { stock };
+                    self.state.set_product_stock(&product_id, owner, stock).await.or_fail("Failed to set product stock");
+                }
                 
-                // Get post to find author
-                if let Ok(Some(post)) = self.state.get_post(&post_id).await {
-                    let author = post.author;
-                    
-                    // Validate subscription
-                    if participant != author {
-                        let is_valid = self.check_subscription_valid(participant, author, ts).await;
-                        if !is_valid {
-                            return; // Ignore invalid participation
+                let product = self.state.get_product(&product_id).await
+                    .and_then(|product| product.ok_or(StateError::NotFound("Product")))
+                    .or_fail("Failed to get product");
+                self.emit_event(DonationsEvent::ProductUpdated { product: product.clone(), timestamp: ts });
+                
+                // Send to main chain
+                if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&owner).await {
+                    if let Ok(main_chain_id) = main_chain_id_str.parse() {
+                        let chain_id = self.runtime.chain_id();
+                        if main_chain_id != chain_id {
+                            self.send_tracked(Message::ProductUpdated { product }, main_chain_id).await;
                         }
                     }
-                    
-                    // Check giveaway exists and hasn't ended
-                    if let Some(giveaway) = &post.giveaway {
-                        if ts > giveaway.end_timestamp && giveaway.end_timestamp > 0 {
-                            return; // Giveaway has ended
-                        }
-                        if giveaway.is_resolved {
-                            return; // Already resolved
+                }
+                
+                ResponseData::Ok
+            }
+            Operation::UpdateProductChain { product_id, new_chain_id } => {
+                // Run on the chain holding the product; the main chain's mirror follows the update
+                let owner = self.signer()?;
+                let product = self.state.set_product_chain(&product_id, owner, new_chain_id.to_string()).await?;
+                // Orders now go to the new chain, so it gets the whole record, private data included
+                if new_chain_id != self.runtime.chain_id() {
+                    self.send_tracked(Message::ProductMigrated { product: product.clone() }, new_chain_id).await;
+                }
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::ProductUpdated { product: product.clone(), timestamp: ts });
+                
+                if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&owner).await {
+                    if let Ok(main_chain_id) = main_chain_id_str.parse::<ChainId>() {
+                        if main_chain_id != self.runtime.chain_id() {
+                            self.send_tracked(Message::ProductUpdated { product }, main_chain_id).await;
                         }
-                    } else {
-                        return; // No giveaway
                     }
-                    
-                    // Add participant
-                    let giveaway_participant = donations::GiveawayParticipant {
-                        owner: participant,
-                        chain_id: participant_chain_id.to_string(),
-                        joined_at: ts,
-                    };
-                    
-                    if let Ok(updated_giveaway) = self.state.add_giveaway_participant(&post_id, giveaway_participant).await {
-                        // Emit event
-                        self.runtime.emit("donations_events".into(), &DonationsEvent::GiveawayParticipated {
-                            post_id: post_id.clone(),
-                            participant,
-                            timestamp: ts,
-                        });
-                        
-                        // Broadcast updated giveaway to all active subscribers
-                        self.broadcast_giveaway_update(&post_id, &updated_giveaway, author).await;
+                }
+                
+                ResponseData::Ok
+            }
+            Operation::DeleteProduct { product_id } => {
+                let owner = self.signer()?;
+                self.state.delete_product(&product_id, owner).await?;
+                let _ = self.state.unfeature_product(owner, &product_id).await;
+                let _ = self.state.unorder_product(owner, &product_id).await;
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::ProductDeleted { product_id: product_id.clone(), author: owner, timestamp: ts });
+                
+                // Send to main chain
+                if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&owner).await {
+                    if let Ok(main_chain_id) = main_chain_id_str.parse() {
+                        let chain_id = self.runtime.chain_id();
+                        if main_chain_id != chain_id {
+                            self.send_tracked(Message::ProductDeleted { product_id, author: owner }, main_chain_id).await;
+                        }
                     }
                 }
+                
+                ResponseData::Ok
             }
-            Message::GiveawayUpdated { post_id, giveaway } => {
-                // Subscriber's chain receives updated giveaway
-                let _ = self.state.update_giveaway(&post_id, giveaway).await;
+            Operation::SaveOrderFormTemplate { name, fields } => {
+                let owner = self.signer()?;
+                let fields = fields.into_iter().map(|f| donations::OrderFormField {
+                    key: f.key,
+                    label: f.label,
+                    field_type: f.field_type,
+                    required: f.required,
+                }).collect();
+                self.state.save_order_form_template(owner, name, fields).await?;
+                ResponseData::Ok
             }
-        }
-    }
-
-
-
-    async fn store(mut self) { self.state.save().await.expect("save") }
-}
-
-impl DonationsContract {
-    fn normalize_account(&self, account: FungibleAccount) -> Account { Account { chain_id: account.chain_id, owner: account.owner } }
-    async fn process_streams(&mut self, streams: Vec<StreamUpdate>) {
-        let current_chain = self.runtime.chain_id();
-        for stream_update in streams {
-            if stream_update.chain_id == current_chain { continue; }
-            for index in stream_update.previous_index..stream_update.next_index {
-                let stream_name = stream_update.stream_id.stream_name.clone();
-                let event = self.runtime.read_event(stream_update.chain_id, stream_name, index);
-                match event {
-                    DonationsEvent::ProfileNameUpdated { owner, name, timestamp: _ } => {
-                        let _ = self.state.set_name(owner, name).await;
-                    }
-                    DonationsEvent::ProfileBioUpdated { owner, bio, timestamp: _ } => {
-                        let _ = self.state.set_bio(owner, bio).await;
-                    }
-                    DonationsEvent::ProfileSocialUpdated { owner, name, url, timestamp: _ } => {
-                        let _ = self.state.set_social(owner, name, url).await;
-                    }
-                    DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: _ } => {
-                        let _ = self.state.set_avatar(owner, hash).await;
-                    }
-                    DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: _ } => {
-                        let _ = self.state.set_header(owner, hash).await;
-                    }
-                    DonationsEvent::DonationSent { id: _, from, to, amount, message, source_chain_id, to_chain_id, timestamp } => {
-                        let _ = self.state.record_donation(from, to, amount, message, source_chain_id, to_chain_id, timestamp).await;
-                    }
-                    DonationsEvent::ProductCreated { product, timestamp: _ } => {
-                        let _ = self.state.create_product(product).await;
-                    }
-                    DonationsEvent::ProductUpdated { product, timestamp: _ } => {
-                        let product_id = product.id.clone();
-                        let author = product.author;
-                        let _ = self.state.delete_product(&product_id, author).await;
-                        let _ = self.state.create_product(product).await;
-                    }
-                    DonationsEvent::ProductPurchased { purchase_id, product_id, buyer, seller, amount, timestamp } => {
-                        if let Ok(Some(product)) = self.state.get_product(&product_id).await {
-                            let purchase = donations::Purchase {
-                                id: purchase_id,
-                                product_id,
-                                buyer,
-                                buyer_chain_id: current_chain.to_string(),
-                                seller,
-                                seller_chain_id: product.author_chain_id.clone(),
-                                amount,
-                                timestamp,
-                                order_data: std::collections::BTreeMap::new(), // Event doesn't contain order_data
-                                product,
-                            };
-                            let _ = self.state.record_purchase(purchase).await;
+            Operation::DeleteOrderFormTemplate { name } => {
+                let owner = self.signer()?;
+                self.state.delete_order_form_template(owner, &name).await?;
+                ResponseData::Ok
+            }
+            Operation::SetFeaturedProducts { product_ids } => {
+                let owner = self.signer()?;
+                self.state.validate_featured_products(owner, &product_ids).await?;
+                self.state.set_featured_products(owner, product_ids.clone()).await.or_fail("Failed to set featured products");
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::FeaturedProductsUpdated { author: owner, product_ids: product_ids.clone(), timestamp: ts });
+                
+                // Send to main chain
+                if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&owner).await {
+                    if let Ok(main_chain_id) = main_chain_id_str.parse() {
+                        let chain_id = self.runtime.chain_id();
+                        if main_chain_id != chain_id {
+                            self.send_tracked(Message::FeaturedProductsUpdated { author: owner, product_ids }, main_chain_id).await;
                         }
                     }
-                    DonationsEvent::OrderPlaced { purchase_id: _, product_id: _, buyer: _, seller: _, amount: _, timestamp: _ } => {
-                        // Order placed events are handled on seller's chain
-                        // We can add order storage logic here if needed
-                    }
-                    DonationsEvent::ProductDeleted { product_id, author, timestamp: _ } => {
-                        let _ = self.state.delete_product(&product_id, author).await;
-                    }
-                    // Content subscription events
-                    DonationsEvent::SubscriptionPriceSet { author, price, description, timestamp: _ } => {
-                        let _ = self.state.set_subscription_price(author, price, description).await;
-                    }
-                    DonationsEvent::SubscriptionPriceDeleted { author, timestamp: _ } => {
-                        let _ = self.state.delete_subscription_info(author).await;
-                    }
-                    DonationsEvent::UserSubscribed { subscription_id: _, subscriber: _, author: _, price: _, end_timestamp: _, timestamp: _ } => {
-                        // Subscription is already created on the chain where payment was made
-                    }
-                    DonationsEvent::UserUnsubscribed { subscription_id, subscriber, author, timestamp: _ } => {
-                        let _ = self.state.remove_subscription(&subscription_id, author, subscriber).await;
-                    }
-                    DonationsEvent::PostCreated { post, timestamp: _ } => {
-                        let _ = self.state.create_post(post).await;
-                    }
-                    DonationsEvent::PostUpdated { post, timestamp: _ } => {
-                        let _ = self.state.update_post(&post.id, Some(post.title), Some(post.content), post.image_hash).await;
-                    }
-                    DonationsEvent::PostDeleted { post_id, author, timestamp: _ } => {
-                        let _ = self.state.delete_post(&post_id, author).await;
-                    }
-                    DonationsEvent::VoteCasted { post_id: _, voter: _, option_index: _, timestamp: _ } => {
-                        // Vote events are handled through PollResultsUpdated
-                    }
-                    DonationsEvent::PollResultsUpdated { post_id, poll, timestamp: _ } => {
-                        let _ = self.state.update_poll_results(&post_id, poll).await;
-                    }
-                    DonationsEvent::GiveawayParticipated { post_id: _, participant: _, timestamp: _ } => {
-                        // Giveaway participation events are handled through GiveawayUpdated message
-                    }
-                    DonationsEvent::GiveawayResolved { post_id: _, winner: _, winner_chain_id: _, prize_amount: _, timestamp: _ } => {
-                        // Giveaway resolved events are handled through GiveawayUpdated message
-                    }
                 }
-
+                
+                ResponseData::Ok
             }
-        }
-    }
-    
-    /// Check if a subscriber has a valid (non-expired) subscription to an author
-    async fn check_subscription_valid(&self, subscriber: AccountOwner, author: AccountOwner, current_time: u64) -> bool {
-        // Author is always valid for their own content
-        if subscriber == author {
-            return true;
-        }
-        
-        let sub_ids = self.state.subscriptions_by_author.get(&author).await
-            .ok()
-            .flatten()
-            .unwrap_or_default();
-        
-        for sub_id in sub_ids {
-            if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
-                if sub.subscriber == subscriber && sub.end_timestamp >= current_time {
-                    return true;
+            Operation::ReorderProducts { ordered_ids } => {
+                let owner = self.signer()?;
+                self.state.validate_product_order(owner, &ordered_ids).await?;
+                self.state.set_product_order(owner, ordered_ids.clone()).await.or_fail("Failed to set product order");
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::ProductOrderUpdated { author: owner, ordered_ids: ordered_ids.clone(), timestamp: ts });
+                
+                // Send to main chain
+                if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&owner).await {
+                    if let Ok(main_chain_id) = main_chain_id_str.parse() {
+                        let chain_id = self.runtime.chain_id();
+                        if main_chain_id != chain_id {
+                            self.send_tracked(Message::ProductOrderUpdated { author: owner, ordered_ids }, main_chain_id).await;
+                        }
+                    }
                 }
+                
+                ResponseData::Ok
             }
-        }
-        false
-    }
-    
+            Operation::TransferToBuy { owner, product_id, amount, target_account, order_data } => {
+                self.runtime.check_account_permission(owner).map_err(|_| OperationError::unauthorized("Permission denied"))?;
+                let target_account_norm = self.normalize_account(target_account);
+                self.check_transfer(owner, target_account_norm, amount)?;
+                
+                // Reject bad order data and sold-out products before paying when the product is known here;
+                // the seller's chain checks both again and reports orders that got through
+                let mut stocked = false;
+                if let Ok(Some(product)) = self.state.get_product(&product_id).await {
+                    DonationsState::validate_order_data(&product.order_form, &order_data)?;
+                    if product.stock == Some(0) {
+                        return Err(OperationError::new("sold_out", "Product is sold out"));
+                    }
+                    stocked = product.stock.is_some();
+                }
+                
+                // Transfer full amount to author
+                self.pay(owner, target_account_norm, amount).await?;
+                
+                // Take the copy before any purchase message leaves this chain
+                if stocked {
+                    let product = self.state.decrement_stock(&product_id).await.or_fail("Failed to update stock");
+                    if product.author_chain_id == self.runtime.chain_id().to_string() {
+                        self.announce_product_updated(product).await;
+                    }
+                }
+                
+                // Generate purchase ID
+                let ts = self.runtime.system_time().micros();
+                let purchase_id = format!("purchase-{}-{}", ts, self.runtime.chain_id());
+                let buyer_chain_id = self.runtime.chain_id();
+                let seller = target_account_norm.owner;
+                
+                // Emit event
+                self.emit_event(DonationsEvent::ProductPurchased {
+                    purchase_id: purchase_id.clone(),
+                    product_id: product_id.clone(),
+                    buyer: owner,
+                    seller,
+                    amount,
+                    timestamp: ts,
+                });
+                
+                // Buyer, seller and main chain all being this one, the purchase is recorded once, answers
+                // included, and delivered; anything the main chain would refuse is reported as usual
+                let seller_chain_id = target_account_norm.chain_id;
+                let main_chain_id = self.state.subscriptions.get(&owner).await.ok().flatten().and_then(|id| id.parse::<ChainId>().ok());
+                if seller_chain_id == buyer_chain_id && main_chain_id == Some(buyer_chain_id) {
+                    match self.state.get_product(&product_id).await.or_fail("Failed to get product") {
+                        Some(product) if product.price == amount && product.author == seller => {
+                            self.record_local_purchase(purchase_id, owner, amount, order_data, product, ts).await;
+                        }
+                        _ => self.handle_product_purchased(purchase_id, product_id, owner, buyer_chain_id, seller, amount, false).await,
+                    }
+                    return Ok(ResponseData::Ok);
+                }
+                
+                // Send purchase message to main chain, or handle it inline when that is this chain
+                if let Some(main_chain_id) = main_chain_id {
+                    if main_chain_id == buyer_chain_id {
+                        self.handle_product_purchased(purchase_id.clone(), product_id.clone(), owner, buyer_chain_id, seller, amount, false).await;
+                    } else {
+                        self.send_tracked(Message::ProductPurchased {
+                            purchase_id: purchase_id.clone(),
+                            product_id: product_id.clone(),
+                            buyer: owner,
+                            buyer_chain_id,
+                            seller,
+                            amount,
+                        }, main_chain_id).await;
+                    }
+                }
+                
+                // NEW: Send order notification directly to seller's chain
+                // NEW: Send order notification directly to seller's chain
+                // We trust the target_account chain_id as it comes from the product metadata
+                // and we already transferred funds there.
+                if seller_chain_id != buyer_chain_id {
+                    self.runtime.prepare_message(Message::OrderReceived {
+                        purchase_id: purchase_id.clone(),
+                        product_id: product_id.clone(),
+                        buyer: owner,
+                        buyer_chain_id,
+                        amount,
+                        order_data: order_data.clone(),
+                        timestamp: ts,
+                    }).with_authentication().send_to(seller_chain_id);
+                } else {
+                    // Same chain: Record purchase immediately if product exists locally
+                    // This covers local purchases and self-purchases
+                    if let Ok(Some(product)) = self.state.get_product(&product_id).await {
+                         let purchase = donations::Purchase {
+                            id: purchase_id.clone(),
+                            product_id: product_id.clone(),
+                            buyer: owner,
+                            buyer_chain_id: buyer_chain_id.to_string(),
+                            seller,
+                            seller_chain_id: product.author_chain_id.clone(),
+                            // ...
+                            amount,
+                            timestamp: ts,
+                            order_data: order_data.clone(),
+                            content_modified: false,
+                            payment_app: self.payment_app_label(),
+                            status: OrderStatus::Pending,
+                            product: product.clone(),
+                        };
+                        let grants = DonationsState::purchase_grants(&product, &purchase_id, ts);
+                        self.state.record_purchase_with_grants(purchase, grants).await.or_fail("Failed to record purchase");
+                    }
+                    if owner != seller {
+                        self.accrue_income_redirect(seller, amount).await;
+                    }
+                }
+                
+                ResponseData::Ok
+            }
+            Operation::AddOrderNote { purchase_id, text } => {
+                let from = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let note = donations::OrderNote { from, text: text.trim().to_string(), timestamp: ts };
+                let purchase = self.state.add_order_note(&purchase_id, note.clone()).await?;
+                
+                // Deliver to the other party's chain
+                let other_chain_id = if from == purchase.buyer { &purchase.seller_chain_id } else { &purchase.buyer_chain_id };
+                if let Ok(other_chain_id) = other_chain_id.parse::<ChainId>() {
+                    if other_chain_id != self.runtime.chain_id() {
+                        self.runtime.prepare_message(Message::OrderNoteAdded { purchase_id, note }).with_authentication().send_to(other_chain_id);
+                    }
+                }
+                ResponseData::Ok
+            }
+            Operation::ReviewProduct { product_id, rating, comment } => {
+                let reviewer = self.signer()?;
+                let purchase = self.state.purchase_of(reviewer, &product_id).await?
+                    .ok_or(OperationError::unauthorized("Only buyers of the product can review it"))?;
+                let ts = self.runtime.system_time().micros();
+                let review = self.state.put_review(donations::Review { product_id, reviewer, rating, comment, created_at: ts, updated_at: ts }).await?;
+                self.emit_event(DonationsEvent::ProductReviewed { review: review.clone(), timestamp: ts });
+                
+                // Send to the chain the product lives on; the buyer's chain may only have the purchase snapshot
+                let product = match self.state.get_product(&review.product_id).await {
+                    Ok(Some(product)) => product,
+                    _ => purchase.product,
+                };
+                if let Ok(author_chain_id) = product.author_chain_id.parse::<ChainId>() {
+                    if author_chain_id != self.runtime.chain_id() {
+                        self.send_tracked(Message::ProductReviewed { review }, author_chain_id).await;
+                    }
+                }
+                ResponseData::Ok
+            }
+            Operation::RevokePurchaseEntitlements { purchase_id } => {
+                let seller = self.signer()?;
+                let purchase = self.state.get_purchase(&purchase_id).await?.ok_or(StateError::NotFound("Purchase"))?;
+                if purchase.seller != seller {
+                    return Err(OperationError::unauthorized("Not the seller"));
+                }
+                self.state.revoke_purchase_entitlements(purchase.buyer, &purchase_id).await?;
+                
+                match purchase.buyer_chain_id.parse::<ChainId>() {
+                    Ok(buyer_chain_id) if buyer_chain_id != self.runtime.chain_id() => {
+                        self.runtime.prepare_message(Message::EntitlementsRevoked { buyer: purchase.buyer, purchase_id }).with_authentication().send_to(buyer_chain_id);
+                    }
+                    _ => {}
+                }
+                
+                ResponseData::Ok
+            }
+            Operation::AcceptAllPendingOrders => {
+                let seller = self.signer()?;
+                let accepted = self.state.accept_pending_orders(seller).await?;
+                let ts = self.runtime.system_time().micros();
+                for purchase in accepted {
+                    self.emit_event(DonationsEvent::OrderStatusChanged {
+                        purchase_id: purchase.id.clone(),
+                        buyer: purchase.buyer,
+                        seller,
+                        status: OrderStatus::Accepted,
+                        timestamp: ts,
+                    });
+                    match purchase.buyer_chain_id.parse::<ChainId>() {
+                        Ok(buyer_chain_id) if buyer_chain_id != self.runtime.chain_id() => {
+                            self.runtime.prepare_message(Message::OrderStatusChanged { purchase_id: purchase.id, status: OrderStatus::Accepted }).with_authentication().send_to(buyer_chain_id);
+                        }
+                        _ => {}
+                    }
+                }
+                ResponseData::Ok
+            }
+            Operation::UpdateOrderStatus { purchase_id, status } => {
+                let seller = self.signer()?;
+                let purchase = self.state.check_order_status_change(seller, &purchase_id, status).await?;
+                // Cancelling pays the buyer back the same way RefundPurchase does
+                if status == OrderStatus::Cancelled {
+                    self.pay_back_purchase(&purchase).await?;
+                }
+                let purchase = self.state.update_order_status(seller, &purchase_id, status).await.or_fail("Failed to update order status");
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::OrderStatusChanged {
+                    purchase_id: purchase.id.clone(),
+                    buyer: purchase.buyer,
+                    seller,
+                    status,
+                    timestamp: ts,
+                });
+                match purchase.buyer_chain_id.parse::<ChainId>() {
+                    Ok(buyer_chain_id) if buyer_chain_id != self.runtime.chain_id() => {
+                        self.runtime.prepare_message(Message::OrderStatusChanged { purchase_id: purchase.id, status }).with_authentication().send_to(buyer_chain_id);
+                    }
+                    _ => {}
+                }
+                ResponseData::Ok
+            }
+            Operation::RefundPurchase { purchase_id } => {
+                let seller = self.signer()?;
+                let purchase = self.state.get_purchase(&purchase_id).await?.ok_or(StateError::NotFound("Purchase"))?;
+                if purchase.seller != seller {
+                    return Err(OperationError::unauthorized("Not the seller"));
+                }
+                // A cancelled order was already paid back
+                if matches!(purchase.status, OrderStatus::Refunded | OrderStatus::Cancelled) {
+                    return Ok(ResponseData::Ok);
+                }
+                let buyer_chain_id = self.pay_back_purchase(&purchase).await?;
+                self.state.set_order_status(&purchase_id, OrderStatus::Refunded).await.or_fail("Failed to mark purchase refunded");
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::PurchaseRefunded {
+                    purchase_id: purchase_id.clone(),
+                    product_id: purchase.product_id,
+                    buyer: purchase.buyer,
+                    seller,
+                    amount: purchase.amount,
+                    timestamp: ts,
+                });
+                if buyer_chain_id != self.runtime.chain_id() {
+                    self.runtime.prepare_message(Message::OrderStatusChanged { purchase_id, status: OrderStatus::Refunded }).with_authentication().send_to(buyer_chain_id);
+                }
+                ResponseData::Ok
+            }
+            Operation::ReadDataBlob { hash } => {
+                use linera_sdk::linera_base_types::{CryptoHash, DataBlobHash};
+                use std::str::FromStr;
+                
+                match CryptoHash::from_str(&hash) {
+                    Ok(crypto_hash) => {
+                        let blob_hash = DataBlobHash(crypto_hash);
+                        let data = self.runtime.read_data_blob(blob_hash);
+                        eprintln!("[READ_BLOB] Read {} bytes from blob {}", data.len(), hash);
+                    }
+                    Err(e) => {
+                        eprintln!("[READ_BLOB] ERROR: Invalid blob hash format '{}': {:?}", hash, e);
+                    }
+                }
+                ResponseData::Ok
+            }
+            
+            // Content subscription operations
+            Operation::SetSubscriptionPrice { price, description, grandfather_renewals } => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                self.apply_pending_subscription_price(owner).await;
+                
+                // Increases wait out a notice period; new prices and decreases apply right away
+                let current = self.state.get_subscription_price(owner).await.or_fail("Failed to get subscription price");
+                if let Some(current) = current.filter(|c| price > c.price) {
+                    let pending = donations::PendingPrice { price, description, grandfather_renewals, effective_at: ts + donations::PRICE_INCREASE_NOTICE_MICROS };
+                    self.state.schedule_subscription_price(owner, pending.clone()).await.or_fail("Failed to schedule subscription price");
+                    self.emit_event(DonationsEvent::SubscriptionPriceChangeScheduled { author: owner, pending: pending.clone(), timestamp: ts });
+                    self.notify_price_change(owner, current.price, &pending).await;
+                    return Ok(ResponseData::Ok);
+                }
+                
+                self.state.set_subscription_price(owner, price, description.clone(), grandfather_renewals).await.or_fail("Failed to set subscription price");
+                self.emit_event(DonationsEvent::SubscriptionPriceSet { 
+                    author: owner, 
+                    price,
+                    description,
+                    grandfather_renewals,
+                    timestamp: ts 
+                });
+                
+                ResponseData::Ok
+            }
+            
+            Operation::SetSubscriptionEntitlements { entitlements } => {
+                let owner = self.signer()?;
+                self.state.set_subscription_entitlements(owner, entitlements.clone()).await?;
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::SubscriptionEntitlementsSet { author: owner, entitlements, timestamp: ts });
+                
+                ResponseData::Ok
+            }
+            
+            Operation::SetSubscriptionGracePeriod { grace_period_micros } => {
+                let owner = self.signer()?;
+                self.state.set_subscription_grace_period(owner, grace_period_micros).await?;
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::SubscriptionGracePeriodSet { author: owner, grace_period_micros, timestamp: ts });
+                
+                ResponseData::Ok
+            }
+            
+            Operation::DeleteSubscriptionPrice => {
+                let owner = self.signer()?;
+                self.state.delete_subscription_info(owner).await?;
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::SubscriptionPriceDeleted {
+                    author: owner,
+                    timestamp: ts,
+                });
+                
+                ResponseData::Ok
+            }
+            
+            Operation::SubscribeToAuthor { owner, amount, target_account, auto_renew } => {
+                let subscriber = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                // Transfer payment to author
+                let target_account_norm = self.normalize_account(target_account);
+                let author = target_account_norm.owner;
+                let author_chain_id = target_account_norm.chain_id;
+                
+                let end_timestamp = ts + donations::SUBSCRIPTION_PERIOD_MICROS;
+                let subscriber_chain_id = self.runtime.chain_id();
+                let sub_id = format!("sub-{}-{}-{}", subscriber, author, ts);
+                
+                // Only a due price change may have been applied before a refusal below;
+                // that would have happened on the next operation anyway
+                // On the author's chain the terms are known right away; otherwise the
+                // author's chain confirms them with a SubscriptionConfirmed message,
+                // or rejects the payment with SubscriptionRejected
+                let (locked_price, renewals_remaining_at_locked_price) = if author_chain_id == subscriber_chain_id {
+                    self.apply_pending_subscription_price(author).await;
+                    self.state.subscription_terms(author, subscriber, amount, ts).await?
+                } else {
+                    // Refuse underpayments this chain can already tell apart, before paying
+                    if let Some(price) = self.known_renewal_price(author, subscriber, ts).await {
+                        if amount < price {
+                            return Err(OperationError::new("insufficient_payment", "Insufficient subscription payment"));
+                        }
+                    }
+                    (amount, 0)
+                };
+                self.pay(owner, target_account_norm, amount).await?;
+                
+                // Create local subscription (for mySubscriptions query)
+                let subscription = donations::ContentSubscription {
+                    id: sub_id.clone(),
+                    subscriber,
+                    subscriber_chain_id: subscriber_chain_id.to_string(),
+                    author,
+                    author_chain_id: author_chain_id.to_string(),
+                    start_timestamp: ts,
+                    end_timestamp,
+                    price: amount,
+                    locked_price,
+                    renewals_remaining_at_locked_price,
+                    auto_renew: auto_renew.unwrap_or(false),
+                    auto_renew_failures: 0,
+                };
+                
+                self.state.create_subscription(subscription.clone()).await.or_fail("Failed to create subscription");
+                
+                if author_chain_id == subscriber_chain_id && owner != author {
+                    self.accrue_income_redirect(author, amount).await;
+                }
+                
+                // Notify author's chain about subscription payment
+                if author_chain_id != subscriber_chain_id {
+                    self.runtime.prepare_message(Message::SubscriptionPayment {
+                        subscriber,
+                        subscriber_chain_id: subscriber_chain_id.to_string(),
+                        author,
+                        amount,
+                        duration_micros: donations::SUBSCRIPTION_PERIOD_MICROS,
+                        timestamp: ts,
+                    }).with_authentication().send_to(author_chain_id);
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::SetAutoRenew { subscription_id, auto_renew } => {
+                let caller = self.signer()?;
+                self.state.set_auto_renew(&subscription_id, caller, auto_renew).await?;
+                ResponseData::Ok
+            }
+            
+            Operation::ProcessAutoRenewals => {
+                let subscriber = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let due = self.state.subscriptions_due_for_renewal(subscriber, ts, donations::AUTO_RENEW_WINDOW_MICROS).await?;
+                for sub in due {
+                    self.renew_subscription(sub, ts).await;
+                }
+                ResponseData::Ok
+            }
+            
+            Operation::Unsubscribe { subscription_id } => {
+                let caller = self.signer()?;
+                let sub = self.state.content_subscriptions.get(&subscription_id).await
+                    .map_err(StateError::from)?
+                    .ok_or(StateError::NotFound("Subscription"))?;
+                if sub.subscriber != caller {
+                    return Err(OperationError::unauthorized("Not the subscriber"));
+                }
+                self.state.remove_subscription(&subscription_id, sub.author, caller).await?;
+                
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::UserUnsubscribed {
+                    subscription_id: subscription_id.clone(),
+                    subscriber: caller,
+                    author: sub.author,
+                    timestamp: ts,
+                });
+                
+                // Stop delivery from the author's chain as well
+                if let Ok(author_chain_id) = sub.author_chain_id.parse::<ChainId>() {
+                    if author_chain_id != self.runtime.chain_id() {
+                        self.runtime.prepare_message(Message::Unsubscribed {
+                            subscription_id,
+                            subscriber: caller,
+                        }).with_authentication().send_to(author_chain_id);
+                    }
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::Announce { text } => {
+                let author = self.signer()?;
+                let text = text.trim().to_string();
+                if text.is_empty() || text.chars().count() > donations::MAX_ANNOUNCEMENT_CHARS {
+                    return Err(OperationError::invalid(format!("Announcement must be 1-{} characters", donations::MAX_ANNOUNCEMENT_CHARS)));
+                }
+                self.announce(author, text).await;
+                ResponseData::Ok
+            }
+            
+            Operation::CreatePost { title, content, image_hash, poll_options, poll_end_timestamp, poll_broadcast_every_votes, poll_broadcast_interval_micros, giveaway_prize, giveaway_end_timestamp, giveaway_max_participants, giveaway_participants_visible, visibility } => {
+                let author = self.signer()?;
+                if !self.state.is_registered(author).await {
+                    return Err(OperationError::invalid("Author must register before posting"));
+                }
+                self.check_text_length("Title", &title)?;
+                if giveaway_max_participants == Some(0) {
+                    return Err(OperationError::invalid("Giveaway must allow at least one participant"));
+                }
+                if let Some(prize_amount) = giveaway_prize {
+                    self.ensure_available(author, prize_amount).await?;
+                }
+                let ts = self.runtime.system_time().micros();
+                let author_chain_id = self.runtime.chain_id();
+                let post_id = self.state.next_post_id(ts, &author_chain_id.to_string());
+                
+                // Create poll if options provided
+                let poll = if !poll_options.is_empty() {
+                    Some(donations::Poll {
+                        options: poll_options.into_iter().map(|text| donations::PollOption {
+                            text,
+                            votes_count: 0,
+                        }).collect(),
+                        end_timestamp: poll_end_timestamp.unwrap_or(0),
+                        voters: std::collections::BTreeMap::new(),
+                        revision: 0,
+                        votes_since_broadcast: 0,
+                        last_broadcast_at: ts,
+                        broadcast_every_votes: poll_broadcast_every_votes.unwrap_or(donations::DEFAULT_POLL_BROADCAST_VOTES).max(1),
+                        broadcast_interval_micros: poll_broadcast_interval_micros.unwrap_or(donations::DEFAULT_POLL_BROADCAST_INTERVAL_MICROS),
+                    })
+                } else {
+                    None
+                };
+                
+                // Lock the giveaway prize until it is resolved
+                if let Some(prize_amount) = giveaway_prize {
+                    self.state.add_commitment(author, donations::Commitment { kind: "giveaway".to_string(), reference_id: post_id.clone(), amount: prize_amount }).await.or_fail("Failed to lock giveaway prize");
+                }
+                
+                // Create giveaway if prize provided
+                let giveaway = giveaway_prize.map(|prize_amount| donations::Giveaway {
+                    prize_amount,
+                    end_timestamp: giveaway_end_timestamp.unwrap_or(0),
+                    participants: Vec::new(),
+                    winner: None,
+                    is_resolved: false,
+                    max_participants: giveaway_max_participants,
+                    participants_visible: giveaway_participants_visible.unwrap_or(false),
+                });
+                
+                let post = donations::Post {
+                    id: post_id.clone(),
+                    author,
+                    author_chain_id: author_chain_id.to_string(),
+                    title,
+                    content,
+                    image_hash,
+                    created_at: ts,
+                    poll,
+                    giveaway,
+                    visibility: visibility.unwrap_or_default(),
+                };
+                
+                // Save post
+                self.state.create_post(post.clone()).await.or_fail("Failed to create post");
+                
+                // Emit event
+                self.emit_event(DonationsEvent::PostCreated { 
+                    post: post.clone(), 
+                    timestamp: ts 
+                });
+                
+                // Get active subscriptions and clean up expired ones
+                let all_subs = self.state.subscriptions_by_author.get(&author).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+                let grace = self.state.subscription_grace_micros(author).await;
+                
+                for sub_id in all_subs {
+                    if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
+                        if !sub.allows(SubscriptionAccess::Read, grace, ts) {
+                            // Expired past the grace period - unsubscribe
+                            let _ = self.state.remove_subscription(&sub_id, author, sub.subscriber).await;
+                            
+                            self.emit_event(DonationsEvent::UserUnsubscribed {
+                                subscription_id: sub_id,
+                                subscriber: sub.subscriber,
+                                author,
+                                timestamp: ts,
+                            });
+                        } else {
+                            // Subscription active - send post to subscriber's chain
+                            if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
+                                if subscriber_chain_id != author_chain_id {
+                                    self.send_tracked(Message::PostPublished {
+                                        post: post.clone(),
+                                    }, subscriber_chain_id).await;
+                                }
+                            }
+                        }
+                    }
+                }
+                
+                ResponseData::Ok
+            }
+
+
+            
+            Operation::UpdatePost { post_id, title, content, image_hash, clear_image } => {
+                let author = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                // Verify ownership
+                let existing = self.state.get_post(&post_id).await?.ok_or(StateError::NotFound("Post"))?;
+                if existing.author != author {
+                    return Err(OperationError::unauthorized("Not post author"));
+                }
+                if let Some(title) = &title {
+                    self.check_text_length("Title", title)?;
+                }
+                
+                // Update post
+                self.state.update_post(&post_id, title, content, image_hash, clear_image.unwrap_or(false)).await?;
+                
+                // Get updated post
+                let post = self.state.get_post(&post_id).await
+                    .or_fail("Failed to get post")
+                    .expect("Post not found");
+                
+                // Emit event
+                self.emit_event(DonationsEvent::PostUpdated {
+                    post: post.clone(),
+                    timestamp: ts,
+                });
+                
+                // Send update to active subscribers
+                let all_subs = self.state.subscriptions_by_author.get(&author).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+                let grace = self.state.subscription_grace_micros(author).await;
+                
+                let author_chain_id = self.runtime.chain_id();
+                for sub_id in all_subs {
+                    if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
+                        if sub.allows(SubscriptionAccess::Read, grace, ts) {
+                            // Active subscription - send update
+                            if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
+                                if subscriber_chain_id != author_chain_id {
+                                    self.send_tracked(Message::PostUpdated {
+                                        post: post.clone(),
+                                    }, subscriber_chain_id).await;
+                                }
+                            }
+                        }
+                    }
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::DeletePost { post_id } => {
+                let author = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                // Delete post (will verify ownership inside)
+                self.state.delete_post(&post_id, author).await?;
+                self.state.release_commitment(author, "giveaway", &post_id).await.or_fail("Failed to release giveaway prize");
+                
+                // Emit event
+                self.emit_event(DonationsEvent::PostDeleted {
+                    post_id: post_id.clone(),
+                    author,
+                    timestamp: ts,
+                });
+                
+                // Send deletion to active subscribers
+                let all_subs = self.state.subscriptions_by_author.get(&author).await
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default();
+                let grace = self.state.subscription_grace_micros(author).await;
+                
+                let author_chain_id = self.runtime.chain_id();
+                for sub_id in all_subs {
+                    if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
+                        if sub.allows(SubscriptionAccess::Read, grace, ts) {
+                            // Active subscription - send deletion
+                            if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
+                                if subscriber_chain_id != author_chain_id {
+                                    self.send_tracked(Message::PostDeleted {
+                                        post_id: post_id.clone(),
+                                        author,
+                                    }, subscriber_chain_id).await;
+                                }
+                            }
+                        }
+                    }
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::CastVote { author_chain_id, author, post_id, option_index } => {
+                let voter = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let voter_chain_id = self.runtime.chain_id();
+                
+                // If we're on the author's chain - vote directly
+                if author_chain_id == voter_chain_id {
+                    // Validate subscription (author can always vote on their own posts)
+                    if voter != author {
+                        let is_valid = self.check_subscription_valid(voter, author, ts).await;
+                        if !is_valid {
+                            return Err(OperationError::unauthorized("Invalid or expired subscription"));
+                        }
+                    }
+                    
+                    // Check poll exists and hasn't ended
+                    if let Ok(Some(post)) = self.state.get_post(&post_id).await {
+                        if let Some(poll) = &post.poll {
+                            if ts > poll.end_timestamp && poll.end_timestamp > 0 {
+                                return Err(OperationError::invalid("Poll has ended"));
+                            }
+                        } else {
+                            return Err(OperationError::invalid("Post has no poll"));
+                        }
+                    } else {
+                        return Err(StateError::NotFound("Post").into());
+                    }
+                    
+                    // Cast vote
+                    let voter_id = voter.to_string();
+                    let updated_poll = self.state.cast_vote(&post_id, voter_id, option_index).await?;
+                    
+                    // Emit event
+                    self.emit_event(DonationsEvent::VoteCasted {
+                        post_id: post_id.clone(),
+                        voter,
+                        option_index,
+                        timestamp: ts,
+                    });
+                    
+                    // Broadcast updated poll results to all active subscribers
+                    self.broadcast_poll_update(&post_id, &updated_poll, author, false).await;
+                } else {
+                    // Send vote message to author's chain
+                    self.runtime.prepare_message(Message::VoteCasted {
+                        post_id,
+                        voter,
+                        voter_chain_id,
+                        option_index,
+                    }).with_authentication().send_to(author_chain_id);
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::ParticipateInGiveaway { author_chain_id, author, post_id } => {
+                let participant = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let participant_chain_id = self.runtime.chain_id();
+                
+                // If we're on the author's chain - participate directly
+                if author_chain_id == participant_chain_id {
+                    // Validate subscription (author can participate in their own giveaway for testing)
+                    if participant != author {
+                        let is_valid = self.check_subscription_valid(participant, author, ts).await;
+                        if !is_valid {
+                            return Err(OperationError::unauthorized("Invalid or expired subscription"));
+                        }
+                    }
+                    
+                    // Check giveaway exists and hasn't ended
+                    if let Ok(Some(post)) = self.state.get_post(&post_id).await {
+                        if let Some(giveaway) = &post.giveaway {
+                            if ts > giveaway.end_timestamp && giveaway.end_timestamp > 0 {
+                                return Err(OperationError::invalid("Giveaway has ended"));
+                            }
+                            if giveaway.is_resolved {
+                                return Err(OperationError::invalid("Giveaway already resolved"));
+                            }
+                        } else {
+                            return Err(OperationError::invalid("Post has no giveaway"));
+                        }
+                    } else {
+                        return Err(StateError::NotFound("Post").into());
+                    }
+                    
+                    // Add participant
+                    let giveaway_participant = donations::GiveawayParticipant {
+                        owner: participant,
+                        chain_id: participant_chain_id.to_string(),
+                        joined_at: ts,
+                    };
+                    
+                    let updated_giveaway = self.state.add_giveaway_participant(&post_id, giveaway_participant).await?;
+                    
+                    // Emit event
+                    self.emit_event(DonationsEvent::GiveawayParticipated {
+                        post_id: post_id.clone(),
+                        participant,
+                        timestamp: ts,
+                    });
+                    
+                    // Broadcast updated giveaway to all active subscribers
+                    self.broadcast_giveaway_update(&post_id, &updated_giveaway, author).await;
+                } else {
+                    // Send participation message to author's chain
+                    self.runtime.prepare_message(Message::GiveawayParticipation {
+                        post_id,
+                        participant,
+                        participant_chain_id,
+                    }).with_authentication().send_to(author_chain_id);
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::ResolveGiveaway { post_id } => {
+                let author = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                // Get post and verify ownership
+                let post = self.state.get_post(&post_id).await?.ok_or(StateError::NotFound("Post"))?;
+                
+                if post.author != author {
+                    return Err(OperationError::unauthorized("Not post author"));
+                }
+                
+                let giveaway = post.giveaway.as_ref().ok_or_else(|| OperationError::invalid("Post has no giveaway"))?;
+                
+                if giveaway.is_resolved {
+                    return Err(OperationError::invalid("Giveaway already resolved"));
+                }
+                
+                if giveaway.participants.is_empty() {
+                    return Err(OperationError::invalid("No participants to pick winner from"));
+                }
+                
+                // Pick winner using pseudo-random selection
+                let participants_count = giveaway.participants.len();
+                let winner_index = (ts as usize + post_id.len() + participants_count) % participants_count;
+                
+                // Resolve and get winner
+                let winner = self.state.resolve_giveaway(&post_id, winner_index).await
+                    .or_fail("Failed to resolve giveaway");
+                
+                // Transfer prize to winner
+                let winner_chain_id: linera_sdk::linera_base_types::ChainId = winner.chain_id.parse()
+                    .expect("Invalid winner chain ID");
+                let winner_account = Account {
+                    chain_id: winner_chain_id,
+                    owner: winner.owner,
+                };
+                self.state.release_commitment(author, "giveaway", &post_id).await.or_fail("Failed to release giveaway prize");
+                self.pay(author, winner_account, giveaway.prize_amount).await.or_fail("Failed to pay giveaway prize");
+                
+                // Emit event
+                self.emit_event(DonationsEvent::GiveawayResolved {
+                    post_id: post_id.clone(),
+                    winner: winner.owner,
+                    winner_chain_id: winner.chain_id.clone(),
+                    prize_amount: giveaway.prize_amount,
+                    timestamp: ts,
+                });
+                
+                // Broadcast resolved giveaway to all active subscribers
+                if let Ok(Some(updated_post)) = self.state.get_post(&post_id).await {
+                    if let Some(updated_giveaway) = &updated_post.giveaway {
+                        self.broadcast_giveaway_update(&post_id, updated_giveaway, author).await;
+                    }
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::ClosePoll { post_id } => {
+                let author = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                let poll = self.state.close_poll(&post_id, author, ts).await?;
+                
+                // The final results always go out
+                self.broadcast_poll_update(&post_id, &poll, author, true).await;
+                
+                ResponseData::Ok
+            }
+            
+            Operation::AddComment { author_chain_id, author, post_id, parent_id, text } => {
+                let commenter = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let commenter_chain_id = self.runtime.chain_id();
+                
+                // If we're on the author's chain - comment directly
+                if author_chain_id == commenter_chain_id {
+                    if !self.check_subscription_valid(commenter, author, ts).await {
+                        return Err(OperationError::unauthorized("Invalid or expired subscription"));
+                    }
+                    let comment = self.state.new_comment(&post_id, commenter, commenter_chain_id.to_string(), parent_id, text, false, ts).await?;
+                    if comment.content_modified {
+                        self.state.record_content_adjustment("comment", ts).await.or_fail("Failed to record content adjustment");
+                    }
+                    self.emit_event(DonationsEvent::CommentAdded { comment: comment.clone(), timestamp: ts });
+                    self.broadcast_comment(comment, author, true).await;
+                } else {
+                    // Send comment to author's chain
+                    self.runtime.prepare_message(Message::CommentPosted {
+                        post_id,
+                        parent_id,
+                        commenter,
+                        commenter_chain_id,
+                        text,
+                    }).with_authentication().send_to(author_chain_id);
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::DeleteComment { author_chain_id, comment_id } => {
+                let requester = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                if author_chain_id == self.runtime.chain_id() {
+                    let comment = self.state.remove_comment(&comment_id, requester, false).await?;
+                    let post = self.state.get_post(&comment.post_id).await
+                        .or_fail("Failed to get post")
+                        .expect("Post not found");
+                    self.emit_event(DonationsEvent::CommentRemoved { comment: comment.clone(), timestamp: ts });
+                    self.broadcast_comment(comment, post.author, false).await;
+                } else {
+                    self.runtime.prepare_message(Message::CommentDeleteRequested {
+                        comment_id,
+                        requester,
+                    }).with_authentication().send_to(author_chain_id);
+                }
+                
+                ResponseData::Ok
+            }
+            
+            Operation::HideComment { comment_id } => {
+                let author = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                
+                let comment = self.state.remove_comment(&comment_id, author, true).await?;
+                self.emit_event(DonationsEvent::CommentRemoved { comment: comment.clone(), timestamp: ts });
+                self.broadcast_comment(comment, author, false).await;
+                
+                ResponseData::Ok
+            }
+            
+            Operation::SetIncomeRedirect { target, basis_points, label } => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let redirect = donations::IncomeRedirect { target, basis_points, label, chain_id: self.runtime.chain_id().to_string() };
+                self.state.set_income_redirect(owner, redirect.clone()).await?;
+                self.emit_event(DonationsEvent::IncomeRedirectSet { owner, redirect, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::ClearIncomeRedirect => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                self.state.clear_income_redirect(owner).await?;
+                self.emit_event(DonationsEvent::IncomeRedirectCleared { owner, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::SetDonationGoal { target, deadline_micros, description } => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let description = description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+                let goal = donations::DonationGoal { target, deadline_micros, description, created_at: ts };
+                self.state.set_donation_goal(owner, goal.clone()).await?;
+                self.emit_event(DonationsEvent::DonationGoalSet { owner, goal, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::DeleteDonationGoal => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                self.state.delete_donation_goal(owner).await?;
+                self.emit_event(DonationsEvent::DonationGoalDeleted { owner, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::ProposeAdmin { new_admin } => {
+                let admin = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                self.state.propose_admin(admin, new_admin)?;
+                self.emit_event(DonationsEvent::AdminProposed { admin, new_admin, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::AcceptAdmin => {
+                let new_admin = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let previous_admin = *self.state.admin.get();
+                self.state.accept_admin(new_admin)?;
+                self.emit_event(DonationsEvent::AdminTransferred { previous_admin, new_admin, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::CommitSupporterDraw { year, month, commitment } => {
+                let author = self.signer()?;
+                if !(1..=12).contains(&month) {
+                    return Err(OperationError::invalid("Invalid month"));
+                }
+                // Once the month is over its supporters are known, and a seed could be picked to suit
+                let ts = self.runtime.system_time().micros();
+                if donations::month_key(year, month) < donations::month_key_of(ts) {
+                    return Err(OperationError::invalid("Commit before the month ends"));
+                }
+                self.state.commit_supporter_draw(author, donations::month_key(year, month), commitment).await?;
+                ResponseData::Ok
+            }
+            
+            Operation::DrawSupporterOfMonth { year, month, seed, prize } => {
+                let author = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let key = donations::month_key(year, month);
+                if key >= donations::month_key_of(ts) {
+                    return Err(OperationError::invalid("Month has not ended yet"));
+                }
+                let (winner, winner_chain) = self.state.draw_supporter_of_month(author, key, seed).await?;
+                
+                if let Some(amount) = prize.filter(|a| *a > Amount::ZERO) {
+                    let chain_id = winner_chain.and_then(|c| c.parse().ok()).unwrap_or_else(|| self.runtime.chain_id());
+                    self.pay(author, Account { chain_id, owner: winner }, amount).await.or_fail("Cannot pay the prize");
+                }
+                self.emit_event(DonationsEvent::SupporterOfMonthDrawn { author, year, month, winner, prize, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::SetMintLimits { max_per_mint, supply_cap } => {
+                let caller = self.signer()?;
+                self.state.set_mint_limits(caller, donations::MintLimits { max_per_mint, supply_cap })?;
+                ResponseData::Ok
+            }
+            
+            Operation::SnapshotState { scope } => {
+                self.require_admin()?;
+                let ts = self.runtime.system_time().micros();
+                
+                let data = self.state.snapshot_data(scope).await?;
+                let bytes = donations::write_snapshot(&data);
+                let chunks: Vec<String> = bytes.chunks(donations::SNAPSHOT_CHUNK_SIZE)
+                    .map(|chunk| self.runtime.create_data_blob(chunk.to_vec()).0.to_string())
+                    .collect();
+                let manifest = donations::SnapshotManifest {
+                    version: donations::SNAPSHOT_VERSION,
+                    created_at: ts,
+                    size: bytes.len() as u64,
+                    chunks,
+                };
+                let manifest_bytes = linera_sdk::bcs::to_bytes(&manifest).expect("Failed to serialize manifest");
+                let manifest_hash = self.runtime.create_data_blob(manifest_bytes).0.to_string();
+                
+                let meta = donations::SnapshotMeta {
+                    id: 0,
+                    manifest_hash,
+                    version: manifest.version,
+                    created_at: ts,
+                    size: manifest.size,
+                    chunks: manifest.chunks.len() as u32,
+                    scope,
+                    profiles: data.profiles.len() as u32,
+                    products: data.products.len() as u32,
+                    purchases: data.purchases.len() as u32,
+                    posts: data.posts.len() as u32,
+                    subscriptions: data.subscriptions.len() as u32,
+                    donations: data.donations.len() as u32,
+                };
+                self.state.record_snapshot(meta).await.or_fail("Failed to record snapshot");
+                ResponseData::Ok
+            }
+            
+            Operation::RestoreFromSnapshot { manifest_hash } => {
+                use linera_sdk::linera_base_types::{CryptoHash, DataBlobHash};
+                use std::str::FromStr;
+                
+                self.require_admin()?;
+                
+                let hash = CryptoHash::from_str(&manifest_hash).map_err(|_| OperationError::invalid("Invalid manifest hash"))?;
+                let manifest = donations::read_snapshot_manifest(&self.runtime.read_data_blob(DataBlobHash(hash)))
+                    .map_err(|_| OperationError::invalid("Invalid snapshot manifest"))?;
+                let mut bytes = Vec::with_capacity(manifest.size as usize);
+                for chunk in &manifest.chunks {
+                    let chunk_hash = CryptoHash::from_str(chunk).map_err(|_| OperationError::invalid("Invalid chunk hash"))?;
+                    bytes.extend(self.runtime.read_data_blob(DataBlobHash(chunk_hash)));
+                }
+                let data = donations::read_snapshot(&bytes).map_err(|_| OperationError::invalid("Invalid snapshot data"))?;
+                self.state.restore_snapshot(data).await.or_fail("Failed to restore snapshot");
+                ResponseData::Ok
+            }
+            
+            Operation::ExportActivityWindow { from, to, skip } => {
+                self.require_admin()?;
+                if to <= from || to - from > donations::MAX_EXPORT_WINDOW_MICROS {
+                    return Err(OperationError::invalid(format!("Export window must be non-empty and at most {} micros", donations::MAX_EXPORT_WINDOW_MICROS)));
+                }
+                let ts = self.runtime.system_time().micros();
+                let (records, continuation) = self.state.activity_window(from, to, skip).await?;
+                let bytes = donations::write_activity_export(from, to, skip, continuation, &records);
+                let blob_hash = self.runtime.create_data_blob(bytes).0.to_string();
+                let export = donations::ActivityExport {
+                    id: 0,
+                    blob_hash,
+                    from,
+                    to,
+                    records: records.len() as u32,
+                    skip,
+                    continuation: continuation.map(|(at, _)| at),
+                    continuation_skip: continuation.map_or(0, |(_, skip)| skip),
+                    created_at: ts,
+                };
+                let export = self.state.record_activity_export(export).await.or_fail("Failed to record export");
+                self.emit_event(DonationsEvent::ActivityExportReady { export, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::SetHoldingAccount { owner, holding } => {
+                let caller = self.signer()?;
+                self.state.set_holding_account(caller, owner, holding).await?;
+                ResponseData::Ok
+            }
+            
+            Operation::FollowMany { authors } => {
+                let follower = self.signer()?;
+                if authors.len() > donations::MAX_FOLLOW_BATCH {
+                    return Err(OperationError::invalid(format!("At most {} authors can be followed at once", donations::MAX_FOLLOW_BATCH)));
+                }
+                let ts = self.runtime.system_time().micros();
+                for author in self.state.follow(follower, authors, ts).await? {
+                    self.emit_event(DonationsEvent::Followed { follower, author, timestamp: ts });
+                }
+                ResponseData::Ok
+            }
+            
+            Operation::Follow { author } => {
+                let follower = self.signer()?;
+                if author == follower {
+                    return Err(OperationError::invalid("Cannot follow yourself"));
+                }
+                let ts = self.runtime.system_time().micros();
+                for author in self.state.follow(follower, vec![author], ts).await? {
+                    self.emit_event(DonationsEvent::Followed { follower, author, timestamp: ts });
+                }
+                ResponseData::Ok
+            }
+            
+            Operation::Unfollow { author } => {
+                let follower = self.signer()?;
+                if self.state.unfollow(follower, author).await? {
+                    let ts = self.runtime.system_time().micros();
+                    self.emit_event(DonationsEvent::Unfollowed { follower, author, timestamp: ts });
+                }
+                ResponseData::Ok
+            }
+            
+            Operation::PinDonation { donation_id } => {
+                let caller = self.signer()?;
+                self.state.pin_donation(caller, donation_id).await?;
+                ResponseData::Ok
+            }
+            
+            Operation::CloseAccount => {
+                let owner = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let product_ids = self.state.close_account(owner, ts).await?;
+                for product_id in product_ids {
+                    self.emit_event(DonationsEvent::ProductDeleted { product_id, author: owner, timestamp: ts });
+                }
+                self.emit_event(DonationsEvent::ProfileDeleted { owner, timestamp: ts });
+                ResponseData::Ok
+            }
+            
+            Operation::DrainStreamBacklog => {
+                self.drain_streams().await;
+                ResponseData::Ok
+            }
+            
+            Operation::RetryOutbox => {
+                let caller = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let due = self.state.due_outbox(ts).await?;
+                for entry in due {
+                    // Its last send went unacked as well: give up on it
+                    if entry.is_exhausted() {
+                        self.state.remove_outbox(entry.target_chain, entry.id).await.or_fail("Failed to update outbox");
+                        continue;
+                    }
+                    if entry.sender.is_some_and(|s| s != caller) {
+                        continue;
+                    }
+                    self.state.note_outbox_attempt(entry.target_chain, entry.id, ts).await.or_fail("Failed to update outbox");
+                    self.runtime.prepare_message(Message::Tracked { outbox_id: entry.id, message: Box::new(entry.message) }).with_authentication().send_to(entry.target_chain);
+                }
+                ResponseData::Ok
+            }
+            
+            Operation::ReplyToDonation { donation_id, text } => {
+                let caller = self.signer()?;
+                let ts = self.runtime.system_time().micros();
+                let rec = self.state.reply_to_donation(caller, donation_id, text, ts).await?;
+                let text = rec.reply.clone().unwrap_or_default();
+                let current_chain = self.runtime.chain_id();
+                let source_chain_id = rec.source_chain_id.clone().unwrap_or_else(|| current_chain.to_string());
+                let source_donation_id = rec.source_donation_id.unwrap_or(donation_id);
+                self.emit_event(DonationsEvent::DonationReplied {
+                    donation_id,
+                    source_chain_id: source_chain_id.clone(),
+                    source_donation_id,
+                    to: rec.to,
+                    text: text.clone(),
+                    timestamp: ts,
+                });
+                
+                // The chain it was sent from keeps its own copy of the donation
+                if let Ok(source_chain) = source_chain_id.parse::<ChainId>() {
+                    if source_chain != current_chain {
+                        self.runtime.prepare_message(Message::DonationReplied { source_donation_id, to: rec.to, text, replied_at: ts }).with_authentication().send_to(source_chain);
+                    }
+                }
+                ResponseData::Ok
+            }
+            
+            Operation::RouteDonation { donation_id, new_recipient_account } => {
+                let caller = self.signer()?;
+                let target = self.normalize_account(new_recipient_account);
+                let held = self.state.donations.get(&donation_id).await.map_err(StateError::from)?.ok_or(StateError::NotFound("Donation"))?;
+                // The admin must also control the holding account the funds sit in
+                self.runtime.check_account_permission(held.to).map_err(|_| OperationError::unauthorized("Permission denied"))?;
+                let rec = self.state.route_donation(caller, donation_id, target.owner, target.chain_id.to_string()).await?;
+                
+                self.pay(held.to, target, rec.amount).await.or_fail("Failed to route donation");
+                let current_chain = self.runtime.chain_id();
+                if target.chain_id != current_chain {
+                    let message = Message::TransferWithMessage { owner: target.owner, amount: rec.amount, text_message: rec.message.clone(), source_chain_id: current_chain, source_owner: rec.from, source_donation_id: rec.id };
+                    self.runtime.prepare_message(message).with_authentication().send_to(target.chain_id);
+                } else {
+                    self.accrue_income_redirect(target.owner, rec.amount).await;
+                }
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::DonationSent {
+                    id: rec.id,
+                    from: rec.from,
+                    to: rec.to,
+                    amount: rec.amount,
+                    message: rec.message,
+                    source_chain_id: rec.source_chain_id,
+                    source_donation_id: rec.source_donation_id,
+                    to_chain_id: rec.to_chain_id,
+                    timestamp: ts,
+                });
+                ResponseData::Ok
+            }
+        })
+    }
+    
+    fn normalize_account(&self, account: FungibleAccount) -> Account { Account { chain_id: account.chain_id, owner: account.owner } }
+    
+    /// After paying `target` on another chain: tell that chain, and record the donation here
+    async fn announce_cross_chain_transfer(&mut self, owner: AccountOwner, target: Account, amount: Amount, text_message: Option<String>) {
+        let current_chain = self.runtime.chain_id();
+        let current_chain_str = current_chain.to_string();
+        let ts = self.runtime.system_time().micros();
+        let payment_app = self.payment_app_label();
+        let id = self.state.record_donation(owner, target.owner, amount, text_message.clone(), Some(current_chain_str.clone()), None, Some(target.chain_id.to_string()), ts, payment_app).await.or_fail("Failed to record donation");
+        let message = Message::TransferWithMessage { owner: target.owner, amount, text_message: text_message.clone(), source_chain_id: current_chain, source_owner: owner, source_donation_id: id };
+        self.runtime.prepare_message(message).with_authentication().send_to(target.chain_id);
+        self.emit_event(DonationsEvent::DonationSent { id, from: owner, to: target.owner, amount, message: text_message, source_chain_id: Some(current_chain_str), source_donation_id: Some(id), to_chain_id: Some(target.chain_id.to_string()), timestamp: ts });
+    }
+    
+    /// Refuse text longer than the application's message limit
+    fn check_text_length(&mut self, what: &str, text: &str) -> Result<(), OperationError> {
+        let limit = self.runtime.application_parameters().message_limit();
+        if text.chars().count() > limit {
+            return Err(OperationError::invalid(format!("{} must be at most {} characters", what, limit)));
+        }
+        Ok(())
+    }
+    
+    /// A donation message without control characters, refused if over the limit; blank becomes None
+    fn clean_message(&mut self, text: Option<String>) -> Result<Option<String>, OperationError> {
+        let Some(text) = text.map(|t| donations::strip_control_chars(&t)).filter(|t| !t.trim().is_empty()) else {
+            return Ok(None);
+        };
+        self.check_text_length("Message", &text)?;
+        Ok(Some(text))
+    }
+    
+    /// An inbound donation message cut down to what `clean_message` would have accepted,
+    /// and whether that changed it
+    fn sanitize_inbound_message(&mut self, text: Option<String>) -> (Option<String>, bool) {
+        let Some(text) = text else { return (None, false) };
+        let (cleaned, modified) = self.sanitize_inbound_text(text);
+        (Some(cleaned), modified)
+    }
+    
+    /// Inbound text without control characters, cut to the message limit, and whether that changed it
+    fn sanitize_inbound_text(&mut self, text: String) -> (String, bool) {
+        let limit = self.runtime.application_parameters().message_limit();
+        let cleaned: String = donations::strip_control_chars(&text).chars().take(limit).collect();
+        let modified = cleaned != text;
+        (cleaned, modified)
+    }
+    
+    /// Inbound order answers, each cleaned like a message, and whether any changed
+    fn sanitize_inbound_order_data(&mut self, order_data: donations::OrderResponses) -> (donations::OrderResponses, bool) {
+        let mut modified = false;
+        let cleaned = order_data.into_iter().map(|(key, value)| {
+            let (value, cut) = self.sanitize_inbound_text(value);
+            modified |= cut;
+            (key, value)
+        }).collect();
+        (cleaned, modified)
+    }
+    
+    /// Refuse zero amounts and payments to the payer's own account on this chain
+    fn check_transfer(&mut self, owner: AccountOwner, target: Account, amount: Amount) -> Result<(), OperationError> {
+        if amount == Amount::ZERO {
+            return Err(OperationError::invalid("Amount must be greater than zero"));
+        }
+        if target.owner == owner && target.chain_id == self.runtime.chain_id() {
+            return Err(OperationError::invalid("Cannot transfer to your own account"));
+        }
+        Ok(())
+    }
+    
+    /// Publish `event` on this chain's events stream
+    fn emit_event(&mut self, event: DonationsEvent) {
+        self.runtime.emit(EVENTS_STREAM.into(), &event);
+        self.events_emitted = self.events_emitted.saturating_add(1);
+    }
+    
+    /// Refuse operations from a signer whose operations used up the event budget of the current
+    /// window, then charge the events this one emitted
+    async fn apply_rate_limited(&mut self, operation: Operation) -> Result<ResponseData, OperationError> {
+        let signer = self.runtime.authenticated_signer();
+        let now = self.runtime.system_time().micros();
+        if let Some(signer) = signer {
+            self.state.check_event_rate(signer, now).await?;
+        }
+        self.events_emitted = 0;
+        let result = self.apply_operation(operation).await;
+        if let Some(signer) = signer {
+            self.state.note_events(signer, now, self.events_emitted).await.or_fail("Failed to record emitted events");
+        }
+        result
+    }
+    
+    /// Apply the given profile fields, emitting an event only for those that actually changed
+    #[allow(clippy::too_many_arguments)]
+    async fn apply_profile_fields(&mut self, owner: AccountOwner, name: Option<String>, bio: Option<String>, socials: Vec<SocialLinkInput>, avatar_hash: Option<String>, header_hash: Option<String>, ts: u64) {
+        if let Some(n) = name.map(DonationsState::normalize_name) {
+            if let Ok(true) = self.state.set_name(owner, n.clone()).await {
+                self.emit_event(DonationsEvent::ProfileNameUpdated { owner, name: n, timestamp: ts });
+            }
+        }
+        if let Some(b) = bio {
+            if let Ok(true) = self.state.set_bio(owner, b.clone()).await {
+                self.emit_event(DonationsEvent::ProfileBioUpdated { owner, bio: b, timestamp: ts });
+            }
+        }
+        for s in socials {
+            if let Ok(true) = self.state.set_social(owner, s.name.clone(), s.url.clone()).await {
+                self.emit_event(DonationsEvent::ProfileSocialUpdated { owner, name: s.name, url: s.url, timestamp: ts });
+            }
+        }
+        if let Some(hash) = avatar_hash {
+            if let Ok(true) = self.state.set_avatar(owner, hash.clone()).await {
+                self.emit_event(DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: ts });
+            }
+        }
+        if let Some(hash) = header_hash {
+            if let Ok(true) = self.state.set_header(owner, hash.clone()).await {
+                self.emit_event(DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: ts });
+            }
+        }
+    }
+    
+    /// The signer, if they are the app admin. Guards minting and every other operation
+    /// that moves chain funds or bulk data on the operator's behalf.
+    fn require_admin(&mut self) -> Result<AccountOwner, OperationError> {
+        let caller = self.signer()?;
+        if !self.state.is_admin(caller) {
+            return Err(OperationError::unauthorized("Not admin"));
+        }
+        Ok(caller)
+    }
+    
+    /// The operation's signer; unsigned operations are refused
+    fn signer(&mut self) -> Result<AccountOwner, OperationError> {
+        self.runtime.authenticated_signer().ok_or_else(|| OperationError::new("unauthenticated", "Authentication required"))
+    }
+    
+    /// The fungible application payments go through, if the application was configured with one
+    fn payment_app(&mut self) -> Option<ApplicationId> { self.runtime.application_parameters().payment_app }
+    
+    /// Label recorded on donations and purchases paid in this application's mode
+    fn payment_app_label(&mut self) -> Option<String> { self.payment_app().map(|app| app.to_string()) }
+    
+    /// The owner's balance in the currency payments are made in
+    fn balance_of(&mut self, owner: AccountOwner) -> Amount {
+        match self.payment_app() {
+            Some(app) => match self.runtime.call_application(true, app.with_abi::<FungibleTokenAbi>(), &FungibleOperation::Balance { owner }) {
+                FungibleResponse::Balance(balance) => balance,
+                _ => Amount::ZERO,
+            },
+            None => self.runtime.owner_balance(owner),
+        }
+    }
+    
+    /// Move `amount` from `owner` to `target`, natively or through the payment application.
+    /// A rejected token transfer aborts the whole operation, so nothing gets recorded.
+    async fn pay(&mut self, owner: AccountOwner, target: Account, amount: Amount) -> Result<(), OperationError> {
+        match self.payment_app() {
+            Some(app) => {
+                let call = FungibleOperation::Transfer { owner, amount, target_account: FungibleAccount { chain_id: target.chain_id, owner: target.owner } };
+                match self.runtime.call_application(true, app.with_abi::<FungibleTokenAbi>(), &call) {
+                    FungibleResponse::Ok => Ok(()),
+                    other => Err(OperationError::new("payment_rejected", format!("Payment application rejected transfer: {:?}", other))),
+                }
+            }
+            None => {
+                self.ensure_available(owner, amount).await?;
+                self.runtime.transfer(owner, target, amount);
+                Ok(())
+            }
+        }
+    }
+    
+    async fn process_streams(&mut self, streams: Vec<StreamUpdate>) {
+        let current_chain = self.runtime.chain_id();
+        for stream_update in streams {
+            if stream_update.chain_id == current_chain { continue; }
+            let key = (stream_update.chain_id.to_string(), stream_update.stream_id.stream_name.to_string());
+            let _ = self.state.note_stream_head(key, stream_update.previous_index as u64, stream_update.next_index as u64).await;
+        }
+        self.drain_streams().await;
+    }
+    
+    /// Apply pending stream events in order, under a per-invocation budget so a long backlog
+    /// can't stall the chain; cursors persist the next index to apply for each (chain, stream).
+    /// Whatever is left waits for the next stream update or a DrainStreamBacklog operation.
+    async fn drain_streams(&mut self) {
+        let pending = self.state.pending_streams().await.unwrap_or_default();
+        let mut budget = donations::STREAM_EVENT_BUDGET;
+        for ((chain, stream), mut cursor, head) in pending {
+            let (Ok(chain_id), Ok(stream_name)) = (chain.parse::<ChainId>(), stream.parse::<StreamName>()) else { continue };
+            while cursor < head && budget > 0 {
+                let event = self.runtime.read_event(chain_id, stream_name.clone(), cursor as u32);
+                self.apply_stream_event(chain_id, event).await;
+                budget -= 1;
+                cursor += 1;
+            }
+            let remaining = head - cursor;
+            let _ = self.state.advance_stream_cursor((chain, stream), cursor, head).await;
+            if remaining > 0 {
+                let ts = self.runtime.system_time().micros();
+                self.emit_event(DonationsEvent::StreamBacklog { chain: chain_id, remaining, timestamp: ts });
+            }
+        }
+    }
+    
+    /// Mirror one event from `chain_id`'s stream into local state
+    async fn apply_stream_event(&mut self, chain_id: ChainId, event: DonationsEvent) {
+        let current_chain = self.runtime.chain_id();
+        match event {
+            DonationsEvent::ProfileNameUpdated { owner, name, timestamp: _ } => {
+                let _ = self.state.set_name(owner, name).await;
+            }
+            DonationsEvent::ProfileBioUpdated { owner, bio, timestamp: _ } => {
+                let _ = self.state.set_bio(owner, bio).await;
+            }
+            DonationsEvent::ProfileSocialUpdated { owner, name, url, timestamp: _ } => {
+                let _ = self.state.set_social(owner, name, url).await;
+            }
+            DonationsEvent::ProfileAvatarUpdated { owner, hash, timestamp: _ } => {
+                let _ = self.state.set_avatar(owner, hash).await;
+            }
+            DonationsEvent::ProfileHeaderUpdated { owner, hash, timestamp: _ } => {
+                let _ = self.state.set_header(owner, hash).await;
+            }
+            DonationsEvent::ProfileDeleted { owner, timestamp } => {
+                let _ = self.state.close_account(owner, timestamp).await;
+            }
+            DonationsEvent::DonationSent { id, from, to, amount, message, source_chain_id, source_donation_id, to_chain_id, timestamp } => {
+                // A donation made on the emitting chain is known by its id there
+                let source_chain_id = source_chain_id.unwrap_or_else(|| chain_id.to_string());
+                let payment_app = self.payment_app_label();
+                let _ = self.state.record_donation(from, to, amount, message, Some(source_chain_id), Some(source_donation_id.unwrap_or(id)), to_chain_id, timestamp, payment_app).await;
+            }
+            DonationsEvent::Followed { follower, author, timestamp } => {
+                let _ = self.state.follow(follower, vec![author], timestamp).await;
+            }
+            DonationsEvent::Unfollowed { follower, author, timestamp: _ } => {
+                let _ = self.state.unfollow(follower, author).await;
+            }
+            DonationsEvent::DonationReplied { donation_id: _, source_chain_id, source_donation_id, to, text, timestamp } => {
+                let _ = self.state.mirror_donation_reply((source_chain_id, source_donation_id), &current_chain.to_string(), to, text, timestamp).await;
+            }
+            DonationsEvent::ProductCreated { product, timestamp: _ } => {
+                self.mirror_product(product).await;
+            }
+            DonationsEvent::ProductUpdated { product, timestamp: _ } => {
+                self.mirror_product(product).await;
+            }
+            DonationsEvent::ProductSlugConflict { product_id: _, slug: _, timestamp: _ } => {
+                // The main chain already dropped the conflicting slug
+            }
+            DonationsEvent::ProductPurchased { purchase_id, product_id, buyer, seller, amount, timestamp } => {
+                if let Ok(Some(product)) = self.state.get_product(&product_id).await {
+                    let purchase = donations::Purchase {
+                        id: purchase_id,
+                        product_id,
+                        buyer,
+                        buyer_chain_id: current_chain.to_string(),
+                        seller,
+                        seller_chain_id: product.author_chain_id.clone(),
+                        amount,
+                        timestamp,
+                        order_data: std::collections::BTreeMap::new(), // Event doesn't contain order_data
+                        content_modified: false,
+                        payment_app: self.payment_app_label(),
+                        status: OrderStatus::Pending,
+                        product,
+                    };
+                    let _ = self.state.record_purchase(purchase).await;
+                }
+            }
+            DonationsEvent::OrderPlaced { purchase_id: _, product_id: _, buyer: _, seller: _, amount: _, timestamp: _ } => {
+                // Order placed events are handled on seller's chain
+                // We can add order storage logic here if needed
+            }
+            DonationsEvent::ProductDeleted { product_id, author, timestamp: _ } => {
+                let _ = self.state.delete_product(&product_id, author).await;
+                let _ = self.state.unfeature_product(author, &product_id).await;
+                let _ = self.state.unorder_product(author, &product_id).await;
+            }
+            DonationsEvent::FeaturedProductsUpdated { author, product_ids, timestamp: _ } => {
+                let _ = self.state.set_featured_products(author, product_ids).await;
+            }
+            DonationsEvent::ProductOrderUpdated { author, ordered_ids, timestamp: _ } => {
+                let _ = self.state.set_product_order(author, ordered_ids).await;
+            }
+            // Content subscription events
+            DonationsEvent::SubscriptionPriceSet { author, price, description, grandfather_renewals, timestamp: _ } => {
+                let _ = self.state.set_subscription_price(author, price, description, grandfather_renewals).await;
+            }
+            DonationsEvent::SubscriptionPriceChangeScheduled { author, pending, timestamp: _ } => {
+                let _ = self.state.schedule_subscription_price(author, pending).await;
+            }
+            DonationsEvent::SubscriptionEntitlementsSet { author, entitlements, timestamp: _ } => {
+                let _ = self.state.set_subscription_entitlements(author, entitlements).await;
+            }
+            DonationsEvent::SubscriptionGracePeriodSet { author, grace_period_micros, timestamp: _ } => {
+                let _ = self.state.set_subscription_grace_period(author, grace_period_micros).await;
+            }
+            DonationsEvent::SubscriptionPriceDeleted { author, timestamp: _ } => {
+                let _ = self.state.delete_subscription_info(author).await;
+            }
+            DonationsEvent::UserSubscribed { subscription_id: _, subscriber: _, author: _, price: _, end_timestamp: _, timestamp: _ } => {
+                // Subscription is already created on the chain where payment was made
+            }
+            DonationsEvent::UserUnsubscribed { subscription_id, subscriber, author, timestamp: _ } => {
+                let _ = self.state.remove_subscription(&subscription_id, author, subscriber).await;
+            }
+            DonationsEvent::PostCreated { post, timestamp: _ } => {
+                let _ = self.state.create_post(post).await;
+            }
+            DonationsEvent::PostUpdated { post, timestamp: _ } => {
+                let clear_image = post.image_hash.is_none();
+                let _ = self.state.update_post(&post.id, Some(post.title), Some(post.content), post.image_hash, clear_image).await;
+            }
+            DonationsEvent::PostDeleted { post_id, author, timestamp: _ } => {
+                let _ = self.state.delete_post(&post_id, author).await;
+            }
+            DonationsEvent::VoteCasted { post_id: _, voter: _, option_index: _, timestamp: _ } => {
+                // Vote events are handled through PollResultsUpdated
+            }
+            DonationsEvent::PollResultsUpdated { post_id, poll, timestamp: _ } => {
+                let _ = self.state.update_poll_results(&post_id, poll).await;
+            }
+            DonationsEvent::GiveawayParticipated { post_id: _, participant: _, timestamp: _ } => {
+                // Giveaway participation events are handled through GiveawayUpdated message
+            }
+            DonationsEvent::GiveawayResolved { post_id: _, winner: _, winner_chain_id: _, prize_amount: _, timestamp: _ } => {
+                // Giveaway resolved events are handled through GiveawayUpdated message
+            }
+            DonationsEvent::CommentAdded { comment, timestamp: _ } | DonationsEvent::CommentRemoved { comment, timestamp: _ } => {
+                let _ = self.state.upsert_comment(comment).await;
+            }
+            DonationsEvent::IncomeRedirectSet { owner, redirect, timestamp: _ } => {
+                let _ = self.state.set_income_redirect(owner, redirect).await;
+            }
+            DonationsEvent::IncomeRedirectCleared { owner, timestamp: _ } => {
+                let _ = self.state.clear_income_redirect(owner).await;
+            }
+            DonationsEvent::DonationGoalSet { owner, goal, timestamp: _ } => {
+                let _ = self.state.set_donation_goal(owner, goal).await;
+            }
+            DonationsEvent::DonationGoalDeleted { owner, timestamp: _ } => {
+                let _ = self.state.delete_donation_goal(owner).await;
+            }
+            DonationsEvent::IncomeRedirected { owner, target_chain_id: _, target_owner: _, amount, label: _, timestamp: _ } => {
+                if let Ok(true) = self.state.add_redirect_total(owner, amount).await {
+                    self.report_overflow("redirect_total");
+                }
+            }
+            DonationsEvent::AdminProposed { admin: _, new_admin: _, timestamp: _ } => {
+                // Admin role is local to each chain
+            }
+            DonationsEvent::AdminTransferred { previous_admin: _, new_admin: _, timestamp: _ } => {
+                // Admin role is local to each chain
+            }
+            DonationsEvent::SupporterOfMonthDrawn { author, year, month, winner, prize: _, timestamp: _ } => {
+                let _ = self.state.monthly_winners.insert(&(author, donations::month_key(year, month)), winner);
+            }
+            DonationsEvent::ActivityExportReady { export: _, timestamp: _ } => {
+                // Exports are local to the chain that made them
+            }
+            DonationsEvent::PurchaseReady { purchase_id, product_id: _, buyer, timestamp } => {
+                // The main chain already recorded the purchase; note that it reached the buyer
+                if let Ok(Some(purchase)) = self.state.get_purchase(&purchase_id).await {
+                    if purchase.buyer == buyer {
+                        let _ = self.state.mark_purchase_delivered(&purchase_id, timestamp).await;
+                    }
+                }
+            }
+            DonationsEvent::ProductReviewed { review, timestamp: _ } => {
+                if let Ok(true) = self.state.has_purchased(review.reviewer, &review.product_id).await {
+                    let _ = self.state.put_review(review).await;
+                }
+            }
+            DonationsEvent::OrderStatusChanged { purchase_id, buyer: _, seller, status, timestamp: _ } => {
+                if let Ok(Some(purchase)) = self.state.get_purchase(&purchase_id).await {
+                    if purchase.seller == seller {
+                        let _ = self.state.set_order_status(&purchase_id, status).await;
+                    }
+                }
+            }
+            DonationsEvent::PurchaseRefunded { purchase_id, product_id: _, buyer: _, seller, amount: _, timestamp: _ } => {
+                if let Ok(Some(purchase)) = self.state.get_purchase(&purchase_id).await {
+                    if purchase.seller == seller {
+                        let _ = self.state.set_order_status(&purchase_id, OrderStatus::Refunded).await;
+                    }
+                }
+            }
+            DonationsEvent::ArithmeticAnomaly { context: _, timestamp: _ } => {
+                // Monitoring only
+            }
+            DonationsEvent::StreamBacklog { chain: _, remaining: _, timestamp: _ } => {
+                // Monitoring only
+            }
+        }
+    }
+    
+    /// Check if a subscriber has a valid (non-expired) subscription to an author.
+    /// Used to gate interactions, so the author's grace period doesn't apply.
+    async fn check_subscription_valid(&self, subscriber: AccountOwner, author: AccountOwner, current_time: u64) -> bool {
+        // Author is always valid for their own content
+        if subscriber == author {
+            return true;
+        }
+        
+        let sub_ids = self.state.subscriptions_by_author.get(&author).await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        
+        for sub_id in sub_ids {
+            if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
+                if sub.subscriber == subscriber && sub.end_timestamp >= current_time {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    
+    /// Store a product mirrored from the seller's chain, replacing any previous copy.
+    /// A slug already held by another of the seller's products is dropped, and the
+    /// seller's chain is told to clear it with a SlugConflict message.
+    async fn mirror_product(&mut self, mut product: donations::Product) {
+        let ts = self.runtime.system_time().micros();
+        if let Ok(Some(previous)) = self.state.get_product(&product.id).await {
+            let _ = self.state.delete_product(&product.id, previous.author).await;
+            if let Some(old_slug) = previous.slug {
+                if product.slug.as_ref() != Some(&old_slug) {
+                    let _ = self.state.add_slug_redirect(previous.author, &old_slug, &product.id, ts).await;
+                }
+            }
+        }
+        
+        if let Some(slug) = product.slug.clone() {
+            let taken = self.state.slug_owner(product.author, &slug).await.ok().flatten().is_some_and(|id| id != product.id);
+            if taken || DonationsState::validate_slug(&slug).is_err() {
+                product.slug = None;
+                if let Ok(seller_chain_id) = product.author_chain_id.parse::<ChainId>() {
+                    if seller_chain_id != self.runtime.chain_id() {
+                        self.runtime.prepare_message(Message::SlugConflict { product_id: product.id.clone(), slug }).with_authentication().send_to(seller_chain_id);
+                    }
+                }
+            }
+        }
+        
+        let _ = self.state.create_product(product).await;
+    }
+    
+    /// Send a mirror message through the outbox, so `RetryOutbox` re-sends it until the target acks
+    async fn send_tracked(&mut self, message: Message, target_chain: ChainId) {
+        let ts = self.runtime.system_time().micros();
+        let sender = self.runtime.authenticated_signer();
+        match self.state.queue_outbox(target_chain, message.clone(), sender, ts).await {
+            Ok(outbox_id) => {
+                self.runtime.prepare_message(Message::Tracked { outbox_id, message: Box::new(message) }).with_authentication().send_to(target_chain);
+            }
+            // Without an outbox entry the message still gets its one untracked attempt
+            Err(_) => {
+                self.runtime.prepare_message(message).with_authentication().send_to(target_chain);
+            }
+        }
+    }
+    
+    /// Publish a changed product to subscribers and the author's main chain
+    async fn announce_product_updated(&mut self, product: donations::Product) {
+        let ts = self.runtime.system_time().micros();
+        self.emit_event(DonationsEvent::ProductUpdated { product: product.clone(), timestamp: ts });
+        if let Ok(Some(main_chain_id_str)) = self.state.subscriptions.get(&product.author).await {
+            if let Ok(main_chain_id) = main_chain_id_str.parse::<ChainId>() {
+                if main_chain_id != self.runtime.chain_id() {
+                    self.send_tracked(Message::ProductUpdated { product }, main_chain_id).await;
+                }
+            }
+        }
+    }
+    
+    /// Main chain side of a purchase, shared by the `ProductPurchased` message and same-chain purchases
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_product_purchased(
+        &mut self,
+        purchase_id: String,
+        product_id: String,
+        buyer: AccountOwner,
+        buyer_chain_id: ChainId,
+        seller: AccountOwner,
+        amount: Amount,
+        emit_event: bool,
+    ) {
+        // The buyer has already paid the seller, so a purchase that can't be delivered is reported back
+        let product = match self.state.get_product(&product_id).await {
+            Ok(Some(product)) if amount == product.price => product,
+            Ok(found) => {
+                let reason = if found.is_some() { "Paid amount doesn't match the product price" } else { "Product was deleted before it could be delivered" };
+                let order = donations::FailedOrder {
+                    purchase_id,
+                    product_id,
+                    seller,
+                    amount,
+                    reason: reason.to_string(),
+                    timestamp: self.runtime.system_time().micros(),
+                };
+                if buyer_chain_id == self.runtime.chain_id() {
+                    self.record_order_failure(buyer, order).await;
+                } else {
+                    self.runtime.prepare_message(Message::OrderFailed { buyer, order }).with_authentication().send_to(buyer_chain_id);
+                }
+                return;
+            }
+            Err(_) => return,
+        };
+        
+        // Send product data to buyer's chain, or store it directly when that is this chain
+        let ts = self.runtime.system_time().micros();
+        let entitlements = DonationsState::purchase_grants(&product, &purchase_id, ts);
+        if buyer_chain_id == self.runtime.chain_id() {
+            self.receive_product_data(buyer, purchase_id.clone(), product.clone(), entitlements).await;
+        } else {
+            self.runtime.prepare_message(Message::SendProductData {
+                buyer,
+                purchase_id: purchase_id.clone(),
+                product: product.clone(),
+                entitlements,
+            }).with_authentication().send_to(buyer_chain_id);
+        }
+        
+        // Record purchase on main chain
+        let purchase = donations::Purchase {
+            id: purchase_id.clone(),
+            product_id: product_id.clone(),
+            buyer,
+            buyer_chain_id: buyer_chain_id.to_string(),
+            seller,
+            seller_chain_id: product.author_chain_id.clone(),
+            amount,
+            timestamp: ts,
+            order_data: std::collections::BTreeMap::new(), // Main chain doesn't have order data
+            content_modified: false,
+            payment_app: self.payment_app_label(),
+            status: OrderStatus::Pending,
+            product,
+        };
+        let recorded = self.state.record_purchase(purchase).await.is_ok();
+        
+        // Emit event so subscribers to Main Chain see the purchase; inline callers already emitted it
+        if emit_event && recorded {
+            self.emit_event(DonationsEvent::ProductPurchased {
+                purchase_id,
+                product_id,
+                buyer,
+                seller,
+                amount,
+                timestamp: ts,
+            });
+        }
+    }
+    
+    /// Seller pays the buyer back for one order, takes back its entitlements on both chains and
+    /// restocks the product; the caller records the order's new status
+    async fn pay_back_purchase(&mut self, purchase: &donations::Purchase) -> Result<ChainId, OperationError> {
+        let buyer_chain_id = purchase.buyer_chain_id.parse::<ChainId>()
+            .map_err(|_| OperationError::invalid("Purchase has no valid buyer chain"))?;
+        self.pay(purchase.seller, Account { chain_id: buyer_chain_id, owner: purchase.buyer }, purchase.amount).await?;
+        self.state.revoke_purchase_entitlements(purchase.buyer, &purchase.id).await.or_fail("Failed to revoke entitlements");
+        if buyer_chain_id != self.runtime.chain_id() {
+            self.runtime.prepare_message(Message::EntitlementsRevoked { buyer: purchase.buyer, purchase_id: purchase.id.clone() }).with_authentication().send_to(buyer_chain_id);
+        }
+        
+        // A sold-out or rejected order never took a copy, so there is none to put back
+        let restock = !matches!(purchase.status, OrderStatus::SoldOut | OrderStatus::Rejected);
+        if let (true, Ok(Some(product))) = (restock, self.state.get_product(&purchase.product_id).await) {
+            if product.stock.is_some() && product.author == purchase.seller {
+                let product = self.state.increment_stock(&purchase.product_id).await.or_fail("Failed to update stock");
+                if product.author_chain_id == self.runtime.chain_id().to_string() {
+                    self.announce_product_updated(product).await;
+                }
+            }
+        }
+        Ok(buyer_chain_id)
+    }
+    
+    /// Seller's chain refused a paid order: ask the seller to refund it and report it to the
+    /// buyer's chain as failed, with the reason
+    #[allow(clippy::too_many_arguments)]
+    async fn reject_order(&mut self, purchase_id: String, product_id: String, buyer: AccountOwner, buyer_chain_id: ChainId, seller: AccountOwner, amount: Amount, reason: String, timestamp: u64) {
+        let _ = self.state.push_notification(seller, donations::Notification {
+            kind: "order_rejected".to_string(),
+            from: buyer,
+            text: format!("Order {} for {} was refused: {}. Refund it with RefundPurchase", purchase_id, amount, reason),
+            timestamp,
+        }).await;
+        let order = donations::FailedOrder {
+            purchase_id,
+            product_id,
+            seller,
+            amount,
+            reason,
+            timestamp,
+        };
+        if buyer_chain_id == self.runtime.chain_id() {
+            self.record_order_failure(buyer, order).await;
+        } else {
+            self.runtime.prepare_message(Message::OrderFailed { buyer, order }).with_authentication().send_to(buyer_chain_id);
+        }
+    }
+    
+    /// Buyer's chain keeps an undeliverable purchase and tells the buyer to claim a refund from the seller
+    async fn record_order_failure(&mut self, buyer: AccountOwner, order: donations::FailedOrder) {
+        let (seller, text) = (order.seller, format!("Order {} failed: {}. Contact the seller for a refund of {}", order.purchase_id, order.reason, order.amount));
+        if let Ok(true) = self.state.record_failed_order(buyer, order).await {
+            let _ = self.state.push_notification(buyer, donations::Notification {
+                kind: "order_failed".to_string(),
+                from: seller,
+                text,
+                timestamp: self.runtime.system_time().micros(),
+            }).await;
+        }
+    }
+    
+    /// A purchase whose buyer, seller and main chain are all this chain: one record with the buyer's
+    /// answers and grants, delivered at once
+    async fn record_local_purchase(&mut self, purchase_id: String, buyer: AccountOwner, amount: Amount, order_data: donations::OrderResponses, product: donations::Product, timestamp: u64) {
+        let product_id = product.id.clone();
+        let seller = product.author;
+        let grants = DonationsState::purchase_grants(&product, &purchase_id, timestamp);
+        let purchase = donations::Purchase {
+            id: purchase_id.clone(),
+            product_id: product_id.clone(),
+            buyer,
+            buyer_chain_id: self.runtime.chain_id().to_string(),
+            seller,
+            seller_chain_id: product.author_chain_id.clone(),
+            amount,
+            timestamp,
+            order_data,
+            content_modified: false,
+            payment_app: self.payment_app_label(),
+            status: OrderStatus::Pending,
+            product,
+        };
+        self.state.record_purchase_with_grants(purchase, grants).await.or_fail("Failed to record purchase");
+        self.state.mark_purchase_delivered(&purchase_id, timestamp).await.or_fail("Failed to record delivery");
+        self.emit_event(DonationsEvent::PurchaseReady { purchase_id, product_id, buyer, timestamp });
+        if buyer != seller {
+            self.accrue_income_redirect(seller, amount).await;
+        }
+    }
+    
+    /// Buyer's chain stores the purchased product data and announces it is ready
+    async fn receive_product_data(&mut self, buyer: AccountOwner, purchase_id: String, product: donations::Product, entitlements: Vec<donations::EntitlementGrant>) {
+        let ts = self.runtime.system_time().micros();
+        let product_id = product.id.clone();
+        let purchase = donations::Purchase {
+            id: purchase_id.clone(),
+            product_id: product.id.clone(),
+            buyer,
+            buyer_chain_id: self.runtime.chain_id().to_string(),
+            seller: product.author,
+            seller_chain_id: product.author_chain_id.clone(),
+            amount: product.price,
+            timestamp: ts,
+            order_data: std::collections::BTreeMap::new(), // Empty for now
+            content_modified: false,
+            payment_app: self.payment_app_label(),
+            status: OrderStatus::Pending,
+            product,
+        };
+        if self.state.record_purchase_with_grants(purchase, entitlements).await.is_err() {
+            return;
+        }
+        if self.state.mark_purchase_delivered(&purchase_id, ts).await.is_ok() {
+            self.emit_event(DonationsEvent::PurchaseReady { purchase_id, product_id, buyer, timestamp: ts });
+        }
+    }
+    
+    /// Emit an anomaly for an aggregation that saturated instead of overflowing
+    fn report_overflow(&mut self, context: &str) {
+        let ts = self.runtime.system_time().micros();
+        self.emit_event(DonationsEvent::ArithmeticAnomaly { context: context.to_string(), timestamp: ts });
+    }
+    
+    /// Apply the author's pending price increase once its notice period is over
+    async fn apply_pending_subscription_price(&mut self, author: AccountOwner) {
+        let ts = self.runtime.system_time().micros();
+        if let Ok(Some(info)) = self.state.apply_pending_price(author, ts).await {
+            self.emit_event(DonationsEvent::SubscriptionPriceSet {
+                author,
+                price: info.price,
+                description: info.description,
+                grandfather_renewals: info.grandfather_renewals,
+                timestamp: ts,
+            });
+        }
+    }
+    
+    /// Tell active subscribers about a scheduled price increase
+    async fn notify_price_change(&mut self, author: AccountOwner, old_price: Amount, pending: &donations::PendingPrice) {
+        let ts = self.runtime.system_time().micros();
+        let author_chain_id = self.runtime.chain_id();
+        let subs = self.state.get_active_subscriptions(author, ts, SubscriptionAccess::Read).await.unwrap_or_default();
+        for sub in subs {
+            let message = Message::PriceChangeNotice {
+                subscriber: sub.subscriber,
+                author,
+                old_price,
+                new_price: pending.price,
+                effective_at: pending.effective_at,
+            };
+            match sub.subscriber_chain_id.parse::<ChainId>() {
+                Ok(chain_id) if chain_id != author_chain_id => {
+                    self.runtime.prepare_message(message).with_authentication().send_to(chain_id);
+                }
+                Ok(_) => {
+                    let _ = self.state.push_notification(sub.subscriber, donations::Notification {
+                        kind: "price_change".to_string(),
+                        from: author,
+                        text: format!("Subscription price changes from {} to {} at {}", old_price, pending.price, pending.effective_at),
+                        timestamp: ts,
+                    }).await;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    
+    /// Renewal price this chain knows for a remote author: the terms the author's chain
+    /// last confirmed, else the price mirrored from its events
+    async fn known_renewal_price(&mut self, author: AccountOwner, subscriber: AccountOwner, now: u64) -> Option<Amount> {
+        match self.state.renewal_price_cache.get(&format!("{}:{}", author, subscriber)).await {
+            Ok(Some(price)) => Some(price),
+            _ => self.state.effective_renewal_price(author, subscriber, now).await.ok().flatten(),
+        }
+    }
+    
+    /// Pay for the period after `sub` and record it as a new subscription starting when `sub`
+    /// ends. A renewal that can't be paid is skipped and counted against the subscription.
+    async fn renew_subscription(&mut self, sub: donations::ContentSubscription, now: u64) {
+        let author = sub.author;
+        let subscriber = sub.subscriber;
+        let Ok(author_chain_id) = sub.author_chain_id.parse::<ChainId>() else { return };
+        let subscriber_chain_id = self.runtime.chain_id();
+        let price = self.known_renewal_price(author, subscriber, now).await.unwrap_or(sub.price);
+        
+        let terms = if author_chain_id == subscriber_chain_id {
+            self.apply_pending_subscription_price(author).await;
+            self.state.subscription_terms(author, subscriber, price, now).await.ok()
+        } else {
+            Some((price, 0))
+        };
+        let funded = match terms {
+            Some(_) if self.payment_app().is_none() && self.available_balance(subscriber).await.0 < price => false,
+            Some(_) => self.pay(subscriber, Account { chain_id: author_chain_id, owner: author }, price).await.is_ok(),
+            None => false,
+        };
+        let Some((locked_price, renewals_remaining_at_locked_price)) = terms.filter(|_| funded) else {
+            if let Ok(true) = self.state.note_auto_renew_failure(&sub.id).await {
+                let _ = self.state.push_notification(subscriber, donations::Notification {
+                    kind: "auto_renew_disabled".to_string(),
+                    from: author,
+                    text: format!("Auto-renew was turned off after {} renewals couldn't be paid", donations::MAX_AUTO_RENEW_FAILURES),
+                    timestamp: now,
+                }).await;
+            }
+            return;
+        };
+        
+        let start_timestamp = sub.end_timestamp;
+        let end_timestamp = start_timestamp + donations::SUBSCRIPTION_PERIOD_MICROS;
+        let sub_id = format!("sub-{}-{}-{}", subscriber, author, start_timestamp);
+        let renewed = donations::ContentSubscription {
+            id: sub_id.clone(),
+            subscriber,
+            subscriber_chain_id: subscriber_chain_id.to_string(),
+            author,
+            author_chain_id: sub.author_chain_id.clone(),
+            start_timestamp,
+            end_timestamp,
+            price,
+            locked_price,
+            renewals_remaining_at_locked_price,
+            auto_renew: true,
+            auto_renew_failures: 0,
+        };
+        // Payment went out, so recording the renewal must not fail quietly
+        self.state.set_auto_renew(&sub.id, subscriber, false).await.or_fail("Failed to record renewal");
+        self.state.create_subscription(renewed).await.or_fail("Failed to record renewal");
+        
+        if author_chain_id == subscriber_chain_id {
+            if subscriber != author {
+                self.accrue_income_redirect(author, price).await;
+            }
+        } else {
+            // The author's chain derives the same id from the start timestamp
+            self.runtime.prepare_message(Message::SubscriptionPayment {
+                subscriber,
+                subscriber_chain_id: subscriber_chain_id.to_string(),
+                author,
+                amount: price,
+                duration_micros: donations::SUBSCRIPTION_PERIOD_MICROS,
+                timestamp: start_timestamp,
+            }).with_authentication().send_to(author_chain_id);
+        }
+        
+        self.emit_event(DonationsEvent::UserSubscribed {
+            subscription_id: sub_id,
+            subscriber,
+            author,
+            price,
+            end_timestamp,
+            timestamp: now,
+        });
+    }
+    
+    /// Deliver an announcement to the inbox of each active subscriber
+    async fn announce(&mut self, author: AccountOwner, text: String) {
+        let ts = self.runtime.system_time().micros();
+        let author_chain_id = self.runtime.chain_id();
+        let subs = self.state.get_active_subscriptions(author, ts, SubscriptionAccess::Read).await.unwrap_or_default();
+        for sub in subs {
+            match sub.subscriber_chain_id.parse::<ChainId>() {
+                Ok(chain_id) if chain_id != author_chain_id => {
+                    self.runtime.prepare_message(Message::Announcement {
+                        subscriber: sub.subscriber,
+                        author,
+                        text: text.clone(),
+                        timestamp: ts,
+                    }).with_authentication().send_to(chain_id);
+                }
+                Ok(_) => {
+                    let _ = self.state.push_announcement(sub.subscriber, donations::Notification {
+                        kind: "announcement".to_string(),
+                        from: author,
+                        text: text.clone(),
+                        timestamp: ts,
+                    }).await;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    
+    /// Owner balance minus funds committed elsewhere, with the commitments
+    async fn available_balance(&mut self, owner: AccountOwner) -> (Amount, Vec<donations::Commitment>) {
+        let commitments = self.state.get_commitments(owner).await.unwrap_or_default();
+        let committed = match donations::amount_math::sum_tracking_overflow(commitments.iter().map(|c| c.amount)) {
+            Ok(total) => total,
+            Err(saturated) => {
+                self.report_overflow("committed_total");
+                saturated
+            }
+        };
+        (self.runtime.owner_balance(owner).saturating_sub(committed), commitments)
+    }
+    
+    /// Panic unless the owner can spend the amount without touching committed funds
+    async fn ensure_available(&mut self, owner: AccountOwner, amount: Amount) -> Result<(), OperationError> {
+        let (available, commitments) = self.available_balance(owner).await;
+        if !commitments.is_empty() && amount > available {
+            let blocking: Vec<String> = commitments.iter().map(|c| format!("{} {} ({})", c.kind, c.reference_id, c.amount)).collect();
+            return Err(OperationError::new("insufficient_balance", format!("Insufficient available balance {}, committed to: {}", available, blocking.join(", "))));
+        }
+        Ok(())
+    }
+    
+    /// Accrue the owner's pledged share of an incoming credit and pay it out if possible
+    async fn accrue_income_redirect(&mut self, owner: AccountOwner, gross: Amount) {
+        let here = self.runtime.chain_id().to_string();
+        if let Ok((share, overflowed)) = self.state.accrue_income_redirect(owner, gross, &here).await {
+            if overflowed {
+                self.report_overflow("pending_redirect");
+            }
+            if share > Amount::ZERO {
+                self.settle_income_redirect(owner).await;
+            }
+        }
+    }
+    
+    /// Owe `amount` back to `to`: it reached the payee's account here, which only moves when the
+    /// payee signs, so the refund goes out with their next operation
+    async fn refund_later(&mut self, payee: AccountOwner, to: Account, amount: Amount, reason: String) {
+        let ts = self.runtime.system_time().micros();
+        let refund = donations::PendingRefund { to: to.owner, to_chain_id: to.chain_id, amount, reason: reason.clone(), created_at: ts };
+        if self.state.queue_refund(payee, refund).await.is_ok() {
+            let _ = self.state.push_notification(payee, donations::Notification {
+                kind: "refund_pending".to_string(),
+                from: to.owner,
+                text: format!("{} will be refunded with your next operation: {}", amount, reason),
+                timestamp: ts,
+            }).await;
+        }
+    }
+    
+    /// Author's chain refuses a subscription payment: it is owed back, and the subscriber's chain
+    /// drops its copy of the subscription
+    async fn reject_subscription_payment(&mut self, subscription_id: String, subscriber: AccountOwner, subscriber_chain: ChainId, author: AccountOwner, amount: Amount, reason: String) {
+        self.refund_later(author, Account { chain_id: subscriber_chain, owner: subscriber }, amount, reason.clone()).await;
+        self.runtime.prepare_message(Message::SubscriptionRejected { subscription_id, subscriber, author, amount, reason }).with_authentication().send_to(subscriber_chain);
+    }
+    
+    /// Pay the owner's owed refunds that their balance covers; the rest wait for a later operation
+    async fn settle_pending_refunds(&mut self, owner: AccountOwner) {
+        let refunds = self.state.get_pending_refunds(owner).await.unwrap_or_default();
+        if refunds.is_empty() {
+            return;
+        }
+        let mut unpaid = Vec::new();
+        for refund in refunds {
+            let affordable = self.payment_app().is_some() || self.available_balance(owner).await.0 >= refund.amount;
+            let target = Account { chain_id: refund.to_chain_id, owner: refund.to };
+            if !affordable || self.pay(owner, target, refund.amount).await.is_err() {
+                unpaid.push(refund);
+            }
+        }
+        self.state.set_pending_refunds(owner, unpaid).await.or_fail("Failed to update refunds");
+    }
+    
+    /// Transfer the owner's pending redirect to their pledge target.
+    /// Funds can only leave the owner's account when they signed the block, so credits
+    /// received from other people stay pending until the owner's next operation.
+    async fn settle_income_redirect(&mut self, owner: AccountOwner) {
+        if self.runtime.authenticated_signer() != Some(owner) {
+            return;
+        }
+        // Runs before every operation, so bail out early unless a share is owed here
+        let pending = self.state.pending_redirect(owner).await.unwrap_or_default();
+        if pending == Amount::ZERO {
+            return;
+        }
+        let redirect = match self.state.get_income_redirect(owner).await {
+            Ok(Some(r)) if r.chain_id == self.runtime.chain_id().to_string() => r,
+            _ => return,
+        };
+        let available = match self.payment_app() {
+            Some(_) => self.balance_of(owner),
+            None => self.available_balance(owner).await.0,
+        };
+        let amount = pending.min(available);
+        if amount == Amount::ZERO {
+            return;
+        }
+        
+        let target = self.normalize_account(redirect.target);
+        if self.pay(owner, target, amount).await.is_err() {
+            return;
+        }
+        if let Ok(true) = self.state.settle_redirect(owner, amount).await {
+            self.report_overflow("redirect_total");
+        }
+        
+        // Record the redirect as an outgoing donation linked to the pledge
+        let ts = self.runtime.system_time().micros();
+        let current_chain = self.runtime.chain_id();
+        let message = Some(format!("Income pledge: {}", redirect.label));
+        let source_chain_id = (target.chain_id != current_chain).then(|| current_chain.to_string());
+        let payment_app = self.payment_app_label();
+        let id = self.state.record_donation(owner, target.owner, amount, message.clone(), source_chain_id.clone(), None, Some(target.chain_id.to_string()), ts, payment_app).await.or_fail("Failed to record redirect");
+        if source_chain_id.is_some() {
+            self.runtime.prepare_message(Message::TransferWithMessage { owner: target.owner, amount, text_message: message.clone(), source_chain_id: current_chain, source_owner: owner, source_donation_id: id }).with_authentication().send_to(target.chain_id);
+        }
+        let source_donation_id = source_chain_id.as_ref().map(|_| id);
+        self.emit_event(DonationsEvent::DonationSent { id, from: owner, to: target.owner, amount, message, source_chain_id, source_donation_id, to_chain_id: Some(target.chain_id.to_string()), timestamp: ts });
+        
+        self.emit_event(DonationsEvent::IncomeRedirected {
+            owner,
+            target_chain_id: target.chain_id.to_string(),
+            target_owner: target.owner,
+            amount,
+            label: redirect.label,
+            timestamp: ts,
+        });
+    }
+    
     /// Broadcast updated poll results to all active subscribers
-    async fn broadcast_poll_update(&mut self, post_id: &str, poll: &donations::Poll, author: AccountOwner) {
+    async fn broadcast_poll_update(&mut self, post_id: &str, poll: &donations::Poll, author: AccountOwner, force: bool) {
         let ts = self.runtime.system_time().micros();
         let author_chain_id = self.runtime.chain_id();
         
         // Emit poll updated event
-        self.runtime.emit("donations_events".into(), &DonationsEvent::PollResultsUpdated {
+        self.emit_event(DonationsEvent::PollResultsUpdated {
             post_id: post_id.to_string(),
             poll: poll.clone(),
             timestamp: ts,
         });
         
+        // Coalesce messages to subscribers; the interval is only checked when a vote comes in
+        let due = force
+            || poll.votes_since_broadcast >= poll.broadcast_every_votes
+            || ts.saturating_sub(poll.last_broadcast_at) >= poll.broadcast_interval_micros;
+        if !due {
+            return;
+        }
+        let poll = match self.state.mark_poll_broadcast(post_id, ts).await {
+            Ok(p) => p,
+            Err(_) => return,
+        };
+        
         // Get all active subscriptions and send to subscribers
         let all_subs = self.state.subscriptions_by_author.get(&author).await
             .ok()
             .flatten()
             .unwrap_or_default();
+        let grace = self.state.subscription_grace_micros(author).await;
         
         for sub_id in all_subs {
             if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
-                if sub.end_timestamp >= ts {
+                if sub.allows(SubscriptionAccess::Read, grace, ts) {
                     // Active subscription - send poll update
                     if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
                         if subscriber_chain_id != author_chain_id {
@@ -1228,10 +3233,11 @@ impl DonationsContract {
             .ok()
             .flatten()
             .unwrap_or_default();
+        let grace = self.state.subscription_grace_micros(author).await;
         
         for sub_id in all_subs {
             if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
-                if sub.end_timestamp >= ts {
+                if sub.allows(SubscriptionAccess::Read, grace, ts) {
                     // Active subscription - send giveaway update
                     if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
                         if subscriber_chain_id != author_chain_id {
@@ -1245,6 +3251,1391 @@ impl DonationsContract {
             }
         }
     }
+    
+    /// Send a new or changed comment, with its parent linkage, to all active subscribers
+    async fn broadcast_comment(&mut self, comment: donations::Comment, author: AccountOwner, is_new: bool) {
+        let ts = self.runtime.system_time().micros();
+        let author_chain_id = self.runtime.chain_id();
+        
+        let all_subs = self.state.subscriptions_by_author.get(&author).await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let grace = self.state.subscription_grace_micros(author).await;
+        
+        for sub_id in all_subs {
+            if let Ok(Some(sub)) = self.state.content_subscriptions.get(&sub_id).await {
+                if sub.allows(SubscriptionAccess::Read, grace, ts) {
+                    if let Ok(subscriber_chain_id) = sub.subscriber_chain_id.parse() {
+                        if subscriber_chain_id != author_chain_id {
+                            let message = if is_new {
+                                Message::CommentPublished { comment: comment.clone() }
+                            } else {
+                                Message::CommentUpdated { comment: comment.clone() }
+                            };
+                            self.runtime.prepare_message(message).with_authentication().send_to(subscriber_chain_id);
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linera_sdk::{linera_base_types::{CryptoHash, Timestamp}, util::BlockingWait};
+    use donations::DonationSide;
+    use state::{DonationPageQuery, MICROS_PER_DAY, EVENT_RATE_WINDOW_MICROS, MAX_EVENTS_PER_WINDOW, PROFILE_UPDATE_COOLDOWN_MICROS};
+
+    // 2025-02-10T00:00:00Z
+    const FEB_10_2025: u64 = 1_739_145_600_000_000;
+
+    fn owner(name: &str) -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash(name))
+    }
+
+    fn chain(name: &str) -> ChainId {
+        ChainId(CryptoHash::test_hash(name))
+    }
+
+    /// A contract on chain "main" with `signer` signing at `now`, every named owner holding 100 tokens
+    fn contract(signer: AccountOwner, now: u64) -> DonationsContract {
+        let parameters = DonationsParameters { ticker_symbol: "LF".to_string(), payment_app: None, max_message_chars: None, operator_mode: false };
+        let balances = ["alice", "bob", "carol"].map(|name| (owner(name), Amount::from_tokens(100)));
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(parameters)
+            .with_chain_id(chain("main"))
+            .with_authenticated_signer(signer)
+            .with_system_time(Timestamp::from(now))
+            .with_owner_balances(balances)
+            .with_chain_balance(Amount::from_tokens(1_000));
+        DonationsContract::load(runtime).blocking_wait()
+    }
+
+    fn run(contract: &mut DonationsContract, operation: Operation) -> Result<(), String> {
+        match contract.execute_operation(operation).blocking_wait() {
+            ResponseData::Error { code, .. } => Err(code),
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn supporter_draw_commit_after_month_end_is_rejected() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        let late = run(&mut contract, Operation::CommitSupporterDraw { year: 2025, month: 1, commitment: "c".to_string() });
+        assert_eq!(late, Err("invalid".to_string()));
+        let current = run(&mut contract, Operation::CommitSupporterDraw { year: 2025, month: 2, commitment: "c".to_string() });
+        assert_eq!(current, Ok(()));
+    }
+
+    #[test]
+    fn donation_reply_finds_the_donors_copy_by_its_source_id() {
+        // Alice on chain "main" donates to Bob on chain "bob"
+        let mut donor = contract(owner("alice"), FEB_10_2025);
+        let target_account = FungibleAccount { chain_id: chain("bob"), owner: owner("bob") };
+        run(&mut donor, Operation::Transfer { owner: owner("alice"), amount: Amount::from_tokens(5), target_account, text_message: None }).unwrap();
+        let sent = donor.runtime.created_send_message_requests().last().map(|request| request.message.clone());
+        let Some(Message::TransferWithMessage { source_donation_id, .. }) = sent else { panic!("no transfer message") };
+
+        // Bob's chain records it later, under its own id, and Bob replies later still
+        let mut recipient = contract(owner("bob"), FEB_10_2025 + 5_000_000);
+        recipient.runtime.set_chain_id(chain("bob"));
+        recipient.state.record_donation(owner("carol"), owner("bob"), Amount::ONE, None, None, None, None, FEB_10_2025, None).blocking_wait().unwrap();
+        recipient.execute_message(Message::TransferWithMessage {
+            owner: owner("bob"), amount: Amount::from_tokens(5), text_message: None,
+            source_chain_id: chain("main"), source_owner: owner("alice"), source_donation_id,
+        }).blocking_wait();
+        recipient.runtime.set_system_time(Timestamp::from(FEB_10_2025 + 9_000_000));
+        run(&mut recipient, Operation::ReplyToDonation { donation_id: 2, text: "Thanks!".to_string() }).unwrap();
+        let reply = recipient.runtime.created_send_message_requests().iter()
+            .find(|request| matches!(request.message, Message::DonationReplied { .. })).cloned().expect("no reply message");
+        assert_eq!(reply.destination, chain("main"));
+
+        donor.runtime.set_authenticated_signer(owner("bob"));
+        donor.execute_message(reply.message).blocking_wait();
+        let rec = donor.state.donations.get(&source_donation_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(rec.reply.as_deref(), Some("Thanks!"));
+        assert_eq!(rec.replied_at, Some(FEB_10_2025 + 9_000_000));
+    }
+
+    #[test]
+    fn activity_window_caps_every_export_and_resumes_inside_a_crowded_timestamp() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        let cap = donations::MAX_EXPORT_RECORDS;
+        let donate = |contract: &mut DonationsContract, count: usize, at: u64| for _ in 0..count {
+            contract.state.record_donation(owner("alice"), owner("bob"), Amount::ONE, None, None, None, None, at, None).blocking_wait().unwrap();
+        };
+        donate(&mut contract, cap + 2, FEB_10_2025);
+        donate(&mut contract, 3, FEB_10_2025 + 1);
+        let window = |skip| contract.state.activity_window(FEB_10_2025, FEB_10_2025 + 10, skip).blocking_wait().unwrap();
+
+        let (first, continuation) = window(0);
+        assert_eq!(first.len(), cap);
+        assert_eq!(continuation, Some((FEB_10_2025, cap as u32)));
+        let (rest, continuation) = window(cap as u32);
+        assert_eq!(rest.len(), 5);
+        assert_eq!(continuation, None);
+        let ids = |records: &[donations::ActivityRecord]| records.iter().map(|r| match r { donations::ActivityRecord::Donation { id, .. } => *id, _ => 0 }).collect::<Vec<_>>();
+        let mut all = ids(&first);
+        all.extend(ids(&rest));
+        assert_eq!(all, (1..=cap as u64 + 5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn activity_window_cuts_at_a_timestamp_boundary_when_it_can() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        let cap = donations::MAX_EXPORT_RECORDS;
+        for (count, at) in [(cap - 2, FEB_10_2025), (5, FEB_10_2025 + 1)] {
+            for _ in 0..count {
+                contract.state.record_donation(owner("alice"), owner("bob"), Amount::ONE, None, None, None, None, at, None).blocking_wait().unwrap();
+            }
+        }
+        let (records, continuation) = contract.state.activity_window(FEB_10_2025, FEB_10_2025 + 10, 0).blocking_wait().unwrap();
+        assert_eq!(records.len(), cap - 2);
+        assert_eq!(continuation, Some((FEB_10_2025 + 1, 0)));
+    }
+
+    /// Bob's product on chain "main", priced at 5 tokens
+    fn product(stock: Option<u32>) -> donations::Product {
+        donations::Product {
+            id: "prod".to_string(), author: owner("bob"), author_chain_id: chain("main").to_string(),
+            public_data: Default::default(), price: Amount::from_tokens(5), private_data: Default::default(), encrypted: false,
+            success_message: None, order_form: Vec::new(), slug: None, category: None, entitlements: vec!["vip".to_string()],
+            content_revision: 0, form_revision: 0, created_at: FEB_10_2025, stock,
+        }
+    }
+
+    fn order(purchase_id: &str) -> Message {
+        Message::OrderReceived {
+            purchase_id: purchase_id.to_string(), product_id: "prod".to_string(), buyer: owner("alice"), buyer_chain_id: chain("alice"),
+            amount: Amount::from_tokens(5), order_data: Default::default(), timestamp: FEB_10_2025,
+        }
+    }
+
+    #[test]
+    fn order_after_sell_out_is_reported_to_the_buyer() {
+        let mut seller = contract(owner("alice"), FEB_10_2025);
+        seller.state.create_product(product(Some(1))).blocking_wait().unwrap();
+        seller.execute_message(order("first")).blocking_wait();
+        seller.execute_message(order("second")).blocking_wait();
+
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().stock, Some(0));
+        let first = seller.state.get_purchase("first").blocking_wait().unwrap().unwrap();
+        let second = seller.state.get_purchase("second").blocking_wait().unwrap().unwrap();
+        assert_eq!((first.status, second.status), (OrderStatus::Pending, OrderStatus::SoldOut));
+        let failed: Vec<_> = seller.runtime.created_send_message_requests().iter()
+            .filter_map(|request| match &request.message {
+                Message::OrderFailed { order, .. } => Some((request.destination, order.purchase_id.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(failed, vec![(chain("alice"), "second".to_string())]);
+
+        // Refunding the sold-out order doesn't invent a copy
+        seller.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut seller, Operation::RefundPurchase { purchase_id: "second".to_string() }).unwrap();
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().stock, Some(0));
+    }
+
+    #[test]
+    fn order_with_bad_data_is_refused_on_the_sellers_chain() {
+        let mut seller = contract(owner("alice"), FEB_10_2025);
+        let mut listed = product(Some(2));
+        listed.order_form = vec![donations::OrderFormField {
+            key: "email".to_string(), label: "Email".to_string(), field_type: "email".to_string(), required: true,
+        }];
+        seller.state.create_product(listed).blocking_wait().unwrap();
+        let with_data = |purchase_id: &str, answers: &[(&str, &str)]| match order(purchase_id) {
+            Message::OrderReceived { purchase_id, product_id, buyer, buyer_chain_id, amount, timestamp, .. } => Message::OrderReceived {
+                purchase_id, product_id, buyer, buyer_chain_id, amount, timestamp,
+                order_data: answers.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            },
+            _ => unreachable!(),
+        };
+        seller.execute_message(with_data("missing", &[])).blocking_wait();
+        seller.execute_message(with_data("unknown", &[("email", "a@b.c"), ("shoe_size", "42")])).blocking_wait();
+        seller.execute_message(with_data("good", &[("email", "a@b.c")])).blocking_wait();
+
+        let status = |id: &str| seller.state.get_purchase(id).blocking_wait().unwrap().unwrap().status;
+        assert_eq!((status("missing"), status("unknown"), status("good")), (OrderStatus::Rejected, OrderStatus::Rejected, OrderStatus::Pending));
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().stock, Some(1));
+        let failed: Vec<_> = seller.runtime.created_send_message_requests().iter()
+            .filter_map(|request| match &request.message {
+                Message::OrderFailed { order, .. } => Some((order.purchase_id.clone(), order.reason.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(failed, vec![
+            ("missing".to_string(), "Missing required order form field: Email".to_string()),
+            ("unknown".to_string(), "Unknown order form field: shoe_size".to_string()),
+        ]);
+
+        // Refunding a refused order doesn't restock a copy it never took
+        seller.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut seller, Operation::RefundPurchase { purchase_id: "missing".to_string() }).unwrap();
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().stock, Some(1));
+    }
+
+    #[test]
+    fn goal_progress_counts_only_donations_after_the_goal_was_set() {
+        let mut contract = contract(owner("bob"), FEB_10_2025);
+        let donate = |contract: &mut DonationsContract, tokens: u128, at: u64| {
+            contract.state.record_donation(owner("alice"), owner("bob"), Amount::from_tokens(tokens), None, None, None, None, at, None).blocking_wait().unwrap();
+        };
+        donate(&mut contract, 30, FEB_10_2025 - 1);
+        let goal = Operation::SetDonationGoal { target: Amount::from_tokens(40), deadline_micros: FEB_10_2025 + 10, description: None };
+        run(&mut contract, goal).unwrap();
+        donate(&mut contract, 10, FEB_10_2025);
+
+        let goal = contract.state.donation_goals.get(&owner("bob")).blocking_wait().unwrap().unwrap();
+        let received = contract.state.goal_received(owner("bob")).blocking_wait().unwrap();
+        assert_eq!(received, Amount::from_tokens(10));
+        assert_eq!(goal.progress_bps(received), 2_500);
+        assert_eq!(contract.state.total_received(owner("bob")).blocking_wait().unwrap(), Amount::from_tokens(40));
+
+        // Replacing the goal starts counting again
+        contract.runtime.set_system_time(Timestamp::from(FEB_10_2025 + 1));
+        let goal = Operation::SetDonationGoal { target: Amount::from_tokens(40), deadline_micros: FEB_10_2025 + 10, description: None };
+        run(&mut contract, goal).unwrap();
+        assert_eq!(contract.state.goal_received(owner("bob")).blocking_wait().unwrap(), Amount::ZERO);
+    }
+
+    #[test]
+    fn all_donations_page_walks_ids_from_the_cursor() {
+        let mut source = contract(owner("alice"), FEB_10_2025);
+        for _ in 0..5 {
+            source.state.record_donation(owner("alice"), owner("bob"), Amount::ONE, None, None, None, None, FEB_10_2025, None).blocking_wait().unwrap();
+        }
+        let page = |state: &DonationsState, after_id, offset, newest_first| {
+            let query = DonationPageQuery { after_id, offset, limit: 2, newest_first };
+            let (records, total) = state.list_all_donations_page(query).blocking_wait().unwrap();
+            (records.iter().map(|r| r.id).collect::<Vec<_>>(), total)
+        };
+        assert_eq!(page(&source.state, None, 0, true), (vec![5, 4], 5));
+        assert_eq!(page(&source.state, Some(4), 0, true), (vec![3, 2], 5));
+        assert_eq!(page(&source.state, Some(2), 0, true), (vec![1], 5));
+        assert_eq!(page(&source.state, None, 1, false), (vec![2, 3], 5));
+        assert_eq!(page(&source.state, Some(4), 0, false), (vec![5], 5));
+
+        // A restored snapshot may leave ids unused; they are stepped over and not counted
+        let kept = [1, 3, 5].map(|id| source.state.donations.get(&id).blocking_wait().unwrap().unwrap());
+        let mut restored = contract(owner("alice"), FEB_10_2025);
+        let data = donations::SnapshotData {
+            scope: donations::SnapshotScope { donations: true, ..Default::default() },
+            donations: kept.to_vec(),
+            ..Default::default()
+        };
+        restored.state.restore_snapshot(data).blocking_wait().unwrap();
+        assert_eq!(page(&restored.state, None, 0, true), (vec![5, 3], 3));
+        assert_eq!(page(&restored.state, Some(3), 0, true), (vec![1], 3));
+    }
+
+    #[test]
+    fn all_donations_between_read_the_day_indexes() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        for (to, day) in [("bob", 0), ("carol", 1), ("bob", 1), ("carol", 3)] {
+            let at = FEB_10_2025 + day * MICROS_PER_DAY;
+            contract.state.record_donation(owner("alice"), owner(to), Amount::ONE, None, None, None, None, at, None).blocking_wait().unwrap();
+        }
+        let between = |state: &DonationsState, side, from_day: u64, to_day: u64| {
+            let (from, to) = (FEB_10_2025 + from_day * MICROS_PER_DAY, FEB_10_2025 + to_day * MICROS_PER_DAY);
+            state.list_donations_between(side, Some(from), Some(to)).blocking_wait().unwrap().iter().map(|r| r.id).collect::<Vec<_>>()
+        };
+        assert_eq!(between(&contract.state, DonationSide::All, 1, 2), vec![2, 3]);
+        assert_eq!(between(&contract.state, DonationSide::Recipient(owner("carol")), 1, 3), vec![2, 4]);
+
+        // Donations older than the day indexes are found by walking every id instead
+        contract.state.day_index_start.set(Some(2));
+        assert_eq!(between(&contract.state, DonationSide::All, 0, 1), vec![1, 2, 3]);
+        assert_eq!(between(&contract.state, DonationSide::Donor(owner("alice")), 3, 3), vec![4]);
+    }
+
+    #[test]
+    fn state_errors_map_to_stable_codes() {
+        let code = |error: StateError| OperationError::from(error).code;
+        assert_eq!(code(StateError::NotFound("Product")), "not_found");
+        assert_eq!(code(StateError::Unauthorized("not admin")), "unauthorized");
+        assert_eq!(code(StateError::Validation("bad".to_string())), "invalid");
+
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        assert_eq!(run(&mut contract, Operation::DeleteDonationGoal), Err("not_found".to_string()));
+    }
+
+    #[test]
+    fn product_is_deleted_only_by_its_author() {
+        let mut state = contract(owner("alice"), FEB_10_2025).state;
+        state.create_product(product(None)).blocking_wait().unwrap();
+        let refused = state.delete_product("prod", owner("alice")).blocking_wait();
+        assert!(matches!(refused, Err(StateError::Unauthorized("not product owner"))));
+        assert!(state.get_product("prod").blocking_wait().unwrap().is_some());
+        assert!(matches!(state.delete_product("gone", owner("bob")).blocking_wait(), Err(StateError::NotFound("Product"))));
+        state.delete_product("prod", owner("bob")).blocking_wait().unwrap();
+        assert!(state.get_product("prod").blocking_wait().unwrap().is_none());
+    }
+
+    fn update_product(product_id: &str, price: Amount, slug: Option<&str>, category: Option<&str>) -> Operation {
+        Operation::UpdateProduct {
+            product_id: product_id.to_string(), public_data: None, price: Some(price), private_data: None, encrypted: None, success_message: None,
+            order_form: None, slug: slug.map(str::to_string), category: category.map(str::to_string), entitlements: None, stock: None, clear_stock: None,
+        }
+    }
+
+    #[test]
+    fn product_edit_with_a_bad_slug_or_category_changes_nothing() {
+        let mut seller = contract(owner("bob"), FEB_10_2025);
+        bobs_products(&mut seller.state, "main", 2);
+        run(&mut seller, update_product("prod1", Amount::from_tokens(5), Some("hat"), None)).unwrap();
+        let price = Amount::from_tokens(7);
+        for (slug, category) in [(Some("Not A Slug"), None), (Some("hat"), None), (None, Some(" padded "))] {
+            assert_eq!(run(&mut seller, update_product("prod2", price, slug, category)), Err("invalid".to_string()));
+        }
+        assert_eq!(run(&mut seller, update_product("missing", price, None, None)), Err("not_found".to_string()));
+        let unchanged = seller.state.get_product("prod2").blocking_wait().unwrap().unwrap();
+        assert_eq!((unchanged.price, unchanged.slug, unchanged.category), (Amount::from_tokens(5), None, None));
+
+        run(&mut seller, update_product("prod2", price, Some("scarf"), Some("Clothing"))).unwrap();
+        let updated = seller.state.get_product("prod2").blocking_wait().unwrap().unwrap();
+        assert_eq!((updated.price, updated.slug.as_deref(), updated.category.as_deref()), (price, Some("scarf"), Some("Clothing")));
+    }
+
+    #[test]
+    fn another_sellers_product_cannot_be_deleted() {
+        let mut seller = contract(owner("alice"), FEB_10_2025);
+        seller.state.create_product(product(None)).blocking_wait().unwrap();
+        assert_eq!(run(&mut seller, Operation::DeleteProduct { product_id: "prod".to_string() }), Err("unauthorized".to_string()));
+        seller.execute_message(Message::ProductDeleted { product_id: "prod".to_string(), author: owner("bob") }).blocking_wait();
+        assert!(seller.state.get_product("prod").blocking_wait().unwrap().is_some());
+    }
+
+    fn update_name(name: &str) -> Operation {
+        Operation::UpdateProfile { name: Some(name.to_string()), bio: None, socials: Vec::new(), avatar_hash: None, header_hash: None }
+    }
+
+    #[test]
+    fn profile_update_with_an_unchanged_name_emits_no_event() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        run(&mut contract, update_name("")).unwrap();
+        assert_eq!(contract.events_emitted, 1);
+        assert_eq!(contract.state.profiles.get(&owner("alice")).blocking_wait().unwrap().unwrap().name, "anon");
+
+        // "anon" is what the empty name was stored as, so nothing changed
+        contract.runtime.set_system_time(Timestamp::from(FEB_10_2025 + PROFILE_UPDATE_COOLDOWN_MICROS));
+        run(&mut contract, update_name("anon")).unwrap();
+        assert_eq!(contract.events_emitted, 0);
+    }
+
+    #[test]
+    fn signer_over_the_event_budget_waits_for_the_next_window() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        contract.state.note_events(owner("alice"), FEB_10_2025, MAX_EVENTS_PER_WINDOW - 1).blocking_wait().unwrap();
+        run(&mut contract, update_name("Alice")).unwrap();
+        assert_eq!(contract.state.event_rate.get(&owner("alice")).blocking_wait().unwrap(), Some((FEB_10_2025, MAX_EVENTS_PER_WINDOW)));
+
+        let later = FEB_10_2025 + PROFILE_UPDATE_COOLDOWN_MICROS;
+        contract.runtime.set_system_time(Timestamp::from(later));
+        assert_eq!(run(&mut contract, update_name("Al")), Err("invalid".to_string()));
+        // Other signers keep their own budget
+        contract.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut contract, update_name("Bob")).unwrap();
+
+        contract.runtime.set_authenticated_signer(owner("alice"));
+        contract.runtime.set_system_time(Timestamp::from(FEB_10_2025 + EVENT_RATE_WINDOW_MICROS));
+        run(&mut contract, update_name("Al")).unwrap();
+        assert_eq!(contract.state.event_rate.get(&owner("alice")).blocking_wait().unwrap(), Some((FEB_10_2025 + EVENT_RATE_WINDOW_MICROS, 1)));
+    }
+
+    #[test]
+    fn product_moved_to_a_new_chain_is_stored_there() {
+        let mut old_home = contract(owner("bob"), FEB_10_2025);
+        old_home.state.create_product(product(Some(3))).blocking_wait().unwrap();
+        run(&mut old_home, Operation::UpdateProductChain { product_id: "prod".to_string(), new_chain_id: chain("home") }).unwrap();
+        let migrated = old_home.runtime.created_send_message_requests().iter()
+            .find(|request| matches!(&request.message, Message::Tracked { message, .. } if matches!(**message, Message::ProductMigrated { .. })))
+            .cloned().expect("no migration message");
+        assert_eq!(migrated.destination, chain("home"));
+
+        let mut new_home = contract(owner("bob"), FEB_10_2025);
+        new_home.runtime.set_chain_id(chain("home"));
+        new_home.runtime.set_message_origin_chain_id(chain("main"));
+        new_home.execute_message(migrated.message).blocking_wait();
+        let stored = new_home.state.get_product("prod").blocking_wait().unwrap().expect("product not migrated");
+        assert_eq!((stored.author_chain_id, stored.stock), (chain("home").to_string(), Some(3)));
+
+        // Orders sent to the new chain now find the product
+        new_home.execute_message(order("p1")).blocking_wait();
+        assert!(new_home.state.get_purchase("p1").blocking_wait().unwrap().is_some());
+    }
+
+    fn subscription_payment(amount: Amount) -> Message {
+        Message::SubscriptionPayment {
+            subscriber: owner("alice"), subscriber_chain_id: chain("alice").to_string(), author: owner("bob"),
+            amount, duration_micros: donations::SUBSCRIPTION_PERIOD_MICROS, timestamp: FEB_10_2025,
+        }
+    }
+
+    #[test]
+    fn rejected_subscription_payment_is_refunded_on_the_authors_next_operation() {
+        // Bob offers no subscription, so Alice's payment is refused
+        let mut author = contract(owner("alice"), FEB_10_2025);
+        author.execute_message(subscription_payment(Amount::from_tokens(5))).blocking_wait();
+        let refunds = author.state.get_pending_refunds(owner("bob")).blocking_wait().unwrap();
+        assert_eq!(refunds.len(), 1);
+        assert!(author.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap().is_empty());
+
+        author.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut author, Operation::RetryOutbox).unwrap();
+        let alice = Account { chain_id: chain("alice"), owner: owner("alice") };
+        assert_eq!(author.runtime.outgoing_transfers().get(&alice), Some(&Amount::from_tokens(5)));
+        assert!(author.state.get_pending_refunds(owner("bob")).blocking_wait().unwrap().is_empty());
+    }
+
+    #[test]
+    fn renewal_rejected_by_the_authors_chain_is_refunded() {
+        // Alice on chain "main" subscribes to Bob on chain "bob" with auto-renew
+        let mut subscriber = contract(owner("alice"), FEB_10_2025);
+        let target_account = FungibleAccount { chain_id: chain("bob"), owner: owner("bob") };
+        run(&mut subscriber, Operation::SubscribeToAuthor { owner: owner("alice"), amount: Amount::from_tokens(4), target_account, auto_renew: Some(true) }).unwrap();
+        subscriber.runtime.set_system_time(Timestamp::from(FEB_10_2025 + donations::SUBSCRIPTION_PERIOD_MICROS - 1));
+        run(&mut subscriber, Operation::ProcessAutoRenewals).unwrap();
+        let renewal = subscriber.runtime.created_send_message_requests().iter()
+            .filter(|request| matches!(request.message, Message::SubscriptionPayment { .. })).nth(1).cloned().expect("no renewal payment");
+
+        // Bob has since raised his price, so his chain refuses the renewal and owes it back
+        let mut author = contract(owner("bob"), FEB_10_2025 + donations::SUBSCRIPTION_PERIOD_MICROS);
+        author.runtime.set_chain_id(chain("bob"));
+        run(&mut author, Operation::SetSubscriptionPrice { price: Amount::from_tokens(10), description: None, grandfather_renewals: 0 }).unwrap();
+        author.runtime.set_authenticated_signer(owner("alice"));
+        author.runtime.set_message_origin_chain_id(chain("main"));
+        author.execute_message(renewal.message).blocking_wait();
+        author.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut author, Operation::RetryOutbox).unwrap();
+        let alice = Account { chain_id: chain("main"), owner: owner("alice") };
+        assert_eq!(author.runtime.outgoing_transfers().get(&alice), Some(&Amount::from_tokens(4)));
+    }
+
+    #[test]
+    fn underpaid_subscription_is_rejected_and_refunded_in_full() {
+        let mut author = contract(owner("bob"), FEB_10_2025);
+        run(&mut author, Operation::SetSubscriptionPrice { price: Amount::from_tokens(10), description: None, grandfather_renewals: 0 }).unwrap();
+        author.runtime.set_authenticated_signer(owner("alice"));
+        author.execute_message(subscription_payment(Amount::from_tokens(4))).blocking_wait();
+
+        assert!(author.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap().is_empty());
+        let refunds = author.state.get_pending_refunds(owner("bob")).blocking_wait().unwrap();
+        assert_eq!(refunds.len(), 1);
+        assert_eq!((refunds[0].to, refunds[0].amount), (owner("alice"), Amount::from_tokens(4)));
+        assert!(refunds[0].reason.contains("Insufficient subscription payment"));
+    }
+
+    #[test]
+    fn subscription_terms_are_taken_only_from_the_authors_chain() {
+        let mut subscriber = contract(owner("alice"), FEB_10_2025);
+        let target_account = FungibleAccount { chain_id: chain("bob"), owner: owner("bob") };
+        run(&mut subscriber, Operation::SubscribeToAuthor { owner: owner("alice"), amount: Amount::from_tokens(4), target_account, auto_renew: None }).unwrap();
+        let sub_id = subscriber.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap()[0].id.clone();
+        let confirmed = Message::SubscriptionConfirmed {
+            subscription_id: sub_id.clone(), subscriber: owner("alice"), author: owner("bob"),
+            locked_price: Amount::ONE, renewals_remaining_at_locked_price: 9, renewal_price: Amount::ONE,
+        };
+        let terms = |subscriber: &DonationsContract| {
+            let sub = subscriber.state.content_subscriptions.get(&sub_id).blocking_wait().unwrap().unwrap();
+            (sub.locked_price, sub.renewals_remaining_at_locked_price)
+        };
+        let granted = terms(&subscriber);
+
+        // From another chain, or signed by neither party, the terms are ignored
+        subscriber.runtime.set_message_origin_chain_id(chain("carol"));
+        subscriber.execute_message(confirmed.clone()).blocking_wait();
+        subscriber.runtime.set_message_origin_chain_id(chain("bob"));
+        subscriber.runtime.set_authenticated_signer(owner("carol"));
+        subscriber.execute_message(confirmed.clone()).blocking_wait();
+        assert_eq!(terms(&subscriber), granted);
+        assert!(subscriber.state.renewal_price_cache.get(&format!("{}:{}", owner("bob"), owner("alice"))).blocking_wait().unwrap().is_none());
+
+        subscriber.runtime.set_authenticated_signer(owner("alice"));
+        subscriber.execute_message(confirmed).blocking_wait();
+        assert_eq!(terms(&subscriber), (Amount::ONE, 9));
+    }
+
+    #[test]
+    fn price_change_notice_is_filed_only_when_the_author_signed_it() {
+        let mut subscriber = contract(owner("carol"), FEB_10_2025);
+        let notice = Message::PriceChangeNotice {
+            subscriber: owner("alice"), author: owner("bob"), old_price: Amount::ONE, new_price: Amount::from_tokens(2), effective_at: FEB_10_2025,
+        };
+        subscriber.execute_message(notice.clone()).blocking_wait();
+        assert!(subscriber.state.notifications.get(&owner("alice")).blocking_wait().unwrap().unwrap_or_default().is_empty());
+        subscriber.runtime.set_authenticated_signer(owner("bob"));
+        subscriber.execute_message(notice).blocking_wait();
+        let inbox = subscriber.state.notifications.get(&owner("alice")).blocking_wait().unwrap().unwrap_or_default();
+        assert_eq!(inbox.iter().map(|n| (n.kind.as_str(), n.from)).collect::<Vec<_>>(), [("price_change", owner("bob"))]);
+    }
+
+    #[test]
+    fn rejection_tells_the_subscriber_about_the_refund() {
+        let mut subscriber = contract(owner("alice"), FEB_10_2025);
+        let target_account = FungibleAccount { chain_id: chain("bob"), owner: owner("bob") };
+        run(&mut subscriber, Operation::SubscribeToAuthor { owner: owner("alice"), amount: Amount::from_tokens(4), target_account, auto_renew: None }).unwrap();
+        let sub_id = subscriber.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap()[0].id.clone();
+
+        subscriber.execute_message(Message::SubscriptionRejected {
+            subscription_id: sub_id, subscriber: owner("alice"), author: owner("bob"),
+            amount: Amount::from_tokens(4), reason: "Insufficient subscription payment".to_string(),
+        }).blocking_wait();
+        assert!(subscriber.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap().is_empty());
+        let inbox = subscriber.state.notifications.get(&owner("alice")).blocking_wait().unwrap().unwrap_or_default();
+        let text = &inbox.last().expect("no notification").text;
+        assert!(text.contains("refunded") && text.contains(&Amount::from_tokens(4).to_string()));
+    }
+
+    #[test]
+    fn payment_with_a_malformed_subscriber_chain_is_refunded_to_its_origin() {
+        let mut author = contract(owner("alice"), FEB_10_2025);
+        author.runtime.set_message_origin_chain_id(chain("alice"));
+        author.execute_message(Message::SubscriptionPayment {
+            subscriber: owner("alice"), subscriber_chain_id: "not-a-chain".to_string(), author: owner("bob"),
+            amount: Amount::from_tokens(5), duration_micros: donations::SUBSCRIPTION_PERIOD_MICROS, timestamp: FEB_10_2025,
+        }).blocking_wait();
+        let refunds = author.state.get_pending_refunds(owner("bob")).blocking_wait().unwrap();
+        assert_eq!((refunds[0].to, refunds[0].to_chain_id), (owner("alice"), chain("alice")));
+    }
+
+    #[test]
+    fn post_with_a_malformed_author_chain_is_refused() {
+        let mut state = contract(owner("alice"), FEB_10_2025).state;
+        let post = donations::Post {
+            id: "p1".to_string(), author: owner("alice"), author_chain_id: "not-a-chain".to_string(), title: String::new(),
+            content: String::new(), image_hash: None, created_at: FEB_10_2025, poll: None, giveaway: None, visibility: Default::default(),
+        };
+        assert!(state.create_post(post).blocking_wait().is_err());
+    }
+
+    /// Bob's products "prod1".."prod`count`" on `home`
+    fn bobs_products(state: &mut DonationsState, home: &str, count: usize) {
+        for i in 1..=count {
+            let mut product = product(None);
+            product.id = format!("prod{}", i);
+            product.author_chain_id = chain(home).to_string();
+            state.create_product(product).blocking_wait().unwrap();
+        }
+    }
+
+    #[test]
+    fn featured_products_are_at_most_three_of_the_authors_own() {
+        let mut seller = contract(owner("bob"), FEB_10_2025);
+        bobs_products(&mut seller.state, "main", 4);
+        let mut foreign = product(None);
+        (foreign.id, foreign.author) = ("alices".to_string(), owner("alice"));
+        seller.state.create_product(foreign).blocking_wait().unwrap();
+        let feature = |seller: &mut DonationsContract, ids: &[&str]| {
+            run(seller, Operation::SetFeaturedProducts { product_ids: ids.iter().map(|id| id.to_string()).collect() })
+        };
+
+        assert_eq!(feature(&mut seller, &["prod1", "prod2", "prod3", "prod4"]), Err("invalid".to_string()));
+        assert_eq!(feature(&mut seller, &["prod1", "alices"]), Err("unauthorized".to_string()));
+        assert_eq!(feature(&mut seller, &["prod1", "never-published"]), Err("not_found".to_string()));
+        assert!(seller.state.get_featured_products(owner("bob")).blocking_wait().unwrap().is_empty());
+        assert_eq!(feature(&mut seller, &["prod3", "prod1"]), Ok(()));
+        assert_eq!(seller.state.get_featured_products(owner("bob")).blocking_wait().unwrap(), ["prod3", "prod1"]);
+    }
+
+    #[test]
+    fn deleted_product_is_unfeatured_on_both_chains() {
+        let mut seller = contract(owner("bob"), FEB_10_2025);
+        seller.runtime.set_chain_id(chain("seller"));
+        seller.state.subscriptions.insert(&owner("bob"), chain("main").to_string()).unwrap();
+        bobs_products(&mut seller.state, "seller", 2);
+        run(&mut seller, Operation::SetFeaturedProducts { product_ids: vec!["prod1".to_string(), "prod2".to_string()] }).unwrap();
+        run(&mut seller, Operation::DeleteProduct { product_id: "prod1".to_string() }).unwrap();
+        assert_eq!(seller.state.get_featured_products(owner("bob")).blocking_wait().unwrap(), ["prod2"]);
+        let sent = seller.runtime.created_send_message_requests().iter()
+            .filter(|request| request.destination == chain("main")).map(|request| request.message.clone()).collect::<Vec<_>>();
+
+        // The main chain takes the featured list from Bob only
+        let mut main = contract(owner("alice"), FEB_10_2025);
+        bobs_products(&mut main.state, "seller", 2);
+        main.execute_message(Message::FeaturedProductsUpdated { author: owner("bob"), product_ids: vec!["prod1".to_string()] }).blocking_wait();
+        assert!(main.state.get_featured_products(owner("bob")).blocking_wait().unwrap().is_empty());
+        main.runtime.set_authenticated_signer(owner("bob"));
+        main.runtime.set_message_origin_chain_id(chain("seller"));
+        for message in sent {
+            main.execute_message(message).blocking_wait();
+        }
+        assert_eq!(main.state.get_featured_products(owner("bob")).blocking_wait().unwrap(), ["prod2"]);
+    }
+
+    #[test]
+    fn storefront_order_is_mirrored_from_the_author_only() {
+        let mut main = contract(owner("alice"), FEB_10_2025);
+        bobs_products(&mut main.state, "seller", 2);
+        let reorder = Message::ProductOrderUpdated { author: owner("bob"), ordered_ids: vec!["prod2".to_string(), "prod1".to_string()] };
+        main.execute_message(reorder.clone()).blocking_wait();
+        assert!(main.state.get_product_order(owner("bob")).blocking_wait().unwrap().is_empty());
+        main.runtime.set_authenticated_signer(owner("bob"));
+        main.execute_message(reorder).blocking_wait();
+        assert_eq!(main.state.get_product_order(owner("bob")).blocking_wait().unwrap(), ["prod2", "prod1"]);
+    }
+
+    #[test]
+    fn slug_conflict_is_taken_only_from_the_sellers_main_chain() {
+        let mut seller = contract(owner("bob"), FEB_10_2025);
+        seller.runtime.set_chain_id(chain("seller"));
+        seller.state.subscriptions.insert(&owner("bob"), chain("main").to_string()).unwrap();
+        let mut slugged = product(None);
+        (slugged.author_chain_id, slugged.slug) = (chain("seller").to_string(), Some("hat".to_string()));
+        seller.state.create_product(slugged).blocking_wait().unwrap();
+        let conflict = Message::SlugConflict { product_id: "prod".to_string(), slug: "hat".to_string() };
+
+        seller.runtime.set_message_origin_chain_id(chain("carol"));
+        seller.execute_message(conflict.clone()).blocking_wait();
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().slug.as_deref(), Some("hat"));
+        seller.runtime.set_message_origin_chain_id(chain("main"));
+        seller.execute_message(conflict).blocking_wait();
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().slug, None);
+    }
+
+    /// Adjustments of `kind` counted on Feb 10, 2025
+    fn adjustments(state: &DonationsState, kind: &str) -> u64 {
+        let day = (FEB_10_2025 / MICROS_PER_DAY) as u32;
+        state.content_adjustments.get(&(kind.to_string(), day)).blocking_wait().unwrap().unwrap_or(0)
+    }
+
+    #[test]
+    fn oversized_donation_message_is_cut_flagged_and_counted() {
+        let mut recipient = contract(owner("bob"), FEB_10_2025);
+        for (source_donation_id, text) in [(1, "x".repeat(donations::DEFAULT_MAX_MESSAGE_CHARS + 1)), (2, "Thanks".to_string())] {
+            recipient.execute_message(Message::TransferWithMessage {
+                owner: owner("bob"), amount: Amount::ONE, text_message: Some(text),
+                source_chain_id: chain("alice"), source_owner: owner("alice"), source_donation_id,
+            }).blocking_wait();
+        }
+        let record = |id| recipient.state.donations.get(&id).blocking_wait().unwrap().unwrap();
+        assert_eq!(record(1).message.map(|m| m.len()), Some(donations::DEFAULT_MAX_MESSAGE_CHARS));
+        assert!(record(1).content_modified);
+        assert!(!record(2).content_modified);
+        assert_eq!(adjustments(&recipient.state, "donation_message"), 1);
+    }
+
+    #[test]
+    fn inbound_order_answers_are_cleaned_flagged_and_counted() {
+        let mut seller = contract(owner("alice"), FEB_10_2025);
+        let mut listed = product(None);
+        listed.order_form = vec![donations::OrderFormField {
+            key: "size".to_string(), label: "Size".to_string(), field_type: "text".to_string(), required: true,
+        }];
+        seller.state.create_product(listed).blocking_wait().unwrap();
+        for (purchase_id, size) in [("dirty", "L\u{0}"), ("clean", "M")] {
+            let Message::OrderReceived { product_id, buyer, buyer_chain_id, amount, timestamp, .. } = order(purchase_id) else { unreachable!() };
+            seller.execute_message(Message::OrderReceived {
+                purchase_id: purchase_id.to_string(), product_id, buyer, buyer_chain_id, amount, timestamp,
+                order_data: [("size".to_string(), size.to_string())].into(),
+            }).blocking_wait();
+        }
+        let purchase = |id: &str| seller.state.get_purchase(id).blocking_wait().unwrap().unwrap();
+        let dirty = purchase("dirty");
+        assert_eq!((dirty.order_data["size"].as_str(), dirty.content_modified, dirty.status), ("L", true, OrderStatus::Pending));
+        assert!(!purchase("clean").content_modified);
+        assert_eq!(adjustments(&seller.state, "order_data"), 1);
+    }
+
+    /// Alice's post on chain "main", with Bob subscribed from chain "bob"
+    fn commented_post(state: &mut DonationsState) {
+        state.create_post(donations::Post {
+            id: "p1".to_string(), author: owner("alice"), author_chain_id: chain("main").to_string(), title: String::new(),
+            content: String::new(), image_hash: None, created_at: FEB_10_2025, poll: None, giveaway: None, visibility: Default::default(),
+        }).blocking_wait().unwrap();
+        state.create_subscription(donations::ContentSubscription {
+            id: "s1".to_string(), subscriber: owner("bob"), subscriber_chain_id: chain("bob").to_string(),
+            author: owner("alice"), author_chain_id: chain("main").to_string(),
+            start_timestamp: FEB_10_2025, end_timestamp: FEB_10_2025 + donations::SUBSCRIPTION_PERIOD_MICROS,
+            price: Amount::ONE, locked_price: Amount::ONE, renewals_remaining_at_locked_price: 0, auto_renew: false, auto_renew_failures: 0,
+        }).blocking_wait().unwrap();
+    }
+
+    #[test]
+    fn comment_messages_from_a_forged_commenter_or_requester_are_ignored() {
+        let mut author = contract(owner("carol"), FEB_10_2025);
+        commented_post(&mut author.state);
+        let posted = Message::CommentPosted {
+            post_id: "p1".to_string(), parent_id: None, commenter: owner("bob"), commenter_chain_id: chain("bob"), text: "Hi".to_string(),
+        };
+        author.execute_message(posted.clone()).blocking_wait();
+        assert_eq!(author.state.comment_count("p1").blocking_wait().unwrap(), 0);
+        author.runtime.set_authenticated_signer(owner("bob"));
+        author.execute_message(posted).blocking_wait();
+        assert_eq!(author.state.comment_count("p1").blocking_wait().unwrap(), 1);
 
+        let delete = Message::CommentDeleteRequested { comment_id: "p1:1".to_string(), requester: owner("bob") };
+        author.runtime.set_authenticated_signer(owner("carol"));
+        author.execute_message(delete.clone()).blocking_wait();
+        assert!(!author.state.get_comment("p1:1").blocking_wait().unwrap().unwrap().deleted);
+        author.runtime.set_authenticated_signer(owner("bob"));
+        author.execute_message(delete).blocking_wait();
+        assert!(author.state.get_comment("p1:1").blocking_wait().unwrap().unwrap().deleted);
+    }
+
+    #[test]
+    fn inbound_comment_is_cleaned_flagged_and_counted() {
+        let mut author = contract(owner("bob"), FEB_10_2025);
+        commented_post(&mut author.state);
+        for text in ["Hi\u{7}", "Fine"] {
+            author.execute_message(Message::CommentPosted {
+                post_id: "p1".to_string(), parent_id: None, commenter: owner("bob"), commenter_chain_id: chain("bob"), text: text.to_string(),
+            }).blocking_wait();
+        }
+        let comment = |id: &str| author.state.get_comment(id).blocking_wait().unwrap().unwrap();
+        assert_eq!((comment("p1:1").text.as_str(), comment("p1:1").content_modified), ("Hi", true));
+        assert!(!comment("p1:2").content_modified);
+        assert_eq!(adjustments(&author.state, "comment"), 1);
+    }
+
+    #[test]
+    fn mirrored_comment_is_taken_only_from_the_posts_chain_and_its_participants() {
+        let mut subscriber = contract(owner("carol"), FEB_10_2025);
+        subscriber.runtime.set_chain_id(chain("bob"));
+        commented_post(&mut subscriber.state);
+        let published = Message::CommentPublished { comment: donations::Comment {
+            id: "p1:1".to_string(), post_id: "p1".to_string(), author: owner("bob"), author_chain_id: chain("bob").to_string(),
+            parent_id: None, text: "Hi".to_string(), created_at: FEB_10_2025, deleted: false, hidden: false, answered_at: None, content_modified: false,
+        } };
+        // A forged signer, then the right signer from the wrong chain
+        subscriber.runtime.set_message_origin_chain_id(chain("main"));
+        subscriber.execute_message(published.clone()).blocking_wait();
+        subscriber.runtime.set_authenticated_signer(owner("bob"));
+        subscriber.runtime.set_message_origin_chain_id(chain("carol"));
+        subscriber.execute_message(published.clone()).blocking_wait();
+        assert!(subscriber.state.get_comment("p1:1").blocking_wait().unwrap().is_none());
+
+        subscriber.runtime.set_message_origin_chain_id(chain("main"));
+        subscriber.execute_message(published).blocking_wait();
+        assert_eq!(subscriber.state.comment_count("p1").blocking_wait().unwrap(), 1);
+    }
+
+    #[test]
+    fn same_chain_purchase_is_recorded_once_with_its_answers_and_grants() {
+        let mut chain_main = contract(owner("alice"), FEB_10_2025);
+        chain_main.state.subscriptions.insert(&owner("alice"), chain("main").to_string()).unwrap();
+        let mut listed = product(None);
+        listed.order_form = vec![donations::OrderFormField {
+            key: "size".to_string(), label: "Size".to_string(), field_type: "text".to_string(), required: true,
+        }];
+        chain_main.state.create_product(listed).blocking_wait().unwrap();
+        run(&mut chain_main, Operation::TransferToBuy {
+            owner: owner("alice"), product_id: "prod".to_string(), amount: Amount::from_tokens(5),
+            target_account: fungible("main", "bob"), order_data: [("size".to_string(), "L".to_string())].into(),
+        }).unwrap();
+
+        // ProductPurchased and PurchaseReady, once each
+        assert!(chain_main.runtime.created_send_message_requests().is_empty());
+        assert_eq!(chain_main.events_emitted, 2);
+        let bought = chain_main.state.purchases_by_buyer.get(&owner("alice")).blocking_wait().unwrap().unwrap_or_default();
+        let sold = chain_main.state.purchases_by_seller.get(&owner("bob")).blocking_wait().unwrap().unwrap_or_default();
+        assert_eq!((bought.len(), &sold), (1, &bought));
+        let purchase = chain_main.state.get_purchase(&bought[0]).blocking_wait().unwrap().unwrap();
+        assert_eq!(purchase.order_data.get("size").map(String::as_str), Some("L"));
+        assert!(chain_main.state.has_entitlement(owner("alice"), "vip", FEB_10_2025).blocking_wait().unwrap());
+        assert!(chain_main.state.delivered_purchases.contains_key(&bought[0]).blocking_wait().unwrap());
+    }
+
+    #[test]
+    fn second_purchase_keeps_its_own_grant() {
+        let mut state = contract(owner("alice"), FEB_10_2025).state;
+        let product = product(None);
+        for purchase_id in ["first", "second"] {
+            let grants = DonationsState::purchase_grants(&product, purchase_id, FEB_10_2025);
+            state.store_entitlements(owner("alice"), grants).blocking_wait().unwrap();
+        }
+        state.store_entitlements(owner("alice"), DonationsState::purchase_grants(&product, "first", FEB_10_2025)).blocking_wait().unwrap();
+        assert_eq!(state.entitlements_of(owner("alice"), FEB_10_2025).blocking_wait().unwrap().len(), 2);
+
+        assert_eq!(state.revoke_purchase_entitlements(owner("alice"), "first").blocking_wait().unwrap(), 1);
+        assert!(state.has_entitlement(owner("alice"), "vip", FEB_10_2025).blocking_wait().unwrap());
+        state.revoke_purchase_entitlements(owner("alice"), "second").blocking_wait().unwrap();
+        assert!(!state.has_entitlement(owner("alice"), "vip", FEB_10_2025).blocking_wait().unwrap());
+    }
+
+    #[test]
+    fn refund_takes_the_purchase_grants_back_on_both_chains() {
+        let mut seller = contract(owner("bob"), FEB_10_2025);
+        seller.state.create_product(product(None)).blocking_wait().unwrap();
+        seller.execute_message(order("first")).blocking_wait();
+        assert!(seller.state.has_entitlement(owner("alice"), "vip", FEB_10_2025).blocking_wait().unwrap());
+
+        run(&mut seller, Operation::RefundPurchase { purchase_id: "first".to_string() }).unwrap();
+        assert!(!seller.state.has_entitlement(owner("alice"), "vip", FEB_10_2025).blocking_wait().unwrap());
+        let revoked: Vec<_> = seller.runtime.created_send_message_requests().iter()
+            .filter_map(|request| match &request.message {
+                Message::EntitlementsRevoked { buyer, purchase_id } => Some((request.destination, *buyer, purchase_id.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(revoked, vec![(chain("alice"), owner("alice"), "first".to_string())]);
+    }
+
+    #[test]
+    fn cancelled_order_is_paid_back_once() {
+        let mut seller = contract(owner("bob"), FEB_10_2025);
+        seller.state.create_product(product(Some(2))).blocking_wait().unwrap();
+        seller.execute_message(order("first")).blocking_wait();
+        run(&mut seller, Operation::UpdateOrderStatus { purchase_id: "first".to_string(), status: OrderStatus::Cancelled }).unwrap();
+        run(&mut seller, Operation::RefundPurchase { purchase_id: "first".to_string() }).unwrap();
+
+        let buyer = Account { chain_id: chain("alice"), owner: owner("alice") };
+        assert_eq!(seller.runtime.outgoing_transfers().get(&buyer), Some(&Amount::from_tokens(5)));
+        let purchase = seller.state.get_purchase("first").blocking_wait().unwrap().unwrap();
+        assert_eq!(purchase.status, OrderStatus::Cancelled);
+        assert_eq!(seller.state.get_product("prod").blocking_wait().unwrap().unwrap().stock, Some(2));
+        assert!(!seller.state.has_entitlement(owner("alice"), "vip", FEB_10_2025).blocking_wait().unwrap());
+    }
+
+    #[test]
+    fn zero_withdrawal_moves_everything_through_the_transfer_message_flow() {
+        let mut owner_chain = contract(owner("alice"), FEB_10_2025);
+        let wallet = Account { chain_id: chain("wallet"), owner: owner("alice") };
+        let target = linera_sdk::abis::fungible::Account { chain_id: wallet.chain_id, owner: wallet.owner };
+        run(&mut owner_chain, Operation::Withdraw { target: Some(target), amount: Some(Amount::ZERO) }).unwrap();
+
+        assert_eq!(owner_chain.runtime.outgoing_transfers().get(&wallet), Some(&Amount::from_tokens(100)));
+        let sent: Vec<_> = owner_chain.runtime.created_send_message_requests().iter()
+            .map(|request| (request.destination, request.message.clone())).collect();
+        let [(destination, Message::TransferWithMessage { owner: to, amount, source_owner, source_donation_id, .. })] = sent.as_slice() else { panic!("expected one transfer message") };
+        assert_eq!((*destination, *to, *amount, *source_owner), (chain("wallet"), owner("alice"), Amount::from_tokens(100), owner("alice")));
+        let record = owner_chain.state.donations.get(source_donation_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(record.to_chain_id, Some(chain("wallet").to_string()));
+
+        // The wallet's chain records it without counting it as income
+        let mut wallet_chain = contract(owner("alice"), FEB_10_2025);
+        wallet_chain.runtime.set_chain_id(chain("wallet"));
+        wallet_chain.execute_message(sent[0].1.clone()).blocking_wait();
+        assert_eq!(wallet_chain.state.donations.indices().blocking_wait().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn withdrawal_on_the_same_chain_records_nothing() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        run(&mut contract, Operation::Withdraw { target: None, amount: Some(Amount::from_tokens(3)) }).unwrap();
+        assert_eq!(contract.runtime.owner_balance(owner("alice")), Amount::from_tokens(97));
+        assert!(contract.runtime.created_send_message_requests().is_empty());
+        assert!(contract.state.donations.indices().blocking_wait().unwrap().is_empty());
+    }
+
+    #[test]
+    fn review_goes_to_the_products_chain() {
+        let mut buyer = contract(owner("alice"), FEB_10_2025);
+        buyer.runtime.set_chain_id(chain("alice"));
+        buyer.state.subscriptions.insert(&owner("alice"), chain("elsewhere").to_string()).unwrap();
+        buyer.execute_message(Message::SendProductData { buyer: owner("alice"), purchase_id: "first".to_string(), product: product(None), entitlements: Vec::new() }).blocking_wait();
+        run(&mut buyer, Operation::ReviewProduct { product_id: "prod".to_string(), rating: 5, comment: None }).unwrap();
+
+        let destinations: Vec<_> = buyer.runtime.created_send_message_requests().iter()
+            .filter(|request| matches!(&request.message, Message::Tracked { message, .. } if matches!(**message, Message::ProductReviewed { .. })))
+            .map(|request| request.destination)
+            .collect();
+        assert_eq!(destinations, vec![chain("main")]);
+    }
+
+    #[test]
+    fn withdrawal_reaching_into_committed_funds_fails_whole() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        let commitment = donations::Commitment { kind: "giveaway".to_string(), reference_id: "g".to_string(), amount: Amount::from_tokens(40) };
+        contract.state.add_commitment(owner("alice"), commitment).blocking_wait().unwrap();
+
+        assert_eq!(run(&mut contract, Operation::Withdraw { target: None, amount: None }), Err("insufficient_balance".to_string()));
+        assert_eq!(run(&mut contract, Operation::Withdraw { target: None, amount: Some(Amount::from_tokens(61)) }), Err("insufficient_balance".to_string()));
+        assert!(contract.runtime.outgoing_transfers().is_empty());
+        run(&mut contract, Operation::Withdraw { target: None, amount: Some(Amount::from_tokens(60)) }).unwrap();
+        assert_eq!(contract.runtime.owner_balance(owner("alice")), Amount::from_tokens(40));
+    }
+
+    fn fungible(chain_name: &str, owner_name: &str) -> linera_sdk::abis::fungible::Account {
+        linera_sdk::abis::fungible::Account { chain_id: chain(chain_name), owner: owner(owner_name) }
+    }
+
+    fn bob_pays_alice(contract: &mut DonationsContract, tokens: u128) {
+        contract.runtime.set_authenticated_signer(owner("bob"));
+        let transfer = Operation::Transfer { owner: owner("bob"), amount: Amount::from_tokens(tokens), target_account: fungible("main", "alice"), text_message: None };
+        run(contract, transfer).unwrap();
+    }
+
+    #[test]
+    fn pledged_share_is_paid_out_at_the_owners_next_operation() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        run(&mut contract, Operation::SetIncomeRedirect { target: fungible("charity", "carol"), basis_points: 1_000, label: "c".to_string() }).unwrap();
+        bob_pays_alice(&mut contract, 10);
+        assert_eq!(contract.state.pending_redirect(owner("alice")).blocking_wait().unwrap(), Amount::ONE);
+
+        contract.runtime.set_authenticated_signer(owner("alice"));
+        run(&mut contract, Operation::RetryOutbox).unwrap();
+        let charity = Account { chain_id: chain("charity"), owner: owner("carol") };
+        assert_eq!(contract.runtime.outgoing_transfers().get(&charity), Some(&Amount::ONE));
+        assert_eq!(contract.state.pending_redirect(owner("alice")).blocking_wait().unwrap(), Amount::ZERO);
+    }
+
+    #[test]
+    fn mirrored_pledge_does_not_redirect_income_on_another_chain() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        let redirect = donations::IncomeRedirect { target: fungible("charity", "carol"), basis_points: 1_000, label: "c".to_string(), chain_id: chain("home").to_string() };
+        contract.state.set_income_redirect(owner("alice"), redirect).blocking_wait().unwrap();
+        bob_pays_alice(&mut contract, 10);
+        contract.runtime.set_authenticated_signer(owner("alice"));
+        run(&mut contract, Operation::RetryOutbox).unwrap();
+        assert_eq!(contract.state.pending_redirect(owner("alice")).blocking_wait().unwrap(), Amount::ZERO);
+        assert!(contract.runtime.outgoing_transfers().is_empty());
+    }
+
+    #[test]
+    fn follow_many_mixes_new_and_already_followed_authors() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        run(&mut contract, Operation::Follow { author: owner("bob") }).unwrap();
+        assert_eq!(run(&mut contract, Operation::Follow { author: owner("alice") }), Err("invalid".to_string()));
+        contract.runtime.set_system_time(Timestamp::from(FEB_10_2025 + 1));
+        let authors = vec![owner("bob"), owner("carol"), owner("alice"), owner("carol"), owner("dave")];
+        run(&mut contract, Operation::FollowMany { authors }).unwrap();
+
+        let following = |contract: &DonationsContract, who| contract.state.list_follows(&contract.state.following, owner(who)).blocking_wait().unwrap();
+        let followers = |contract: &DonationsContract, who| contract.state.list_follows(&contract.state.followers, owner(who)).blocking_wait().unwrap();
+        assert_eq!(following(&contract, "alice").len(), 3);
+        assert_eq!(following(&contract, "alice")[0], owner("bob"));
+        assert_eq!(followers(&contract, "bob"), vec![owner("alice")]);
+        assert_eq!(followers(&contract, "carol"), vec![owner("alice")]);
+
+        run(&mut contract, Operation::Unfollow { author: owner("bob") }).unwrap();
+        run(&mut contract, Operation::Unfollow { author: owner("bob") }).unwrap();
+        assert!(followers(&contract, "bob").is_empty());
+        assert!(!following(&contract, "alice").contains(&owner("bob")));
+        assert!(contract.state.is_following(owner("alice"), owner("dave")).blocking_wait().unwrap());
+    }
+
+    /// Switch the contract to token mode; returns the transfers the payment application was asked for
+    fn token_mode(contract: &mut DonationsContract) -> std::rc::Rc<std::cell::RefCell<Vec<(AccountOwner, Account, Amount)>>> {
+        use linera_sdk::linera_base_types::ApplicationId;
+        let parameters = DonationsParameters { ticker_symbol: "LF".to_string(), payment_app: Some(ApplicationId::new(CryptoHash::test_hash("tokens"))), max_message_chars: None, operator_mode: false };
+        contract.runtime.set_application_parameters(parameters);
+        contract.runtime.set_application_id(ApplicationId::new(CryptoHash::test_hash("app")).with_abi());
+        let transfers = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen = transfers.clone();
+        contract.runtime.set_call_application_handler(move |_, _, bytes| {
+            let response = match linera_sdk::bcs::from_bytes(&bytes).unwrap() {
+                FungibleOperation::Balance { .. } => FungibleResponse::Balance(Amount::from_tokens(50)),
+                FungibleOperation::Transfer { owner, amount, target_account } => {
+                    seen.borrow_mut().push((owner, Account { chain_id: target_account.chain_id, owner: target_account.owner }, amount));
+                    FungibleResponse::Ok
+                }
+                other => panic!("unexpected call {:?}", other),
+            };
+            linera_sdk::bcs::to_bytes(&response).unwrap()
+        });
+        transfers
+    }
+
+    fn token_transfer(from: AccountOwner, to: linera_sdk::abis::fungible::Account, tokens: u128) -> (AccountOwner, Account, Amount) {
+        (from, Account { chain_id: to.chain_id, owner: to.owner }, Amount::from_tokens(tokens))
+    }
+
+    #[test]
+    fn token_mode_mints_withdrawals_and_redirects_go_through_the_payment_app() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        let transfers = token_mode(&mut contract);
+        contract.state.admin.set(Some(owner("alice")));
+        run(&mut contract, Operation::Mint { owner: owner("bob"), amount: Amount::from_tokens(5) }).unwrap();
+        run(&mut contract, Operation::Withdraw { target: Some(fungible("elsewhere", "alice")), amount: Some(Amount::from_tokens(3)) }).unwrap();
+        assert_eq!(run(&mut contract, Operation::Withdraw { target: None, amount: Some(Amount::from_tokens(51)) }), Err("insufficient_balance".to_string()));
+        run(&mut contract, Operation::SetIncomeRedirect { target: fungible("charity", "carol"), basis_points: 1_000, label: "c".to_string() }).unwrap();
+        bob_pays_alice(&mut contract, 10);
+        contract.runtime.set_authenticated_signer(owner("alice"));
+        run(&mut contract, Operation::RetryOutbox).unwrap();
+
+        let treasury = AccountOwner::from(contract.runtime.application_id().forget_abi());
+        assert_eq!(*transfers.borrow(), vec![
+            token_transfer(treasury, fungible("main", "bob"), 5),
+            token_transfer(owner("alice"), fungible("elsewhere", "alice"), 3),
+            token_transfer(owner("bob"), fungible("main", "alice"), 10),
+            token_transfer(owner("alice"), fungible("charity", "carol"), 1),
+        ]);
+        assert!(contract.runtime.outgoing_transfers().is_empty());
+        assert_eq!(contract.runtime.owner_balance(owner("alice")), Amount::from_tokens(100));
+    }
+
+    #[test]
+    fn stream_backlog_is_applied_in_order_across_invocations() {
+        use linera_sdk::linera_base_types::{ApplicationId, GenericApplicationId, StreamId};
+        const EVENTS: u32 = 1_200;
+        let mut mirror = contract(owner("alice"), FEB_10_2025);
+        for index in 0..EVENTS {
+            let event = DonationsEvent::ProfileNameUpdated { owner: owner("dave"), name: format!("n{}", index), timestamp: FEB_10_2025 };
+            mirror.runtime.add_event(chain("creator"), StreamName::from(EVENTS_STREAM), index, &linera_sdk::bcs::to_bytes(&event).unwrap());
+        }
+        let name = |mirror: &DonationsContract| mirror.state.profiles.get(&owner("dave")).blocking_wait().unwrap().unwrap().name;
+        let stream_id = StreamId { application_id: GenericApplicationId::User(ApplicationId::new(CryptoHash::test_hash("app"))), stream_name: StreamName::from(EVENTS_STREAM) };
+        mirror.process_streams(vec![StreamUpdate { chain_id: chain("creator"), stream_id, previous_index: 0, next_index: EVENTS }]).blocking_wait();
+        assert_eq!(name(&mirror), format!("n{}", donations::STREAM_EVENT_BUDGET - 1));
+
+        for _ in 0..2 {
+            run(&mut mirror, Operation::DrainStreamBacklog).unwrap();
+        }
+        assert_eq!(name(&mirror), format!("n{}", EVENTS - 1));
+        assert!(mirror.state.pending_streams().blocking_wait().unwrap().is_empty());
+        let key = (chain("creator").to_string(), StreamName::from(EVENTS_STREAM).to_string());
+        assert_eq!(mirror.state.stream_cursors.get(&key).blocking_wait().unwrap(), Some(EVENTS as u64));
+    }
+
+    #[test]
+    fn home_chain_is_where_the_owner_registered_from() {
+        let register = || Operation::Register { main_chain_id: chain("main"), name: None, bio: None, socials: Vec::new(), avatar_hash: None, header_hash: None };
+        let mut home = contract(owner("alice"), FEB_10_2025);
+        home.runtime.set_chain_id(chain("alice"));
+        run(&mut home, register()).unwrap();
+        assert_eq!(home.state.resolve_chain_id(owner("alice")).blocking_wait(), Some(chain("alice").to_string()));
+        assert_eq!(home.state.resolve_chain_id(owner("bob")).blocking_wait(), None);
+
+        let mut main = contract(owner("alice"), FEB_10_2025);
+        main.runtime.set_application_id(ApplicationId::new(CryptoHash::test_hash("app")).with_abi());
+        main.execute_message(Message::Register { source_chain_id: chain("alice"), owner: owner("alice"), name: None, bio: None, socials: Vec::new() }).blocking_wait();
+        assert_eq!(main.state.resolve_chain_id(owner("alice")).blocking_wait(), Some(chain("alice").to_string()));
+    }
+
+    fn resent(contract: &DonationsContract) -> Vec<(ChainId, u64)> {
+        contract.runtime.created_send_message_requests().iter()
+            .filter_map(|request| match &request.message {
+                Message::Tracked { outbox_id, .. } => Some((request.destination, *outbox_id)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unacked_message_is_resent_until_the_target_acks_it() {
+        let mut sender = contract(owner("alice"), FEB_10_2025);
+        sender.send_tracked(Message::Notify, chain("mirror")).blocking_wait();
+        run(&mut sender, Operation::RetryOutbox).unwrap();
+        assert_eq!(resent(&sender).len(), 1);
+
+        sender.runtime.set_system_time(Timestamp::from(FEB_10_2025 + donations::OutboxEntry::retry_delay(1)));
+        run(&mut sender, Operation::RetryOutbox).unwrap();
+        assert_eq!(resent(&sender), vec![(chain("mirror"), 0), (chain("mirror"), 0)]);
+
+        // Only the chain it was sent to can ack it
+        sender.runtime.set_message_origin_chain_id(chain("other"));
+        sender.execute_message(Message::OutboxAck { outbox_id: 0 }).blocking_wait();
+        assert_eq!(sender.state.list_outbox().blocking_wait().unwrap().len(), 1);
+        sender.runtime.set_message_origin_chain_id(chain("mirror"));
+        sender.execute_message(Message::OutboxAck { outbox_id: 0 }).blocking_wait();
+        assert!(sender.state.list_outbox().blocking_wait().unwrap().is_empty());
+        assert!(sender.state.outbox_due.get().is_empty());
+    }
+
+    #[test]
+    fn exhausted_message_is_dropped_after_its_last_wait() {
+        let mut sender = contract(owner("alice"), FEB_10_2025);
+        sender.send_tracked(Message::Notify, chain("mirror")).blocking_wait();
+        let mut now = FEB_10_2025;
+        for attempts in 1..=donations::MAX_OUTBOX_ATTEMPTS {
+            now += donations::OutboxEntry::retry_delay(attempts);
+            sender.runtime.set_system_time(Timestamp::from(now));
+            run(&mut sender, Operation::RetryOutbox).unwrap();
+        }
+        assert_eq!(resent(&sender).len() as u32, donations::MAX_OUTBOX_ATTEMPTS);
+        assert!(sender.state.list_outbox().blocking_wait().unwrap().is_empty());
+        assert!(sender.state.outbox_due.get().is_empty());
+    }
+
+    #[test]
+    fn tracked_deliveries_collapse_into_a_high_water_mark() {
+        let mut receiver = contract(owner("alice"), FEB_10_2025);
+        receiver.runtime.set_message_origin_chain_id(chain("sender"));
+        for outbox_id in [1, 0, 1, 2] {
+            receiver.execute_message(Message::Tracked { outbox_id, message: Box::new(Message::Notify) }).blocking_wait();
+        }
+        let delivered = receiver.state.outbox_delivered.get(&chain("sender")).blocking_wait().unwrap().unwrap();
+        assert_eq!(delivered, donations::DeliveredSequences { below: 3, above: Default::default() });
+        assert!(!receiver.state.note_tracked_delivery(chain("sender"), 1).blocking_wait().unwrap());
+        assert_eq!(receiver.runtime.created_send_message_requests().len(), 4);
+    }
+
+    #[test]
+    fn admin_role_moves_only_when_the_proposed_account_accepts() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        contract.state.admin.set(Some(owner("alice")));
+        run(&mut contract, Operation::ProposeAdmin { new_admin: owner("bob") }).unwrap();
+
+        // A later proposal replaces the first, so Bob can no longer accept
+        run(&mut contract, Operation::ProposeAdmin { new_admin: owner("carol") }).unwrap();
+        contract.runtime.set_authenticated_signer(owner("bob"));
+        assert_eq!(run(&mut contract, Operation::AcceptAdmin), Err("unauthorized".to_string()));
+        assert_eq!(run(&mut contract, Operation::ProposeAdmin { new_admin: owner("bob") }), Err("unauthorized".to_string()));
+        assert_eq!(*contract.state.admin.get(), Some(owner("alice")));
+
+        contract.runtime.set_authenticated_signer(owner("carol"));
+        run(&mut contract, Operation::AcceptAdmin).unwrap();
+        assert_eq!(*contract.state.admin.get(), Some(owner("carol")));
+        assert_eq!(*contract.state.pending_admin.get(), None);
+        assert_eq!(run(&mut contract, Operation::AcceptAdmin), Err("unauthorized".to_string()));
+    }
+
+    #[test]
+    fn mints_are_bounded_by_the_per_mint_maximum_and_the_supply_cap() {
+        let mut contract = contract(owner("alice"), FEB_10_2025);
+        contract.state.admin.set(Some(owner("alice")));
+        let limits = |max_per_mint, supply_cap| Operation::SetMintLimits { max_per_mint: Amount::from_tokens(max_per_mint), supply_cap: Amount::from_tokens(supply_cap) };
+        let mint = |tokens| Operation::Mint { owner: owner("bob"), amount: Amount::from_tokens(tokens) };
+        assert_eq!(run(&mut contract, limits(30, 25)), Err("invalid".to_string()));
+        run(&mut contract, limits(10, 25)).unwrap();
+
+        run(&mut contract, mint(10)).unwrap();
+        assert_eq!(run(&mut contract, mint(11)), Err("invalid".to_string()));
+        run(&mut contract, mint(10)).unwrap();
+        run(&mut contract, mint(5)).unwrap();
+        assert_eq!(*contract.state.total_minted.get(), Amount::from_tokens(25));
+
+        // Past the cap nothing is minted, however small
+        assert_eq!(run(&mut contract, Operation::Mint { owner: owner("bob"), amount: Amount::from_attos(1) }), Err("invalid".to_string()));
+        assert_eq!(*contract.state.total_minted.get(), Amount::from_tokens(25));
+        assert_eq!(contract.runtime.owner_balance(owner("bob")), Amount::from_tokens(125));
+    }
+
+    #[test]
+    fn snapshot_restores_into_an_empty_chain() {
+        use linera_sdk::linera_base_types::{BlobId, BlobType, DataBlobHash};
+        let scope = donations::SnapshotScope { profiles: true, products: true, purchases: true, posts: true, subscriptions: true, donations: true };
+        let mut source = contract(owner("alice"), FEB_10_2025);
+        source.state.admin.set(Some(owner("alice")));
+        source.state.create_product(product(Some(3))).blocking_wait().unwrap();
+        bob_pays_alice(&mut source, 4);
+        source.runtime.set_authenticated_signer(owner("alice"));
+
+        // The mock runtime hands out the blob hashes the snapshot will be stored under
+        let payload = donations::write_snapshot(&source.state.snapshot_data(scope).blocking_wait().unwrap());
+        let (chunk_hash, manifest_hash) = (CryptoHash::test_hash("chunk"), CryptoHash::test_hash("manifest"));
+        let manifest = donations::SnapshotManifest { version: donations::SNAPSHOT_VERSION, created_at: FEB_10_2025, size: payload.len() as u64, chunks: vec![chunk_hash.to_string()] };
+        let manifest_bytes = linera_sdk::bcs::to_bytes(&manifest).unwrap();
+        source.runtime.add_expected_create_data_blob_call(payload.clone(), BlobId::new(chunk_hash, BlobType::Data));
+        source.runtime.add_expected_create_data_blob_call(manifest_bytes.clone(), BlobId::new(manifest_hash, BlobType::Data));
+        run(&mut source, Operation::SnapshotState { scope }).unwrap();
+        let meta = source.state.snapshots.get(&1).blocking_wait().unwrap().expect("snapshot not recorded");
+        assert_eq!((meta.manifest_hash.as_str(), meta.products, meta.donations), (manifest_hash.to_string().as_str(), 1, 1));
+
+        let mut restored = contract(owner("carol"), FEB_10_2025);
+        restored.state.admin.set(Some(owner("carol")));
+        restored.runtime.add_expected_read_data_blob_requests(DataBlobHash(manifest_hash), manifest_bytes);
+        restored.runtime.add_expected_read_data_blob_requests(DataBlobHash(chunk_hash), payload.clone());
+        run(&mut restored, Operation::RestoreFromSnapshot { manifest_hash: manifest_hash.to_string() }).unwrap();
+        assert_eq!(donations::write_snapshot(&restored.state.snapshot_data(scope).blocking_wait().unwrap()), payload);
+        assert_eq!(restored.state.donations_by_recipient.get(&owner("alice")).blocking_wait().unwrap(), Some(vec![1]));
+        assert_eq!(restored.state.get_product("prod").blocking_wait().unwrap().map(|p| p.stock), Some(Some(3)));
+
+        // A second restore would overwrite what is there now
+        let again = donations::read_snapshot(&payload).unwrap();
+        assert!(restored.state.restore_snapshot(again).blocking_wait().is_err());
+    }
+
+    #[test]
+    fn version_one_snapshots_stay_readable() {
+        // An empty version 1 payload: magic, version, scope flags, then six empty sections
+        let mut v1 = b"LFSS".to_vec();
+        v1.extend([1, 0, 0, 0]);
+        v1.extend([1, 0, 0, 0, 0, 1]);
+        v1.extend([0; 6]);
+        let data = donations::read_snapshot(&v1).unwrap();
+        assert!(data.scope.profiles && data.scope.donations && !data.scope.posts);
+        assert!(data.profiles.is_empty() && data.donations.is_empty());
+
+        let mut future = v1.clone();
+        future[4] = 2;
+        assert!(donations::read_snapshot(&future).is_err());
+        let manifest = donations::SnapshotManifest { version: donations::SNAPSHOT_VERSION + 1, created_at: 0, size: 0, chunks: Vec::new() };
+        assert!(donations::read_snapshot_manifest(&linera_sdk::bcs::to_bytes(&manifest).unwrap()).is_err());
+    }
+
+    /// Bob's post "poll" on chain "main" with a two-option poll
+    fn poll_post() -> donations::Post {
+        donations::Post {
+            id: "poll".to_string(), author: owner("bob"), author_chain_id: chain("main").to_string(), title: String::new(), content: String::new(),
+            image_hash: None, created_at: FEB_10_2025, giveaway: None, visibility: Default::default(),
+            poll: Some(donations::Poll {
+                options: ["yes", "no"].map(|text| donations::PollOption { text: text.to_string(), votes_count: 0 }).to_vec(),
+                end_timestamp: 0, voters: Default::default(), revision: 0, votes_since_broadcast: 0, last_broadcast_at: FEB_10_2025,
+                broadcast_every_votes: donations::DEFAULT_POLL_BROADCAST_VOTES, broadcast_interval_micros: donations::DEFAULT_POLL_BROADCAST_INTERVAL_MICROS,
+            }),
+        }
+    }
+
+    /// A month of `subscriber`'s subscription to Bob, held on `subscriber_chain`
+    fn subscription_to_bob(subscriber: AccountOwner, subscriber_chain: &str) -> donations::ContentSubscription {
+        donations::ContentSubscription {
+            id: format!("sub-{}", subscriber), subscriber, subscriber_chain_id: chain(subscriber_chain).to_string(), author: owner("bob"),
+            author_chain_id: chain("main").to_string(), start_timestamp: FEB_10_2025, end_timestamp: FEB_10_2025 + donations::SUBSCRIPTION_PERIOD_MICROS,
+            price: Amount::ONE, locked_price: Amount::ONE, renewals_remaining_at_locked_price: 0, auto_renew: false, auto_renew_failures: 0,
+        }
+    }
+
+    #[test]
+    fn rapid_votes_are_broadcast_in_batches_and_the_close_always_goes_out() {
+        let mut author = contract(owner("bob"), FEB_10_2025);
+        author.state.create_post(poll_post()).blocking_wait().unwrap();
+        author.state.create_subscription(subscription_to_bob(owner("carol"), "carol")).blocking_wait().unwrap();
+        for i in 0..25 {
+            let voter = owner(&format!("voter{}", i));
+            author.state.create_subscription(subscription_to_bob(voter, "main")).blocking_wait().unwrap();
+            author.execute_message(Message::VoteCasted { post_id: "poll".to_string(), voter, voter_chain_id: chain("main"), option_index: i % 2 }).blocking_wait();
+        }
+        run(&mut author, Operation::ClosePoll { post_id: "poll".to_string() }).unwrap();
+
+        let broadcasts: Vec<donations::Poll> = author.runtime.created_send_message_requests().iter()
+            .filter_map(|request| match &request.message {
+                Message::PollResultsUpdated { poll, .. } if request.destination == chain("carol") => Some(poll.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(broadcasts.len() <= 3);
+        let last = broadcasts.last().expect("final results not broadcast");
+        assert_eq!(last.options.iter().map(|o| o.votes_count).collect::<Vec<_>>(), [13, 12]);
+
+        // A mirror keeps the newest results it has seen, whatever order they arrive in
+        let mut mirror = contract(owner("carol"), FEB_10_2025);
+        mirror.runtime.set_chain_id(chain("carol"));
+        mirror.state.create_post(poll_post()).blocking_wait().unwrap();
+        for poll in broadcasts.iter().rev() {
+            mirror.execute_message(Message::PollResultsUpdated { post_id: "poll".to_string(), poll: poll.clone() }).blocking_wait();
+        }
+        let mirrored = mirror.state.get_post("poll").blocking_wait().unwrap().unwrap().poll.unwrap();
+        assert_eq!(mirrored.revision, last.revision);
+    }
+
+    fn buy_from_bob(contract: &mut DonationsContract) -> Result<(), String> {
+        run(contract, Operation::TransferToBuy {
+            owner: owner("alice"), product_id: "prod".to_string(), amount: Amount::from_tokens(5),
+            target_account: fungible("main", "bob"), order_data: Default::default(),
+        })
+    }
+
+    #[test]
+    fn purchase_is_recorded_alike_in_native_and_token_mode_and_labelled_with_the_token() {
+        for token in [false, true] {
+            let mut shop = contract(owner("alice"), FEB_10_2025);
+            shop.state.subscriptions.insert(&owner("alice"), chain("main").to_string()).unwrap();
+            shop.state.create_product(product(None)).blocking_wait().unwrap();
+            let transfers = token.then(|| token_mode(&mut shop));
+            buy_from_bob(&mut shop).unwrap();
+
+            let bought = shop.state.purchases_by_buyer.get(&owner("alice")).blocking_wait().unwrap().unwrap_or_default();
+            let purchase = shop.state.get_purchase(&bought[0]).blocking_wait().unwrap().unwrap();
+            assert_eq!(purchase.payment_app, shop.payment_app_label());
+            assert_eq!(purchase.payment_app.is_some(), token);
+            match transfers {
+                Some(transfers) => {
+                    assert_eq!(*transfers.borrow(), vec![token_transfer(owner("alice"), fungible("main", "bob"), 5)]);
+                    assert!(shop.runtime.outgoing_transfers().is_empty());
+                }
+                None => assert_eq!(shop.runtime.owner_balance(owner("alice")), Amount::from_tokens(95)),
+            }
+        }
+    }
+
+    #[test]
+    fn refused_token_payment_leaves_no_purchase() {
+        let mut shop = contract(owner("alice"), FEB_10_2025);
+        shop.state.subscriptions.insert(&owner("alice"), chain("main").to_string()).unwrap();
+        shop.state.create_product(product(Some(2))).blocking_wait().unwrap();
+        token_mode(&mut shop);
+        shop.runtime.set_call_application_handler(|_, _, _| linera_sdk::bcs::to_bytes(&FungibleResponse::Balance(Amount::ZERO)).unwrap());
+
+        assert_eq!(buy_from_bob(&mut shop), Err("payment_rejected".to_string()));
+        assert!(shop.state.purchases_by_buyer.get(&owner("alice")).blocking_wait().unwrap().unwrap_or_default().is_empty());
+        assert_eq!(shop.state.get_product("prod").blocking_wait().unwrap().unwrap().stock, Some(2));
+        assert_eq!(shop.events_emitted, 0);
+    }
+
+    #[test]
+    fn local_subscription_is_created_only_when_the_price_is_paid() {
+        let mut chain_main = contract(owner("bob"), FEB_10_2025);
+        run(&mut chain_main, Operation::SetSubscriptionPrice { price: Amount::from_tokens(10), description: None, grandfather_renewals: 0 }).unwrap();
+        let subscribe = |contract: &mut DonationsContract, who: &str, tokens| {
+            contract.runtime.set_authenticated_signer(owner(who));
+            let target_account = fungible("main", "bob");
+            run(contract, Operation::SubscribeToAuthor { owner: owner(who), amount: Amount::from_tokens(tokens), target_account, auto_renew: None })
+        };
+        let subscribers = |contract: &DonationsContract| contract.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap()
+            .into_iter().map(|sub| (sub.subscriber, sub.price)).collect::<Vec<_>>();
+
+        subscribe(&mut chain_main, "alice", 10).unwrap();
+        subscribe(&mut chain_main, "carol", 12).unwrap();
+        assert_eq!(subscribers(&chain_main), [(owner("alice"), Amount::from_tokens(10)), (owner("carol"), Amount::from_tokens(12))]);
+
+        // Underpaying, or paying once the price is gone, is refused before anything is paid
+        chain_main.runtime.set_system_time(Timestamp::from(FEB_10_2025 + 1));
+        assert_eq!(subscribe(&mut chain_main, "carol", 9), Err("invalid".to_string()));
+        chain_main.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut chain_main, Operation::DeleteSubscriptionPrice).unwrap();
+        assert_eq!(subscribe(&mut chain_main, "carol", 12), Err("invalid".to_string()));
+        assert_eq!(subscribers(&chain_main).len(), 2);
+        assert_eq!(chain_main.runtime.owner_balance(owner("carol")), Amount::from_tokens(88));
+    }
+
+    #[test]
+    fn paid_subscription_from_another_chain_is_created_only_at_or_above_the_price() {
+        let mut author = contract(owner("bob"), FEB_10_2025);
+        run(&mut author, Operation::SetSubscriptionPrice { price: Amount::from_tokens(10), description: None, grandfather_renewals: 0 }).unwrap();
+        let payment = |who: &str, tokens| Message::SubscriptionPayment {
+            subscriber: owner(who), subscriber_chain_id: chain(who).to_string(), author: owner("bob"),
+            amount: Amount::from_tokens(tokens), duration_micros: donations::SUBSCRIPTION_PERIOD_MICROS, timestamp: FEB_10_2025,
+        };
+        for (who, tokens) in [("alice", 10), ("carol", 12)] {
+            author.runtime.set_authenticated_signer(owner(who));
+            author.runtime.set_message_origin_chain_id(chain(who));
+            author.execute_message(payment(who, tokens)).blocking_wait();
+        }
+        let subscribers = author.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap();
+        assert_eq!(subscribers.iter().map(|sub| sub.subscriber).collect::<Vec<_>>(), [owner("alice"), owner("carol")]);
+
+        // With the price deleted, the next payment is refused and owed back
+        author.runtime.set_authenticated_signer(owner("bob"));
+        run(&mut author, Operation::DeleteSubscriptionPrice).unwrap();
+        author.runtime.set_authenticated_signer(owner("dave"));
+        author.runtime.set_message_origin_chain_id(chain("dave"));
+        author.execute_message(payment("dave", 10)).blocking_wait();
+        assert_eq!(author.state.list_subscriptions_by_author(owner("bob")).blocking_wait().unwrap().len(), 2);
+        let refunds = author.state.get_pending_refunds(owner("bob")).blocking_wait().unwrap();
+        assert_eq!(refunds.iter().map(|r| (r.to, r.amount)).collect::<Vec<_>>(), [(owner("dave"), Amount::from_tokens(10))]);
+    }
+
+    #[test]
+    fn donation_messages_and_post_titles_are_capped_at_the_limit() {
+        let limit = donations::DEFAULT_MAX_MESSAGE_CHARS;
+        let mut contract = contract(owner("bob"), FEB_10_2025);
+        let donate = |contract: &mut DonationsContract, text: String| {
+            let transfer = Operation::Transfer { owner: owner("bob"), amount: Amount::ONE, target_account: fungible("main", "alice"), text_message: Some(text) };
+            run(contract, transfer)
+        };
+
+        // Limits count characters, after control characters are dropped
+        donate(&mut contract, format!("{}\u{7}", "é".repeat(limit))).unwrap();
+        assert_eq!(donate(&mut contract, "é".repeat(limit + 1)), Err("invalid".to_string()));
+        let kept = contract.state.donations.get(&1).blocking_wait().unwrap().unwrap().message;
+        assert_eq!(kept, Some("é".repeat(limit)));
+        assert_eq!(contract.state.donations.count().blocking_wait().unwrap(), 1);
+        assert_eq!(contract.runtime.owner_balance(owner("bob")), Amount::from_tokens(99));
+
+        contract.state.subscriptions.insert(&owner("bob"), chain("main").to_string()).unwrap();
+        let post = |title: String| Operation::CreatePost {
+            title, content: String::new(), image_hash: None, poll_options: Vec::new(), poll_end_timestamp: None,
+            poll_broadcast_every_votes: None, poll_broadcast_interval_micros: None, giveaway_prize: None, giveaway_end_timestamp: None,
+            giveaway_max_participants: None, giveaway_participants_visible: None, visibility: None,
+        };
+        run(&mut contract, post("t".repeat(limit))).unwrap();
+        assert_eq!(run(&mut contract, post("t".repeat(limit + 1))), Err("invalid".to_string()));
+    }
+
+    #[test]
+    fn message_limit_comes_from_the_application_parameters() {
+        let mut contract = contract(owner("bob"), FEB_10_2025);
+        contract.runtime.set_application_parameters(DonationsParameters { ticker_symbol: "LF".to_string(), payment_app: None, max_message_chars: Some(5), operator_mode: false });
+        let donate = |contract: &mut DonationsContract, text: &str| {
+            let transfer = Operation::Transfer { owner: owner("bob"), amount: Amount::ONE, target_account: fungible("main", "alice"), text_message: Some(text.to_string()) };
+            run(contract, transfer)
+        };
+        donate(&mut contract, "12345").unwrap();
+        assert_eq!(donate(&mut contract, "123456"), Err("invalid".to_string()));
+    }
+}
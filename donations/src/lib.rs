@@ -1,14 +1,17 @@
-use async_graphql::{Request, Response, SimpleObject, InputObject};
-use linera_sdk::linera_base_types::{AccountOwner, Amount, ContractAbi, ServiceAbi, ChainId};
+pub mod amount_math;
+pub mod metrics;
+
+use async_graphql::{Enum, Request, Response, SimpleObject, InputObject};
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ApplicationId, BcsHashable, ContractAbi, CryptoHash, ServiceAbi, ChainId};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 // Type aliases for custom fields
 pub type CustomFields = BTreeMap<String, String>;
 pub type OrderResponses = BTreeMap<String, String>;
 pub type VotersMap = BTreeMap<String, u32>;  // voter_id -> option_index
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum Message {
     Notify,
     TransferWithMessage {
@@ -17,6 +20,8 @@ pub enum Message {
         text_message: Option<String>,
         source_chain_id: ChainId,
         source_owner: AccountOwner,
+        // The donation's id on the source chain, which replies use to find it
+        source_donation_id: u64,
     },
     Register {
         source_chain_id: ChainId,
@@ -31,10 +36,27 @@ pub enum Message {
     ProductUpdated {
         product: Product,
     },
+    // Full product record handed to the author's new home chain, which takes its orders from then on
+    ProductMigrated {
+        product: Product,
+    },
     ProductDeleted {
         product_id: String,
         author: AccountOwner,
     },
+    FeaturedProductsUpdated {
+        author: AccountOwner,
+        product_ids: Vec<String>,
+    },
+    ProductOrderUpdated {
+        author: AccountOwner,
+        ordered_ids: Vec<String>,
+    },
+    // Main chain rejected a product slug already used by another of the seller's products
+    SlugConflict {
+        product_id: String,
+        slug: String,
+    },
     ProductPurchased {
         purchase_id: String,
         product_id: String,
@@ -47,6 +69,18 @@ pub enum Message {
         buyer: AccountOwner,
         purchase_id: String,
         product: Product,
+        // Entitlements the purchase grants, for the buyer's local copy
+        entitlements: Vec<EntitlementGrant>,
+    },
+    // Main chain tells the buyer's chain a paid purchase can't be delivered
+    OrderFailed {
+        buyer: AccountOwner,
+        order: FailedOrder,
+    },
+    // Seller's chain revokes a purchase's entitlements on the buyer's chain (refunds)
+    EntitlementsRevoked {
+        buyer: AccountOwner,
+        purchase_id: String,
     },
     // NEW: Order notification to seller
     OrderReceived {
@@ -58,6 +92,28 @@ pub enum Message {
         order_data: OrderResponses,
         timestamp: u64,
     },
+    // Recipient's reply to a donation, delivered to the chain it was sent from
+    DonationReplied {
+        // The donation's id on the receiving chain
+        source_donation_id: u64,
+        to: AccountOwner,
+        text: String,
+        replied_at: u64,
+    },
+    // Note on an order, delivered to the other party's chain
+    OrderNoteAdded {
+        purchase_id: String,
+        note: OrderNote,
+    },
+    // Buyer's chain forwards a review to the main chain
+    ProductReviewed {
+        review: Review,
+    },
+    // Seller's chain updates the buyer's copy of an order
+    OrderStatusChanged {
+        purchase_id: String,
+        status: OrderStatus,
+    },
     // Content subscription messages
     SubscriptionPayment {
         subscriber: AccountOwner,
@@ -67,6 +123,43 @@ pub enum Message {
         duration_micros: u64,
         timestamp: u64,
     },
+    // Author's chain warns a subscriber about an upcoming price increase
+    PriceChangeNotice {
+        subscriber: AccountOwner,
+        author: AccountOwner,
+        old_price: Amount,
+        new_price: Amount,
+        effective_at: u64,
+    },
+    // Author's chain refuses a subscription payment below its price; `amount` is refunded
+    SubscriptionRejected {
+        subscription_id: String,
+        subscriber: AccountOwner,
+        author: AccountOwner,
+        amount: Amount,
+        reason: String,
+    },
+    // Author's chain delivers an announcement to a subscriber's inbox
+    Announcement {
+        subscriber: AccountOwner,
+        author: AccountOwner,
+        text: String,
+        timestamp: u64,
+    },
+    // Author's chain confirms the subscription terms back to the subscriber's chain
+    SubscriptionConfirmed {
+        subscription_id: String,
+        subscriber: AccountOwner,
+        author: AccountOwner,
+        locked_price: Amount,
+        renewals_remaining_at_locked_price: u8,
+        renewal_price: Amount,
+    },
+    // Subscriber's chain tells the author's chain a subscription was cancelled
+    Unsubscribed {
+        subscription_id: String,
+        subscriber: AccountOwner,
+    },
     PostPublished {
         post: Post,
     },
@@ -98,6 +191,308 @@ pub enum Message {
         post_id: String,
         giveaway: Giveaway,
     },
+    // Comment messages
+    CommentPosted {
+        post_id: String,
+        parent_id: Option<String>,
+        commenter: AccountOwner,
+        commenter_chain_id: ChainId,
+        text: String,
+    },
+    CommentDeleteRequested {
+        comment_id: String,
+        requester: AccountOwner,
+    },
+    CommentPublished {
+        comment: Comment,
+    },
+    CommentUpdated {
+        comment: Comment,
+    },
+    // A mirror message sent through the sender's outbox; the receiver applies it once and acks.
+    // `outbox_id` numbers the sender's tracked messages to this receiver from 0 up
+    Tracked {
+        outbox_id: u64,
+        message: Box<Message>,
+    },
+    // Receiver confirms a tracked message, so the sender drops it from its outbox
+    OutboxAck {
+        outbox_id: u64,
+    },
+}
+
+// Seed revealed for a supporter-of-the-month drawing; its hash is the commitment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawSeed {
+    pub author: AccountOwner,
+    pub month_key: u32,
+    pub seed: String,
+}
+
+impl BcsHashable<'_> for DrawSeed {}
+
+impl DrawSeed {
+    pub fn commitment(&self) -> String {
+        CryptoHash::new(self).to_string()
+    }
+
+    /// Deterministic ticket number drawn from the seed
+    pub fn ticket(&self) -> u128 {
+        let words: [u64; 4] = CryptoHash::new(self).into();
+        ((words[0] as u128) << 64) | words[1] as u128
+    }
+}
+
+// Canonical summary of an owner's mirrored products and posts; chains holding the same
+// content produce the same hash regardless of index order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentDigest {
+    pub owner: AccountOwner,
+    pub products: Vec<(String, u32, u32)>,  // (id, content_revision, form_revision)
+    pub posts: Vec<(String, String, String, Option<String>)>,  // (id, title, content, image_hash)
+}
+
+impl BcsHashable<'_> for ContentDigest {}
+
+impl ContentDigest {
+    pub fn new(owner: AccountOwner, products: &[Product], posts: &[Post]) -> Self {
+        let mut products: Vec<_> = products.iter().map(|p| (p.id.clone(), p.content_revision, p.form_revision)).collect();
+        products.sort();
+        let mut posts: Vec<_> = posts.iter().map(|p| (p.id.clone(), p.title.clone(), p.content.clone(), p.image_hash.clone())).collect();
+        posts.sort();
+        ContentDigest { owner, products, posts }
+    }
+
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash::new(self)
+    }
+}
+
+/// Month key `YYYYMM` for a year and month (1-12)
+pub fn month_key(year: u32, month: u32) -> u32 {
+    year * 100 + month
+}
+
+/// Month key `YYYYMM` of a timestamp in microseconds since the Unix epoch (UTC)
+pub fn month_key_of(timestamp_micros: u64) -> u32 {
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let days = (timestamp_micros / 86_400_000_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    month_key(year as u32, month as u32)
+}
+
+/// Pick an entry with probability proportional to its weight.
+/// `ticket` is reduced modulo the total weight, then walked through the cumulative weights.
+pub fn pick_weighted<T: Copy>(weights: &[(T, u128)], ticket: u128) -> Option<T> {
+    let total = weights.iter().fold(0u128, |acc, (_, w)| acc.saturating_add(*w));
+    if total == 0 {
+        return None;
+    }
+    let mut point = ticket % total;
+    for (item, weight) in weights {
+        if point < *weight {
+            return Some(*item);
+        }
+        point -= weight;
+    }
+    None
+}
+
+// State snapshots for off-chain backups.
+// Payload layout: SNAPSHOT_MAGIC, format version (u32 little-endian), BCS-encoded body.
+// The payload is split into data blobs of at most SNAPSHOT_CHUNK_SIZE bytes, listed
+// in order by a BCS-encoded SnapshotManifest blob.
+pub const SNAPSHOT_MAGIC: &[u8; 4] = b"LFSS";
+pub const SNAPSHOT_VERSION: u32 = 1;
+pub const SNAPSHOT_CHUNK_SIZE: usize = 256 * 1024;
+
+// Views included in a snapshot
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "SnapshotScopeInput")]
+pub struct SnapshotScope {
+    pub profiles: bool,
+    pub products: bool,
+    pub purchases: bool,
+    pub posts: bool,
+    pub subscriptions: bool,
+    pub donations: bool,
+}
+
+// Snapshot body (format version 1); sections outside the scope are empty
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotData {
+    pub scope: SnapshotScope,
+    pub profiles: Vec<Profile>,
+    pub products: Vec<Product>,
+    pub purchases: Vec<Purchase>,
+    pub posts: Vec<Post>,
+    pub subscriptions: Vec<ContentSubscription>,
+    pub donations: Vec<DonationRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub version: u32,
+    pub created_at: u64,
+    pub size: u64,
+    // Hashes of the payload data blobs, in order
+    pub chunks: Vec<String>,
+}
+
+// Snapshot taken on this chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SnapshotMeta {
+    pub id: u64,
+    pub manifest_hash: String,
+    pub version: u32,
+    pub created_at: u64,
+    pub size: u64,
+    pub chunks: u32,
+    pub scope: SnapshotScope,
+    pub profiles: u32,
+    pub products: u32,
+    pub purchases: u32,
+    pub posts: u32,
+    pub subscriptions: u32,
+    pub donations: u32,
+}
+
+pub fn write_snapshot(data: &SnapshotData) -> Vec<u8> {
+    let mut bytes = SNAPSHOT_MAGIC.to_vec();
+    bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    bytes.extend(linera_sdk::bcs::to_bytes(data).expect("Failed to serialize snapshot"));
+    bytes
+}
+
+/// Decode a snapshot payload (the concatenated chunks). Usable off-chain.
+/// Each format version keeps its own decoding branch so old backups stay readable.
+pub fn read_snapshot(bytes: &[u8]) -> Result<SnapshotData, String> {
+    if bytes.len() < 8 || &bytes[..4] != SNAPSHOT_MAGIC {
+        return Err("Not a snapshot".to_string());
+    }
+    let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    match version {
+        1 => linera_sdk::bcs::from_bytes(&bytes[8..]).map_err(|e| format!("{:?}", e)),
+        v => Err(format!("Unsupported snapshot version {}", v)),
+    }
+}
+
+pub fn read_snapshot_manifest(bytes: &[u8]) -> Result<SnapshotManifest, String> {
+    let manifest: SnapshotManifest = linera_sdk::bcs::from_bytes(bytes).map_err(|e| format!("{:?}", e))?;
+    if manifest.version > SNAPSHOT_VERSION {
+        return Err(format!("Unsupported snapshot version {}", manifest.version));
+    }
+    Ok(manifest)
+}
+
+// Activity exports: a JSONL data blob, one `ActivityRecord` per line tagged by `type`,
+// starting with a header. Windows are capped in span and record count; a full export
+// names the timestamp the next one should start from.
+pub const ACTIVITY_EXPORT_VERSION: u32 = 1;
+pub const MAX_EXPORT_WINDOW_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+pub const MAX_EXPORT_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActivityRecord {
+    Header {
+        version: u32,
+        from: u64,
+        to: u64,
+        #[serde(default)]
+        skip: u32,
+        continuation: Option<u64>,
+        #[serde(default)]
+        continuation_skip: u32,
+    },
+    Purchase { id: String, product_id: String, buyer: AccountOwner, seller: AccountOwner, amount: Amount, timestamp: u64 },
+    Subscription { id: String, subscriber: AccountOwner, author: AccountOwner, price: Amount, timestamp: u64 },
+    Donation { id: u64, from: AccountOwner, to: AccountOwner, amount: Amount, timestamp: u64 },
+}
+
+impl ActivityRecord {
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            ActivityRecord::Header { from, .. } => *from,
+            ActivityRecord::Purchase { timestamp, .. }
+            | ActivityRecord::Subscription { timestamp, .. }
+            | ActivityRecord::Donation { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Export order: by timestamp, then kind and id, so a resumed window skips the same records
+    pub fn order_key(&self) -> (u64, u8, String) {
+        match self {
+            ActivityRecord::Header { from, .. } => (*from, 0, String::new()),
+            ActivityRecord::Purchase { id, timestamp, .. } => (*timestamp, 1, id.clone()),
+            ActivityRecord::Subscription { id, timestamp, .. } => (*timestamp, 2, id.clone()),
+            ActivityRecord::Donation { id, timestamp, .. } => (*timestamp, 3, format!("{:020}", id)),
+        }
+    }
+}
+
+// Export recorded on this chain
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct ActivityExport {
+    pub id: u64,
+    pub blob_hash: String,
+    pub from: u64,
+    pub to: u64,
+    pub records: u32,
+    // Records at `from` left out because the previous export already had them
+    #[serde(default)]
+    pub skip: u32,
+    // Start of the next export when this one hit the record cap, and its `skip`
+    pub continuation: Option<u64>,
+    #[serde(default)]
+    pub continuation_skip: u32,
+    pub created_at: u64,
+}
+
+/// Serialize an export: header line, then one line per record
+pub fn write_activity_export(from: u64, to: u64, skip: u32, continuation: Option<(u64, u32)>, records: &[ActivityRecord]) -> Vec<u8> {
+    let header = ActivityRecord::Header {
+        version: ACTIVITY_EXPORT_VERSION,
+        from,
+        to,
+        skip,
+        continuation: continuation.map(|(at, _)| at),
+        continuation_skip: continuation.map_or(0, |(_, skip)| skip),
+    };
+    let mut bytes = Vec::new();
+    for record in std::iter::once(&header).chain(records) {
+        bytes.extend(serde_json::to_vec(record).expect("Failed to serialize activity record"));
+        bytes.push(b'\n');
+    }
+    bytes
+}
+
+/// Iterate the records of an export blob, header first. Usable off-chain.
+pub fn parse_activity_export(bytes: &[u8]) -> impl Iterator<Item = Result<ActivityRecord, String>> + '_ {
+    bytes
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            let record: ActivityRecord = serde_json::from_slice(line).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+            match record {
+                ActivityRecord::Header { version, .. } if version > ACTIVITY_EXPORT_VERSION => Err(format!("Unsupported export version {}", version)),
+                ActivityRecord::Header { .. } if i > 0 => Err(format!("Line {}: unexpected header", i + 1)),
+                _ if i == 0 && !matches!(record, ActivityRecord::Header { .. }) => Err("Missing header".to_string()),
+                record => Ok(record),
+            }
+        })
+}
+
+/// Parse a chain id string and return its canonical form, rejecting malformed ids
+pub fn canonical_chain_id(chain_id: &str) -> Result<String, String> {
+    chain_id.trim().parse::<ChainId>().map(|id| id.to_string()).map_err(|_| format!("Invalid chain ID: {}", chain_id))
 }
 
 #[derive(Debug, Deserialize, Serialize, InputObject)]
@@ -124,6 +519,30 @@ pub struct AccountEntry {
     pub value: Amount,
 }
 
+pub const DEFAULT_MAX_MINT: Amount = Amount::from_tokens(1_000_000);
+pub const DEFAULT_MINT_SUPPLY_CAP: Amount = Amount::from_tokens(1_000_000_000);
+
+// Bounds on Mint, set by the admin
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, SimpleObject)]
+pub struct MintLimits {
+    pub max_per_mint: Amount,
+    pub supply_cap: Amount,
+}
+
+impl Default for MintLimits {
+    fn default() -> Self {
+        MintLimits { max_per_mint: DEFAULT_MAX_MINT, supply_cap: DEFAULT_MINT_SUPPLY_CAP }
+    }
+}
+
+// Funds of an owner locked by a feature (e.g. a giveaway prize) until it is released
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Commitment {
+    pub kind: String,
+    pub reference_id: String,
+    pub amount: Amount,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct Profile {
     pub owner: AccountOwner,
@@ -138,11 +557,81 @@ pub struct Profile {
 pub struct ProfileView {
     pub owner: AccountOwner,
     pub chain_id: String,
+    // False when the owner's chain is unknown and `chain_id` is the local chain
+    pub chain_id_known: bool,
     pub name: String,
     pub bio: String,
     pub socials: Vec<SocialLink>,
     pub avatar_hash: Option<String>,
     pub header_hash: Option<String>,
+    pub income_redirect: Option<IncomeRedirect>,
+}
+
+// A donor's running figures, updated as each of their donations is recorded; the breakdown
+// per recipient is kept as one `RecipientTally` per (donor, recipient)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DonorStats {
+    pub total: Amount,
+    pub count: u32,
+    pub largest: Amount,
+    pub first_at: Option<u64>,
+    pub last_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RecipientTally {
+    pub total: Amount,
+    pub count: u32,
+}
+
+impl DonorStats {
+    pub fn add(&mut self, amount: Amount, timestamp: u64) {
+        self.total = amount_math::add_tracking_overflow(self.total, amount).unwrap_or_else(|saturated| saturated);
+        self.count += 1;
+        self.largest = self.largest.max(amount);
+        self.first_at = Some(self.first_at.map_or(timestamp, |t| t.min(timestamp)));
+        self.last_at = Some(self.last_at.map_or(timestamp, |t| t.max(timestamp)));
+    }
+}
+
+impl RecipientTally {
+    pub fn add(&mut self, amount: Amount) {
+        self.total = amount_math::add_tracking_overflow(self.total, amount).unwrap_or_else(|saturated| saturated);
+        self.count += 1;
+    }
+
+    /// Take back one donation, e.g. when a held donation is routed elsewhere
+    pub fn remove(&mut self, amount: Amount) {
+        self.total = amount_math::sub_tracking_underflow(self.total, amount).unwrap_or_else(|floor| floor);
+        self.count = self.count.saturating_sub(1);
+    }
+}
+
+// A recipient's fundraising target; progress counts donations received since `created_at`
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct DonationGoal {
+    pub target: Amount,
+    pub deadline_micros: u64,
+    pub description: Option<String>,
+    pub created_at: u64,
+}
+
+impl DonationGoal {
+    /// Progress towards the target in basis points, capped at 10_000 (100%)
+    pub fn progress_bps(&self, received: Amount) -> u32 {
+        amount_math::ratio_bps(received, self.target)
+    }
+}
+
+// Creator's pledge to pass a share of all income on to another account (e.g. a charity)
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct IncomeRedirect {
+    pub target: linera_sdk::abis::fungible::Account,
+    pub basis_points: u16,  // 100 = 1%
+    pub label: String,
+    // Chain the pledge was made on; only income credited there is redirected
+    #[serde(default)]
+    pub chain_id: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -150,6 +639,80 @@ pub struct SubscriptionInfo {
     pub author: AccountOwner,
     pub price: Amount,
     pub description: Option<String>,
+    // Renewals existing subscribers keep their locked price for after a price increase
+    pub grandfather_renewals: u8,
+    // Price increase waiting out its notice period
+    pub pending_price: Option<PendingPrice>,
+    // Entitlements active subscribers hold while their subscription lasts
+    pub entitlements: Vec<String>,
+    // How long lapsed subscribers keep receiving and reading posts; see SubscriptionAccess
+    #[serde(default)]
+    pub grace_period_micros: u64,
+}
+
+pub const MAX_SUBSCRIPTION_GRACE_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+
+// What a subscription is checked for. Reading keeps working through the author's grace
+// period after expiry; interacting (voting, giveaway entries, comments) needs the hard expiry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionAccess {
+    Read,
+    Interact,
+}
+
+impl ContentSubscription {
+    /// Whether this subscription allows `access` at `now`, given the author's grace period
+    pub fn allows(&self, access: SubscriptionAccess, grace_period_micros: u64, now: u64) -> bool {
+        match access {
+            SubscriptionAccess::Read => self.end_timestamp.saturating_add(grace_period_micros) >= now,
+            SubscriptionAccess::Interact => self.end_timestamp >= now,
+        }
+    }
+}
+
+// Price increases take effect only after subscribers had this long to react
+pub const PRICE_INCREASE_NOTICE_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+
+// Length of one paid subscription period
+pub const SUBSCRIPTION_PERIOD_MICROS: u64 = 30 * 24 * 60 * 60 * 1_000_000;
+
+// Auto-renew subscriptions ending within this window are renewed by ProcessAutoRenewals
+pub const AUTO_RENEW_WINDOW_MICROS: u64 = 3 * 24 * 60 * 60 * 1_000_000;
+
+// Auto-renew is switched off after this many renewals in a row couldn't be paid
+pub const MAX_AUTO_RENEW_FAILURES: u8 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PendingPrice {
+    pub price: Amount,
+    pub description: Option<String>,
+    pub grandfather_renewals: u8,
+    pub effective_at: u64,
+}
+
+impl SubscriptionInfo {
+    /// Terms in force at `now`, with a pending price applied once it is effective
+    pub fn settled(mut self, now: u64) -> Self {
+        if let Some(pending) = self.pending_price.take() {
+            if pending.effective_at <= now {
+                self.price = pending.price;
+                self.description = pending.description;
+                self.grandfather_renewals = pending.grandfather_renewals;
+            } else {
+                self.pending_price = Some(pending);
+            }
+        }
+        self
+    }
+}
+
+// Entry in an owner's notification inbox
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Notification {
+    pub kind: String,
+    pub from: AccountOwner,
+    pub text: String,
+    pub timestamp: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -161,7 +724,27 @@ pub struct DonationRecord {
     pub amount: Amount,
     pub message: Option<String>,
     pub source_chain_id: Option<String>,
+    // Id of the donation on `source_chain_id`
+    #[serde(default)]
+    pub source_donation_id: Option<u64>,
     pub to_chain_id: Option<String>,
+    // Set when the message was truncated or stripped on receipt
+    pub content_modified: bool,
+    // Fungible application that carried the payment; None for native transfers
+    pub payment_app: Option<String>,
+    // Recipient's public thank-you, and when it was last written
+    #[serde(default)]
+    pub reply: Option<String>,
+    #[serde(default)]
+    pub replied_at: Option<u64>,
+}
+
+// Whose donations a time-range listing covers
+#[derive(Debug, Clone, Copy)]
+pub enum DonationSide {
+    Recipient(AccountOwner),
+    Donor(AccountOwner),
+    All,
 }
 
 // Content subscription structure
@@ -175,6 +758,15 @@ pub struct ContentSubscription {
     pub start_timestamp: u64,
     pub end_timestamp: u64,
     pub price: Amount,
+    // Price this subscriber keeps paying on renewal while renewals remain
+    pub locked_price: Amount,
+    pub renewals_remaining_at_locked_price: u8,
+    // Subscriber wants it renewed before it ends; only the subscriber's chain tracks this
+    #[serde(default)]
+    pub auto_renew: bool,
+    // Renewal attempts in a row that couldn't be paid
+    #[serde(default)]
+    pub auto_renew_failures: u8,
 }
 
 // Poll option structure
@@ -189,12 +781,23 @@ pub struct PollOptionInput {
     pub text: String,
 }
 
+pub const DEFAULT_POLL_BROADCAST_VOTES: u32 = 10;
+pub const DEFAULT_POLL_BROADCAST_INTERVAL_MICROS: u64 = 60 * 1_000_000;
+
 // Poll structure
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct Poll {
     pub options: Vec<PollOption>,
     pub end_timestamp: u64,
     pub voters: VotersMap,
+    // Bumped on every vote or close; mirrors ignore results that aren't newer
+    pub revision: u64,
+    // Result broadcasts to subscriber chains are coalesced: sent after
+    // `broadcast_every_votes` votes or `broadcast_interval_micros`, whichever comes first
+    pub votes_since_broadcast: u32,
+    pub last_broadcast_at: u64,
+    pub broadcast_every_votes: u32,
+    pub broadcast_interval_micros: u64,
 }
 
 // Giveaway participant - stores chain_id for prize transfer
@@ -213,6 +816,19 @@ pub struct Giveaway {
     pub participants: Vec<GiveawayParticipant>,
     pub winner: Option<GiveawayParticipant>,
     pub is_resolved: bool,
+    // No limit when None
+    #[serde(default)]
+    pub max_participants: Option<u32>,
+    // Whether anyone may list the entrants; otherwise only the count is shown
+    #[serde(default)]
+    pub participants_visible: bool,
+}
+
+impl Giveaway {
+    /// Entries left before the cap, or None when unlimited
+    pub fn remaining_slots(&self) -> Option<u32> {
+        self.max_participants.map(|max| max.saturating_sub(self.participants.len() as u32))
+    }
 }
 
 // Post structure
@@ -227,6 +843,51 @@ pub struct Post {
     pub created_at: u64,
     pub poll: Option<Poll>,
     pub giveaway: Option<Giveaway>,
+    #[serde(default)]
+    pub visibility: PostVisibility,
+}
+
+// Who a post is meant for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum PostVisibility {
+    #[default]
+    Public,
+    // Only for the author's active subscribers
+    Subscribers,
+}
+
+// Comment on a post; replies point at their top-level comment through parent_id
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Comment {
+    pub id: String,
+    pub post_id: String,
+    pub author: AccountOwner,
+    pub author_chain_id: String,
+    pub parent_id: Option<String>,
+    pub text: String,
+    pub created_at: u64,
+    // Deleted by its author (text cleared) or hidden by the post author
+    pub deleted: bool,
+    pub hidden: bool,
+    // When the post author first replied to this top-level comment
+    pub answered_at: Option<u64>,
+    // Set when the text was cut or stripped on receipt
+    #[serde(default)]
+    pub content_modified: bool,
+}
+
+// Author replies within this long count as answering a comment
+pub const COMMENT_ANSWER_DEADLINE_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+// Days of comment engagement kept per author
+pub const ENGAGEMENT_WINDOW_DAYS: u32 = 90;
+
+// Top-level comments an author received on one day, and how many they answered in time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EngagementDay {
+    pub received: u32,
+    pub answered: u32,
+    // Response time in whole hours -> number of answers
+    pub response_hours: BTreeMap<u32, u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -235,21 +896,28 @@ pub struct DonationView {
     pub timestamp: u64,
     pub from_owner: AccountOwner,
     pub from_chain_id: String,
+    pub from_chain_id_known: bool,
     pub to_owner: AccountOwner,
     pub to_chain_id: String,
+    pub to_chain_id_known: bool,
     pub amount: Amount,
     pub message: Option<String>,
+    pub content_modified: bool,
+    pub payment_app: Option<String>,
+    pub reply: Option<String>,
+    pub replied_at: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct TotalAmountView {
     pub owner: AccountOwner,
     pub chain_id: String,
+    pub chain_id_known: bool,
     pub amount: Amount,
 }
 
 // NEW: Order form field definition
-#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
 pub struct OrderFormField {
     pub key: String,
     pub label: String,
@@ -278,6 +946,9 @@ pub struct Product {
     
     // Private data (visible after purchase) - includes data_blob_hash, links, etc.
     pub private_data: CustomFields,
+    // private_data values are client-side ciphertext: stored and returned verbatim, never read
+    #[serde(default)]
+    pub encrypted: bool,
     
     // Success message shown after purchase
     pub success_message: Option<String>,
@@ -285,7 +956,32 @@ pub struct Product {
     // Order form template
     pub order_form: Vec<OrderFormField>,
     
+    // Pretty URL slug, unique per seller
+    pub slug: Option<String>,
+    
+    // Storefront category, indexed for browsing; at most MAX_CATEGORY_CHARS
+    pub category: Option<String>,
+    
+    // Named entitlements a purchase grants (e.g. "discord-role:vip"), at most MAX_ENTITLEMENTS
+    pub entitlements: Vec<String>,
+    
+    // Bumped on public/private data or price changes (what buyers paid for)
+    pub content_revision: u32,
+    // Bumped on order form or success message changes
+    pub form_revision: u32,
+    
     pub created_at: u64,
+    
+    // Copies left to sell; None means unlimited. The seller's chain holds the authoritative count
+    #[serde(default)]
+    pub stock: Option<u32>,
+}
+
+// Old product slug kept resolvable after the seller changed it
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct SlugRedirect {
+    pub product_id: String,
+    pub expires_at: u64,
 }
 
 // Legacy ProductView for backward compatibility in queries
@@ -303,6 +999,37 @@ pub struct ProductView {
     pub created_at: u64,
 }
 
+impl ProductView {
+    /// Flatten a product for legacy clients, leaving out private data
+    pub fn from_public(product: &Product) -> Self {
+        let field = |key: &str| product.public_data.get(key).cloned().unwrap_or_default();
+        Self {
+            id: product.id.clone(),
+            author: product.author,
+            author_chain_id: product.author_chain_id.clone(),
+            name: field("name"),
+            description: field("description"),
+            link: String::new(),
+            data_blob_hash: String::new(),
+            image_preview_hash: field("image_preview_hash"),
+            price: product.price,
+            created_at: product.created_at,
+        }
+    }
+    
+    /// Flatten a purchased product, looking keys up in public then private data
+    /// (public only when the private data is encrypted)
+    pub fn from_full(product: &Product) -> Self {
+        let private = |key: &str| if product.encrypted { None } else { product.private_data.get(key) };
+        let field = |key: &str| product.public_data.get(key).or_else(|| private(key)).cloned().unwrap_or_default();
+        Self {
+            link: field("link"),
+            data_blob_hash: field("data_blob_hash"),
+            ..Self::from_public(product)
+        }
+    }
+}
+
 // NEW: Purchase with order data
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct Purchase {
@@ -317,11 +1044,173 @@ pub struct Purchase {
     
     // Order responses from buyer
     pub order_data: OrderResponses,
+    // Set when order_data was truncated or stripped on receipt
+    pub content_modified: bool,
+    // Fungible application that carried the payment; None for native transfers
+    pub payment_app: Option<String>,
+    pub status: OrderStatus,
     
     // Product snapshot at time of purchase
     pub product: Product,
 }
 
+// A payment this chain received but refused; sent back from the payee's account on their next operation
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PendingRefund {
+    pub to: AccountOwner,
+    pub to_chain_id: ChainId,
+    pub amount: Amount,
+    pub reason: String,
+    pub created_at: u64,
+}
+
+// A paid purchase the main chain couldn't deliver, kept on the buyer's chain so they can claim a refund
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct FailedOrder {
+    pub purchase_id: String,
+    pub product_id: String,
+    pub seller: AccountOwner,
+    pub amount: Amount,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+// Seller-side progress of an order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum OrderStatus {
+    #[default]
+    Pending,
+    Accepted,
+    // Shipped or otherwise delivered by the seller
+    Fulfilled,
+    Cancelled,
+    // The seller paid the amount back through RefundPurchase
+    Refunded,
+    // Paid for after the last copy was sold; waits for the seller's RefundPurchase
+    SoldOut,
+    // Order data the seller's copy of the form doesn't accept; waits for the seller's RefundPurchase
+    Rejected,
+}
+
+impl OrderStatus {
+    /// Paid for but not yet fulfilled
+    pub fn is_open(self) -> bool {
+        matches!(self, OrderStatus::Pending | OrderStatus::Accepted)
+    }
+}
+
+pub const MAX_OUTBOX_ATTEMPTS: u32 = 8;
+pub const OUTBOX_RETRY_BASE_MICROS: u64 = 60 * 1_000_000;
+
+// A mirror message awaiting the receiver's ack; RetryOutbox re-sends it with doubling delays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    // Sequence number among the messages tracked to `target_chain`
+    pub id: u64,
+    pub target_chain: ChainId,
+    pub message: Message,
+    // Signer the message was first sent with; only they can re-send it with the same authentication
+    pub sender: Option<AccountOwner>,
+    pub attempts: u32,
+    pub next_retry: u64,
+    pub created_at: u64,
+}
+
+impl OutboxEntry {
+    /// Wait after the `attempts`-th send: the base delay, then twice that, four times, ...
+    pub fn retry_delay(attempts: u32) -> u64 {
+        OUTBOX_RETRY_BASE_MICROS.saturating_mul(1 << attempts.saturating_sub(1).min(16))
+    }
+
+    /// Out of attempts: no longer re-sent, and dropped once its last wait passes without an ack
+    pub fn is_exhausted(&self) -> bool {
+        self.attempts >= MAX_OUTBOX_ATTEMPTS
+    }
+}
+
+pub const MAX_OUTBOX_GAPS: usize = 256;
+
+// Tracked messages from one origin chain already applied: every sequence below `below`, plus
+// the ones that arrived ahead of a gap
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeliveredSequences {
+    pub below: u64,
+    pub above: BTreeSet<u64>,
+}
+
+impl DeliveredSequences {
+    /// Record `sequence` as applied; false if it already was
+    pub fn insert(&mut self, sequence: u64) -> bool {
+        if sequence < self.below || !self.above.insert(sequence) {
+            return false;
+        }
+        // A gap whose message was dropped never fills; past the cap the oldest gap counts as applied
+        if self.above.len() > MAX_OUTBOX_GAPS {
+            if let Some(first) = self.above.first().copied() {
+                self.below = first;
+            }
+        }
+        while self.above.remove(&self.below) {
+            self.below += 1;
+        }
+        true
+    }
+}
+
+pub const MAX_FOLLOW_BATCH: usize = 50;
+
+pub const MAX_CATEGORY_CHARS: usize = 40;
+
+pub const MAX_ANNOUNCEMENT_CHARS: usize = 500;
+
+pub const MAX_DONATION_REPLY_CHARS: usize = 280;
+
+pub const MAX_GOAL_DESCRIPTION_CHARS: usize = 280;
+
+pub const MAX_ENTITLEMENTS: usize = 5;
+pub const MAX_ENTITLEMENT_CHARS: usize = 64;
+
+// Entitlement held by an owner; `purchase_id` is the purchase or subscription that granted it
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct EntitlementGrant {
+    pub name: String,
+    pub purchase_id: String,
+    pub granted_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+pub fn validate_entitlements(entitlements: &[String]) -> Result<(), String> {
+    if entitlements.len() > MAX_ENTITLEMENTS {
+        return Err(format!("Maximum {} entitlements allowed", MAX_ENTITLEMENTS));
+    }
+    if entitlements.iter().any(|e| e.trim().is_empty() || e.chars().count() > MAX_ENTITLEMENT_CHARS) {
+        return Err(format!("Entitlement names must be 1 to {} characters", MAX_ENTITLEMENT_CHARS));
+    }
+    Ok(())
+}
+
+pub const MAX_REVIEW_COMMENT_CHARS: usize = 1000;
+
+// A buyer's review of a product; one per buyer and product, replaced when they review again
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Review {
+    pub product_id: String,
+    pub reviewer: AccountOwner,
+    // 1 to 5
+    pub rating: u8,
+    pub comment: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+// Message between buyer and seller about a purchase
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct OrderNote {
+    pub from: AccountOwner,
+    pub text: String,
+    pub timestamp: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct PurchaseView {
     pub id: String,
@@ -335,6 +1224,25 @@ pub struct PurchaseView {
     pub product: ProductView,
 }
 
+impl From<&Purchase> for PurchaseView {
+    fn from(purchase: &Purchase) -> Self {
+        Self {
+            id: purchase.id.clone(),
+            product_id: purchase.product_id.clone(),
+            buyer: purchase.buyer,
+            buyer_chain_id: purchase.buyer_chain_id.clone(),
+            seller: purchase.seller,
+            seller_chain_id: purchase.seller_chain_id.clone(),
+            amount: purchase.amount,
+            timestamp: purchase.timestamp,
+            product: ProductView::from_full(&purchase.product),
+        }
+    }
+}
+
+/// Stream every chain publishes its `DonationsEvent`s on
+pub const EVENTS_STREAM: &str = "donations_events";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DonationsEvent {
     ProfileNameUpdated { owner: AccountOwner, name: String, timestamp: u64 },
@@ -342,15 +1250,32 @@ pub enum DonationsEvent {
     ProfileSocialUpdated { owner: AccountOwner, name: String, url: String, timestamp: u64 },
     ProfileAvatarUpdated { owner: AccountOwner, hash: String, timestamp: u64 },
     ProfileHeaderUpdated { owner: AccountOwner, hash: String, timestamp: u64 },
-    DonationSent { id: u64, from: AccountOwner, to: AccountOwner, amount: Amount, message: Option<String>, source_chain_id: Option<String>, to_chain_id: Option<String>, timestamp: u64 },
+    ProfileDeleted { owner: AccountOwner, timestamp: u64 },
+    Followed { follower: AccountOwner, author: AccountOwner, timestamp: u64 },
+    Unfollowed { follower: AccountOwner, author: AccountOwner, timestamp: u64 },
+    DonationSent { id: u64, from: AccountOwner, to: AccountOwner, amount: Amount, message: Option<String>, source_chain_id: Option<String>, source_donation_id: Option<u64>, to_chain_id: Option<String>, timestamp: u64 },
+    // Ids are per chain, so mirrors find the donation by the chain it was sent from and its id there
+    DonationReplied { donation_id: u64, source_chain_id: String, source_donation_id: u64, to: AccountOwner, text: String, timestamp: u64 },
     ProductCreated { product: Product, timestamp: u64 },
     ProductUpdated { product: Product, timestamp: u64 },
     ProductDeleted { product_id: String, author: AccountOwner, timestamp: u64 },
+    ProductSlugConflict { product_id: String, slug: String, timestamp: u64 },
+    FeaturedProductsUpdated { author: AccountOwner, product_ids: Vec<String>, timestamp: u64 },
+    ProductOrderUpdated { author: AccountOwner, ordered_ids: Vec<String>, timestamp: u64 },
     ProductPurchased { purchase_id: String, product_id: String, buyer: AccountOwner, seller: AccountOwner, amount: Amount, timestamp: u64 },
     // NEW: Order placed event
     OrderPlaced { purchase_id: String, product_id: String, buyer: AccountOwner, seller: AccountOwner, amount: Amount, timestamp: u64 },
+    ActivityExportReady { export: ActivityExport, timestamp: u64 },
+    // Buyer's chain received the product data for a purchase
+    PurchaseReady { purchase_id: String, product_id: String, buyer: AccountOwner, timestamp: u64 },
+    OrderStatusChanged { purchase_id: String, buyer: AccountOwner, seller: AccountOwner, status: OrderStatus, timestamp: u64 },
+    PurchaseRefunded { purchase_id: String, product_id: String, buyer: AccountOwner, seller: AccountOwner, amount: Amount, timestamp: u64 },
+    ProductReviewed { review: Review, timestamp: u64 },
     // Content subscription events
-    SubscriptionPriceSet { author: AccountOwner, price: Amount, description: Option<String>, timestamp: u64 },
+    SubscriptionPriceSet { author: AccountOwner, price: Amount, description: Option<String>, grandfather_renewals: u8, timestamp: u64 },
+    SubscriptionPriceChangeScheduled { author: AccountOwner, pending: PendingPrice, timestamp: u64 },
+    SubscriptionEntitlementsSet { author: AccountOwner, entitlements: Vec<String>, timestamp: u64 },
+    SubscriptionGracePeriodSet { author: AccountOwner, grace_period_micros: u64, timestamp: u64 },
     SubscriptionPriceDeleted { author: AccountOwner, timestamp: u64 },
     UserSubscribed { subscription_id: String, subscriber: AccountOwner, author: AccountOwner, price: Amount, end_timestamp: u64, timestamp: u64 },
     UserUnsubscribed { subscription_id: String, subscriber: AccountOwner, author: AccountOwner, timestamp: u64 },
@@ -363,6 +1288,57 @@ pub enum DonationsEvent {
     // Giveaway events
     GiveawayParticipated { post_id: String, participant: AccountOwner, timestamp: u64 },
     GiveawayResolved { post_id: String, winner: AccountOwner, winner_chain_id: String, prize_amount: Amount, timestamp: u64 },
+    // Comment events
+    CommentAdded { comment: Comment, timestamp: u64 },
+    CommentRemoved { comment: Comment, timestamp: u64 },
+    // Income redirect events
+    IncomeRedirectSet { owner: AccountOwner, redirect: IncomeRedirect, timestamp: u64 },
+    IncomeRedirectCleared { owner: AccountOwner, timestamp: u64 },
+    // Donation goal events
+    DonationGoalSet { owner: AccountOwner, goal: DonationGoal, timestamp: u64 },
+    DonationGoalDeleted { owner: AccountOwner, timestamp: u64 },
+    IncomeRedirected { owner: AccountOwner, target_chain_id: String, target_owner: AccountOwner, amount: Amount, label: String, timestamp: u64 },
+    // Admin events
+    AdminProposed { admin: AccountOwner, new_admin: AccountOwner, timestamp: u64 },
+    AdminTransferred { previous_admin: Option<AccountOwner>, new_admin: AccountOwner, timestamp: u64 },
+    // Supporter of the month
+    SupporterOfMonthDrawn { author: AccountOwner, year: u32, month: u32, winner: AccountOwner, prize: Option<Amount>, timestamp: u64 },
+    // An aggregation overflowed and was saturated instead
+    ArithmeticAnomaly { context: String, timestamp: u64 },
+    // Stream processing deferred events from `chain` to a later invocation
+    StreamBacklog { chain: ChainId, remaining: u64, timestamp: u64 },
+}
+
+// Most stream events applied in one `process_streams` invocation
+pub const STREAM_EVENT_BUDGET: u64 = 500;
+
+// Application parameters: the fungible `Parameters` plus an optional payment application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DonationsParameters {
+    pub ticker_symbol: String,
+    // When set, payments go through this fungible token application instead of native transfers
+    #[serde(default)]
+    pub payment_app: Option<ApplicationId>,
+    // Longest donation message and post title in characters; DEFAULT_MAX_MESSAGE_CHARS when unset
+    #[serde(default)]
+    pub max_message_chars: Option<u32>,
+    // Set when only the deployment's operator can reach the service: unlocks queries that read
+    // other users' private data or scan the whole state
+    #[serde(default)]
+    pub operator_mode: bool,
+}
+
+impl DonationsParameters {
+    pub fn message_limit(&self) -> usize {
+        self.max_message_chars.map_or(DEFAULT_MAX_MESSAGE_CHARS, |max| max as usize)
+    }
+}
+
+pub const DEFAULT_MAX_MESSAGE_CHARS: usize = 500;
+
+/// Drop control characters other than line breaks and tabs
+pub fn strip_control_chars(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control() || *c == '\n' || *c == '\t').collect()
 }
 
 pub struct DonationsAbi;
@@ -385,7 +1361,14 @@ pub enum Operation {
         target_account: linera_sdk::abis::fungible::Account,
         text_message: Option<String>,
     },
-    Withdraw,
+    // Move funds out of the signer's account: to `target` (default: the chain's own balance),
+    // `amount` defaulting to the whole balance (so does zero); fails if any of it is committed.
+    // Cross-chain targets are recorded like a Transfer.
+    Withdraw {
+        target: Option<linera_sdk::abis::fungible::Account>,
+        amount: Option<Amount>,
+    },
+    // Admin only, within the mint limits
     Mint { owner: AccountOwner, amount: Amount },
     UpdateProfile { name: Option<String>, bio: Option<String>, socials: Vec<SocialLinkInput>, avatar_hash: Option<String>, header_hash: Option<String> },
     Register { main_chain_id: ChainId, name: Option<String>, bio: Option<String>, socials: Vec<SocialLinkInput>, avatar_hash: Option<String>, header_hash: Option<String> },
@@ -400,8 +1383,17 @@ pub enum Operation {
         public_data: CustomFields,
         price: Amount,
         private_data: CustomFields,
+        // private_data is client-side encrypted
+        encrypted: Option<bool>,
         success_message: Option<String>,
         order_form: Vec<OrderFormFieldInput>,
+        slug: Option<String>,
+        category: Option<String>,
+        entitlements: Vec<String>,
+        // Name of a saved template to take the order form from, instead of `order_form`
+        order_form_template: Option<String>,
+        // Copies for sale; unlimited if None
+        stock: Option<u32>,
     },
     
     // NEW: Flexible UpdateProduct
@@ -410,14 +1402,48 @@ pub enum Operation {
         public_data: Option<CustomFields>,
         price: Option<Amount>,
         private_data: Option<CustomFields>,
+        encrypted: Option<bool>,
         success_message: Option<String>,
         order_form: Option<Vec<OrderFormFieldInput>>,
+        slug: Option<String>,  // Some("") clears the slug
+        category: Option<String>,  // Some("") clears the category
+        entitlements: Option<Vec<String>>,
+        // Set the copies left; `clear_stock` makes the product unlimited again
+        stock: Option<u32>,
+        clear_stock: Option<bool>,
     },
     
     DeleteProduct {
         product_id: String,
     },
     
+    // Point a product at the author's new home chain after they migrated, so payments route there
+    UpdateProductChain {
+        product_id: String,
+        new_chain_id: ChainId,
+    },
+    
+    // Pin up to 3 of the author's products to the top of their storefront, in order
+    SetFeaturedProducts {
+        product_ids: Vec<String>,
+    },
+    
+    // Arrange the storefront by hand: every one of the author's products, once each.
+    // An empty list goes back to newest first
+    ReorderProducts {
+        ordered_ids: Vec<String>,
+    },
+    
+    // Reusable order forms, kept on the author's chain only
+    SaveOrderFormTemplate {
+        name: String,
+        fields: Vec<OrderFormFieldInput>,
+    },
+    
+    DeleteOrderFormTemplate {
+        name: String,
+    },
+    
     // NEW: TransferToBuy with order data
     TransferToBuy {
         owner: AccountOwner,
@@ -427,14 +1453,56 @@ pub enum Operation {
         order_data: OrderResponses,
     },
     
+    AddOrderNote {
+        purchase_id: String,
+        text: String,
+    },
+    
+    // Rate a purchased product 1-5; reviewing again replaces the earlier review
+    ReviewProduct {
+        product_id: String,
+        rating: u8,
+        comment: Option<String>,
+    },
+    
     ReadDataBlob {
         hash: String,
     },
     
+    // Seller revokes a purchase's entitlements, e.g. after a refund
+    RevokePurchaseEntitlements {
+        purchase_id: String,
+    },
+    
+    // Seller accepts every one of their orders still pending
+    AcceptAllPendingOrders,
+    
+    // Seller moves one order along, e.g. to Fulfilled once shipped; Fulfilled and Cancelled are final,
+    // and cancelling pays the buyer back
+    UpdateOrderStatus {
+        purchase_id: String,
+        status: OrderStatus,
+    },
+    // Seller pays a purchase back to the buyer and restocks the product; repeating it does nothing
+    RefundPurchase {
+        purchase_id: String,
+    },
+    
     // Content subscription operations    
     SetSubscriptionPrice {
         price: Amount,
         description: Option<String>,
+        grandfather_renewals: u8,
+    },
+    
+    // Entitlements granted to the author's active subscribers
+    SetSubscriptionEntitlements {
+        entitlements: Vec<String>,
+    },
+    
+    // Lapsed subscribers keep read access this long, at most MAX_SUBSCRIPTION_GRACE_MICROS
+    SetSubscriptionGracePeriod {
+        grace_period_micros: u64,
     },
     
     DeleteSubscriptionPrice,
@@ -443,16 +1511,47 @@ pub enum Operation {
         owner: AccountOwner,
         amount: Amount,
         target_account: linera_sdk::abis::fungible::Account,
+        auto_renew: Option<bool>,
+    },
+    
+    // Subscriber turns renewal before expiry on or off
+    SetAutoRenew {
+        subscription_id: String,
+        auto_renew: bool,
     },
     
+    // Renew the signer's auto-renew subscriptions that end within AUTO_RENEW_WINDOW_MICROS.
+    // Each renewal is a new subscription starting when the old one ends.
+    ProcessAutoRenewals,
+    
+    // Subscriber ends a subscription before it expires; no refund
+    Unsubscribe {
+        subscription_id: String,
+    },
+    
+    // Short note to active subscribers' notification inboxes; unlike a post it isn't kept by the author
+    Announce {
+        text: String,
+    },
+    
+    // Only registered owners (those who ran `Register` on this chain) may post,
+    // so every post author has a profile and a main chain for feed enrichment
     CreatePost {
         title: String,
         content: String,
         image_hash: Option<String>,
         poll_options: Vec<String>,
         poll_end_timestamp: Option<u64>,
+        poll_broadcast_every_votes: Option<u32>,
+        poll_broadcast_interval_micros: Option<u64>,
         giveaway_prize: Option<Amount>,
         giveaway_end_timestamp: Option<u64>,
+        giveaway_max_participants: Option<u32>,
+        // Let others see who entered; defaults to hidden
+        #[serde(default)]
+        giveaway_participants_visible: Option<bool>,
+        // Defaults to public
+        visibility: Option<PostVisibility>,
     },
     
     UpdatePost {
@@ -460,6 +1559,8 @@ pub enum Operation {
         title: Option<String>,
         content: Option<String>,
         image_hash: Option<String>,
+        // Remove the image; `image_hash: None` alone leaves it unchanged
+        clear_image: Option<bool>,
     },
     
     DeletePost {
@@ -484,6 +1585,137 @@ pub enum Operation {
     ResolveGiveaway {
         post_id: String,
     },
+    
+    // End a poll now and push the final results to subscribers
+    ClosePoll {
+        post_id: String,
+    },
+    
+    // Comment operations
+    AddComment {
+        author_chain_id: ChainId,
+        author: AccountOwner,
+        post_id: String,
+        parent_id: Option<String>,
+        text: String,
+    },
+    
+    DeleteComment {
+        author_chain_id: ChainId,
+        comment_id: String,
+    },
+    
+    HideComment {
+        comment_id: String,
+    },
+    
+    // Income redirect operations
+    SetIncomeRedirect {
+        target: linera_sdk::abis::fungible::Account,
+        basis_points: u16,
+        label: String,
+    },
+    
+    ClearIncomeRedirect,
+    
+    // Fundraising goal shown with progress on the recipient's page
+    SetDonationGoal {
+        target: Amount,
+        deadline_micros: u64,
+        description: Option<String>,
+    },
+    
+    DeleteDonationGoal,
+    
+    // Admin role transfer (two steps so a mistyped address can't take over)
+    ProposeAdmin {
+        new_admin: AccountOwner,
+    },
+    
+    AcceptAdmin,
+    
+    // Supporter of the month: commit to a seed first, then reveal it to draw
+    CommitSupporterDraw {
+        year: u32,
+        month: u32,
+        commitment: String,
+    },
+    
+    DrawSupporterOfMonth {
+        year: u32,
+        month: u32,
+        seed: String,
+        prize: Option<Amount>,
+    },
+    
+    SetMintLimits {
+        max_per_mint: Amount,
+        supply_cap: Amount,
+    },
+    
+    // Backups (admin only); restore only fills empty views
+    SnapshotState {
+        scope: SnapshotScope,
+    },
+    
+    RestoreFromSnapshot {
+        manifest_hash: String,
+    },
+    
+    // Operator export of purchases, subscriptions and donations in [from, to) (admin only)
+    ExportActivityWindow {
+        from: u64,
+        to: u64,
+        // Continuing a capped export: records at `from` it already had
+        #[serde(default)]
+        skip: u32,
+    },
+    
+    // Custodial holding accounts (admin only): donations to them wait to be routed on
+    SetHoldingAccount {
+        owner: AccountOwner,
+        holding: bool,
+    },
+    
+    RouteDonation {
+        donation_id: u64,
+        new_recipient_account: linera_sdk::abis::fungible::Account,
+    },
+    
+    // Recipient only: feature one received donation on their page
+    PinDonation {
+        donation_id: u64,
+    },
+    
+    // Following oneself is refused; following an author twice is a no-op
+    Follow {
+        author: AccountOwner,
+    },
+    
+    Unfollow {
+        author: AccountOwner,
+    },
+    
+    // Follow several authors at once; ones already followed and the caller themselves are skipped
+    FollowMany {
+        authors: Vec<AccountOwner>,
+    },
+    
+    // Recipient only: public thank-you shown under a received donation; replaces an earlier one
+    ReplyToDonation {
+        donation_id: u64,
+        text: String,
+    },
+    
+    // Permanently close the caller's account: the profile, products and subscription offer
+    // are removed, donation and purchase records are kept. Only Withdraw works afterwards.
+    CloseAccount,
+    
+    // Re-send this chain's unacknowledged mirror messages whose retry time has passed
+    RetryOutbox,
+    
+    // Apply stream events left over by an earlier invocation's budget; anyone may run it
+    DrainStreamBacklog,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -491,4 +1723,6 @@ pub enum ResponseData {
     Ok,
     Profile(Option<Profile>),
     Donations(Vec<DonationRecord>),
+    // The operation was refused without changing anything; `code` is stable, `message` is for people
+    Error { code: String, message: String },
 }
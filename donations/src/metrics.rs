@@ -0,0 +1,320 @@
+//! Opt-in query metrics for finding slow resolvers and N+1 reads.
+//! Built only with the `debug-metrics` feature; otherwise the counters are no-ops.
+//! A request asks for them by setting the `lfDebug` request extension, and gets back
+//! an `lfDebug` response extension with totals and per top-level resolver figures.
+//! Map reads are counted by [`CountedMapView`], which the state uses for all of its maps.
+
+use linera_sdk::views::{linera_views, MapView, View, ViewError, ViewStorageContext};
+use serde::{de::DeserializeOwned, Serialize};
+use std::borrow::Borrow;
+
+/// Request extension that turns metrics on for one query
+pub const DEBUG_FLAG: &str = "lfDebug";
+
+#[cfg(feature = "debug-metrics")]
+mod counters {
+    use std::cell::RefCell;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// Figures for one request
+    #[derive(Default)]
+    pub struct Counters {
+        loads: AtomicU64,
+        reads: AtomicU64,
+    }
+
+    impl Counters {
+        /// (state loads, map reads) so far
+        pub fn totals(&self) -> (u64, u64) {
+            (self.loads.load(Ordering::Relaxed), self.reads.load(Ordering::Relaxed))
+        }
+    }
+
+    thread_local! {
+        // Counters of the request executing on this thread, if it asked for metrics.
+        // Services run one query at a time on a single thread, so this is the request's scope.
+        static CURRENT: RefCell<Option<Arc<Counters>>> = const { RefCell::new(None) };
+    }
+
+    fn with_current(count: impl FnOnce(&Counters)) {
+        CURRENT.with(|current| {
+            if let Some(counters) = current.borrow().as_deref() {
+                count(counters);
+            }
+        });
+    }
+
+    pub fn count_load() {
+        with_current(|c| { c.loads.fetch_add(1, Ordering::Relaxed); });
+    }
+
+    pub fn count_reads(n: u64) {
+        with_current(|c| { c.reads.fetch_add(n, Ordering::Relaxed); });
+    }
+
+    /// Count into `counters` until the guard is dropped; the previous scope is then restored
+    pub fn scope(counters: Arc<Counters>) -> Scope {
+        Scope { previous: CURRENT.with(|current| current.replace(Some(counters))) }
+    }
+
+    pub struct Scope {
+        previous: Option<Arc<Counters>>,
+    }
+
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            CURRENT.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+}
+
+#[cfg(feature = "debug-metrics")]
+pub use counters::{count_load, count_reads, scope, Counters};
+
+#[cfg(not(feature = "debug-metrics"))]
+#[inline(always)]
+pub fn count_load() {}
+
+#[cfg(not(feature = "debug-metrics"))]
+#[inline(always)]
+pub fn count_reads(_n: u64) {}
+
+/// A `MapView` whose reads count as map reads: one per key looked up, one per listing.
+/// Writes go straight to the inner view.
+pub struct CountedMapView<I, V>(MapView<I, V>);
+
+impl<I: Send + Sync, V: Send + Sync + Serialize> View for CountedMapView<I, V> {
+    const NUM_INIT_KEYS: usize = <MapView<I, V> as View>::NUM_INIT_KEYS;
+
+    type Context = ViewStorageContext;
+
+    fn context(&self) -> &ViewStorageContext {
+        self.0.context()
+    }
+
+    fn pre_load(context: &ViewStorageContext) -> Result<Vec<Vec<u8>>, ViewError> {
+        MapView::<I, V>::pre_load(context)
+    }
+
+    fn post_load(context: ViewStorageContext, values: &[Option<Vec<u8>>]) -> Result<Self, ViewError> {
+        MapView::post_load(context, values).map(CountedMapView)
+    }
+
+    fn rollback(&mut self) {
+        self.0.rollback()
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        self.0.has_pending_changes().await
+    }
+
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    fn pre_save(&self, batch: &mut linera_views::batch::Batch) -> Result<bool, ViewError> {
+        self.0.pre_save(batch)
+    }
+
+    fn post_save(&mut self) {
+        self.0.post_save()
+    }
+}
+
+impl<I, V> std::ops::Deref for CountedMapView<I, V> {
+    type Target = MapView<I, V>;
+
+    fn deref(&self) -> &MapView<I, V> {
+        &self.0
+    }
+}
+
+impl<I, V> std::ops::DerefMut for CountedMapView<I, V> {
+    fn deref_mut(&mut self) -> &mut MapView<I, V> {
+        &mut self.0
+    }
+}
+
+impl<I: Serialize, V: Clone + DeserializeOwned + 'static> CountedMapView<I, V> {
+    pub async fn get<Q: Serialize + ?Sized>(&self, index: &Q) -> Result<Option<V>, ViewError> where I: Borrow<Q> {
+        count_reads(1);
+        self.0.get(index).await
+    }
+
+    pub async fn get_mut<Q: Serialize + ?Sized>(&mut self, index: &Q) -> Result<Option<&mut V>, ViewError> where I: Borrow<Q> {
+        count_reads(1);
+        self.0.get_mut(index).await
+    }
+
+    pub async fn contains_key<Q: Serialize + ?Sized>(&self, index: &Q) -> Result<bool, ViewError> where I: Borrow<Q> {
+        count_reads(1);
+        self.0.contains_key(index).await
+    }
+}
+
+impl<I: Send + DeserializeOwned, V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static> CountedMapView<I, V> {
+    pub async fn indices(&self) -> Result<Vec<I>, ViewError> {
+        count_reads(1);
+        self.0.indices().await
+    }
+
+    pub async fn index_values(&self) -> Result<Vec<(I, V)>, ViewError> {
+        count_reads(1);
+        self.0.index_values().await
+    }
+
+    pub async fn count(&self) -> Result<usize, ViewError> {
+        count_reads(1);
+        self.0.count().await
+    }
+}
+
+#[cfg(feature = "debug-metrics")]
+mod extension {
+    use super::counters::{scope, Counters};
+    use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo};
+    use async_graphql::{ObjectType, Request, Response, SchemaBuilder, ServerResult, SubscriptionType, Value};
+    use std::sync::{Arc, Mutex};
+
+    struct ResolverMetric {
+        field: String,
+        micros: Option<u64>,
+        loads: u64,
+        reads: u64,
+    }
+
+    #[derive(Clone, Default)]
+    struct ResolverMetrics {
+        counters: Arc<Counters>,
+        resolvers: Arc<Mutex<Vec<ResolverMetric>>>,
+    }
+
+    impl ExtensionFactory for ResolverMetrics {
+        fn create(&self) -> Arc<dyn Extension> {
+            Arc::new(self.clone())
+        }
+    }
+
+    // Wasm has no wall clock, so resolver times are only measured on native builds
+    #[cfg(not(target_arch = "wasm32"))]
+    type Started = std::time::Instant;
+    #[cfg(target_arch = "wasm32")]
+    type Started = ();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start() -> Started {
+        std::time::Instant::now()
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn start() -> Started {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn elapsed_micros(started: Started) -> Option<u64> {
+        Some(started.elapsed().as_micros() as u64)
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn elapsed_micros(_started: Started) -> Option<u64> {
+        None
+    }
+
+    #[async_trait::async_trait]
+    impl Extension for ResolverMetrics {
+        async fn resolve(&self, ctx: &ExtensionContext<'_>, info: ResolveInfo<'_>, next: NextResolve<'_>) -> ServerResult<Option<Value>> {
+            // Only top-level fields; nested ones are part of their parent's figures
+            if info.path_node.parent.is_some() || info.is_for_introspection {
+                return next.run(ctx, info).await;
+            }
+            let field = info.alias.unwrap_or(info.name).to_string();
+            let (loads, reads) = self.counters.totals();
+            let started = start();
+            let res = next.run(ctx, info).await;
+            let micros = elapsed_micros(started);
+            let (loads_after, reads_after) = self.counters.totals();
+            self.resolvers.lock().unwrap().push(ResolverMetric { field, micros, loads: loads_after - loads, reads: reads_after - reads });
+            res
+        }
+    }
+
+    /// Execute `request` with metrics on and attach them as the `lfDebug` response extension.
+    /// Totals are exact; per-resolver counts are exact only when top-level fields don't
+    /// interleave, i.e. for single-field queries and for mutations.
+    pub async fn execute_with_metrics<Q, M, S>(builder: SchemaBuilder<Q, M, S>, request: Request) -> Response
+    where
+        Q: ObjectType + 'static,
+        M: ObjectType + 'static,
+        S: SubscriptionType + 'static,
+    {
+        let metrics = ResolverMetrics::default();
+        let schema = builder.extension(metrics.clone()).finish();
+        let mut response = {
+            let _scope = scope(metrics.counters.clone());
+            schema.execute(request).await
+        };
+        let (loads, reads) = metrics.counters.totals();
+        let resolvers: Vec<serde_json::Value> = metrics.resolvers.lock().unwrap().iter().map(|m| serde_json::json!({
+            "field": m.field,
+            "micros": m.micros,
+            "stateLoads": m.loads,
+            "mapReads": m.reads,
+        })).collect();
+        let report = serde_json::json!({ "stateLoads": loads, "mapReads": reads, "resolvers": resolvers });
+        if let Ok(value) = Value::from_json(report) {
+            response.extensions.insert(super::DEBUG_FLAG.to_string(), value);
+        }
+        response
+    }
+}
+
+#[cfg(feature = "debug-metrics")]
+pub use extension::execute_with_metrics;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linera_sdk::{util::BlockingWait, views::KeyValueStore};
+
+    fn map() -> CountedMapView<u64, String> {
+        let context = ViewStorageContext::new_unchecked(KeyValueStore::mock().to_mut(), Vec::new(), ());
+        let mut map = CountedMapView::<u64, String>::load(context).blocking_wait().unwrap();
+        map.insert(&1, "one".to_string()).unwrap();
+        map.insert(&2, "two".to_string()).unwrap();
+        map
+    }
+
+    #[test]
+    fn counted_map_reads_like_the_map_it_wraps() {
+        let mut map = map();
+        assert_eq!(map.get(&1).blocking_wait().unwrap().as_deref(), Some("one"));
+        assert!(map.contains_key(&2).blocking_wait().unwrap());
+        assert_eq!(map.indices().blocking_wait().unwrap(), vec![1, 2]);
+        assert_eq!(map.count().blocking_wait().unwrap(), 2);
+        map.get_mut(&2).blocking_wait().unwrap().unwrap().push('!');
+        assert_eq!(map.index_values().blocking_wait().unwrap()[1].1, "two!");
+    }
+
+    #[cfg(feature = "debug-metrics")]
+    #[test]
+    fn reads_are_counted_only_inside_their_own_request() {
+        use std::sync::Arc;
+        let map = map();
+        map.get(&1).blocking_wait().unwrap();
+        let counters = Arc::new(Counters::default());
+        {
+            let _scope = scope(counters.clone());
+            map.get(&1).blocking_wait().unwrap();
+            map.index_values().blocking_wait().unwrap();
+            // Another request on its own thread keeps its own figures
+            let other = Arc::new(Counters::default());
+            let theirs = other.clone();
+            std::thread::spawn(move || {
+                let _scope = scope(theirs);
+                count_reads(5);
+            }).join().unwrap();
+            assert_eq!(other.totals(), (0, 5));
+        }
+        map.get(&2).blocking_wait().unwrap();
+        assert_eq!(counters.totals(), (0, 2));
+    }
+}
@@ -4,13 +4,13 @@ mod state;
 
 use std::sync::Arc;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
-use linera_sdk::{linera_base_types::{AccountOwner, WithServiceAbi, Amount}, views::View, Service, ServiceRuntime};
+use linera_sdk::{linera_base_types::{AccountOwner, ChainId, CryptoHash, StreamName, WithServiceAbi, Amount}, Service, ServiceRuntime};
 use donations::{
-    DonationsAbi, Operation, AccountInput, Profile as LibProfile, DonationRecord as LibDonationRecord,
+    DonationsAbi, DonationsParameters, Operation, AccountInput, Profile as LibProfile, DonationRecord as LibDonationRecord,
     ProfileView, DonationView, SocialLinkInput, TotalAmountView, CustomFields, OrderFormField,
-    OrderFormFieldInput, OrderResponses, Product, ContentSubscription, Post, Poll, PollOption, Giveaway, GiveawayParticipant,
+    OrderFormFieldInput, OrderResponses, Product, ContentSubscription, Post, PostVisibility, SubscriptionAccess, Poll, Giveaway, Comment, DonationSide,
 };
-use state::DonationsState;
+use state::{DonationsState, DonationPageQuery};
 use async_graphql::{SimpleObject, InputObject};
 
 // NEW: Product public view (visible to all, excludes private data)
@@ -22,7 +22,24 @@ struct ProductPublicView {
     public_data: Vec<KeyValuePair>,
     price: Amount,
     order_form: Vec<OrderFormFieldView>,
+    slug: Option<String>,
+    category: Option<String>,
+    entitlements: Vec<String>,
+    featured: bool,
+    featured_rank: Option<u8>,  // 0 = first
+    content_revision: u32,
+    form_revision: u32,
     created_at: u64,
+    stock: Option<u32>,  // Copies left; None when unlimited
+    rating: RatingView,
+    encrypted: bool,  // Delivered private data is client-side encrypted
+}
+
+// Average review rating, rounded to one decimal; 0 with no reviews
+#[derive(SimpleObject)]
+struct RatingView {
+    average: f64,
+    count: u32,
 }
 
 // NEW: Product full view (includes private data, for purchased products)
@@ -34,8 +51,13 @@ struct ProductFullView {
     public_data: Vec<KeyValuePair>,
     price: Amount,
     private_data: Vec<KeyValuePair>,
+    encrypted: bool,  // private_data values are ciphertext for the client to decrypt
     success_message: Option<String>,
     order_form: Vec<OrderFormFieldView>,
+    slug: Option<String>,
+    category: Option<String>,
+    content_revision: u32,
+    form_revision: u32,
     created_at: u64,
 }
 
@@ -67,6 +89,9 @@ struct PurchaseFullView {
     amount: Amount,
     timestamp: u64,
     order_data: Vec<KeyValuePair>,
+    content_modified: bool,
+    payment_app: Option<String>,
+    status: donations::OrderStatus,
     product: ProductFullView,
 }
 
@@ -86,6 +111,35 @@ struct PollView {
     is_ended: bool,
 }
 
+// How a subscriber chain's mirror of an author's posts differs from the author's own list
+#[derive(SimpleObject)]
+struct SyncGapView {
+    author: AccountOwner,
+    author_chain_id: Option<String>,
+    held_count: u32,
+    expected_count: u32,
+    // On the author's list but not mirrored here
+    missing: Vec<String>,
+    // Mirrored here but no longer on the author's list
+    extra: Vec<String>,
+    // Author's events applied here so far, and the last index known to exist; null when not followed
+    applied_events: Option<u64>,
+    known_events: Option<u64>,
+}
+
+// One poll post in an author's overview
+#[derive(SimpleObject)]
+struct PollSummaryView {
+    post_id: String,
+    title: String,
+    total_votes: u32,
+    // None until the poll has votes; ties go to the earlier option
+    leading_option: Option<String>,
+    leading_votes: u32,
+    is_ended: bool,
+    created_at: u64,
+}
+
 // Post view with poll
 #[derive(SimpleObject)]
 struct PostView {
@@ -98,6 +152,34 @@ struct PostView {
     created_at: u64,
     poll: Option<PollView>,
     giveaway: Option<GiveawayView>,
+    visibility: PostVisibility,
+    // Subscriber-only post the viewing chain has no access to; content and image are left out
+    locked: bool,
+    comment_count: u32,
+}
+
+// Comment with its visible reply count; removed comments keep author and linkage
+#[derive(SimpleObject)]
+struct CommentView {
+    id: String,
+    post_id: String,
+    author: AccountOwner,
+    author_chain_id: String,
+    parent_id: Option<String>,
+    text: String,
+    created_at: u64,
+    deleted: bool,
+    hidden: bool,
+    content_modified: bool,
+    reply_count: u32,
+}
+
+// Ended giveaway still waiting for its author to pick a winner
+#[derive(SimpleObject)]
+struct GiveawayTaskView {
+    post_id: String,
+    participant_count: u32,
+    end_timestamp: u64,
 }
 
 // Giveaway participant view
@@ -107,18 +189,278 @@ struct GiveawayParticipantView {
     chain_id: String,
 }
 
+// Giveaway entrant joined with their profile, if they have one
+#[derive(SimpleObject)]
+struct GiveawayEntrantView {
+    owner: AccountOwner,
+    chain_id: String,
+    joined_at: u64,
+    profile: Option<ProfileView>,
+}
+
+// Giveaway entrants; only the count when the creator keeps them hidden
+#[derive(SimpleObject)]
+struct GiveawayParticipantsView {
+    post_id: String,
+    participants_count: u32,
+    participants_visible: bool,
+    participants: Vec<GiveawayEntrantView>,
+}
+
 // Giveaway view with computed fields
 #[derive(SimpleObject)]
 struct GiveawayView {
     prize_amount: Amount,
     end_timestamp: u64,
     participants_count: u32,
+    // None when the giveaway has no participant cap
+    remaining_slots: Option<u32>,
     is_ended: bool,
     is_resolved: bool,
     winner: Option<GiveawayParticipantView>,
 }
 
+// Count of inbound content adjustments of one kind on one day
+#[derive(SimpleObject)]
+struct ContentAdjustmentStat {
+    kind: String,
+    day: u32,
+    count: u64,
+}
+
+// public_data keys that changed between a purchase snapshot and the current product
+#[derive(SimpleObject)]
+struct ProductDiffView {
+    purchase_id: String,
+    product_id: String,
+    product_exists: bool,
+    // Content revision differs from the purchased one; order form edits don't count
+    content_changed: bool,
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+}
+
+// A donor's cumulative giving to one recipient, with their profile basics if they have one
+#[derive(SimpleObject)]
+struct TopDonorView {
+    donor: AccountOwner,
+    total: Amount,
+    name: Option<String>,
+    avatar_hash: Option<String>,
+}
+
+// An outbound mirror message this chain is still waiting to have acked
+#[derive(SimpleObject)]
+struct OutboxEntryView {
+    id: u64,
+    target_chain: String,
+    sender: Option<AccountOwner>,
+    attempts: u32,
+    next_retry: u64,
+    created_at: u64,
+    // Out of attempts; retryOutbox drops it if still unacked at nextRetry
+    exhausted: bool,
+}
+
+// A donor's giving overall, with a breakdown per recipient (largest total first)
+#[derive(SimpleObject)]
+struct DonorStatsView {
+    owner: AccountOwner,
+    total: Amount,
+    donation_count: u32,
+    distinct_recipients: u32,
+    largest: Amount,
+    first_at: Option<u64>,
+    last_at: Option<u64>,
+    recipients: Vec<RecipientTallyView>,
+}
+
+#[derive(SimpleObject)]
+struct RecipientTallyView {
+    recipient: AccountOwner,
+    total: Amount,
+    count: u32,
+}
+
+// One page of donations. Pass `next_cursor` as `afterId` to get the next page;
+// it is null once the last page was returned
+#[derive(SimpleObject)]
+struct DonationViewPage {
+    donations: Vec<DonationView>,
+    total_count: u32,
+    next_cursor: Option<u64>,
+}
+
+/// Page arguments shared by the donation list queries: newest first unless
+/// `oldest_first`, 50 per page by default and at most 500
+fn donation_page_query(after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>) -> DonationPageQuery {
+    DonationPageQuery {
+        after_id,
+        offset: offset.unwrap_or(0) as usize,
+        limit: limit.unwrap_or(50).min(500) as usize,
+        newest_first: !oldest_first.unwrap_or(false),
+    }
+}
+
+/// The cursor after a page: its last id, unless the page came back short
+fn next_cursor(list: &[LibDonationRecord], page: &DonationPageQuery) -> Option<u64> {
+    if list.len() < page.limit { None } else { list.last().map(|r| r.id) }
+}
+
+// What one donor sent one recipient, newest first
+#[derive(SimpleObject)]
+struct DonationsBetweenView {
+    donations: Vec<DonationView>,
+    // Saturates at the maximum amount rather than overflowing
+    total: Amount,
+}
+
+// A seller's orders in a time window, oldest first, with their sum
+#[derive(SimpleObject)]
+struct SalesInRangeView {
+    sales: Vec<PurchaseFullView>,
+    // Saturates at the maximum amount rather than overflowing
+    total: Amount,
+}
+
+// A seller's saved order form
+#[derive(SimpleObject)]
+struct OrderFormTemplateView {
+    name: String,
+    fields: Vec<OrderFormField>,
+}
+
+// One entry of a subscriber's merged feed; exactly one of post and announcement is set
+#[derive(SimpleObject)]
+struct ActivityFeedItem {
+    // "post" or "announcement"
+    kind: String,
+    timestamp: u64,
+    post: Option<PostView>,
+    announcement: Option<donations::Notification>,
+}
+
+// A donation goal and how far along it is
+#[derive(SimpleObject)]
+struct DonationGoalView {
+    owner: AccountOwner,
+    goal: donations::DonationGoal,
+    received: Amount,
+    percent_complete: u32,  // Basis points, 10000 = reached
+    expired: bool,
+}
+
+// Lifetime income redirected under a creator's pledge
+#[derive(SimpleObject)]
+struct RedirectTotalsView {
+    owner: AccountOwner,
+    redirected: Amount,
+    pending: Amount,
+}
+
+// Subscriber growth for one time bucket
+#[derive(SimpleObject)]
+struct SubscriberGrowthBucket {
+    bucket_start: u64,
+    new_subscribers: u32,
+    expired_subscribers: u32,
+    net_growth: i32,
+}
+
+// One entry of an owner's merged activity timeline
+#[derive(SimpleObject)]
+struct TimelineEntry {
+    // donation_sent, donation_received, purchase, sale, subscription or subscriber
+    kind: String,
+    reference_id: String,
+    counterparty: AccountOwner,
+    amount: Amount,
+    timestamp: u64,
+}
+
+// Cheapest and most expensive product of an author
+#[derive(SimpleObject)]
+struct PriceRangeView {
+    min: Amount,
+    max: Amount,
+}
+
+// How an author responds to comments over the last ENGAGEMENT_WINDOW_DAYS
+#[derive(SimpleObject)]
+struct AuthorEngagementView {
+    owner: AccountOwner,
+    comments_received: u32,
+    comments_answered: u32,
+    response_rate_bps: u32,  // 10000 = every comment answered within 7 days
+    median_response_hours: Option<u32>,
+}
+
+// Everything a creator's page shows, read in one query
+#[derive(SimpleObject)]
+struct CreatorPageView {
+    profile: Option<ProfileView>,
+    products: Vec<ProductPublicView>,  // Storefront order: featured first
+    goal: Option<DonationGoalView>,
+    engagement: EngagementSummaryView,
+}
+
+// The headline figures of AuthorEngagementView
+#[derive(SimpleObject)]
+struct EngagementSummaryView {
+    comments_received: u32,
+    response_rate_bps: u32,
+    median_response_hours: Option<u32>,
+}
+
+#[derive(SimpleObject)]
+struct DeprecatedFieldView {
+    type_name: String,
+    field: String,
+    replacement: String,
+    removal_version: String,
+}
+
+// Audience members of a creator on one chain
+#[derive(SimpleObject)]
+struct AudienceChainView {
+    chain_id: String,
+    subscribers: u32,
+    buyers: u32,
+}
+
+// One page of a JSON backup of this chain's state
+#[derive(SimpleObject, Default)]
+struct StateExportView {
+    profiles: Vec<LibProfile>,
+    products: Vec<Product>,
+    purchases: Vec<donations::Purchase>,
+    posts: Vec<Post>,
+    subscriptions: Vec<ContentSubscription>,
+    donations: Vec<LibDonationRecord>,
+    next_offset: Option<u32>,  // None on the last page
+}
+
+const STATE_EXPORT_MAX_RECORDS: usize = 500;
+
+// Deprecated fields as (type, field, replacement); keep in sync with the `deprecation` attributes
+const DEPRECATED_FIELDS: &[(&str, &str, &str)] = &[
+    ("QueryRoot", "totalReceivedAmount", "totalReceivedView"),
+    ("QueryRoot", "totalSentAmount", "totalSentView"),
+    ("QueryRoot", "productLegacy", "product"),
+    ("QueryRoot", "purchasesLegacy", "purchases"),
+];
+const LEGACY_REMOVAL_VERSION: &str = "0.2.0";
+
 // Helper functions
+/// A known chain id, or the local chain flagged as assumed
+fn chain_id_or_local(known: Option<String>, local: ChainId) -> (String, bool) {
+    match known {
+        Some(chain_id) => (chain_id, true),
+        None => (local.to_string(), false),
+    }
+}
+
 fn btree_to_pairs(map: &CustomFields) -> Vec<KeyValuePair> {
     map.iter().map(|(k, v)| KeyValuePair { key: k.clone(), value: v.clone() }).collect()
 }
@@ -132,7 +474,32 @@ fn order_form_to_views(form: &[OrderFormField]) -> Vec<OrderFormFieldView> {
     }).collect()
 }
 
-fn product_to_public_view(p: &Product) -> ProductPublicView {
+fn featured_rank(featured: &[String], product_id: &str) -> Option<u8> {
+    featured.iter().position(|id| id == product_id).map(|i| i as u8)
+}
+
+// Storefront order: featured products in the author's order, then the author's manual
+// order if set, then newest first (products added after the manual order was set)
+fn sort_storefront(products: &mut [Product], featured: &[String], order: &[String]) {
+    products.sort_by_key(|p| (
+        featured_rank(featured, &p.id).unwrap_or(u8::MAX),
+        order.iter().position(|id| *id == p.id).unwrap_or(usize::MAX),
+        std::cmp::Reverse(p.created_at),
+    ));
+}
+
+fn rating_view((sum, count): (u32, u32)) -> RatingView {
+    let tenths = (sum * 10 + count / 2).checked_div(count).unwrap_or(0);
+    RatingView { average: tenths as f64 / 10.0, count }
+}
+
+async fn load_public_view(state: &DonationsState, p: &Product, featured: &[String]) -> ProductPublicView {
+    let rating = rating_view(state.rating_totals(&p.id).await.unwrap_or_default());
+    product_to_public_view(p, featured, rating)
+}
+
+fn product_to_public_view(p: &Product, featured: &[String], rating: RatingView) -> ProductPublicView {
+    let featured_rank = featured_rank(featured, &p.id);
     ProductPublicView {
         id: p.id.clone(),
         author: p.author,
@@ -140,7 +507,17 @@ fn product_to_public_view(p: &Product) -> ProductPublicView {
         public_data: btree_to_pairs(&p.public_data),
         price: p.price,
         order_form: order_form_to_views(&p.order_form),
+        slug: p.slug.clone(),
+        category: p.category.clone(),
+        entitlements: p.entitlements.clone(),
+        featured: featured_rank.is_some(),
+        featured_rank,
+        content_revision: p.content_revision,
+        form_revision: p.form_revision,
         created_at: p.created_at,
+        stock: p.stock,
+        rating,
+        encrypted: p.encrypted,
     }
 }
 
@@ -152,12 +529,40 @@ fn product_to_full_view(p: &Product) -> ProductFullView {
         public_data: btree_to_pairs(&p.public_data),
         price: p.price,
         private_data: btree_to_pairs(&p.private_data),
+        encrypted: p.encrypted,
         success_message: p.success_message.clone(),
         order_form: order_form_to_views(&p.order_form),
+        slug: p.slug.clone(),
+        category: p.category.clone(),
+        content_revision: p.content_revision,
+        form_revision: p.form_revision,
         created_at: p.created_at,
     }
 }
 
+fn public_data_diff(purchase_id: &str, snapshot: &Product, current: Option<&Product>) -> ProductDiffView {
+    let mut diff = ProductDiffView {
+        purchase_id: purchase_id.to_string(),
+        product_id: snapshot.id.clone(),
+        product_exists: current.is_some(),
+        content_changed: current.is_some_and(|c| c.content_revision != snapshot.content_revision),
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+    };
+    if let Some(current) = current {
+        for (key, value) in &current.public_data {
+            match snapshot.public_data.get(key) {
+                None => diff.added.push(key.clone()),
+                Some(old) if old != value => diff.modified.push(key.clone()),
+                _ => {}
+            }
+        }
+        diff.removed = snapshot.public_data.keys().filter(|k| !current.public_data.contains_key(*k)).cloned().collect();
+    }
+    diff
+}
+
 fn poll_to_view(poll: &Poll, current_time: u64) -> PollView {
     let total_votes = poll.options.iter().map(|o| o.votes_count).sum();
     PollView {
@@ -176,6 +581,7 @@ fn giveaway_to_view(giveaway: &Giveaway, current_time: u64) -> GiveawayView {
         prize_amount: giveaway.prize_amount,
         end_timestamp: giveaway.end_timestamp,
         participants_count: giveaway.participants.len() as u32,
+        remaining_slots: giveaway.remaining_slots(),
         is_ended: giveaway.end_timestamp > 0 && current_time > giveaway.end_timestamp,
         is_resolved: giveaway.is_resolved,
         winner: giveaway.winner.as_ref().map(|w| GiveawayParticipantView {
@@ -185,18 +591,232 @@ fn giveaway_to_view(giveaway: &Giveaway, current_time: u64) -> GiveawayView {
     }
 }
 
-fn post_to_view(post: &Post, current_time: u64) -> PostView {
+/// `unlocked`: whether the viewer may read the author's subscriber-only posts
+fn post_to_view(post: &Post, current_time: u64, comment_count: u32, unlocked: bool) -> PostView {
+    let locked = post.visibility == PostVisibility::Subscribers && !unlocked;
     PostView {
         id: post.id.clone(),
         author: post.author,
         author_chain_id: post.author_chain_id.clone(),
         title: post.title.clone(),
-        content: post.content.clone(),
-        image_hash: post.image_hash.clone(),
+        content: if locked { String::new() } else { post.content.clone() },
+        image_hash: if locked { None } else { post.image_hash.clone() },
         created_at: post.created_at,
         poll: post.poll.as_ref().map(|p| poll_to_view(p, current_time)),
         giveaway: post.giveaway.as_ref().map(|g| giveaway_to_view(g, current_time)),
+        visibility: post.visibility,
+        locked,
+        comment_count,
+    }
+}
+
+fn comment_to_view(comment: Comment, reply_count: u32) -> CommentView {
+    CommentView {
+        id: comment.id,
+        post_id: comment.post_id,
+        author: comment.author,
+        author_chain_id: comment.author_chain_id,
+        parent_id: comment.parent_id,
+        text: comment.text,
+        created_at: comment.created_at,
+        deleted: comment.deleted,
+        hidden: comment.hidden,
+        content_modified: comment.content_modified,
+        reply_count,
+    }
+}
+
+/// Take the part of `items` that falls in the current page, updating what is left to skip and to fill
+fn page_section<T>(items: Vec<T>, skip: &mut usize, room: &mut usize) -> Vec<T> {
+    let skipped = (*skip).min(items.len());
+    *skip -= skipped;
+    let page: Vec<T> = items.into_iter().skip(skipped).take(*room).collect();
+    *room -= page.len();
+    page
+}
+
+async fn load_profile_view(state: &DonationsState, owner: AccountOwner, local: ChainId) -> Option<ProfileView> {
+    let p = state.get_profile(owner).await.ok().flatten()?;
+    let (chain_id, chain_id_known) = chain_id_or_local(state.resolve_chain_id(owner).await, local);
+    let income_redirect = state.get_income_redirect(owner).await.ok().flatten();
+    Some(ProfileView {
+        owner: p.owner,
+        chain_id,
+        chain_id_known,
+        name: p.name,
+        bio: p.bio,
+        socials: p.socials,
+        avatar_hash: p.avatar_hash,
+        header_hash: p.header_hash,
+        income_redirect,
+    })
+}
+
+/// The author's products in storefront order: featured first, then the manual order or newest
+async fn storefront_views(state: &DonationsState, owner: AccountOwner) -> Vec<ProductPublicView> {
+    let featured = state.get_featured_products(owner).await.unwrap_or_default();
+    let order = state.get_product_order(owner).await.unwrap_or_default();
+    let Ok(mut products) = state.list_products_by_author(owner).await else { return Vec::new() };
+    sort_storefront(&mut products, &featured, &order);
+    let mut res = Vec::with_capacity(products.len());
+    for p in &products {
+        res.push(load_public_view(state, p, &featured).await);
+    }
+    res
+}
+
+async fn load_goal_view(state: &DonationsState, owner: AccountOwner, current_time: u64) -> Option<DonationGoalView> {
+    let goal = state.donation_goals.get(&owner).await.ok().flatten()?;
+    let received = state.goal_received(owner).await.unwrap_or_default();
+    Some(DonationGoalView {
+        owner,
+        percent_complete: goal.progress_bps(received),
+        expired: current_time > goal.deadline_micros,
+        received,
+        goal,
+    })
+}
+
+async fn load_engagement_view(state: &DonationsState, owner: AccountOwner, current_time: u64) -> AuthorEngagementView {
+    let days = state.comment_engagement.get(&owner).await.ok().flatten().unwrap_or_default();
+    let today = (current_time / state::MICROS_PER_DAY) as u32;
+    let mut view = AuthorEngagementView { owner, comments_received: 0, comments_answered: 0, response_rate_bps: 0, median_response_hours: None };
+    let mut hours: std::collections::BTreeMap<u32, u32> = std::collections::BTreeMap::new();
+    for (_, day) in days.range(today.saturating_sub(donations::ENGAGEMENT_WINDOW_DAYS - 1)..) {
+        view.comments_received += day.received;
+        view.comments_answered += day.answered;
+        for (h, n) in &day.response_hours {
+            *hours.entry(*h).or_default() += n;
+        }
+    }
+    if view.comments_received > 0 {
+        view.response_rate_bps = (view.comments_answered as u64 * 10_000 / view.comments_received as u64) as u32;
+    }
+    // Median over the answered comments' response-time histogram
+    let total: u32 = hours.values().sum();
+    let mut seen = 0;
+    for (h, n) in hours {
+        seen += n;
+        if seen * 2 >= total {
+            view.median_response_hours = Some(h);
+            break;
+        }
+    }
+    view
+}
+
+/// Donor-side donation views, resolving the donor's chain once
+async fn donor_donation_views(state: &DonationsState, owner: AccountOwner, list: Vec<LibDonationRecord>, local: ChainId) -> Vec<DonationView> {
+    let owner_chain_id = state.resolve_chain_id(owner).await;
+    let mut res = Vec::with_capacity(list.len());
+    for r in list {
+        let (from_chain_id, from_chain_id_known) = chain_id_or_local(r.source_chain_id.clone().or(owner_chain_id.clone()), local);
+        let to_known = match r.to_chain_id.clone() { Some(id) => Some(id), None => state.resolve_chain_id(r.to).await };
+        let (to_chain_id, to_chain_id_known) = chain_id_or_local(to_known, local);
+        res.push(DonationView {
+            id: r.id,
+            timestamp: r.timestamp,
+            from_owner: r.from,
+            from_chain_id,
+            from_chain_id_known,
+            to_owner: r.to,
+            to_chain_id,
+            to_chain_id_known,
+            amount: r.amount,
+            message: r.message,
+            content_modified: r.content_modified,
+            payment_app: r.payment_app,
+            reply: r.reply,
+            replied_at: r.replied_at,
+        });
+    }
+    res
+}
+
+/// Load purchases by id in order, keeping those that match the filter, starting after
+/// the `after` id and stopping once `limit` views are built
+async fn build_purchase_views(state: &DonationsState, ids: Vec<String>, filter: &PurchaseFilter, after: Option<String>, limit: Option<u32>) -> Vec<PurchaseFullView> {
+    let start = match after {
+        Some(after) => ids.iter().position(|id| *id == after).map_or(ids.len(), |i| i + 1),
+        None => 0,
+    };
+    let limit = limit.map_or(usize::MAX, |l| l as usize);
+    let mut res = Vec::new();
+    for id in &ids[start..] {
+        if res.len() >= limit {
+            break;
+        }
+        if let Ok(Some(pur)) = state.get_purchase(id).await {
+            if !filter.matches(&pur) {
+                continue;
+            }
+            res.push(PurchaseFullView {
+                id: pur.id,
+                product_id: pur.product_id,
+                buyer: pur.buyer,
+                buyer_chain_id: pur.buyer_chain_id,
+                seller: pur.seller,
+                seller_chain_id: pur.seller_chain_id,
+                amount: pur.amount,
+                timestamp: pur.timestamp,
+                order_data: btree_to_pairs(&pur.order_data),
+                content_modified: pur.content_modified,
+                payment_app: pur.payment_app,
+                status: pur.status,
+                product: product_to_full_view(&pur.product),
+            });
+        }
+    }
+    res
+}
+
+/// Posts by the authors a subscriber actively subscribes to, newest first
+async fn feed_posts(state: &DonationsState, subscriber: AccountOwner, current_time: u64) -> Vec<Post> {
+    let sub_ids = state.subscriptions_by_subscriber.get(&subscriber).await.ok().flatten().unwrap_or_default();
+    let mut all_posts = Vec::new();
+    for sub_id in sub_ids {
+        if let Ok(Some(sub)) = state.content_subscriptions.get(&sub_id).await {
+            // Only include posts from active subscriptions, or lapsed ones within the author's grace period
+            if sub.allows(SubscriptionAccess::Read, state.subscription_grace_micros(sub.author).await, current_time) {
+                if let Ok(posts) = state.list_posts_by_author(sub.author).await {
+                    all_posts.extend(posts);
+                }
+            }
+        }
+    }
+    all_posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    all_posts
+}
+
+/// Whether the `viewer` chain may read `author`'s subscriber-only posts: it is the author's own
+/// chain, or a subscriber's chain within the read grace period. Queries carry no signer, so the
+/// chain they run on is the only viewer identity a service can rely on.
+async fn can_read_locked(state: &DonationsState, author: AccountOwner, author_chain_id: &str, viewer: ChainId, current_time: u64) -> bool {
+    if author_chain_id == viewer.to_string() {
+        return true;
+    }
+    let grace = state.subscription_grace_micros(author).await;
+    state.list_subscriptions_by_author(author).await.unwrap_or_default().iter()
+        .any(|s| s.subscriber_chain_id == viewer.to_string() && s.allows(SubscriptionAccess::Read, grace, current_time))
+}
+
+async fn posts_to_views(state: &DonationsState, posts: &[Post], current_time: u64, viewer: ChainId) -> Vec<PostView> {
+    let mut unlocked = std::collections::BTreeMap::new();
+    let mut views = Vec::with_capacity(posts.len());
+    for p in posts {
+        let comment_count = state.comment_count(&p.id).await.unwrap_or(0);
+        let can_read = match unlocked.get(&p.author) {
+            Some(can_read) => *can_read,
+            None if p.visibility == PostVisibility::Public => false,
+            None => {
+                let can_read = can_read_locked(state, p.author, &p.author_chain_id, viewer, current_time).await;
+                unlocked.insert(p.author, can_read);
+                can_read
+            }
+        };
+        views.push(post_to_view(p, current_time, comment_count, can_read));
     }
+    views
 }
 
 
@@ -208,16 +828,21 @@ pub struct DonationsService { runtime: Arc<ServiceRuntime<Self>> }
 impl WithServiceAbi for DonationsService { type Abi = DonationsAbi; }
 
 impl Service for DonationsService {
-    type Parameters = ();
+    type Parameters = DonationsParameters;
     async fn new(runtime: ServiceRuntime<Self>) -> Self { DonationsService { runtime: Arc::new(runtime) } }
     async fn handle_query(&self, request: Request) -> Response {
-        let schema = Schema::build(QueryRoot { runtime: self.runtime.clone(), storage_context: self.runtime.root_view_storage_context() }, MutationRoot { runtime: self.runtime.clone() }, EmptySubscription).finish();
-        schema.execute(request).await
+        let builder = Schema::build(QueryRoot { runtime: self.runtime.clone(), storage_context: self.runtime.root_view_storage_context() }, MutationRoot { runtime: self.runtime.clone() }, EmptySubscription);
+        #[cfg(feature = "debug-metrics")]
+        if request.extensions.contains_key(donations::metrics::DEBUG_FLAG) {
+            return donations::metrics::execute_with_metrics(builder, request).await;
+        }
+        builder.finish().execute(request).await
     }
 }
 
 struct Accounts {
     runtime: Arc<ServiceRuntime<DonationsService>>,
+    storage_context: linera_sdk::views::ViewStorageContext,
 }
 
 #[Object]
@@ -238,6 +863,17 @@ impl Accounts {
             .collect()
     }
 
+    /// Balance minus funds committed to giveaways and other locks
+    async fn available_balance(&self, owner: AccountOwner) -> Amount {
+        let committed = match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.committed_total(owner).await.unwrap_or_default(), Err(_) => Amount::ZERO };
+        self.runtime.owner_balance(owner).saturating_sub(committed)
+    }
+
+    /// Funds locked for an owner
+    async fn commitments(&self, owner: AccountOwner) -> Vec<donations::Commitment> {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.get_commitments(owner).await.unwrap_or_default(), Err(_) => Vec::new() }
+    }
+
     async fn keys(&self) -> Vec<AccountOwner> {
         self.runtime.balance_owners()
     }
@@ -255,119 +891,208 @@ impl QueryRoot {
     async fn accounts(&self) -> Accounts {
         Accounts {
             runtime: self.runtime.clone(),
+            storage_context: self.storage_context.clone(),
         }
     }
 
-    async fn profile(&self, owner: AccountOwner) -> Option<LibProfile> {
-        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.get_profile(owner).await.ok().flatten(), Err(_) => None }
+    /// Get the current app admin
+    async fn admin(&self) -> Option<AccountOwner> {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => *state.admin.get(), Err(_) => None }
+    }
+
+    /// Get the account proposed as next admin, waiting to accept
+    async fn pending_admin(&self) -> Option<AccountOwner> {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => *state.pending_admin.get(), Err(_) => None }
     }
-    async fn donations_by_recipient(&self, owner: AccountOwner) -> Vec<LibDonationRecord> {
-        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.list_donations_by_recipient(owner).await.unwrap_or_default(), Err(_) => Vec::new() }
+
+    /// Get the drawn supporter of the month for an author
+    async fn supporter_of_month(&self, author: AccountOwner, year: u32, month: u32) -> Option<AccountOwner> {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.monthly_winners.get(&(author, donations::month_key(year, month))).await.ok().flatten(), Err(_) => None }
     }
-    async fn donations_by_donor(&self, owner: AccountOwner) -> Vec<LibDonationRecord> {
-        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.list_donations_by_donor(owner).await.unwrap_or_default(), Err(_) => Vec::new() }
+
+    /// Compute the commitment to submit before revealing a supporter draw seed
+    async fn supporter_draw_commitment(&self, author: AccountOwner, year: u32, month: u32, seed: String) -> String {
+        donations::DrawSeed { author, month_key: donations::month_key(year, month), seed }.commitment()
     }
-    async fn all_profiles(&self) -> Vec<LibProfile> {
+
+    /// Get the total minted so far
+    async fn total_minted(&self) -> Amount {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => *state.total_minted.get(), Err(_) => Amount::ZERO }
+    }
+
+    /// Get the per-mint maximum and supply cap
+    async fn mint_limits(&self) -> donations::MintLimits {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.get_mint_limits(), Err(_) => donations::MintLimits::default() }
+    }
+
+    /// Get an owner's notifications, newest first
+    async fn notifications(&self, owner: AccountOwner) -> Vec<donations::Notification> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.profiles.indices().await {
-                    Ok(owners) => {
-                        let mut res = Vec::new();
-                        for owner in owners {
-                            if let Ok(Some(p)) = state.profiles.get(&owner).await { res.push(p); }
-                        }
-                        res
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let mut inbox = state.notifications.get(&owner).await.ok().flatten().unwrap_or_default();
+                inbox.reverse();
+                inbox
             },
             Err(_) => Vec::new(),
         }
     }
-    async fn all_donations(&self) -> Vec<LibDonationRecord> {
+
+    /// Get a product's reviews, most recently updated first
+    async fn product_reviews(&self, product_id: String) -> Vec<donations::Review> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.donations.indices().await {
-                    Ok(ids) => {
-                        let mut res = Vec::new();
-                        for id in ids {
-                            if let Ok(Some(r)) = state.donations.get(&id).await { res.push(r); }
-                        }
-                        res
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let mut list = state.list_reviews(&product_id).await.unwrap_or_default();
+                list.sort_by_key(|r| std::cmp::Reverse(r.updated_at));
+                list
             },
             Err(_) => Vec::new(),
         }
     }
 
-    async fn profile_view(&self, owner: AccountOwner) -> Option<ProfileView> {
+    /// Get the refused payments the owner still has to send back, oldest first
+    async fn pending_refunds(&self, owner: AccountOwner) -> Vec<donations::PendingRefund> {
         match DonationsState::load(self.storage_context.clone()).await {
-            Ok(state) => {
-                let chain_id = state.subscriptions.get(&owner).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
-                state.get_profile(owner).await.ok().flatten().map(|p| ProfileView {
-                    owner: p.owner,
-                    chain_id,
-                    name: p.name,
-                    bio: p.bio,
-                    socials: p.socials,
-                    avatar_hash: p.avatar_hash,
-                    header_hash: p.header_hash,
-                })
-            },
-            Err(_) => None,
+            Ok(state) => state.get_pending_refunds(owner).await.unwrap_or_default(),
+            Err(_) => Vec::new(),
         }
     }
 
-    async fn all_profiles_view(&self) -> Vec<ProfileView> {
+    /// Get the buyer's paid purchases that couldn't be delivered, newest first
+    async fn failed_orders(&self, buyer: AccountOwner) -> Vec<donations::FailedOrder> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.profiles.indices().await {
-                    Ok(owners) => {
-                        let mut res = Vec::new();
-                        for owner in owners {
-                            let chain_id = state.subscriptions.get(&owner).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
-                            if let Ok(Some(p)) = state.profiles.get(&owner).await {
-                                res.push(ProfileView { 
-                                    owner: p.owner, 
-                                    chain_id, 
-                                    name: p.name, 
-                                    bio: p.bio, 
-                                    socials: p.socials,
-                                    avatar_hash: p.avatar_hash,
-                                    header_hash: p.header_hash,
-                                });
-                            }
-                        }
-                        res
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let mut list = state.failed_orders.get(&buyer).await.ok().flatten().unwrap_or_default();
+                list.reverse();
+                list
             },
             Err(_) => Vec::new(),
         }
     }
 
-    async fn donations_view_by_recipient(&self, owner: AccountOwner) -> Vec<DonationView> {
+    /// Get this chain's unacknowledged outbound messages, oldest first
+    async fn outbox(&self) -> Vec<OutboxEntryView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_outbox().await.unwrap_or_default().into_iter().map(|e| OutboxEntryView {
+                id: e.id,
+                target_chain: e.target_chain.to_string(),
+                sender: e.sender,
+                attempts: e.attempts,
+                next_retry: e.next_retry,
+                created_at: e.created_at,
+                exhausted: e.is_exhausted(),
+            }).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get donations held in custodial accounts awaiting routing
+    async fn pending_donations(&self) -> Vec<LibDonationRecord> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_pending_donations().await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get activity exports made on this chain, newest first
+    async fn activity_exports(&self) -> Vec<donations::ActivityExport> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                let to_chain_id = state.subscriptions.get(&owner).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
-                match state.list_donations_by_recipient(owner).await {
-                    Ok(list) => {
-                        let mut res = Vec::with_capacity(list.len());
-                        for r in list {
-                            let from_chain_id = state.subscriptions.get(&r.from).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
-                            res.push(DonationView {
-                                id: r.id,
-                                timestamp: r.timestamp,
-                                from_owner: r.from,
-                                from_chain_id,
-                                to_owner: r.to,
-                                to_chain_id: to_chain_id.clone(),
-                                amount: r.amount,
-                                message: r.message,
-                            });
+                let mut res: Vec<donations::ActivityExport> = state.activity_exports.index_values().await.unwrap_or_default().into_iter().map(|(_, v)| v).collect();
+                res.reverse();
+                res
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Export this chain's state as JSON for backup, one page at a time.
+    /// Records are numbered across the scoped sections in order (profiles, products,
+    /// purchases, posts, subscriptions, donations); offset skips that many, limit caps
+    /// the page (default and max 500). Product private data is blanked unless
+    /// include_private is set, and even then only kept for products authored on this chain and
+    /// purchases made or sold from it, or for everything when the app runs in operator mode
+    async fn state_export(&self, scope: donations::SnapshotScope, offset: Option<u32>, limit: Option<u32>, include_private: Option<bool>) -> StateExportView {
+        let state = match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state,
+            Err(_) => return StateExportView::default(),
+        };
+        let mut data = match state.snapshot_data(scope).await {
+            Ok(data) => data,
+            Err(_) => return StateExportView::default(),
+        };
+        // Queries aren't authenticated: whoever queries this chain is trusted with its own records only
+        let include_private = include_private.unwrap_or(false);
+        let operator_mode = self.runtime.application_parameters().operator_mode;
+        let this_chain = self.runtime.chain_id().to_string();
+        for product in data.products.iter_mut() {
+            if !(include_private && (operator_mode || product.author_chain_id == this_chain)) {
+                product.private_data.clear();
+            }
+        }
+        for purchase in data.purchases.iter_mut() {
+            if !(include_private && (operator_mode || purchase.buyer_chain_id == this_chain || purchase.seller_chain_id == this_chain)) {
+                purchase.product.private_data.clear();
+            }
+        }
+        let total = data.profiles.len() + data.products.len() + data.purchases.len() + data.posts.len() + data.subscriptions.len() + data.donations.len();
+        let offset = offset.unwrap_or(0) as usize;
+        let mut skip = offset;
+        let mut room = limit.map_or(STATE_EXPORT_MAX_RECORDS, |l| (l as usize).clamp(1, STATE_EXPORT_MAX_RECORDS));
+        let mut page = StateExportView {
+            profiles: page_section(data.profiles, &mut skip, &mut room),
+            products: page_section(data.products, &mut skip, &mut room),
+            purchases: page_section(data.purchases, &mut skip, &mut room),
+            posts: page_section(data.posts, &mut skip, &mut room),
+            subscriptions: page_section(data.subscriptions, &mut skip, &mut room),
+            donations: page_section(data.donations, &mut skip, &mut room),
+            next_offset: None,
+        };
+        let end = offset + page.profiles.len() + page.products.len() + page.purchases.len() + page.posts.len() + page.subscriptions.len() + page.donations.len();
+        if end < total {
+            page.next_offset = Some(end as u32);
+        }
+        page
+    }
+
+    /// Get snapshots taken on this chain, newest first
+    async fn snapshots(&self) -> Vec<donations::SnapshotMeta> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let mut res: Vec<donations::SnapshotMeta> = state.snapshots.index_values().await.unwrap_or_default().into_iter().map(|(_, v)| v).collect();
+                res.reverse();
+                res
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Whether the owner closed their account
+    async fn account_closed(&self, owner: AccountOwner) -> bool {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.is_closed(owner).await, Err(_) => false }
+    }
+    async fn profile(&self, owner: AccountOwner) -> Option<LibProfile> {
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.get_profile(owner).await.ok().flatten(), Err(_) => None }
+    }
+    /// Get a page of a recipient's donations, newest first unless oldest_first
+    /// after_id: cursor from the previous page; limit: default 50, at most 500
+    async fn donations_by_recipient(&self, owner: AccountOwner, after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>) -> Vec<LibDonationRecord> {
+        let page = donation_page_query(after_id, offset, limit, oldest_first);
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.list_donations_by_recipient_page(owner, page).await.map(|(list, _)| list).unwrap_or_default(), Err(_) => Vec::new() }
+    }
+    /// Get a page of a donor's donations, newest first unless oldest_first
+    /// after_id: cursor from the previous page; limit: default 50, at most 500
+    async fn donations_by_donor(&self, owner: AccountOwner, after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>) -> Vec<LibDonationRecord> {
+        let page = donation_page_query(after_id, offset, limit, oldest_first);
+        match DonationsState::load(self.storage_context.clone()).await { Ok(state) => state.list_donations_by_donor_page(owner, page).await.map(|(list, _)| list).unwrap_or_default(), Err(_) => Vec::new() }
+    }
+    async fn all_profiles(&self) -> Vec<LibProfile> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                match state.profiles.indices().await {
+                    Ok(owners) => {
+                        let mut res = Vec::new();
+                        for owner in owners {
+                            if let Ok(Some(p)) = state.profiles.get(&owner).await { res.push(p); }
                         }
                         res
                     },
@@ -377,82 +1102,312 @@ impl QueryRoot {
             Err(_) => Vec::new(),
         }
     }
+    /// Get a page of all donations on this chain, newest first unless oldest_first
+    /// after_id: cursor from the previous page; limit: default 50, at most 500
+    async fn all_donations(&self, after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>) -> Vec<LibDonationRecord> {
+        let page = donation_page_query(after_id, offset, limit, oldest_first);
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_all_donations_page(page).await.map(|(list, _)| list).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
 
-    async fn donations_view_by_donor(&self, owner: AccountOwner) -> Vec<DonationView> {
+    /// Get an owner's home chain, or null when it isn't known on this chain
+    async fn owner_chain_id(&self, owner: AccountOwner) -> Option<String> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.resolve_chain_id(owner).await,
+            Err(_) => None,
+        }
+    }
+
+    async fn profile_view(&self, owner: AccountOwner) -> Option<ProfileView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => load_profile_view(&state, owner, self.runtime.chain_id()).await,
+            Err(_) => None,
+        }
+    }
+
+    async fn all_profiles_view(&self) -> Vec<ProfileView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                match state.profiles.indices().await {
+                    Ok(owners) => {
+                        let mut res = Vec::new();
+                        for owner in owners {
+                            let (chain_id, chain_id_known) = chain_id_or_local(state.resolve_chain_id(owner).await, self.runtime.chain_id());
+                            let income_redirect = state.get_income_redirect(owner).await.ok().flatten();
+                            if let Ok(Some(p)) = state.profiles.get(&owner).await {
+                                res.push(ProfileView { 
+                                    owner: p.owner, 
+                                    chain_id, 
+                                    chain_id_known,
+                                    name: p.name, 
+                                    bio: p.bio, 
+                                    socials: p.socials,
+                                    avatar_hash: p.avatar_hash,
+                                    header_hash: p.header_hash,
+                                    income_redirect,
+                                });
+                            }
+                        }
+                        res
+                    },
+                    Err(_) => Vec::new(),
+                }
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get a page of a recipient's donations with their total count, newest first unless oldest_first
+    /// after_id: cursor from the previous page; limit: default 50, at most 500
+    /// from_micros/to_micros: inclusive time bounds; with either set the page is oldest first
+    #[allow(clippy::too_many_arguments)]
+    async fn donations_view_by_recipient(&self, owner: AccountOwner, after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>, from_micros: Option<u64>, to_micros: Option<u64>) -> DonationViewPage {
+        let page = donation_page_query(after_id, offset, limit, oldest_first);
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                let from_chain_id = state.subscriptions.get(&owner).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
-                match state.list_donations_by_donor(owner).await {
-                    Ok(list) => {
+                let owner_chain_id = state.resolve_chain_id(owner).await;
+                let listed = if from_micros.is_some() || to_micros.is_some() {
+                    state.list_donations_between_page(DonationSide::Recipient(owner), from_micros, to_micros, page).await
+                } else {
+                    state.list_donations_by_recipient_page(owner, page).await
+                };
+                match listed {
+                    Ok((list, total_count)) => {
+                        let next_cursor = next_cursor(&list, &page);
                         let mut res = Vec::with_capacity(list.len());
                         for r in list {
-                            let to_chain_id = state.subscriptions.get(&r.to).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
+                            let from_known = match r.source_chain_id.clone() { Some(id) => Some(id), None => state.resolve_chain_id(r.from).await };
+                            let (from_chain_id, from_chain_id_known) = chain_id_or_local(from_known, self.runtime.chain_id());
+                            let (to_chain_id, to_chain_id_known) = chain_id_or_local(r.to_chain_id.clone().or(owner_chain_id.clone()), self.runtime.chain_id());
                             res.push(DonationView {
                                 id: r.id,
                                 timestamp: r.timestamp,
                                 from_owner: r.from,
-                                from_chain_id: from_chain_id.clone(),
+                                from_chain_id,
+                                from_chain_id_known,
                                 to_owner: r.to,
                                 to_chain_id,
+                                to_chain_id_known,
                                 amount: r.amount,
                                 message: r.message,
+                                content_modified: r.content_modified,
+                                payment_app: r.payment_app,
+                                reply: r.reply,
+                                replied_at: r.replied_at,
                             });
                         }
-                        res
+                        DonationViewPage { donations: res, total_count, next_cursor }
                     },
-                    Err(_) => Vec::new(),
+                    Err(_) => DonationViewPage { donations: Vec::new(), total_count: 0, next_cursor: None },
                 }
             },
-            Err(_) => Vec::new(),
+            Err(_) => DonationViewPage { donations: Vec::new(), total_count: 0, next_cursor: None },
+        }
+    }
+
+    /// Number of donations a recipient received, for page controls
+    async fn donations_count_by_recipient(&self, owner: AccountOwner) -> u32 {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.count_donations_by_recipient(owner).await.unwrap_or(0),
+            Err(_) => 0,
         }
     }
 
-    async fn all_donations_view(&self) -> Vec<DonationView> {
+    /// Get a page of a donor's donations with their total count, newest first unless oldest_first
+    /// after_id: cursor from the previous page; limit: default 50, at most 500
+    /// from_micros/to_micros: inclusive time bounds; with either set the page is oldest first
+    #[allow(clippy::too_many_arguments)]
+    async fn donations_view_by_donor(&self, owner: AccountOwner, after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>, from_micros: Option<u64>, to_micros: Option<u64>) -> DonationViewPage {
+        let page = donation_page_query(after_id, offset, limit, oldest_first);
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.donations.indices().await {
-                    Ok(ids) => {
-                        let mut res = Vec::new();
-                        for id in ids {
-                            if let Ok(Some(r)) = state.donations.get(&id).await {
-                                let from_chain_id = match r.source_chain_id.clone() {
-                                    Some(id) => id,
-                                    None => state.subscriptions.get(&r.from).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string())
-                                };
-                                let to_chain_id = match r.to_chain_id.clone() {
-                                    Some(id) => id,
-                                    None => state.subscriptions.get(&r.to).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string())
-                                };
-                                res.push(DonationView { id: r.id, timestamp: r.timestamp, from_owner: r.from, from_chain_id, to_owner: r.to, to_chain_id, amount: r.amount, message: r.message });
-                            }
-                        }
-                        res
+                let listed = if from_micros.is_some() || to_micros.is_some() {
+                    state.list_donations_between_page(DonationSide::Donor(owner), from_micros, to_micros, page).await
+                } else {
+                    state.list_donations_by_donor_page(owner, page).await
+                };
+                match listed {
+                    Ok((list, total_count)) => {
+                        let next_cursor = next_cursor(&list, &page);
+                        let donations = donor_donation_views(&state, owner, list, self.runtime.chain_id()).await;
+                        DonationViewPage { donations, total_count, next_cursor }
                     },
-                    Err(_) => Vec::new(),
+                    Err(_) => DonationViewPage { donations: Vec::new(), total_count: 0, next_cursor: None },
                 }
             },
+            Err(_) => DonationViewPage { donations: Vec::new(), total_count: 0, next_cursor: None },
+        }
+    }
+
+    /// Number of donations a donor sent, for page controls
+    async fn donations_count_by_donor(&self, owner: AccountOwner) -> u32 {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.count_donations_by_donor(owner).await.unwrap_or(0),
+            Err(_) => 0,
+        }
+    }
+
+    /// Get who follows the author, in the order they followed
+    async fn followers(&self, owner: AccountOwner) -> Vec<AccountOwner> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_follows(&state.followers, owner).await.unwrap_or_default(),
             Err(_) => Vec::new(),
         }
     }
 
-    async fn total_received_amount(&self, owner: AccountOwner) -> String {
+    /// Get the authors the owner follows, in the order they followed them
+    async fn following(&self, owner: AccountOwner) -> Vec<AccountOwner> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_follows(&state.following, owner).await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get the donation a recipient has pinned to their page
+    async fn pinned_donation(&self, owner: AccountOwner) -> Option<DonationView> {
+        let state = DonationsState::load(self.storage_context.clone()).await.ok()?;
+        let r = state.get_pinned_donation(owner).await.ok().flatten()?;
+        let from_known = match r.source_chain_id.clone() { Some(id) => Some(id), None => state.resolve_chain_id(r.from).await };
+        let (from_chain_id, from_chain_id_known) = chain_id_or_local(from_known, self.runtime.chain_id());
+        let to_known = match r.to_chain_id.clone() { Some(id) => Some(id), None => state.resolve_chain_id(owner).await };
+        let (to_chain_id, to_chain_id_known) = chain_id_or_local(to_known, self.runtime.chain_id());
+        Some(DonationView {
+            id: r.id,
+            timestamp: r.timestamp,
+            from_owner: r.from,
+            from_chain_id,
+            from_chain_id_known,
+            to_owner: r.to,
+            to_chain_id,
+            to_chain_id_known,
+            amount: r.amount,
+            message: r.message,
+            content_modified: r.content_modified,
+            payment_app: r.payment_app,
+            reply: r.reply,
+            replied_at: r.replied_at,
+        })
+    }
+
+    /// Get a page of a donor's donations, newest first
+    /// start/end: optional time bounds in micros (start inclusive, end exclusive)
+    /// limit: page size, default 20, at most 100
+    async fn donations_by_donor_page(&self, owner: AccountOwner, offset: Option<u32>, limit: Option<u32>, start: Option<u64>, end: Option<u64>) -> Vec<DonationView> {
+        let limit = limit.unwrap_or(20).min(100) as usize;
+        let offset = offset.unwrap_or(0) as usize;
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.donations_by_recipient.get(&owner).await {
-                    Ok(Some(ids)) => {
-                        let mut sum = Amount::ZERO;
-                        for id in ids {
-                            if let Ok(Some(r)) = state.donations.get(&id).await { sum = sum.saturating_add(r.amount); }
+                let mut list = state.list_donations_by_donor(owner).await.unwrap_or_default();
+                list.retain(|r| start.is_none_or(|s| r.timestamp >= s) && end.is_none_or(|e| r.timestamp < e));
+                list.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.id.cmp(&a.id)));
+                let page: Vec<LibDonationRecord> = list.into_iter().skip(offset).take(limit).collect();
+                donor_donation_views(&state, owner, page, self.runtime.chain_id()).await
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get the donations a donor sent to one recipient, newest first, with their total
+    async fn donations_from_to(&self, donor: AccountOwner, recipient: AccountOwner) -> DonationsBetweenView {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let mut list = state.list_donations_by_donor(donor).await.unwrap_or_default();
+                list.retain(|r| r.to == recipient);
+                list.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.id.cmp(&a.id)));
+                let total = donations::amount_math::sum_tracking_overflow(list.iter().map(|r| r.amount)).unwrap_or_else(|saturated| saturated);
+                let donations = donor_donation_views(&state, donor, list, self.runtime.chain_id()).await;
+                DonationsBetweenView { donations, total }
+            },
+            Err(_) => DonationsBetweenView { donations: Vec::new(), total: Amount::ZERO },
+        }
+    }
+
+    /// Get a page of all donations on this chain with their total count, newest first unless oldest_first
+    /// after_id: cursor from the previous page; limit: default 50, at most 500
+    /// from_micros/to_micros: only donations in that window (inclusive), sorted by timestamp ascending
+    #[allow(clippy::too_many_arguments)]
+    async fn all_donations_view(&self, after_id: Option<u64>, offset: Option<u32>, limit: Option<u32>, oldest_first: Option<bool>, from_micros: Option<u64>, to_micros: Option<u64>) -> DonationViewPage {
+        let page = donation_page_query(after_id, offset, limit, oldest_first);
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let listed = if from_micros.is_some() || to_micros.is_some() {
+                    state.list_donations_between_page(DonationSide::All, from_micros, to_micros, page).await
+                } else {
+                    state.list_all_donations_page(page).await
+                };
+                match listed {
+                    Ok((list, total_count)) => {
+                        let next_cursor = next_cursor(&list, &page);
+                        let mut res = Vec::with_capacity(list.len());
+                        for r in list {
+                            let from_known = match r.source_chain_id.clone() { Some(id) => Some(id), None => state.resolve_chain_id(r.from).await };
+                            let to_known = match r.to_chain_id.clone() { Some(id) => Some(id), None => state.resolve_chain_id(r.to).await };
+                            let (from_chain_id, from_chain_id_known) = chain_id_or_local(from_known, self.runtime.chain_id());
+                            let (to_chain_id, to_chain_id_known) = chain_id_or_local(to_known, self.runtime.chain_id());
+                            res.push(DonationView { id: r.id, timestamp: r.timestamp, from_owner: r.from, from_chain_id, from_chain_id_known, to_owner: r.to, to_chain_id, to_chain_id_known, amount: r.amount, message: r.message, content_modified: r.content_modified, payment_app: r.payment_app, reply: r.reply, replied_at: r.replied_at });
                         }
-                        sum.to_string()
+                        DonationViewPage { donations: res, total_count, next_cursor }
                     },
-                    _ => Amount::ZERO.to_string(),
+                    Err(_) => DonationViewPage { donations: Vec::new(), total_count: 0, next_cursor: None },
                 }
             },
+            Err(_) => DonationViewPage { donations: Vec::new(), total_count: 0, next_cursor: None },
+        }
+    }
+
+    #[graphql(deprecation = "Use totalReceivedView; will be removed in 0.2.0")]
+    async fn total_received_amount(&self, owner: AccountOwner) -> String {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.total_received(owner).await.unwrap_or_default().to_string(),
             Err(_) => Amount::ZERO.to_string(),
         }
     }
 
+    /// Get the recipient's biggest supporters by total donated, largest first
+    /// limit: default 10, at most 100
+    async fn top_donors(&self, recipient: AccountOwner, limit: Option<u32>) -> Vec<TopDonorView> {
+        let Ok(state) = DonationsState::load(self.storage_context.clone()).await else { return Vec::new() };
+        let mut totals: Vec<(AccountOwner, Amount)> = state.donor_totals_for(recipient).await.unwrap_or_default().into_iter().collect();
+        totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+        totals.truncate(limit.unwrap_or(10).min(100) as usize);
+        let mut res = Vec::with_capacity(totals.len());
+        for (donor, total) in totals {
+            let profile = state.get_profile(donor).await.ok().flatten();
+            res.push(TopDonorView {
+                donor,
+                total,
+                name: profile.as_ref().map(|p| p.name.clone()),
+                avatar_hash: profile.and_then(|p| p.avatar_hash),
+            });
+        }
+        res
+    }
+
+    /// Get a donor's totals, largest donation, first and last donation time and per-recipient breakdown
+    async fn donor_stats(&self, owner: AccountOwner) -> DonorStatsView {
+        let (stats, tallies) = match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => (state.donor_stats_for(owner).await.unwrap_or_default(), state.recipient_tallies_for(owner).await.unwrap_or_default()),
+            Err(_) => Default::default(),
+        };
+        let mut recipients: Vec<RecipientTallyView> = tallies.iter()
+            .map(|(recipient, tally)| RecipientTallyView { recipient: *recipient, total: tally.total, count: tally.count })
+            .collect();
+        recipients.sort_by_key(|r| std::cmp::Reverse(r.total));
+        DonorStatsView {
+            owner,
+            total: stats.total,
+            donation_count: stats.count,
+            distinct_recipients: recipients.len() as u32,
+            largest: stats.largest,
+            first_at: stats.first_at,
+            last_at: stats.last_at,
+            recipients,
+        }
+    }
+
+    #[graphql(deprecation = "Use totalSentView; will be removed in 0.2.0")]
     async fn total_sent_amount(&self, owner: AccountOwner) -> String {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
@@ -460,7 +1415,7 @@ impl QueryRoot {
                     Ok(Some(ids)) => {
                         let mut sum = Amount::ZERO;
                         for id in ids {
-                            if let Ok(Some(r)) = state.donations.get(&id).await { sum = sum.saturating_add(r.amount); }
+                            if let Ok(Some(r)) = state.donations.get(&id).await { sum = donations::amount_math::add_tracking_overflow(sum, r.amount).unwrap_or_else(|saturated| saturated); }
                         }
                         sum.to_string()
                     },
@@ -474,36 +1429,83 @@ impl QueryRoot {
     async fn total_received_view(&self, owner: AccountOwner) -> TotalAmountView {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                let chain_id = state.subscriptions.get(&owner).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
-                let amount = match state.donations_by_recipient.get(&owner).await {
-                    Ok(Some(ids)) => {
-                        let mut sum = Amount::ZERO;
-                        for id in ids { if let Ok(Some(r)) = state.donations.get(&id).await { sum = sum.saturating_add(r.amount); } }
-                        sum
-                    },
-                    _ => Amount::ZERO,
-                };
-                TotalAmountView { owner, chain_id, amount }
+                let (chain_id, chain_id_known) = chain_id_or_local(state.resolve_chain_id(owner).await, self.runtime.chain_id());
+                let amount = state.total_received(owner).await.unwrap_or_default();
+                TotalAmountView { owner, chain_id, chain_id_known, amount }
             },
-            Err(_) => TotalAmountView { owner, chain_id: self.runtime.chain_id().to_string(), amount: Amount::ZERO },
+            Err(_) => TotalAmountView { owner, chain_id: self.runtime.chain_id().to_string(), chain_id_known: false, amount: Amount::ZERO },
+        }
+    }
+
+    /// Total a recipient received with from_micros <= timestamp <= to_micros, e.g. for a monthly statement
+    async fn total_received_in_range(&self, owner: AccountOwner, from_micros: u64, to_micros: u64) -> TotalAmountView {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let (chain_id, chain_id_known) = chain_id_or_local(state.resolve_chain_id(owner).await, self.runtime.chain_id());
+                let records = state.list_donations_between(DonationSide::Recipient(owner), Some(from_micros), Some(to_micros)).await.unwrap_or_default();
+                let amount = donations::amount_math::sum_tracking_overflow(records.into_iter().map(|r| r.amount)).unwrap_or_else(|saturated| saturated);
+                TotalAmountView { owner, chain_id, chain_id_known, amount }
+            },
+            Err(_) => TotalAmountView { owner, chain_id: self.runtime.chain_id().to_string(), chain_id_known: false, amount: Amount::ZERO },
         }
     }
 
     async fn total_sent_view(&self, owner: AccountOwner) -> TotalAmountView {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                let chain_id = state.subscriptions.get(&owner).await.ok().flatten().unwrap_or_else(|| self.runtime.chain_id().to_string());
+                let (chain_id, chain_id_known) = chain_id_or_local(state.resolve_chain_id(owner).await, self.runtime.chain_id());
                 let amount = match state.donations_by_donor.get(&owner).await {
                     Ok(Some(ids)) => {
                         let mut sum = Amount::ZERO;
-                        for id in ids { if let Ok(Some(r)) = state.donations.get(&id).await { sum = sum.saturating_add(r.amount); } }
+                        for id in ids { if let Ok(Some(r)) = state.donations.get(&id).await { sum = donations::amount_math::add_tracking_overflow(sum, r.amount).unwrap_or_else(|saturated| saturated); } }
                         sum
                     },
                     _ => Amount::ZERO,
                 };
-                TotalAmountView { owner, chain_id, amount }
+                TotalAmountView { owner, chain_id, chain_id_known, amount }
+            },
+            Err(_) => TotalAmountView { owner, chain_id: self.runtime.chain_id().to_string(), chain_id_known: false, amount: Amount::ZERO },
+        }
+    }
+
+    /// Get the owner's donation goal with progress from the donations received since it was set
+    async fn donation_goal(&self, owner: AccountOwner) -> Option<DonationGoalView> {
+        let state = DonationsState::load(self.storage_context.clone()).await.ok()?;
+        load_goal_view(&state, owner, self.runtime.system_time().micros()).await
+    }
+
+    /// Get lifetime redirected income and the share still waiting to be paid out
+    async fn redirect_totals(&self, owner: AccountOwner) -> RedirectTotalsView {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => RedirectTotalsView {
+                owner,
+                redirected: state.redirect_totals.get(&owner).await.ok().flatten().unwrap_or_default(),
+                pending: state.pending_redirect(owner).await.unwrap_or_default(),
             },
-            Err(_) => TotalAmountView { owner, chain_id: self.runtime.chain_id().to_string(), amount: Amount::ZERO },
+            Err(_) => RedirectTotalsView { owner, redirected: Amount::ZERO, pending: Amount::ZERO },
+        }
+    }
+
+    /// Get how often inbound content was truncated or stripped, per kind and day
+    /// Days are counted since the Unix epoch; both bounds are inclusive
+    async fn content_adjustment_stats(&self, from_day: u32, to_day: u32) -> Vec<ContentAdjustmentStat> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                match state.content_adjustments.indices().await {
+                    Ok(keys) => {
+                        let mut res = Vec::new();
+                        for (kind, day) in keys {
+                            if day < from_day || day > to_day { continue; }
+                            if let Ok(Some(count)) = state.content_adjustments.get(&(kind.clone(), day)).await {
+                                res.push(ContentAdjustmentStat { kind, day, count });
+                            }
+                        }
+                        res
+                    },
+                    Err(_) => Vec::new(),
+                }
+            },
+            Err(_) => Vec::new(),
         }
     }
 
@@ -537,9 +1539,13 @@ impl QueryRoot {
                 match state.products.indices().await {
                     Ok(ids) => {
                         let mut res = Vec::new();
+                        let mut featured_by_author = std::collections::BTreeMap::new();
                         for id in ids {
                             if let Ok(Some(p)) = state.products.get(&id).await {
-                                res.push(product_to_public_view(&p));
+                                if let std::collections::btree_map::Entry::Vacant(entry) = featured_by_author.entry(p.author) {
+                                    entry.insert(state.get_featured_products(p.author).await.unwrap_or_default());
+                                }
+                                res.push(load_public_view(&state, &p, &featured_by_author[&p.author]).await);
                             }
                         }
                         res
@@ -551,12 +1557,130 @@ impl QueryRoot {
         }
     }
 
-    /// Get products by author (public view only)
+    /// Get products in a category (public view only), newest first
+    /// offset: products to skip; limit: page size, default 50, at most 200
+    async fn products_by_category(&self, category: String, offset: Option<u32>, limit: Option<u32>) -> Vec<ProductPublicView> {
+        let offset = offset.unwrap_or(0) as usize;
+        let limit = limit.unwrap_or(50).min(200) as usize;
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let mut products = state.list_products_by_category(&category).await.unwrap_or_default();
+                products.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+                let products: Vec<Product> = products.into_iter().skip(offset).take(limit).collect();
+                let mut res = Vec::with_capacity(products.len());
+                let mut featured_by_author = std::collections::BTreeMap::new();
+                for p in products {
+                    if let std::collections::btree_map::Entry::Vacant(entry) = featured_by_author.entry(p.author) {
+                        entry.insert(state.get_featured_products(p.author).await.unwrap_or_default());
+                    }
+                    res.push(load_public_view(&state, &p, &featured_by_author[&p.author]).await);
+                }
+                res
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get every category that has at least one product
+    async fn all_categories(&self) -> Vec<String> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.products_by_category.indices().await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get a seller's saved order form templates, by name
+    async fn my_order_form_templates(&self, owner: AccountOwner) -> Vec<OrderFormTemplateView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.order_form_templates.get(&owner).await.ok().flatten().unwrap_or_default()
+                .into_iter()
+                .map(|(name, fields)| OrderFormTemplateView { name, fields })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get the share of comments the author answers within 7 days and their median response time
+    async fn author_engagement(&self, owner: AccountOwner) -> AuthorEngagementView {
+        let now = self.runtime.system_time().micros();
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => load_engagement_view(&state, owner, now).await,
+            Err(_) => AuthorEngagementView { owner, comments_received: 0, comments_answered: 0, response_rate_bps: 0, median_response_hours: None },
+        }
+    }
+
+    /// Get a creator's page: profile, storefront, fundraising goal and how they answer comments
+    async fn creator_page(&self, owner: AccountOwner) -> Option<CreatorPageView> {
+        let state = DonationsState::load(self.storage_context.clone()).await.ok()?;
+        let now = self.runtime.system_time().micros();
+        let engagement = load_engagement_view(&state, owner, now).await;
+        Some(CreatorPageView {
+            profile: load_profile_view(&state, owner, self.runtime.chain_id()).await,
+            products: storefront_views(&state, owner).await,
+            goal: load_goal_view(&state, owner, now).await,
+            engagement: EngagementSummaryView {
+                comments_received: engagement.comments_received,
+                response_rate_bps: engagement.response_rate_bps,
+                median_response_hours: engagement.median_response_hours,
+            },
+        })
+    }
+
+    /// Get the distinct chains of an author's active subscribers and buyers, with counts per chain
+    async fn audience_chains(&self, author: AccountOwner) -> Vec<AudienceChainView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                let mut chains: std::collections::BTreeMap<String, (u32, u32)> = std::collections::BTreeMap::new();
+                for sub in state.get_active_subscriptions(author, current_time, SubscriptionAccess::Interact).await.unwrap_or_default() {
+                    chains.entry(sub.subscriber_chain_id).or_default().0 += 1;
+                }
+                for sale in state.list_purchases_by_seller(author).await.unwrap_or_default() {
+                    chains.entry(sale.buyer_chain_id).or_default().1 += 1;
+                }
+                chains.into_iter().map(|(chain_id, (subscribers, buyers))| AudienceChainView { chain_id, subscribers, buyers }).collect()
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get the price range of an author's products, None if they have none
+    async fn price_range(&self, author: AccountOwner) -> Option<PriceRangeView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let products = state.list_products_by_author(author).await.unwrap_or_default();
+                let min = products.iter().map(|p| p.price).min()?;
+                let max = products.iter().map(|p| p.price).max()?;
+                Some(PriceRangeView { min, max })
+            },
+            Err(_) => None,
+        }
+    }
+
+    /// Get products by author (public view only), featured first, then the manual order or newest
     async fn products_by_author(&self, owner: AccountOwner) -> Vec<ProductPublicView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => storefront_views(&state, owner).await,
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get products by author on a single chain (public view only), featured first, then the manual order or newest
+    async fn products_by_author_and_chain(&self, owner: AccountOwner, chain_id: String) -> Vec<ProductPublicView> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
+                let featured = state.get_featured_products(owner).await.unwrap_or_default();
+                let order = state.get_product_order(owner).await.unwrap_or_default();
                 match state.list_products_by_author(owner).await {
-                    Ok(products) => products.iter().map(|p| product_to_public_view(p)).collect(),
+                    Ok(products) => {
+                        let mut products: Vec<Product> = products.into_iter().filter(|p| p.author_chain_id == chain_id).collect();
+                        sort_storefront(&mut products, &featured, &order);
+                        let mut res = Vec::with_capacity(products.len());
+                        for p in &products {
+                            res.push(load_public_view(&state, p, &featured).await);
+                        }
+                        res
+                    },
                     Err(_) => Vec::new(),
                 }
             },
@@ -569,7 +1693,7 @@ impl QueryRoot {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
                 match state.list_products_by_author(owner).await {
-                    Ok(products) => products.iter().map(|p| product_to_full_view(p)).collect(),
+                    Ok(products) => products.iter().map(product_to_full_view).collect(),
                     Err(_) => Vec::new(),
                 }
             },
@@ -582,7 +1706,10 @@ impl QueryRoot {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
                 match state.get_product(&id).await {
-                    Ok(Some(p)) => Some(product_to_public_view(&p)),
+                    Ok(Some(p)) => {
+                        let featured = state.get_featured_products(p.author).await.unwrap_or_default();
+                        Some(load_public_view(&state, &p, &featured).await)
+                    },
                     _ => None,
                 }
             },
@@ -590,6 +1717,65 @@ impl QueryRoot {
         }
     }
 
+    /// Get a product in the flat legacy shape (public fields only)
+    #[graphql(deprecation = "Use product; will be removed in 0.2.0")]
+    async fn product_legacy(&self, id: String) -> Option<donations::ProductView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.get_product(&id).await.ok().flatten().map(|p| donations::ProductView::from_public(&p)),
+            Err(_) => None,
+        }
+    }
+
+    /// Get a buyer's purchases in the flat legacy shape
+    #[graphql(deprecation = "Use purchases; will be removed in 0.2.0")]
+    async fn purchases_legacy(&self, owner: AccountOwner) -> Vec<donations::PurchaseView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_purchases_by_buyer(owner).await.unwrap_or_default().iter().map(donations::PurchaseView::from).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// List deprecated fields with their replacements and planned removal version
+    async fn schema_meta(&self) -> Vec<DeprecatedFieldView> {
+        DEPRECATED_FIELDS.iter().map(|(type_name, field, replacement)| DeprecatedFieldView {
+            type_name: type_name.to_string(),
+            field: field.to_string(),
+            replacement: replacement.to_string(),
+            removal_version: LEGACY_REMOVAL_VERSION.to_string(),
+        }).collect()
+    }
+
+    /// Get a product by its seller's slug (public view only)
+    /// author_or_handle: the seller's AccountOwner or profile name
+    async fn product_by_slug(&self, author_or_handle: String, slug: String) -> Option<ProductPublicView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let author = match author_or_handle.parse::<AccountOwner>() {
+                    Ok(owner) => owner,
+                    Err(_) => {
+                        let owners = state.profiles.indices().await.unwrap_or_default();
+                        let mut found = None;
+                        for owner in owners {
+                            if let Ok(Some(p)) = state.profiles.get(&owner).await {
+                                if p.name.eq_ignore_ascii_case(&author_or_handle) {
+                                    found = Some(owner);
+                                    break;
+                                }
+                            }
+                        }
+                        found?
+                    }
+                };
+                let current_time = self.runtime.system_time().micros();
+                let product_id = state.resolve_slug(author, &slug, current_time).await.ok().flatten()?;
+                let featured = state.get_featured_products(author).await.unwrap_or_default();
+                let p = state.get_product(&product_id).await.ok().flatten()?;
+                Some(load_public_view(&state, &p, &featured).await)
+            },
+            Err(_) => None,
+        }
+    }
+
     /// Get single product with full data (for author or buyer)
     async fn product_full(&self, id: String) -> Option<ProductFullView> {
         match DonationsState::load(self.storage_context.clone()).await {
@@ -604,121 +1790,124 @@ impl QueryRoot {
     }
 
     /// Get purchases for buyer with full product data
-    async fn purchases(&self, owner: AccountOwner) -> Vec<PurchaseFullView> {
+    /// after: id of the last purchase already seen; limit: page size (all when omitted)
+    async fn purchases(&self, owner: AccountOwner, filter: Option<PurchaseFilter>, after: Option<String>, limit: Option<u32>) -> Vec<PurchaseFullView> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.list_purchases_by_buyer(owner).await {
-                    Ok(purchases) => {
-                        purchases.into_iter().map(|pur| {
-                            PurchaseFullView {
-                                id: pur.id,
-                                product_id: pur.product_id,
-                                buyer: pur.buyer,
-                                buyer_chain_id: pur.buyer_chain_id,
-                                seller: pur.seller,
-                                seller_chain_id: pur.seller_chain_id,
-                                amount: pur.amount,
-                                timestamp: pur.timestamp,
-                                order_data: btree_to_pairs(&pur.order_data),
-                                product: product_to_full_view(&pur.product),
-                            }
-                        }).collect()
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let ids = state.purchases_by_buyer.get(&owner).await.ok().flatten().unwrap_or_default();
+                build_purchase_views(&state, ids, &filter.unwrap_or_default(), after, limit).await
             },
             Err(_) => Vec::new(),
         }
     }
 
+    /// Get notes on a purchase; empty unless queried on its buyer's or seller's chain
+    async fn order_notes(&self, purchase_id: String) -> Vec<donations::OrderNote> {
+        let chain = self.runtime.chain_id().to_string();
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => match state.get_purchase(&purchase_id).await {
+                Ok(Some(p)) if p.buyer_chain_id == chain || p.seller_chain_id == chain => state.order_notes.get(&purchase_id).await.ok().flatten().unwrap_or_default(),
+                _ => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get purchases recorded for the buyer whose product data never reached the buyer's chain.
+    /// Meaningful on the main chain, which learns of deliveries from the buyer's events; the
+    /// client can cross-check these against its local my_purchases
+    async fn undelivered_purchases(&self, buyer: AccountOwner) -> Vec<PurchaseFullView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let ids = state.undelivered_purchase_ids(buyer).await.unwrap_or_default();
+                build_purchase_views(&state, ids, &PurchaseFilter::default(), None, None).await
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Get the total paid for the seller's orders that aren't fulfilled yet (pending or accepted)
+    async fn pending_order_value(&self, owner: AccountOwner) -> String {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let orders = state.list_purchases_by_seller(owner).await.unwrap_or_default();
+                let amounts = orders.into_iter().filter(|p| p.status.is_open()).map(|p| p.amount);
+                donations::amount_math::sum_tracking_overflow(amounts).unwrap_or_else(|saturated| saturated).to_string()
+            },
+            Err(_) => Amount::ZERO.to_string(),
+        }
+    }
+
     /// Get purchases for buyer (alias for purchases)
-    async fn my_purchases(&self, owner: AccountOwner) -> Vec<PurchaseFullView> {
+    async fn my_purchases(&self, owner: AccountOwner, filter: Option<PurchaseFilter>, after: Option<String>, limit: Option<u32>) -> Vec<PurchaseFullView> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.list_purchases_by_buyer(owner).await {
-                    Ok(purchases) => {
-                        purchases.into_iter().map(|pur| {
-                            PurchaseFullView {
-                                id: pur.id,
-                                product_id: pur.product_id,
-                                buyer: pur.buyer,
-                                buyer_chain_id: pur.buyer_chain_id,
-                                seller: pur.seller,
-                                seller_chain_id: pur.seller_chain_id,
-                                amount: pur.amount,
-                                timestamp: pur.timestamp,
-                                order_data: btree_to_pairs(&pur.order_data),
-                                product: product_to_full_view(&pur.product),
-                            }
-                        }).collect()
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let ids = state.purchases_by_buyer.get(&owner).await.ok().flatten().unwrap_or_default();
+                build_purchase_views(&state, ids, &filter.unwrap_or_default(), after, limit).await
             },
             Err(_) => Vec::new(),
         }
     }
 
     /// Get all orders received by seller (for "My Orders" tab)
-    async fn my_orders(&self, owner: AccountOwner) -> Vec<PurchaseFullView> {
+    async fn my_orders(&self, owner: AccountOwner, filter: Option<PurchaseFilter>, after: Option<String>, limit: Option<u32>) -> Vec<PurchaseFullView> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.list_purchases_by_seller(owner).await {
-                    Ok(purchases) => {
-                        purchases.into_iter().map(|pur| {
-                            PurchaseFullView {
-                                id: pur.id,
-                                product_id: pur.product_id,
-                                buyer: pur.buyer,
-                                buyer_chain_id: pur.buyer_chain_id,
-                                seller: pur.seller,
-                                seller_chain_id: pur.seller_chain_id,
-                                amount: pur.amount,
-                                timestamp: pur.timestamp,
-                                order_data: btree_to_pairs(&pur.order_data),
-                                product: product_to_full_view(&pur.product),
-                            }
-                        }).collect()
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let ids = state.purchases_by_seller.get(&owner).await.ok().flatten().unwrap_or_default();
+                build_purchase_views(&state, ids, &filter.unwrap_or_default(), after, limit).await
             },
             Err(_) => Vec::new(),
         }
     }
 
-    /// Get all purchases in the system (for debugging)
-    async fn all_purchases(&self) -> Vec<PurchaseFullView> {
+    /// Get the seller's orders with start <= timestamp <= end (micros), oldest first, and their total.
+    /// Refunded orders are left out
+    async fn sales_in_range(&self, owner: AccountOwner, start: u64, end: u64) -> SalesInRangeView {
+        let mut sales = match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let ids = state.purchases_by_seller.get(&owner).await.ok().flatten().unwrap_or_default();
+                let filter = PurchaseFilter { since: Some(start), until: end.checked_add(1), ..Default::default() };
+                build_purchase_views(&state, ids, &filter, None, None).await
+            },
+            Err(_) => Vec::new(),
+        };
+        sales.retain(|s| s.status != donations::OrderStatus::Refunded);
+        sales.sort_by(|a, b| (a.timestamp, &a.id).cmp(&(b.timestamp, &b.id)));
+        let total = donations::amount_math::sum_tracking_overflow(sales.iter().map(|s| s.amount)).unwrap_or_else(|saturated| saturated);
+        SalesInRangeView { sales, total }
+    }
+
+    /// Get all purchases in the system (for debugging); empty unless the app runs in operator mode.
+    /// A product_id filter reads that product's purchases only
+    async fn all_purchases(&self, filter: Option<PurchaseFilter>, after: Option<String>, limit: Option<u32>) -> Vec<PurchaseFullView> {
+        if !self.runtime.application_parameters().operator_mode {
+            return Vec::new();
+        }
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
-                match state.purchases.indices().await {
-                    Ok(ids) => {
-                        let mut res = Vec::new();
-                        for id in ids {
-                            if let Ok(Some(pur)) = state.purchases.get(&id).await {
-                                res.push(PurchaseFullView {
-                                    id: pur.id,
-                                    product_id: pur.product_id,
-                                    buyer: pur.buyer,
-                                    buyer_chain_id: pur.buyer_chain_id,
-                                    seller: pur.seller,
-                                    seller_chain_id: pur.seller_chain_id,
-                                    amount: pur.amount,
-                                    timestamp: pur.timestamp,
-                                    order_data: btree_to_pairs(&pur.order_data),
-                                    product: product_to_full_view(&pur.product),
-                                });
-                            }
-                        }
-                        res
-                    },
-                    Err(_) => Vec::new(),
-                }
+                let filter = filter.unwrap_or_default();
+                let ids = match &filter.product_id {
+                    Some(product_id) => state.purchases_by_product.get(product_id).await.ok().flatten().unwrap_or_default(),
+                    None => state.purchases.indices().await.unwrap_or_default(),
+                };
+                build_purchase_views(&state, ids, &filter, after, limit).await
             },
             Err(_) => Vec::new(),
         }
     }
 
+    /// Compare a purchase's product snapshot with the seller's current listing
+    async fn purchase_product_diff(&self, purchase_id: String) -> Option<ProductDiffView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let purchase = state.purchases.get(&purchase_id).await.ok().flatten()?;
+                let current = state.get_product(&purchase.product_id).await.ok().flatten();
+                Some(public_data_diff(&purchase_id, &purchase.product, current.as_ref()))
+            },
+            Err(_) => None,
+        }
+    }
+
     /// Read a data blob by its hash (64-character hex string)
     /// Returns the blob data as bytes, or None if the hash is invalid
     async fn data_blob(&self, hash: String) -> Option<Vec<u8>> {
@@ -739,7 +1928,44 @@ impl QueryRoot {
     /// Get subscription price and description for an author
     async fn subscription_price(&self, author: AccountOwner) -> Option<donations::SubscriptionInfo> {
         match DonationsState::load(self.storage_context.clone()).await {
-            Ok(state) => state.get_subscription_price(author).await.ok().flatten(),
+            Ok(state) => state.get_subscription_price(author).await.ok().flatten().map(|info| info.settled(self.runtime.system_time().micros())),
+            Err(_) => None,
+        }
+    }
+    
+    /// Get entitlements an owner holds from purchases and active subscriptions
+    async fn entitlements_of(&self, owner: AccountOwner) -> Vec<donations::EntitlementGrant> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.entitlements_of(owner, self.runtime.system_time().micros()).await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    async fn has_entitlement(&self, owner: AccountOwner, name: String) -> bool {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.has_entitlement(owner, &name, self.runtime.system_time().micros()).await.unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+    
+    /// Whether an author currently offers subscriptions
+    async fn offers_subscription(&self, author: AccountOwner) -> bool {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.subscription_prices.contains_key(&author).await.unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+    
+    /// Get the price a subscriber pays for their next renewal to an author
+    /// Answered from the author's chain state, or from the terms it confirmed to the subscriber's chain
+    async fn effective_renewal_price(&self, author: AccountOwner, subscriber: AccountOwner) -> Option<Amount> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                match state.renewal_price_cache.get(&format!("{}:{}", author, subscriber)).await {
+                    Ok(Some(price)) => Some(price),
+                    _ => state.effective_renewal_price(author, subscriber, self.runtime.system_time().micros()).await.ok().flatten(),
+                }
+            },
             Err(_) => None,
         }
     }
@@ -782,72 +2008,354 @@ impl QueryRoot {
                     _ => Vec::new(),
                 }
             },
-            Err(_) => Vec::new(),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get the subscriber's auto-renew subscriptions that end within the window, soonest first
+    async fn subscriptions_due_for_renewal(&self, subscriber: AccountOwner, within_micros: u64) -> Vec<ContentSubscription> {
+        let now = self.runtime.system_time().micros();
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.subscriptions_due_for_renewal(subscriber, now, within_micros).await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get donations, purchases, sales and subscriptions touching an owner, newest first
+    async fn timeline(&self, owner: AccountOwner, limit: u32) -> Vec<TimelineEntry> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let mut entries = Vec::new();
+                for d in state.list_donations_by_donor(owner).await.unwrap_or_default() {
+                    entries.push(TimelineEntry { kind: "donation_sent".to_string(), reference_id: d.id.to_string(), counterparty: d.to, amount: d.amount, timestamp: d.timestamp });
+                }
+                for d in state.list_donations_by_recipient(owner).await.unwrap_or_default() {
+                    entries.push(TimelineEntry { kind: "donation_received".to_string(), reference_id: d.id.to_string(), counterparty: d.from, amount: d.amount, timestamp: d.timestamp });
+                }
+                for p in state.list_purchases_by_buyer(owner).await.unwrap_or_default() {
+                    entries.push(TimelineEntry { kind: "purchase".to_string(), reference_id: p.id, counterparty: p.seller, amount: p.amount, timestamp: p.timestamp });
+                }
+                for p in state.list_purchases_by_seller(owner).await.unwrap_or_default() {
+                    entries.push(TimelineEntry { kind: "sale".to_string(), reference_id: p.id, counterparty: p.buyer, amount: p.amount, timestamp: p.timestamp });
+                }
+                for s in state.list_subscriptions_by_subscriber(owner).await.unwrap_or_default() {
+                    entries.push(TimelineEntry { kind: "subscription".to_string(), reference_id: s.id, counterparty: s.author, amount: s.price, timestamp: s.start_timestamp });
+                }
+                for s in state.list_subscriptions_by_author(owner).await.unwrap_or_default() {
+                    entries.push(TimelineEntry { kind: "subscriber".to_string(), reference_id: s.id, counterparty: s.subscriber, amount: s.price, timestamp: s.start_timestamp });
+                }
+                entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+                entries.truncate(limit as usize);
+                entries
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get all subscribers for an author (active subscriptions only)
+    async fn subscribers_of(&self, author: AccountOwner) -> Vec<ContentSubscription> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                state.get_active_subscriptions(author, current_time, SubscriptionAccess::Interact).await.unwrap_or_default()
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get owners who donated to an author but have no active subscription, most recent donor first
+    async fn donors_not_subscribed(&self, author: AccountOwner) -> Vec<AccountOwner> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                let subscribers: std::collections::BTreeSet<AccountOwner> = state.get_active_subscriptions(author, current_time, SubscriptionAccess::Interact).await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|s| s.subscriber)
+                    .collect();
+                let mut seen = std::collections::BTreeSet::new();
+                let mut donors = Vec::new();
+                for r in state.list_donations_by_recipient(author).await.unwrap_or_default().into_iter().rev() {
+                    if r.from != author && !subscribers.contains(&r.from) && seen.insert(r.from) {
+                        donors.push(r.from);
+                    }
+                }
+                donors
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get new and expired subscriptions per time bucket for an author
+    /// The last bucket ends at the current time; buckets are returned oldest first
+    async fn subscriber_growth(&self, author: AccountOwner, bucket_micros: u64, buckets: u32) -> Vec<SubscriberGrowthBucket> {
+        if bucket_micros == 0 || buckets == 0 {
+            return Vec::new();
+        }
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                let range_start = current_time.saturating_sub(bucket_micros.saturating_mul(buckets as u64));
+                let mut res: Vec<SubscriberGrowthBucket> = (0..buckets as u64).map(|i| SubscriberGrowthBucket {
+                    bucket_start: range_start + i * bucket_micros,
+                    new_subscribers: 0,
+                    expired_subscribers: 0,
+                    net_growth: 0,
+                }).collect();
+                let bucket_of = |ts: u64| -> Option<usize> {
+                    if ts < range_start || ts >= current_time { return None; }
+                    Some((((ts - range_start) / bucket_micros) as usize).min(buckets as usize - 1))
+                };
+                let sub_ids = state.subscriptions_by_author.get(&author).await.ok().flatten().unwrap_or_default();
+                for id in sub_ids {
+                    if let Ok(Some(sub)) = state.content_subscriptions.get(&id).await {
+                        if let Some(i) = bucket_of(sub.start_timestamp) {
+                            res[i].new_subscribers += 1;
+                        }
+                        if let Some(i) = bucket_of(sub.end_timestamp) {
+                            res[i].expired_subscribers += 1;
+                        }
+                    }
+                }
+                for bucket in res.iter_mut() {
+                    bucket.net_growth = bucket.new_subscribers as i32 - bucket.expired_subscribers as i32;
+                }
+                res
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get all posts by an author
+    async fn posts_by_author(&self, author: AccountOwner) -> Vec<PostView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                match state.list_posts_by_author(author).await {
+                    Ok(posts) => posts_to_views(&state, &posts, current_time, self.runtime.chain_id()).await,
+                    Err(_) => Vec::new(),
+                }
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get an author's posts that have a poll, with current vote totals
+    async fn posts_with_polls(&self, author: AccountOwner) -> Vec<PostView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                let mut posts = state.list_posts_by_author(author).await.unwrap_or_default();
+                posts.retain(|p| p.poll.is_some());
+                posts_to_views(&state, &posts, current_time, self.runtime.chain_id()).await
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get an author's posts that have a giveaway, with current participant counts
+    async fn posts_with_giveaways(&self, author: AccountOwner) -> Vec<PostView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                let mut posts = state.list_posts_by_author(author).await.unwrap_or_default();
+                posts.retain(|p| p.giveaway.is_some());
+                posts_to_views(&state, &posts, current_time, self.runtime.chain_id()).await
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Diagnose a subscriber chain's mirror of an author's posts. Run it on the subscriber's chain
+    /// with the author's post ids, as read from the author's chain, to see what is missing or stale
+    async fn sync_gap(&self, subscriber: AccountOwner, author: AccountOwner, author_post_ids: Vec<String>) -> Option<SyncGapView> {
+        let state = DonationsState::load(self.storage_context.clone()).await.ok()?;
+        let held = state.posts_by_author.get(&author).await.ok()?.unwrap_or_default();
+        let held_set: std::collections::BTreeSet<&String> = held.iter().collect();
+        let expected_set: std::collections::BTreeSet<&String> = author_post_ids.iter().collect();
+        let missing = author_post_ids.iter().filter(|id| !held_set.contains(id)).cloned().collect();
+        let extra = held.iter().filter(|id| !expected_set.contains(id)).cloned().collect();
+
+        let author_chain_id = state.latest_subscription(author, subscriber).await.ok().flatten().map(|s| s.author_chain_id);
+        let (applied_events, known_events) = match &author_chain_id {
+            Some(chain_id) => {
+                let key = (chain_id.clone(), StreamName::from(donations::EVENTS_STREAM).to_string());
+                let applied = state.stream_cursors.get(&key).await.ok().flatten();
+                let known = state.stream_heads.get(&key).await.ok().flatten().or(applied);
+                (applied, known)
+            }
+            None => (None, None),
+        };
+        Some(SyncGapView {
+            author,
+            author_chain_id,
+            held_count: held.len() as u32,
+            expected_count: author_post_ids.len() as u32,
+            missing,
+            extra,
+            applied_events,
+            known_events,
+        })
+    }
+
+    /// Get totals and the leading option of every poll the author has run, newest first
+    async fn poll_summary(&self, author: AccountOwner) -> Vec<PollSummaryView> {
+        let Ok(state) = DonationsState::load(self.storage_context.clone()).await else { return Vec::new() };
+        let current_time = self.runtime.system_time().micros();
+        let mut summaries: Vec<PollSummaryView> = state.list_posts_by_author(author).await.unwrap_or_default().into_iter().filter_map(|post| {
+            let poll = post.poll?;
+            let leader = poll.options.iter().filter(|o| o.votes_count > 0).rev().max_by_key(|o| o.votes_count);
+            Some(PollSummaryView {
+                post_id: post.id,
+                title: post.title,
+                total_votes: poll.options.iter().map(|o| o.votes_count).sum(),
+                leading_option: leader.map(|o| o.text.clone()),
+                leading_votes: leader.map_or(0, |o| o.votes_count),
+                is_ended: poll.end_timestamp > 0 && current_time > poll.end_timestamp,
+                created_at: post.created_at,
+            })
+        }).collect();
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        summaries
+    }
+    
+    /// Number of an author's subscriber-only posts; post queries return them without content
+    /// unless they run on the author's chain or a subscriber's chain
+    async fn locked_post_count(&self, author: AccountOwner) -> u32 {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let posts = state.list_posts_by_author(author).await.unwrap_or_default();
+                posts.iter().filter(|p| p.visibility == PostVisibility::Subscribers).count() as u32
+            },
+            Err(_) => 0,
         }
     }
     
-    /// Get all subscribers for an author (active subscriptions only)
-    async fn subscribers_of(&self, author: AccountOwner) -> Vec<ContentSubscription> {
+    /// Get feed of posts from authors you're subscribed to
+    async fn my_feed(&self, subscriber: AccountOwner) -> Vec<PostView> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
                 let current_time = self.runtime.system_time().micros();
-                match state.get_active_subscriptions(author, current_time).await {
-                    Ok(subs) => subs,
-                    Err(_) => Vec::new(),
-                }
+                let all_posts = feed_posts(&state, subscriber, current_time).await;
+                posts_to_views(&state, &all_posts, current_time, self.runtime.chain_id()).await
             },
             Err(_) => Vec::new(),
         }
     }
     
-    /// Get all posts by an author
-    async fn posts_by_author(&self, author: AccountOwner) -> Vec<PostView> {
+    /// Get feed posts and announcements received, merged newest first
+    /// limit: default 50, at most 200
+    async fn my_activity_feed(&self, subscriber: AccountOwner, limit: Option<u32>) -> Vec<ActivityFeedItem> {
+        let limit = limit.unwrap_or(50).min(200) as usize;
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
                 let current_time = self.runtime.system_time().micros();
-                match state.list_posts_by_author(author).await {
-                    Ok(posts) => posts.iter().map(|p| post_to_view(p, current_time)).collect(),
-                    Err(_) => Vec::new(),
-                }
+                let mut posts = feed_posts(&state, subscriber, current_time).await;
+                posts.truncate(limit);
+                let mut announcements = state.announcements.get(&subscriber).await.ok().flatten().unwrap_or_default();
+                announcements.reverse();
+                announcements.truncate(limit);
+                
+                let mut items: Vec<ActivityFeedItem> = posts_to_views(&state, &posts, current_time, self.runtime.chain_id()).await
+                    .into_iter()
+                    .map(|p| ActivityFeedItem { kind: "post".to_string(), timestamp: p.created_at, post: Some(p), announcement: None })
+                    .chain(announcements.into_iter().map(|n| ActivityFeedItem { kind: "announcement".to_string(), timestamp: n.timestamp, post: None, announcement: Some(n) }))
+                    .collect();
+                items.sort_by_key(|item| std::cmp::Reverse(item.timestamp));
+                items.truncate(limit);
+                items
             },
             Err(_) => Vec::new(),
         }
     }
     
-    /// Get feed of posts from authors you're subscribed to
-    async fn my_feed(&self, subscriber: AccountOwner) -> Vec<PostView> {
+
+    
+    /// Get posts with open giveaways the subscriber can still enter, from authors they're subscribed to
+    async fn eligible_giveaways(&self, subscriber: AccountOwner) -> Vec<PostView> {
         match DonationsState::load(self.storage_context.clone()).await {
             Ok(state) => {
                 let current_time = self.runtime.system_time().micros();
+                let mut authors = std::collections::BTreeSet::new();
+                for sub in state.list_subscriptions_by_subscriber(subscriber).await.unwrap_or_default() {
+                    if sub.end_timestamp >= current_time {
+                        authors.insert(sub.author);
+                    }
+                }
                 
-                // Get all active subscriptions
-                match state.subscriptions_by_subscriber.get(&subscriber).await {
-                    Ok(Some(sub_ids)) => {
-                        let mut all_posts = Vec::new();
-                        
-                        for sub_id in sub_ids {
-                            if let Ok(Some(sub)) = state.content_subscriptions.get(&sub_id).await {
-                                // Only include posts from active subscriptions
-                                if sub.end_timestamp >= current_time {
-                                    if let Ok(posts) = state.list_posts_by_author(sub.author).await {
-                                        all_posts.extend(posts);
-                                    }
-                                }
-                            }
+                let mut eligible = Vec::new();
+                for author in authors {
+                    for post in state.list_posts_by_author(author).await.unwrap_or_default() {
+                        let open = post.giveaway.as_ref().is_some_and(|g| {
+                            !g.is_resolved
+                                && (g.end_timestamp == 0 || current_time <= g.end_timestamp)
+                                && !g.participants.iter().any(|p| p.owner == subscriber)
+                        });
+                        if open {
+                            eligible.push(post);
                         }
-                        
-                        // Sort by created_at descending (newest first)
-                        all_posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-                        all_posts.iter().map(|p| post_to_view(p, current_time)).collect()
-                    },
-                    _ => Vec::new(),
+                    }
                 }
+                eligible.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+                posts_to_views(&state, &eligible, current_time, self.runtime.chain_id()).await
             },
             Err(_) => Vec::new(),
         }
     }
     
+    /// Hash of the owner's products (id and revisions) and posts (id, title, content, image).
+    /// Compare it across the creator and main chains to check their mirrors agree
+    async fn content_digest(&self, owner: AccountOwner) -> Option<CryptoHash> {
+        let state = DonationsState::load(self.storage_context.clone()).await.ok()?;
+        let products = state.list_products_by_author(owner).await.ok()?;
+        let posts = state.list_posts_by_author(owner).await.ok()?;
+        Some(donations::ContentDigest::new(owner, &products, &posts).hash())
+    }
+    
+    /// Get a giveaway's entrants with their profiles, in joining order.
+    /// Hidden giveaways return the count only; None if the post has no giveaway.
+    async fn giveaway_participants(&self, post_id: String) -> Option<GiveawayParticipantsView> {
+        let state = DonationsState::load(self.storage_context.clone()).await.ok()?;
+        let giveaway = state.get_post(&post_id).await.ok().flatten()?.giveaway?;
+        let mut participants = Vec::new();
+        if giveaway.participants_visible {
+            for p in &giveaway.participants {
+                participants.push(GiveawayEntrantView {
+                    owner: p.owner,
+                    chain_id: p.chain_id.clone(),
+                    joined_at: p.joined_at,
+                    profile: load_profile_view(&state, p.owner, self.runtime.chain_id()).await,
+                });
+            }
+        }
+        Some(GiveawayParticipantsView {
+            post_id,
+            participants_count: giveaway.participants.len() as u32,
+            participants_visible: giveaway.participants_visible,
+            participants,
+        })
+    }
 
+    /// Get the author's ended giveaways that have participants but no winner yet, oldest first
+    async fn giveaways_needing_resolution(&self, author: AccountOwner) -> Vec<GiveawayTaskView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let current_time = self.runtime.system_time().micros();
+                let mut tasks: Vec<GiveawayTaskView> = state.list_posts_by_author(author).await.unwrap_or_default().into_iter().filter_map(|post| {
+                    let g = post.giveaway?;
+                    let ended = g.end_timestamp > 0 && current_time > g.end_timestamp;
+                    (ended && !g.is_resolved && !g.participants.is_empty()).then_some(GiveawayTaskView {
+                        post_id: post.id,
+                        participant_count: g.participants.len() as u32,
+                        end_timestamp: g.end_timestamp,
+                    })
+                }).collect();
+                tasks.sort_by_key(|t| t.end_timestamp);
+                tasks
+            },
+            Err(_) => Vec::new(),
+        }
+    }
     
     /// Get a single post with poll view
     async fn post_view(&self, post_id: String) -> Option<PostView> {
@@ -855,30 +2363,89 @@ impl QueryRoot {
             Ok(state) => {
                 let current_time = self.runtime.system_time().micros();
                 match state.get_post(&post_id).await {
-                    Ok(Some(post)) => Some(post_to_view(&post, current_time)),
+                    Ok(Some(post)) => {
+                        let comment_count = state.comment_count(&post.id).await.unwrap_or(0);
+                        let unlocked = post.visibility == PostVisibility::Public
+                            || can_read_locked(&state, post.author, &post.author_chain_id, self.runtime.chain_id(), current_time).await;
+                        Some(post_to_view(&post, current_time, comment_count, unlocked))
+                    },
                     _ => None,
                 }
             },
             Err(_) => None,
         }
     }
+    
+    /// Get top-level comments of a post, oldest first, created after the `after` timestamp
+    /// Removed comments are kept as placeholders while they have visible replies
+    async fn comments(&self, post_id: String, limit: u32, after: Option<u64>) -> Vec<CommentView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => {
+                let mut res = Vec::new();
+                for c in state.list_comments(&post_id).await.unwrap_or_default() {
+                    if res.len() >= limit as usize {
+                        break;
+                    }
+                    if after.is_some_and(|a| c.created_at <= a) {
+                        continue;
+                    }
+                    let replies = state.list_comment_replies(&c.id).await.unwrap_or_default();
+                    let reply_count = replies.iter().filter(|r| !r.deleted && !r.hidden).count() as u32;
+                    if (c.deleted || c.hidden) && reply_count == 0 {
+                        continue;
+                    }
+                    res.push(comment_to_view(c, reply_count));
+                }
+                res
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Get visible replies to a top-level comment, oldest first, created after the `after` timestamp
+    async fn comment_replies(&self, comment_id: String, limit: u32, after: Option<u64>) -> Vec<CommentView> {
+        match DonationsState::load(self.storage_context.clone()).await {
+            Ok(state) => state.list_comment_replies(&comment_id).await.unwrap_or_default()
+                .into_iter()
+                .filter(|r| !r.deleted && !r.hidden && after.is_none_or(|a| r.created_at > a))
+                .take(limit as usize)
+                .map(|r| comment_to_view(r, 0))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
 }
 
 
+/// Mutations schedule an operation and return "ok", or the reason the input was refused.
+/// Failures inside the operation come back as `ResponseData::Error` in the block's outcome.
 struct MutationRoot { runtime: Arc<ServiceRuntime<DonationsService>> }
 
 #[Object]
+#[allow(clippy::too_many_arguments)]
 impl MutationRoot {
     async fn transfer(&self, owner: AccountOwner, amount: String, target_account: AccountInput, text_message: Option<String>) -> String {
+        let Ok(amount) = amount.parse::<Amount>() else { return "Invalid amount".to_string() };
         let fungible_account = linera_sdk::abis::fungible::Account { chain_id: target_account.chain_id, owner: target_account.owner };
-        self.runtime.schedule_operation(&Operation::Transfer { owner, amount: amount.parse::<Amount>().unwrap_or_default(), target_account: fungible_account, text_message });
+        self.runtime.schedule_operation(&Operation::Transfer { owner, amount, target_account: fungible_account, text_message });
+        "ok".to_string()
+    }
+    /// Withdraw to target_account (default: this chain's balance); amount (none or zero) defaults to the whole balance, and committed funds can't be withdrawn
+    async fn withdraw(&self, target_account: Option<AccountInput>, amount: Option<String>) -> String {
+        let Ok(amount) = amount.map(|a| a.parse::<Amount>()).transpose() else { return "Invalid amount".to_string() };
+        let target = target_account.map(|t| linera_sdk::abis::fungible::Account { chain_id: t.chain_id, owner: t.owner });
+        self.runtime.schedule_operation(&Operation::Withdraw { target, amount });
+        "ok".to_string()
+    }
+    /// Mint from the chain balance to an owner (app admin only)
+    async fn mint(&self, owner: AccountOwner, amount: String) -> String {
+        let Ok(amount) = amount.parse::<Amount>() else { return "Invalid amount".to_string() };
+        self.runtime.schedule_operation(&Operation::Mint { owner, amount });
         "ok".to_string()
     }
-    async fn withdraw(&self) -> String { self.runtime.schedule_operation(&Operation::Withdraw); "ok".to_string() }
-    async fn mint(&self, owner: AccountOwner, amount: String) -> String { self.runtime.schedule_operation(&Operation::Mint { owner, amount: amount.parse::<Amount>().unwrap_or_default() }); "ok".to_string() }
     async fn update_profile(&self, name: Option<String>, bio: Option<String>, socials: Vec<SocialLinkInput>, avatar_hash: Option<String>, header_hash: Option<String>) -> String { self.runtime.schedule_operation(&Operation::UpdateProfile { name, bio, socials, avatar_hash, header_hash }); "ok".to_string() }
     async fn register(&self, main_chain_id: String, name: Option<String>, bio: Option<String>, socials: Vec<SocialLinkInput>, avatar_hash: Option<String>, header_hash: Option<String>) -> String {
-        let chain_id = main_chain_id.parse().unwrap();
+        let Ok(chain_id) = main_chain_id.trim().parse() else { return "Invalid chain ID".to_string() };
         self.runtime.schedule_operation(&Operation::Register { main_chain_id: chain_id, name, bio, socials, avatar_hash, header_hash });
         "ok".to_string()
     }
@@ -901,10 +2468,16 @@ impl MutationRoot {
         public_data: Vec<KeyValueInput>,
         price: String,
         private_data: Vec<KeyValueInput>,
+        encrypted: Option<bool>,  // private_data values are client-side ciphertext
         success_message: Option<String>,
         order_form: Vec<OrderFormFieldInputGql>,
+        slug: Option<String>,
+        category: Option<String>,
+        entitlements: Option<Vec<String>>,
+        order_form_template: Option<String>,  // Saved template to use instead of order_form
+        stock: Option<u32>,  // Copies for sale, unlimited if omitted
     ) -> String {
-        let amount = price.parse::<Amount>().unwrap_or_default();
+        let Ok(amount) = price.parse::<Amount>() else { return "Invalid price".to_string() };
         
         // Convert input vectors to BTreeMaps
         let public_data_map: CustomFields = public_data.into_iter().map(|kv| (kv.key, kv.value)).collect();
@@ -920,23 +2493,38 @@ impl MutationRoot {
             public_data: public_data_map,
             price: amount,
             private_data: private_data_map,
+            encrypted,
             success_message,
             order_form: order_form_list,
+            slug,
+            category,
+            entitlements: entitlements.unwrap_or_default(),
+            order_form_template,
+            stock,
         });
         "ok".to_string()
     }
 
     /// Update an existing product
+    /// slug: new slug for pretty URLs, or "" to remove it
+    /// category: new category, or "" to remove it
+    /// stock: copies left; clear_stock: true makes the product unlimited
     async fn update_product(
         &self,
         product_id: String,
         public_data: Option<Vec<KeyValueInput>>,
         price: Option<String>,
         private_data: Option<Vec<KeyValueInput>>,
+        encrypted: Option<bool>,
         success_message: Option<String>,
         order_form: Option<Vec<OrderFormFieldInputGql>>,
+        slug: Option<String>,
+        category: Option<String>,
+        entitlements: Option<Vec<String>>,
+        stock: Option<u32>,
+        clear_stock: Option<bool>,
     ) -> String {
-        let price_amount = price.and_then(|p| p.parse::<Amount>().ok());
+        let Ok(price_amount) = price.map(|p| p.parse::<Amount>()).transpose() else { return "Invalid price".to_string() };
         let public_data_map = public_data.map(|v| v.into_iter().map(|kv| (kv.key, kv.value)).collect());
         let private_data_map = private_data.map(|v| v.into_iter().map(|kv| (kv.key, kv.value)).collect());
         let order_form_list = order_form.map(|v| v.into_iter().map(|f| OrderFormFieldInput {
@@ -951,17 +2539,66 @@ impl MutationRoot {
             public_data: public_data_map,
             price: price_amount,
             private_data: private_data_map,
+            encrypted,
             success_message,
             order_form: order_form_list,
+            slug,
+            category,
+            entitlements,
+            stock,
+            clear_stock,
         });
         "ok".to_string()
     }
 
+    /// Point a product at your new home chain after migrating
+    async fn update_product_chain(&self, product_id: String, new_chain_id: String) -> String {
+        let Ok(new_chain_id) = new_chain_id.trim().parse() else { return "Invalid chain ID".to_string() };
+        self.runtime.schedule_operation(&Operation::UpdateProductChain { product_id, new_chain_id });
+        "ok".to_string()
+    }
+
     async fn delete_product(&self, product_id: String) -> String {
         self.runtime.schedule_operation(&Operation::DeleteProduct { product_id });
         "ok".to_string()
     }
 
+    /// Pin up to 3 of your products to the top of your storefront, in the given order
+    async fn set_featured_products(&self, product_ids: Vec<String>) -> String {
+        self.runtime.schedule_operation(&Operation::SetFeaturedProducts { product_ids });
+        "ok".to_string()
+    }
+
+    /// Rate a product you bought from 1 to 5, with an optional comment; reviewing again replaces your review
+    async fn review_product(&self, product_id: String, rating: u8, comment: Option<String>) -> String {
+        self.runtime.schedule_operation(&Operation::ReviewProduct { product_id, rating, comment });
+        "ok".to_string()
+    }
+
+    /// Arrange your storefront: list every one of your products once, in display order.
+    /// An empty list goes back to newest first
+    async fn reorder_products(&self, ordered_ids: Vec<String>) -> String {
+        self.runtime.schedule_operation(&Operation::ReorderProducts { ordered_ids });
+        "ok".to_string()
+    }
+
+    /// Save a reusable order form, replacing any template with the same name
+    async fn save_order_form_template(&self, name: String, fields: Vec<OrderFormFieldInputGql>) -> String {
+        let fields = fields.into_iter().map(|f| OrderFormFieldInput {
+            key: f.key,
+            label: f.label,
+            field_type: f.field_type,
+            required: f.required,
+        }).collect();
+        self.runtime.schedule_operation(&Operation::SaveOrderFormTemplate { name, fields });
+        "ok".to_string()
+    }
+
+    async fn delete_order_form_template(&self, name: String) -> String {
+        self.runtime.schedule_operation(&Operation::DeleteOrderFormTemplate { name });
+        "ok".to_string()
+    }
+
     /// Purchase a product with order form data
     async fn transfer_to_buy(
         &self,
@@ -971,19 +2608,26 @@ impl MutationRoot {
         target_account: AccountInput,
         order_data: Vec<KeyValueInput>,
     ) -> String {
+        let Ok(amount) = amount.parse::<Amount>() else { return "Invalid amount".to_string() };
         let fungible_account = linera_sdk::abis::fungible::Account { chain_id: target_account.chain_id, owner: target_account.owner };
         let order_data_map: OrderResponses = order_data.into_iter().map(|kv| (kv.key, kv.value)).collect();
         
         self.runtime.schedule_operation(&Operation::TransferToBuy {
             owner,
             product_id,
-            amount: amount.parse::<Amount>().unwrap_or_default(),
+            amount,
             target_account: fungible_account,
             order_data: order_data_map,
         });
         "ok".to_string()
     }
 
+    /// Leave a note to the other party of a purchase (buyer or seller)
+    async fn add_order_note(&self, purchase_id: String, text: String) -> String {
+        self.runtime.schedule_operation(&Operation::AddOrderNote { purchase_id, text });
+        "ok".to_string()
+    }
+
     /// Schedule reading a data blob by its hash
     /// The hash should be a hex-encoded string of the blob hash (64 characters)
     /// Data blobs must be created externally via CLI `linera publish-data-blob` or GraphQL `publishDataBlob`
@@ -995,9 +2639,10 @@ impl MutationRoot {
     // Content subscription mutations
     
     /// Set subscription price with description for author's content
-    async fn set_subscription_price(&self, price: String, description: Option<String>) -> String {
-        let amount = price.parse::<Amount>().unwrap_or_default();
-        self.runtime.schedule_operation(&Operation::SetSubscriptionPrice { price: amount, description });
+    /// grandfather_renewals: renewals existing subscribers keep their locked price for (default 0)
+    async fn set_subscription_price(&self, price: String, description: Option<String>, grandfather_renewals: Option<u8>) -> String {
+        let Ok(amount) = price.parse::<Amount>() else { return "Invalid price".to_string() };
+        self.runtime.schedule_operation(&Operation::SetSubscriptionPrice { price: amount, description, grandfather_renewals: grandfather_renewals.unwrap_or(0) });
         "ok".to_string()
     }
     
@@ -1013,21 +2658,47 @@ impl MutationRoot {
         owner: AccountOwner,
         amount: String,
         target_account: AccountInput,
+        auto_renew: Option<bool>,
     ) -> String {
         let fungible_account = linera_sdk::abis::fungible::Account { 
             chain_id: target_account.chain_id, 
             owner: target_account.owner 
         };
-        let payment = amount.parse::<Amount>().unwrap_or_default();
+        let Ok(payment) = amount.parse::<Amount>() else { return "Invalid amount".to_string() };
         
         self.runtime.schedule_operation(&Operation::SubscribeToAuthor {
             owner,
             amount: payment,
             target_account: fungible_account,
+            auto_renew,
         });
         "ok".to_string()
     }
     
+    /// Renew your auto-renew subscriptions that are about to end; unfunded ones are skipped
+    async fn process_auto_renewals(&self) -> String {
+        self.runtime.schedule_operation(&Operation::ProcessAutoRenewals);
+        "ok".to_string()
+    }
+    
+    /// Turn renewal before expiry on or off for one of your subscriptions
+    async fn set_auto_renew(&self, subscription_id: String, auto_renew: bool) -> String {
+        self.runtime.schedule_operation(&Operation::SetAutoRenew { subscription_id, auto_renew });
+        "ok".to_string()
+    }
+    
+    /// Cancel one of your subscriptions before it expires; no refund
+    async fn unsubscribe(&self, subscription_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::Unsubscribe { subscription_id });
+        "ok".to_string()
+    }
+    
+    /// Send a short announcement to your active subscribers' notification inboxes
+    async fn announce(&self, text: String) -> String {
+        self.runtime.schedule_operation(&Operation::Announce { text });
+        "ok".to_string()
+    }
+    
     /// Create a new post (will be sent to active subscribers)
     /// Optionally include a poll with options and end timestamp
     /// Optionally include a giveaway with prize amount and end timestamp
@@ -1038,38 +2709,51 @@ impl MutationRoot {
         image_hash: Option<String>,
         poll_options: Option<Vec<String>>,
         poll_end_timestamp: Option<String>,  // Timestamp in microseconds as string
+        poll_broadcast_every_votes: Option<u32>,  // Votes between result broadcasts (default 10)
+        poll_broadcast_interval_secs: Option<u64>,  // Max seconds between result broadcasts (default 60)
         giveaway_prize: Option<String>,       // Prize amount as string
         giveaway_end_timestamp: Option<String>,  // Timestamp in microseconds as string
+        giveaway_max_participants: Option<u32>,  // Participant cap, unlimited if omitted
+        giveaway_participants_visible: Option<bool>,  // Let others list the entrants (default hidden)
+        visibility: Option<PostVisibility>,  // PUBLIC (default) or SUBSCRIBERS
     ) -> String {
 
         let poll_end = poll_end_timestamp.and_then(|ts| ts.parse::<u64>().ok());
         let giveaway_end = giveaway_end_timestamp.and_then(|ts| ts.parse::<u64>().ok());
-        let prize = giveaway_prize.and_then(|p| p.parse::<Amount>().ok());
+        let Ok(prize) = giveaway_prize.map(|p| p.parse::<Amount>()).transpose() else { return "Invalid prize".to_string() };
         self.runtime.schedule_operation(&Operation::CreatePost {
             title,
             content,
             image_hash,
             poll_options: poll_options.unwrap_or_default(),
             poll_end_timestamp: poll_end,
+            poll_broadcast_every_votes,
+            poll_broadcast_interval_micros: poll_broadcast_interval_secs.map(|s| s * 1_000_000),
             giveaway_prize: prize,
             giveaway_end_timestamp: giveaway_end,
+            giveaway_max_participants,
+            giveaway_participants_visible,
+            visibility,
         });
         "ok".to_string()
     }
     
     /// Update an existing post
+    /// Omitted fields are kept; clear_image: true removes the image
     async fn update_post(
         &self,
         post_id: String,
         title: Option<String>,
         content: Option<String>,
         image_hash: Option<String>,
+        clear_image: Option<bool>,
     ) -> String {
         self.runtime.schedule_operation(&Operation::UpdatePost {
             post_id,
             title,
             content,
             image_hash,
+            clear_image,
         });
         "ok".to_string()
     }
@@ -1092,7 +2776,7 @@ impl MutationRoot {
         post_id: String,
         option_index: u32,
     ) -> String {
-        let chain_id = author_chain_id.parse().expect("Invalid chain ID");
+        let Ok(chain_id) = author_chain_id.parse() else { return "Invalid chain ID".to_string() };
         self.runtime.schedule_operation(&Operation::CastVote {
             author_chain_id: chain_id,
             author,
@@ -1112,7 +2796,7 @@ impl MutationRoot {
         author: AccountOwner,
         post_id: String,
     ) -> String {
-        let chain_id = author_chain_id.parse().expect("Invalid chain ID");
+        let Ok(chain_id) = author_chain_id.parse() else { return "Invalid chain ID".to_string() };
         self.runtime.schedule_operation(&Operation::ParticipateInGiveaway {
             author_chain_id: chain_id,
             author,
@@ -1132,6 +2816,216 @@ impl MutationRoot {
         });
         "ok".to_string()
     }
+    
+    /// End a poll now and send the final results to subscribers (author only)
+    async fn close_poll(&self, post_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::ClosePoll { post_id });
+        "ok".to_string()
+    }
+    
+    /// Comment on a post; parent_id replies to an existing comment
+    async fn add_comment(
+        &self,
+        author_chain_id: String,
+        author: AccountOwner,
+        post_id: String,
+        parent_id: Option<String>,
+        text: String,
+    ) -> String {
+        let Ok(chain_id) = author_chain_id.parse() else { return "Invalid chain ID".to_string() };
+        self.runtime.schedule_operation(&Operation::AddComment {
+            author_chain_id: chain_id,
+            author,
+            post_id,
+            parent_id,
+            text,
+        });
+        "ok".to_string()
+    }
+    
+    /// Delete your own comment
+    async fn delete_comment(&self, author_chain_id: String, comment_id: String) -> String {
+        let Ok(chain_id) = author_chain_id.parse() else { return "Invalid chain ID".to_string() };
+        self.runtime.schedule_operation(&Operation::DeleteComment { author_chain_id: chain_id, comment_id });
+        "ok".to_string()
+    }
+    
+    /// Hide a comment on one of your posts
+    async fn hide_comment(&self, comment_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::HideComment { comment_id });
+        "ok".to_string()
+    }
+    
+    /// Pledge a share of all income to another account
+    /// basis_points: share of each credit to pass on (100 = 1%)
+    async fn set_income_redirect(&self, target_account: AccountInput, basis_points: u16, label: String) -> String {
+        let target = linera_sdk::abis::fungible::Account { chain_id: target_account.chain_id, owner: target_account.owner };
+        self.runtime.schedule_operation(&Operation::SetIncomeRedirect { target, basis_points, label });
+        "ok".to_string()
+    }
+    
+    /// Remove the income pledge
+    async fn clear_income_redirect(&self) -> String {
+        self.runtime.schedule_operation(&Operation::ClearIncomeRedirect);
+        "ok".to_string()
+    }
+    
+    /// Set a fundraising goal; deadline_micros is a Unix timestamp in microseconds
+    async fn set_donation_goal(&self, target: String, deadline_micros: u64, description: Option<String>) -> String {
+        let Ok(target) = target.parse::<Amount>() else { return "Invalid amount".to_string() };
+        self.runtime.schedule_operation(&Operation::SetDonationGoal { target, deadline_micros, description });
+        "ok".to_string()
+    }
+    
+    /// Remove the fundraising goal
+    async fn delete_donation_goal(&self) -> String {
+        self.runtime.schedule_operation(&Operation::DeleteDonationGoal);
+        "ok".to_string()
+    }
+    
+    /// Propose a new app admin (current admin only); the proposed account must accept
+    async fn propose_admin(&self, new_admin: AccountOwner) -> String {
+        self.runtime.schedule_operation(&Operation::ProposeAdmin { new_admin });
+        "ok".to_string()
+    }
+    
+    /// Accept a pending admin proposal (proposed account only)
+    async fn accept_admin(&self) -> String {
+        self.runtime.schedule_operation(&Operation::AcceptAdmin);
+        "ok".to_string()
+    }
+
+    async fn set_mint_limits(&self, max_per_mint: String, supply_cap: String) -> String {
+        let (Ok(max_per_mint), Ok(supply_cap)) = (max_per_mint.parse::<Amount>(), supply_cap.parse::<Amount>()) else {
+            return "Invalid amount".to_string();
+        };
+        self.runtime.schedule_operation(&Operation::SetMintLimits { max_per_mint, supply_cap });
+        "ok".to_string()
+    }
+
+    async fn snapshot_state(&self, scope: donations::SnapshotScope) -> String {
+        self.runtime.schedule_operation(&Operation::SnapshotState { scope });
+        "ok".to_string()
+    }
+
+    async fn restore_from_snapshot(&self, manifest_hash: String) -> String {
+        self.runtime.schedule_operation(&Operation::RestoreFromSnapshot { manifest_hash });
+        "ok".to_string()
+    }
+
+    /// Export purchases, subscriptions and donations in [from, to) as a JSONL blob;
+    /// `skip` continues a capped export from its `continuationSkip`
+    async fn export_activity_window(&self, from: u64, to: u64, skip: Option<u32>) -> String {
+        self.runtime.schedule_operation(&Operation::ExportActivityWindow { from, to, skip: skip.unwrap_or(0) });
+        "ok".to_string()
+    }
+
+    /// Entitlements granted to the caller's active subscribers (max 5)
+    async fn set_subscription_entitlements(&self, entitlements: Vec<String>) -> String {
+        self.runtime.schedule_operation(&Operation::SetSubscriptionEntitlements { entitlements });
+        "ok".to_string()
+    }
+
+    /// How long lapsed subscribers keep reading your posts, in seconds (at most 7 days)
+    async fn set_subscription_grace_period(&self, grace_period_secs: u64) -> String {
+        self.runtime.schedule_operation(&Operation::SetSubscriptionGracePeriod { grace_period_micros: grace_period_secs.saturating_mul(1_000_000) });
+        "ok".to_string()
+    }
+
+    /// Revoke what a purchase granted, on this chain and the buyer's (e.g. after a refund)
+    async fn revoke_purchase_entitlements(&self, purchase_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::RevokePurchaseEntitlements { purchase_id });
+        "ok".to_string()
+    }
+
+    /// Accept every order of yours that is still pending
+    async fn accept_all_pending_orders(&self) -> String {
+        self.runtime.schedule_operation(&Operation::AcceptAllPendingOrders);
+        "ok".to_string()
+    }
+
+    /// Pay one of your sales back to the buyer; refunding it again does nothing
+    async fn refund_purchase(&self, purchase_id: String) -> String {
+        self.runtime.schedule_operation(&Operation::RefundPurchase { purchase_id });
+        "ok".to_string()
+    }
+
+    /// Move one of your orders along, e.g. to FULFILLED once shipped or CANCELLED
+    async fn update_order_status(&self, purchase_id: String, status: donations::OrderStatus) -> String {
+        self.runtime.schedule_operation(&Operation::UpdateOrderStatus { purchase_id, status });
+        "ok".to_string()
+    }
+
+    async fn set_holding_account(&self, owner: AccountOwner, holding: bool) -> String {
+        self.runtime.schedule_operation(&Operation::SetHoldingAccount { owner, holding });
+        "ok".to_string()
+    }
+
+    /// Send a held donation on to its real recipient
+    async fn route_donation(&self, donation_id: u64, new_recipient_account: AccountInput) -> String {
+        let new_recipient_account = linera_sdk::abis::fungible::Account { chain_id: new_recipient_account.chain_id, owner: new_recipient_account.owner };
+        self.runtime.schedule_operation(&Operation::RouteDonation { donation_id, new_recipient_account });
+        "ok".to_string()
+    }
+
+    /// Publicly thank the donor of a donation you received (at most 280 characters)
+    async fn reply_to_donation(&self, donation_id: u64, text: String) -> String {
+        self.runtime.schedule_operation(&Operation::ReplyToDonation { donation_id, text });
+        "ok".to_string()
+    }
+
+    /// Follow an author
+    async fn follow(&self, author: AccountOwner) -> String {
+        self.runtime.schedule_operation(&Operation::Follow { author });
+        "ok".to_string()
+    }
+
+    /// Stop following an author
+    async fn unfollow(&self, author: AccountOwner) -> String {
+        self.runtime.schedule_operation(&Operation::Unfollow { author });
+        "ok".to_string()
+    }
+
+    /// Follow several authors at once, e.g. from onboarding suggestions; at most 50
+    async fn follow_many(&self, authors: Vec<AccountOwner>) -> String {
+        self.runtime.schedule_operation(&Operation::FollowMany { authors });
+        "ok".to_string()
+    }
+
+    /// Pin one of your received donations
+    async fn pin_donation(&self, donation_id: u64) -> String {
+        self.runtime.schedule_operation(&Operation::PinDonation { donation_id });
+        "ok".to_string()
+    }
+
+    /// Permanently close your account; only withdrawing funds works afterwards
+    async fn close_account(&self) -> String {
+        self.runtime.schedule_operation(&Operation::CloseAccount);
+        "ok".to_string()
+    }
+
+    /// Re-send your unacknowledged outbound messages whose retry time has passed
+    async fn retry_outbox(&self) -> String {
+        self.runtime.schedule_operation(&Operation::RetryOutbox);
+        "ok".to_string()
+    }
+
+    /// Apply mirrored stream events that an earlier block's budget left for later
+    async fn drain_stream_backlog(&self) -> String {
+        self.runtime.schedule_operation(&Operation::DrainStreamBacklog);
+        "ok".to_string()
+    }
+
+    async fn commit_supporter_draw(&self, year: u32, month: u32, commitment: String) -> String {
+        self.runtime.schedule_operation(&Operation::CommitSupporterDraw { year, month, commitment });
+        "ok".to_string()
+    }
+
+    async fn draw_supporter_of_month(&self, year: u32, month: u32, seed: String, prize: Option<String>) -> String {
+        let Ok(prize) = prize.map(|p| p.parse::<Amount>()).transpose() else { return "Invalid prize".to_string() };
+        self.runtime.schedule_operation(&Operation::DrawSupporterOfMonth { year, month, seed, prize });
+        "ok".to_string()
+    }
 }
 
 
@@ -1149,3 +3043,277 @@ struct OrderFormFieldInputGql {
     field_type: String,
     required: bool,
 }
+
+#[derive(InputObject, Default)]
+struct PurchaseFilter {
+    product_id: Option<String>,
+    status: Option<donations::OrderStatus>,
+    since: Option<u64>,   // Inclusive, micros
+    until: Option<u64>,   // Exclusive, micros
+}
+
+impl PurchaseFilter {
+    fn matches(&self, purchase: &donations::Purchase) -> bool {
+        self.product_id.as_ref().is_none_or(|id| *id == purchase.product_id)
+            && self.status.is_none_or(|s| s == purchase.status)
+            && self.since.is_none_or(|t| purchase.timestamp >= t)
+            && self.until.is_none_or(|t| purchase.timestamp < t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linera_sdk::{linera_base_types::Timestamp, util::BlockingWait, views::{RootView, ViewStorageContext}};
+
+    const NOW: u64 = 1_739_145_600_000_000;
+
+    fn owner(name: &str) -> AccountOwner { AccountOwner::from(CryptoHash::test_hash(name)) }
+    fn chain(name: &str) -> ChainId { ChainId(CryptoHash::test_hash(name)) }
+
+    /// A service on the `viewer` chain over the state `setup` writes
+    fn service_with_state(viewer: &str, operator_mode: bool, setup: impl FnOnce(&mut DonationsState)) -> DonationsService {
+        let parameters = DonationsParameters { ticker_symbol: "LF".to_string(), payment_app: None, max_message_chars: None, operator_mode };
+        let runtime = ServiceRuntime::<DonationsService>::new()
+            .with_application_parameters(parameters)
+            .with_chain_id(chain(viewer))
+            .with_system_time(Timestamp::from(NOW));
+        let context = ViewStorageContext::new_unchecked(runtime.key_value_store().to_mut(), Vec::new(), ());
+        let mut state = DonationsState::load(context).blocking_wait().unwrap();
+        setup(&mut state);
+        state.save().blocking_wait().unwrap();
+        DonationsService { runtime: Arc::new(runtime) }
+    }
+
+    fn execute(service: DonationsService, request: Request) -> Response {
+        // The schema is too deep for the default test thread stack
+        std::thread::Builder::new().stack_size(64 << 20)
+            .spawn(move || service.handle_query(request).blocking_wait())
+            .unwrap().join().unwrap()
+    }
+
+    /// Runs `query` on the `viewer` chain against the state `setup` writes
+    fn query_state(viewer: &str, operator_mode: bool, setup: impl FnOnce(&mut DonationsState), query: &str) -> serde_json::Value {
+        execute(service_with_state(viewer, operator_mode, setup), Request::new(query)).data.into_json().unwrap()
+    }
+
+    /// Runs `query` against Alice's subscriber-only post, with Bob subscribed from his chain,
+    /// on the `viewer` chain
+    fn query(viewer: &str, query: &str) -> serde_json::Value {
+        query_state(viewer, false, members_post, query)
+    }
+
+    fn members_post(state: &mut DonationsState) {
+        state.create_post(Post {
+            id: "p1".to_string(), author: owner("alice"), author_chain_id: chain("alice").to_string(),
+            title: "Members".to_string(), content: "secret".to_string(), image_hash: Some("img".to_string()),
+            created_at: NOW, poll: None, giveaway: None, visibility: PostVisibility::Subscribers,
+        }).blocking_wait().unwrap();
+        state.create_subscription(ContentSubscription {
+            id: "s1".to_string(), subscriber: owner("bob"), subscriber_chain_id: chain("bob").to_string(),
+            author: owner("alice"), author_chain_id: chain("alice").to_string(),
+            start_timestamp: NOW, end_timestamp: NOW + 1_000_000, price: Amount::ONE, locked_price: Amount::ONE,
+            renewals_remaining_at_locked_price: 0, auto_renew: false, auto_renew_failures: 0,
+        }).blocking_wait().unwrap();
+    }
+
+    fn alice_posts(viewer: &str) -> serde_json::Value {
+        let posts = query(viewer, &format!("{{ postsByAuthor(author: \"{}\") {{ locked title content imageHash }} }}", owner("alice")));
+        posts["postsByAuthor"][0].clone()
+    }
+
+    #[test]
+    fn subscriber_only_post_is_locked_for_non_subscribers() {
+        let post = alice_posts("carol");
+        assert_eq!(post, serde_json::json!({ "locked": true, "title": "Members", "content": "", "imageHash": null }));
+        let post = query("carol", "{ postView(postId: \"p1\") { locked content imageHash } }");
+        assert_eq!(post["postView"], serde_json::json!({ "locked": true, "content": "", "imageHash": null }));
+    }
+
+    #[test]
+    fn subscriber_only_post_is_readable_by_subscribers_and_author() {
+        for viewer in ["bob", "alice"] {
+            let post = alice_posts(viewer);
+            assert_eq!(post, serde_json::json!({ "locked": false, "title": "Members", "content": "secret", "imageHash": "img" }));
+        }
+    }
+
+    /// Products by Alice and by Bob, each with a private link
+    fn two_products(state: &mut DonationsState) {
+        for author in ["alice", "bob"] {
+            state.create_product(Product {
+                id: author.to_string(), author: owner(author), author_chain_id: chain(author).to_string(),
+                public_data: Default::default(), price: Amount::ONE, private_data: [("link".to_string(), author.to_string())].into(), encrypted: false,
+                success_message: None, order_form: Vec::new(), slug: None, category: None, entitlements: Vec::new(),
+                content_revision: 0, form_revision: 0, created_at: NOW, stock: None,
+            }).blocking_wait().unwrap();
+        }
+    }
+
+    fn exported_private_data(viewer: &str, operator_mode: bool, include_private: bool) -> serde_json::Value {
+        let query = format!("{{ stateExport(scope: {{ profiles: false, products: true, purchases: false, posts: false, subscriptions: false, donations: false }}, includePrivate: {}) {{ products {{ id privateData }} }} }}", include_private);
+        query_state(viewer, operator_mode, two_products, &query)["stateExport"]["products"].clone()
+    }
+
+    #[test]
+    fn state_export_keeps_private_data_only_for_this_chains_records() {
+        let blank = serde_json::json!({});
+        let [alice, bob] = [serde_json::json!({ "link": "alice" }), serde_json::json!({ "link": "bob" })];
+        // Listed in key order, which puts the shorter id first
+        let products = |alice: &serde_json::Value, bob: &serde_json::Value| serde_json::json!([{ "id": "bob", "privateData": bob }, { "id": "alice", "privateData": alice }]);
+
+        assert_eq!(exported_private_data("alice", false, false), products(&blank, &blank));
+        assert_eq!(exported_private_data("alice", false, true), products(&alice, &blank));
+        assert_eq!(exported_private_data("carol", false, true), products(&blank, &blank));
+        assert_eq!(exported_private_data("carol", true, true), products(&alice, &bob));
+    }
+
+    fn purchase_ids(operator_mode: bool, query: &str) -> serde_json::Value {
+        let purchases = |state: &mut DonationsState| {
+            two_products(state);
+            for (id, product_id) in [("p1", "alice"), ("p2", "bob"), ("p3", "alice")] {
+                let product = state.get_product(product_id).blocking_wait().unwrap().unwrap();
+                state.record_purchase(donations::Purchase {
+                    id: id.to_string(), product_id: product_id.to_string(), buyer: owner("carol"), buyer_chain_id: chain("carol").to_string(),
+                    seller: product.author, seller_chain_id: product.author_chain_id.clone(), amount: Amount::ONE, timestamp: NOW,
+                    order_data: Default::default(), content_modified: false, payment_app: None, status: Default::default(), product,
+                }).blocking_wait().unwrap();
+            }
+        };
+        let result = query_state("carol", operator_mode, purchases, query);
+        serde_json::Value::Array(result["allPurchases"].as_array().unwrap().iter().map(|p| p["id"].clone()).collect())
+    }
+
+    #[test]
+    fn all_purchases_needs_operator_mode_and_reads_one_products_purchases_directly() {
+        assert_eq!(purchase_ids(false, "{ allPurchases { id } }"), serde_json::json!([]));
+        assert_eq!(purchase_ids(true, "{ allPurchases { id } }").as_array().unwrap().len(), 3);
+        assert_eq!(purchase_ids(true, "{ allPurchases(filter: { productId: \"alice\" }) { id } }"), serde_json::json!(["p1", "p3"]));
+    }
+
+    #[test]
+    fn order_notes_are_readable_only_on_the_buyers_and_sellers_chains() {
+        let notes = |viewer: &str| query_state(viewer, false, |state| {
+            two_products(state);
+            let product = state.get_product("alice").blocking_wait().unwrap().unwrap();
+            state.record_purchase(donations::Purchase {
+                id: "p1".to_string(), product_id: "alice".to_string(), buyer: owner("carol"), buyer_chain_id: chain("carol").to_string(),
+                seller: product.author, seller_chain_id: product.author_chain_id.clone(), amount: Amount::ONE, timestamp: NOW,
+                order_data: Default::default(), content_modified: false, payment_app: None, status: Default::default(), product,
+            }).blocking_wait().unwrap();
+            state.add_order_note("p1", donations::OrderNote { from: owner("carol"), text: "Gift wrap".to_string(), timestamp: NOW }).blocking_wait().unwrap();
+        }, "{ orderNotes(purchaseId: \"p1\") { text } }")["orderNotes"].clone();
+        let gift_wrap = serde_json::json!([{ "text": "Gift wrap" }]);
+        assert_eq!(notes("carol"), gift_wrap);
+        assert_eq!(notes("alice"), gift_wrap);
+        assert_eq!(notes("bob"), serde_json::json!([]));
+    }
+
+    #[test]
+    fn creator_page_gathers_profile_storefront_goal_and_engagement() {
+        let page = query_state("carol", false, |state| {
+            two_products(state);
+            state.set_name(owner("alice"), "Alice".to_string()).blocking_wait().unwrap();
+            let goal = donations::DonationGoal { target: Amount::from_tokens(10), deadline_micros: NOW + 1, description: None, created_at: NOW - 1 };
+            state.set_donation_goal(owner("alice"), goal).blocking_wait().unwrap();
+            let today = (NOW / state::MICROS_PER_DAY) as u32;
+            let day = donations::EngagementDay { received: 4, answered: 3, response_hours: [(2, 2), (30, 1)].into() };
+            state.comment_engagement.insert(&owner("alice"), [(today, day)].into()).unwrap();
+        }, &format!("{{ creatorPage(owner: \"{}\") {{ profile {{ name }} products {{ id }} goal {{ percentComplete }} engagement {{ commentsReceived responseRateBps medianResponseHours }} }} }}", owner("alice")));
+        assert_eq!(page["creatorPage"], serde_json::json!({
+            "profile": { "name": "Alice" },
+            "products": [{ "id": "alice" }],
+            "goal": { "percentComplete": 0 },
+            "engagement": { "commentsReceived": 4, "responseRateBps": 7_500, "medianResponseHours": 2 },
+        }));
+    }
+
+    #[test]
+    fn announcements_stay_in_the_feed_after_the_inbox_fills_up() {
+        let notification = |kind: &str, timestamp| donations::Notification { kind: kind.to_string(), from: owner("alice"), text: kind.to_string(), timestamp };
+        let feed = query_state("bob", false, |state| {
+            state.push_announcement(owner("bob"), notification("announcement", NOW)).blocking_wait().unwrap();
+            for i in 0..state::MAX_NOTIFICATIONS as u64 {
+                state.push_notification(owner("bob"), notification("price_change", NOW + i + 1)).blocking_wait().unwrap();
+            }
+            assert!(state.notifications.get(&owner("bob")).blocking_wait().unwrap().unwrap().iter().all(|n| n.kind != "announcement"));
+        }, &format!("{{ myActivityFeed(subscriber: \"{}\") {{ kind announcement {{ text }} }} }}", owner("bob")));
+        assert_eq!(feed["myActivityFeed"], serde_json::json!([{ "kind": "announcement", "announcement": { "text": "announcement" } }]));
+    }
+
+    #[test]
+    fn donor_stats_break_down_giving_per_recipient() {
+        let stats = query_state("alice", false, |state| {
+            for (recipient, tokens) in [("bob", 3), ("carol", 2), ("holding", 4)] {
+                state.record_donation(owner("alice"), owner(recipient), Amount::from_tokens(tokens), None, None, None, None, NOW, None).blocking_wait().unwrap();
+            }
+            state.admin.set(Some(owner("admin")));
+            state.hold_donation(3).blocking_wait().unwrap();
+            state.route_donation(owner("admin"), 3, owner("carol"), chain("carol").to_string()).blocking_wait().unwrap();
+            let tallies = state.recipient_tallies.try_load_entry(&owner("alice")).blocking_wait().unwrap().unwrap();
+            assert_eq!(tallies.get(&owner("holding")).blocking_wait().unwrap().map(|t| t.count), None);
+            assert_eq!(tallies.get(&owner("carol")).blocking_wait().unwrap().map(|t| (t.total, t.count)), Some((Amount::from_tokens(6), 2)));
+        }, &format!("{{ donorStats(owner: \"{}\") {{ total donationCount distinctRecipients recipients {{ recipient total count }} }} }}", owner("alice")));
+        assert_eq!(stats["donorStats"], serde_json::json!({
+            "total": Amount::from_tokens(9), "donationCount": 3, "distinctRecipients": 2,
+            "recipients": [
+                { "recipient": owner("carol"), "total": Amount::from_tokens(6), "count": 2 },
+                { "recipient": owner("bob"), "total": Amount::from_tokens(3), "count": 1 },
+            ],
+        }));
+    }
+
+    #[test]
+    fn top_donors_read_one_total_per_donor() {
+        let ranked = query_state("carol", false, |state| {
+            for (donor, tokens) in [("alice", 3), ("bob", 5), ("alice", 4)] {
+                state.record_donation(owner(donor), owner("carol"), Amount::from_tokens(tokens), None, None, None, None, NOW, None).blocking_wait().unwrap();
+            }
+            let totals = state.donor_totals.try_load_entry(&owner("carol")).blocking_wait().unwrap().unwrap();
+            assert_eq!(totals.get(&owner("alice")).blocking_wait().unwrap(), Some(Amount::from_tokens(7)));
+            assert_eq!(totals.count().blocking_wait().unwrap(), 2);
+        }, &format!("{{ topDonors(recipient: \"{}\") {{ donor total }} }}", owner("carol")));
+        assert_eq!(ranked["topDonors"], serde_json::json!([
+            { "donor": owner("alice"), "total": Amount::from_tokens(7) },
+            { "donor": owner("bob"), "total": Amount::from_tokens(5) },
+        ]));
+    }
+
+    #[cfg(feature = "debug-metrics")]
+    #[test]
+    fn donations_by_recipient_reads_a_fixed_number_of_maps_per_donation() {
+        let service = service_with_state("carol", false, |state| {
+            for donor in ["alice", "bob", "dave"] {
+                state.record_donation(owner(donor), owner("carol"), Amount::ONE, None, None, None, None, NOW, None).blocking_wait().unwrap();
+            }
+        });
+        let mut request = Request::new(format!("{{ donationsViewByRecipient(owner: \"{}\") {{ donations {{ id fromChainId }} }} }}", owner("carol")));
+        request.extensions.insert(donations::metrics::DEBUG_FLAG.to_string(), async_graphql::Value::Boolean(true));
+        let response = execute(service, request);
+        let report = response.extensions[donations::metrics::DEBUG_FLAG].clone().into_json().unwrap();
+        // The recipient's home chain and id list, then each record and its donor's home chain
+        assert_eq!(report["stateLoads"], 1);
+        assert_eq!(report["mapReads"], 2 + 2 * 3);
+    }
+
+    #[test]
+    fn sync_gap_reports_posts_missing_from_and_stale_in_the_mirror() {
+        let gap = query_state("bob", false, |state| {
+            members_post(state);
+            state.create_post(Post {
+                id: "p0".to_string(), author: owner("alice"), author_chain_id: chain("alice").to_string(),
+                title: "Deleted".to_string(), content: String::new(), image_hash: None,
+                created_at: NOW - 1, poll: None, giveaway: None, visibility: PostVisibility::Public,
+            }).blocking_wait().unwrap();
+            let key = (chain("alice").to_string(), StreamName::from(donations::EVENTS_STREAM).to_string());
+            state.note_stream_head(key, 3, 7).blocking_wait().unwrap();
+        }, &format!(
+            "{{ syncGap(subscriber: \"{}\", author: \"{}\", authorPostIds: [\"p1\", \"p2\"]) {{ authorChainId heldCount expectedCount missing extra appliedEvents knownEvents }} }}",
+            owner("bob"), owner("alice"),
+        ));
+        assert_eq!(gap["syncGap"], serde_json::json!({
+            "authorChainId": chain("alice").to_string(), "heldCount": 2, "expectedCount": 2,
+            "missing": ["p2"], "extra": ["p0"], "appliedEvents": 3, "knownEvents": 7,
+        }));
+    }
+}
@@ -1,27 +1,172 @@
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext, ViewError};
-use linera_sdk::linera_base_types::{AccountOwner, Amount};
+use linera_sdk::views::{linera_views, CollectionView, RegisterView, RootView, View, ViewStorageContext, ViewError};
+// Maps count their reads for debug metrics
+use donations::metrics::CountedMapView as MapView;
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ChainId};
 use donations::{
-    Profile, DonationRecord, SocialLink, Product, Purchase, CustomFields, OrderFormField, ContentSubscription, Post, SubscriptionInfo, Poll, PollOption, Giveaway, GiveawayParticipant,
+    Profile, DonationRecord, SocialLink, Product, Purchase, CustomFields, OrderFormField, OrderResponses, ContentSubscription, Post, SubscriptionInfo, Poll, Giveaway, GiveawayParticipant,
+    IncomeRedirect, DeliveredSequences, SlugRedirect, Commitment, Comment, OrderNote, MintLimits, EngagementDay, PendingPrice, Notification, EntitlementGrant, SnapshotScope, SnapshotData, SnapshotMeta, ActivityRecord, ActivityExport, OrderStatus, SubscriptionAccess, DonationGoal, FailedOrder, PendingRefund, Review, OutboxEntry, Message, DonorStats, RecipientTally, DonationSide,
 };
 
+pub const MAX_FEATURED_PRODUCTS: usize = 3;
+
+/// Which slice of a donation id list to load. Ids only grow, so `after_id` is a stable
+/// cursor: the next page starts past it in the requested direction.
+#[derive(Debug, Clone, Copy)]
+pub struct DonationPageQuery {
+    pub after_id: Option<u64>,
+    pub offset: usize,
+    pub limit: usize,
+    pub newest_first: bool,
+}
+
+impl DonationPageQuery {
+    /// The requested ids out of an ascending id list
+    fn select(&self, ids: &[u64]) -> Vec<u64> {
+        let past_cursor = |id: &&u64| match self.after_id {
+            Some(after) if self.newest_first => **id < after,
+            Some(after) => **id > after,
+            None => true,
+        };
+        if self.newest_first {
+            ids.iter().rev().filter(past_cursor).skip(self.offset).take(self.limit).copied().collect()
+        } else {
+            ids.iter().filter(past_cursor).skip(self.offset).take(self.limit).copied().collect()
+        }
+    }
+}
+
+pub const MAX_ORDER_NOTE_CHARS: usize = 1000;
+
+pub const MAX_ORDER_FORM_TEMPLATES: usize = 20;
+
+pub const MAX_TEMPLATE_NAME_CHARS: usize = 60;
+
+pub const MAX_NOTIFICATIONS: usize = 100;
+
+// Announcements kept per subscriber for the activity feed, which shows at most this many
+pub const MAX_ANNOUNCEMENTS: usize = 200;
+
+pub const MICROS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// Day number of a timestamp, the key of the donation day indexes
+fn day_of(timestamp: u64) -> u32 {
+    (timestamp / MICROS_PER_DAY) as u32
+}
+
+// Minimum time between an owner's profile-changing operations, so they can't flood the event stream
+pub const PROFILE_UPDATE_COOLDOWN_MICROS: u64 = 30 * 1_000_000;
+
+// Most events one owner's operations may emit per window; their further operations wait for the next one
+pub const MAX_EVENTS_PER_WINDOW: u32 = 100;
+pub const EVENT_RATE_WINDOW_MICROS: u64 = 60 * 1_000_000;
+
+// How long an old product slug keeps resolving after it was changed
+pub const SLUG_REDIRECT_MICROS: u64 = 30 * 24 * 60 * 60 * 1_000_000;
+
+/// Why a state method failed. `Display` gives the message shown to users.
+#[derive(Debug)]
+pub enum StateError {
+    /// The named record doesn't exist
+    NotFound(&'static str),
+    /// The caller isn't who the record requires, e.g. "not product owner"
+    Unauthorized(&'static str),
+    /// The input or the record's current state rules the change out
+    Validation(String),
+    Storage(ViewError),
+}
+
+impl std::fmt::Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateError::NotFound(what) => write!(f, "{} not found", what),
+            StateError::Unauthorized(reason) => write!(f, "Unauthorized: {}", reason),
+            StateError::Validation(message) => f.write_str(message),
+            StateError::Storage(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl From<ViewError> for StateError {
+    fn from(e: ViewError) -> Self { StateError::Storage(e) }
+}
+
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct DonationsState {
+    pub admin: RegisterView<Option<AccountOwner>>,
+    pub pending_admin: RegisterView<Option<AccountOwner>>,
     pub donation_counter: RegisterView<u64>,
+    pub donation_id_gaps: RegisterView<u64>,  // Ids up to donation_counter a restored snapshot left without a donation
+    pub post_counter: RegisterView<u64>,
+    pub total_minted: RegisterView<Amount>,
+    pub mint_limits: RegisterView<Option<MintLimits>>,  // None until the admin sets them
     pub donations: MapView<u64, DonationRecord>,
     pub donations_by_recipient: MapView<AccountOwner, Vec<u64>>, 
     pub donations_by_donor: MapView<AccountOwner, Vec<u64>>, 
+    pub donations_by_source: MapView<(String, u64), u64>,  // (Source chain, id there) -> local donation id
+    pub donation_days: MapView<u32, Vec<u64>>,  // Day -> donation ids with a timestamp on that day
+    pub donation_days_by_recipient: MapView<(AccountOwner, u32), Vec<u64>>,
+    pub donation_days_by_donor: MapView<(AccountOwner, u32), Vec<u64>>,
+    pub day_index_start: RegisterView<Option<u64>>,  // First donation id in the day indexes; older ones are not in them
+    pub received_totals: MapView<AccountOwner, Amount>,  // Recipient -> all donations received
+    pub donor_totals: CollectionView<AccountOwner, MapView<AccountOwner, Amount>>,  // Recipient -> donor -> total given
+    pub donor_stats: MapView<AccountOwner, DonorStats>,  // Donor -> their giving overall
+    pub recipient_tallies: CollectionView<AccountOwner, MapView<AccountOwner, RecipientTally>>,  // Donor -> recipient -> their giving to them
+    pub holding_accounts: MapView<AccountOwner, bool>,
+    pub pending_donations: MapView<u64, bool>,  // Held donations not yet routed or mirrored
+    pub pinned_donation: MapView<AccountOwner, u64>,  // Recipient -> donation id they highlight
     pub profiles: MapView<AccountOwner, Profile>,
+    pub profile_updated_at: MapView<AccountOwner, u64>,  // Last profile change, for the cooldown
+    pub event_rate: MapView<AccountOwner, (u64, u32)>,  // Owner -> (window start, events emitted in it)
+    pub closed_accounts: MapView<AccountOwner, u64>,  // Owner -> when the account was closed
+    // Follows in both directions, with when each started
+    pub followers: CollectionView<AccountOwner, MapView<AccountOwner, u64>>,  // Author -> follower -> since
+    pub following: CollectionView<AccountOwner, MapView<AccountOwner, u64>>,  // Follower -> author -> since
+    // Registration: on the owner's chain, the main chain they registered with; on the main
+    // chain, the chain they registered from
     pub subscriptions: MapView<AccountOwner, String>,
+    pub home_chains: MapView<AccountOwner, String>,  // Owner -> chain they registered from
     // Marketplace state
     pub products: MapView<String, Product>,
     pub products_by_author: MapView<AccountOwner, Vec<String>>,
     pub products_by_chain: MapView<String, Vec<String>>,  // NEW: Chain-based index
+    pub featured_products: MapView<AccountOwner, Vec<String>>,  // Ordered, at most MAX_FEATURED_PRODUCTS
+    pub product_order: MapView<AccountOwner, Vec<String>>,  // Author's manual storefront order
+    pub order_form_templates: MapView<AccountOwner, std::collections::BTreeMap<String, Vec<OrderFormField>>>,  // Author -> template name -> fields
+    pub product_slugs: MapView<String, String>,  // "author:slug" -> product_id
+    pub products_by_category: MapView<String, Vec<String>>,  // Emptied categories are removed
+    pub slug_redirects: MapView<String, SlugRedirect>,  // "author:old_slug" -> product
+    // Funds locked per owner, not available for spending
+    pub commitments: MapView<AccountOwner, Vec<Commitment>>,
+    // Supporter of the month, keyed by (author, YYYYMM)
+    pub supporter_draw_commitments: MapView<(AccountOwner, u32), String>,
+    pub monthly_winners: MapView<(AccountOwner, u32), AccountOwner>,
+    // Inbound text truncated/stripped by handlers, counted per (kind, day)
+    pub content_adjustments: MapView<(String, u32), u64>,
     pub purchases: MapView<String, Purchase>,
     pub purchases_by_buyer: MapView<AccountOwner, Vec<String>>,
     pub purchases_by_seller: MapView<AccountOwner, Vec<String>>,
+    pub purchases_by_product: MapView<String, Vec<String>>,  // Product id -> purchase ids, oldest first
+    pub delivered_purchases: MapView<String, u64>,  // Purchase id -> when the buyer's chain got the product data
+    pub order_notes: MapView<String, Vec<OrderNote>>,  // purchase_id -> notes, oldest first
+    pub reviews: MapView<String, Vec<Review>>,  // Product id -> one review per buyer
+    pub failed_orders: MapView<AccountOwner, Vec<FailedOrder>>,  // Buyer -> undeliverable purchases, oldest first
+    pub pending_refunds: MapView<AccountOwner, Vec<PendingRefund>>,  // Payee -> refused payments to send back, oldest first
+    // Outbound mirror messages awaiting an ack, keyed by (target chain, sequence), with the
+    // (next retry, target chain, sequence) of each one still to be re-sent or dropped
+    pub outbox_sequences: MapView<ChainId, u64>,  // Target chain -> next sequence to use
+    pub outbox: MapView<(ChainId, u64), OutboxEntry>,
+    pub outbox_due: RegisterView<std::collections::BTreeSet<(u64, ChainId, u64)>>,
+    pub outbox_delivered: MapView<ChainId, DeliveredSequences>,  // Origin chain -> tracked messages applied here
     // Content subscription state
     pub subscription_prices: MapView<AccountOwner, SubscriptionInfo>,
+    pub notifications: MapView<AccountOwner, Vec<Notification>>,  // Oldest first, at most MAX_NOTIFICATIONS
+    pub announcements: MapView<AccountOwner, Vec<Notification>>,  // Subscriber -> received, oldest first, at most MAX_ANNOUNCEMENTS
+    pub entitlements: MapView<AccountOwner, Vec<EntitlementGrant>>,  // Holder -> purchase grants, one per purchase and name; subscription grants are derived
+    // Stream processing, keyed by (chain id, stream name): next index to apply,
+    // and known end of streams with pending work
+    pub stream_cursors: MapView<(String, String), u64>,
+    pub stream_heads: MapView<(String, String), u64>,
     pub content_subscriptions: MapView<String, ContentSubscription>,
     pub subscriptions_by_author: MapView<AccountOwner, Vec<String>>,
     pub subscriptions_by_chain: MapView<String, Vec<String>>,  // NEW: Chain-based index
@@ -29,40 +174,545 @@ pub struct DonationsState {
     pub posts: MapView<String, Post>,
     pub posts_by_author: MapView<AccountOwner, Vec<String>>,
     pub posts_by_chain: MapView<String, Vec<String>>,  // NEW: Chain-based index
+    // Comments: top-level ids per post, reply ids per top-level comment
+    pub comments: MapView<String, Comment>,
+    pub comments_by_post: MapView<String, Vec<String>>,
+    pub comment_replies: MapView<String, Vec<String>>,
+    pub comment_counts: MapView<String, u32>,  // Visible comments per post
+    pub comment_seq: MapView<String, u32>,  // Last comment number per post
+    // Per author: day -> comment engagement counters, pruned to ENGAGEMENT_WINDOW_DAYS
+    pub comment_engagement: MapView<AccountOwner, std::collections::BTreeMap<u32, EngagementDay>>,
+    // Renewal prices confirmed by authors' chains, keyed by "author:subscriber"
+    pub renewal_price_cache: MapView<String, Amount>,
+    // Snapshots taken on this chain, by sequence number
+    pub snapshots: MapView<u64, SnapshotMeta>,
+    pub activity_exports: MapView<u64, ActivityExport>,
+    // Day -> purchase / subscription ids starting that day, read by activity exports
+    pub purchase_days: MapView<u32, Vec<String>>,
+    pub subscription_days: MapView<u32, Vec<String>>,
+    // Income redirect state
+    pub income_redirects: MapView<AccountOwner, IncomeRedirect>,
+    pub pending_redirects: MapView<AccountOwner, Amount>,
+    pub redirect_totals: MapView<AccountOwner, Amount>,
+    pub donation_goals: MapView<AccountOwner, DonationGoal>,
+    pub goal_received: MapView<AccountOwner, Amount>,  // Recipient -> donations received since their goal was set
 }
 
 #[allow(dead_code)]
 impl DonationsState {
-    pub async fn record_donation(&mut self, from: AccountOwner, to: AccountOwner, amount: Amount, message: Option<String>, source_chain_id: Option<String>, to_chain_id: Option<String>, timestamp: u64) -> Result<u64, String> {
+    /// `View::load`, counted for debug metrics; callers keep writing `DonationsState::load`
+    pub async fn load(context: ViewStorageContext) -> Result<Self, ViewError> {
+        donations::metrics::count_load();
+        <Self as View>::load(context).await
+    }
+
+    pub fn is_admin(&self, owner: AccountOwner) -> bool {
+        *self.admin.get() == Some(owner)
+    }
+
+    /// First step of an admin transfer: the current admin names the next one.
+    /// A new proposal replaces any pending one.
+    pub fn propose_admin(&mut self, caller: AccountOwner, new_admin: AccountOwner) -> Result<(), StateError> {
+        if !self.is_admin(caller) {
+            return Err(StateError::Unauthorized("not admin"));
+        }
+        self.pending_admin.set(Some(new_admin));
+        Ok(())
+    }
+
+    /// Second step of an admin transfer: the proposed account takes over
+    pub fn accept_admin(&mut self, caller: AccountOwner) -> Result<(), StateError> {
+        if *self.pending_admin.get() != Some(caller) {
+            return Err(StateError::Unauthorized("not the proposed admin"));
+        }
+        self.admin.set(Some(caller));
+        self.pending_admin.set(None);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_donation(&mut self, from: AccountOwner, to: AccountOwner, amount: Amount, message: Option<String>, source_chain_id: Option<String>, source_donation_id: Option<u64>, to_chain_id: Option<String>, timestamp: u64, payment_app: Option<String>) -> Result<u64, StateError> {
+        self.adjust_donation_totals(from, to, amount, timestamp, true).await?;
+        self.add_to_donor_stats(from, to, amount, timestamp).await?;
         let id = *self.donation_counter.get() + 1;
         self.donation_counter.set(id);
-        let rec = DonationRecord { id, timestamp, from: from.clone(), to: to.clone(), amount, message, source_chain_id, to_chain_id };
-        self.donations.insert(&id, rec).map_err(|e: ViewError| format!("{:?}", e))?;
-        let mut r = self.donations_by_recipient.get(&to).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        // Sent from this chain to another: the source's id is this one
+        let source_donation_id = source_chain_id.as_ref().map(|_| source_donation_id.unwrap_or(id));
+        if let (Some(chain), Some(source_id)) = (&source_chain_id, source_donation_id) {
+            self.donations_by_source.insert(&(chain.clone(), source_id), id)?;
+        }
+        let rec = DonationRecord { id, timestamp, from, to, amount, message, source_chain_id, source_donation_id, to_chain_id, content_modified: false, payment_app, reply: None, replied_at: None };
+        self.donations.insert(&id, rec)?;
+        let mut r = self.donations_by_recipient.get(&to).await?.unwrap_or_default();
         r.push(id);
-        self.donations_by_recipient.insert(&to, r).map_err(|e: ViewError| format!("{:?}", e))?;
-        let mut d = self.donations_by_donor.get(&from).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        self.donations_by_recipient.insert(&to, r)?;
+        let mut d = self.donations_by_donor.get(&from).await?.unwrap_or_default();
         d.push(id);
-        self.donations_by_donor.insert(&from, d).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.donations_by_donor.insert(&from, d)?;
+        self.index_donation_day(id, from, to, timestamp).await?;
+        if self.day_index_start.get().is_none() {
+            self.day_index_start.set(Some(id));
+        }
+        Ok(id)
+    }
+
+    async fn index_donation_day(&mut self, id: u64, from: AccountOwner, to: AccountOwner, timestamp: u64) -> Result<(), StateError> {
+        let day = day_of(timestamp);
+        let mut ids = self.donation_days.get(&day).await?.unwrap_or_default();
+        ids.push(id);
+        self.donation_days.insert(&day, ids)?;
+        let mut ids = self.donation_days_by_recipient.get(&(to, day)).await?.unwrap_or_default();
+        ids.push(id);
+        self.donation_days_by_recipient.insert(&(to, day), ids)?;
+        let mut ids = self.donation_days_by_donor.get(&(from, day)).await?.unwrap_or_default();
+        ids.push(id);
+        self.donation_days_by_donor.insert(&(from, day), ids)?;
+        Ok(())
+    }
+
+    /// Record new events on a stream; a stream seen for the first time starts at `first`
+    pub async fn note_stream_head(&mut self, key: (String, String), first: u64, head: u64) -> Result<(), StateError> {
+        let cursor = self.stream_cursors.get(&key).await?;
+        if cursor.is_none() {
+            self.stream_cursors.insert(&key, first)?;
+        }
+        let known = self.stream_heads.get(&key).await?.unwrap_or(0);
+        if head > known && head > cursor.unwrap_or(first) {
+            self.stream_heads.insert(&key, head)?;
+        }
+        Ok(())
+    }
+
+    /// Streams with unapplied events as (key, cursor, head)
+    pub async fn pending_streams(&self) -> Result<Vec<((String, String), u64, u64)>, StateError> {
+        let mut out = Vec::new();
+        for (key, head) in self.stream_heads.index_values().await? {
+            let cursor = self.stream_cursors.get(&key).await?.unwrap_or(0);
+            out.push((key, cursor, head));
+        }
+        Ok(out)
+    }
+
+    /// Persist progress on a stream, dropping its head once caught up
+    pub async fn advance_stream_cursor(&mut self, key: (String, String), cursor: u64, head: u64) -> Result<(), StateError> {
+        self.stream_cursors.insert(&key, cursor)?;
+        if cursor >= head {
+            self.stream_heads.remove(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Owner's home chain as learned from registration; `None` when it isn't known
+    pub async fn resolve_chain_id(&self, owner: AccountOwner) -> Option<String> {
+        self.home_chains.get(&owner).await.ok().flatten()
+    }
+
+    /// Whether the owner registered from this chain (has a main chain mapping)
+    pub async fn is_registered(&self, owner: AccountOwner) -> bool {
+        self.subscriptions.contains_key(&owner).await.unwrap_or(false)
+    }
+
+    pub async fn is_holding_account(&self, owner: AccountOwner) -> bool {
+        self.holding_accounts.contains_key(&owner).await.unwrap_or(false)
+    }
+
+    pub async fn set_holding_account(&mut self, caller: AccountOwner, owner: AccountOwner, holding: bool) -> Result<(), StateError> {
+        if !self.is_admin(caller) {
+            return Err(StateError::Unauthorized("not admin"));
+        }
+        if holding {
+            self.holding_accounts.insert(&owner, true).map_err(StateError::Storage)
+        } else {
+            self.holding_accounts.remove(&owner).map_err(StateError::Storage)
+        }
+    }
+
+    pub async fn hold_donation(&mut self, id: u64) -> Result<(), StateError> {
+        self.pending_donations.insert(&id, true).map_err(StateError::Storage)
+    }
+
+    pub async fn list_pending_donations(&self) -> Result<Vec<DonationRecord>, StateError> {
+        let ids = self.pending_donations.indices().await?;
+        let mut out = Vec::new();
+        for id in ids {
+            if let Some(rec) = self.donations.get(&id).await? {
+                out.push(rec);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Highlight one of the caller's received donations, replacing any earlier pin
+    pub async fn pin_donation(&mut self, caller: AccountOwner, id: u64) -> Result<(), StateError> {
+        let rec = self.donations.get(&id).await?.ok_or(StateError::NotFound("Donation"))?;
+        if rec.to != caller {
+            return Err(StateError::Unauthorized("not the donation's recipient"));
+        }
+        self.pinned_donation.insert(&caller, id).map_err(StateError::Storage)
+    }
+
+    pub async fn is_following(&self, follower: AccountOwner, author: AccountOwner) -> Result<bool, StateError> {
+        match self.following.try_load_entry(&follower).await? {
+            Some(followed) => Ok(followed.contains_key(&author).await?),
+            None => Ok(false),
+        }
+    }
+
+    /// Make `follower` follow each of `authors` and return the ones newly followed; self-follows,
+    /// repeats and authors already followed are skipped. Everything is read before anything is
+    /// written, so an error leaves the follows unchanged.
+    pub async fn follow(&mut self, follower: AccountOwner, authors: Vec<AccountOwner>, now: u64) -> Result<Vec<AccountOwner>, StateError> {
+        let mut new = Vec::new();
+        for author in authors {
+            if author != follower && !new.contains(&author) && !self.is_following(follower, author).await? {
+                new.push(author);
+            }
+        }
+        for author in &new {
+            self.following.load_entry_mut(&follower).await?.insert(author, now)?;
+            self.followers.load_entry_mut(author).await?.insert(&follower, now)?;
+        }
+        Ok(new)
+    }
+
+    /// Stop `follower` following `author`; false when they weren't
+    pub async fn unfollow(&mut self, follower: AccountOwner, author: AccountOwner) -> Result<bool, StateError> {
+        if !self.is_following(follower, author).await? {
+            return Ok(false);
+        }
+        self.following.load_entry_mut(&follower).await?.remove(&author)?;
+        self.followers.load_entry_mut(&author).await?.remove(&follower)?;
+        Ok(true)
+    }
+
+    /// Who follows the author (`followers`) or whom the owner follows (`following`), in follow order
+    pub async fn list_follows(&self, follows: &CollectionView<AccountOwner, MapView<AccountOwner, u64>>, owner: AccountOwner) -> Result<Vec<AccountOwner>, StateError> {
+        let Some(entry) = follows.try_load_entry(&owner).await? else { return Ok(Vec::new()) };
+        let mut since = entry.index_values().await?;
+        since.sort_by_key(|(_, at)| *at);
+        Ok(since.into_iter().map(|(other, _)| other).collect())
+    }
+
+    /// Store the recipient's reply on a donation they received
+    pub async fn reply_to_donation(&mut self, caller: AccountOwner, id: u64, text: String, now: u64) -> Result<DonationRecord, StateError> {
+        let mut rec = self.donations.get(&id).await?.ok_or(StateError::NotFound("Donation"))?;
+        if rec.to != caller {
+            return Err(StateError::Unauthorized("not the donation's recipient"));
+        }
+        let text = text.trim().to_string();
+        if text.is_empty() || text.chars().count() > donations::MAX_DONATION_REPLY_CHARS {
+            return Err(StateError::Validation(format!("Reply must be 1-{} characters", donations::MAX_DONATION_REPLY_CHARS)));
+        }
+        rec.reply = Some(text);
+        rec.replied_at = Some(now);
+        self.donations.insert(&id, rec.clone())?;
+        Ok(rec)
+    }
+
+    /// Apply a reply made on another chain to the local copy of the donation, found by the chain
+    /// it was sent from and its id there; donations made on this chain are kept under their own id
+    pub async fn mirror_donation_reply(&mut self, source: (String, u64), local_chain_id: &str, to: AccountOwner, text: String, replied_at: u64) -> Result<(), StateError> {
+        let id = match self.donations_by_source.get(&source).await? {
+            Some(id) => id,
+            None if source.0 == local_chain_id => source.1,
+            None => return Ok(()),
+        };
+        if let Some(mut rec) = self.donations.get(&id).await? {
+            if rec.to == to {
+                rec.reply = Some(text);
+                rec.replied_at = Some(replied_at);
+                self.donations.insert(&id, rec)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The owner's pinned donation, if it is still one they received
+    pub async fn get_pinned_donation(&self, owner: AccountOwner) -> Result<Option<DonationRecord>, StateError> {
+        let Some(id) = self.pinned_donation.get(&owner).await? else {
+            return Ok(None);
+        };
+        let rec = self.donations.get(&id).await?;
+        Ok(rec.filter(|r| r.to == owner))
+    }
+
+    /// Move a held donation to its real recipient, re-indexing it under the new owner
+    pub async fn route_donation(&mut self, caller: AccountOwner, id: u64, to: AccountOwner, to_chain_id: String) -> Result<DonationRecord, StateError> {
+        if !self.is_admin(caller) {
+            return Err(StateError::Unauthorized("not admin"));
+        }
+        if !self.pending_donations.contains_key(&id).await? {
+            return Err(StateError::Validation("Donation is not pending".to_string()));
+        }
+        let mut rec = self.donations.get(&id).await?.ok_or(StateError::NotFound("Donation"))?;
+        self.adjust_donation_totals(rec.from, rec.to, rec.amount, rec.timestamp, false).await?;
+        self.adjust_donation_totals(rec.from, to, rec.amount, rec.timestamp, true).await?;
+        self.seed_donor_stats(rec.from).await?;
+        let tallies = self.recipient_tallies.load_entry_mut(&rec.from).await?;
+        if let Some(mut tally) = tallies.get(&rec.to).await? {
+            tally.remove(rec.amount);
+            if tally.count == 0 {
+                tallies.remove(&rec.to)?;
+            } else {
+                tallies.insert(&rec.to, tally)?;
+            }
+        }
+        let mut tally = tallies.get(&to).await?.unwrap_or_default();
+        tally.add(rec.amount);
+        tallies.insert(&to, tally)?;
+
+        let mut r = self.donations_by_recipient.get(&rec.to).await?.unwrap_or_default();
+        r.retain(|x| *x != id);
+        if r.is_empty() {
+            self.donations_by_recipient.remove(&rec.to)?;
+        } else {
+            self.donations_by_recipient.insert(&rec.to, r)?;
+        }
+        let mut r = self.donations_by_recipient.get(&to).await?.unwrap_or_default();
+        r.push(id);
+        self.donations_by_recipient.insert(&to, r)?;
+        let day = day_of(rec.timestamp);
+        let mut r = self.donation_days_by_recipient.get(&(rec.to, day)).await?.unwrap_or_default();
+        r.retain(|x| *x != id);
+        if r.is_empty() {
+            self.donation_days_by_recipient.remove(&(rec.to, day))?;
+        } else {
+            self.donation_days_by_recipient.insert(&(rec.to, day), r)?;
+        }
+        let mut r = self.donation_days_by_recipient.get(&(to, day)).await?.unwrap_or_default();
+        r.push(id);
+        self.donation_days_by_recipient.insert(&(to, day), r)?;
+
+        rec.to = to;
+        rec.to_chain_id = Some(to_chain_id);
+        self.donations.insert(&id, rec.clone())?;
+        self.pending_donations.remove(&id)?;
+        Ok(rec)
+    }
+
+    pub fn get_mint_limits(&self) -> MintLimits {
+        self.mint_limits.get().unwrap_or_default()
+    }
+
+    pub fn set_mint_limits(&mut self, caller: AccountOwner, limits: MintLimits) -> Result<(), StateError> {
+        if !self.is_admin(caller) {
+            return Err(StateError::Unauthorized("not admin"));
+        }
+        if limits.max_per_mint > limits.supply_cap {
+            return Err(StateError::Validation("Per-mint maximum exceeds supply cap".to_string()));
+        }
+        self.mint_limits.set(Some(limits));
+        Ok(())
+    }
+
+    /// Add a mint to `total_minted`, rejecting amounts above the per-mint maximum or the supply cap
+    pub fn record_mint(&mut self, amount: Amount) -> Result<(), StateError> {
+        let limits = self.get_mint_limits();
+        if amount > limits.max_per_mint {
+            return Err(StateError::Validation(format!("Mint exceeds maximum of {}", limits.max_per_mint)));
+        }
+        let total = self.total_minted.get().try_add(amount).map_err(|e| StateError::Validation(format!("{:?}", e)))?;
+        if total > limits.supply_cap {
+            return Err(StateError::Validation(format!("Mint exceeds supply cap of {}", limits.supply_cap)));
+        }
+        self.total_minted.set(total);
+        Ok(())
+    }
+
+    /// Collect the views selected by `scope`, in key order
+    pub async fn snapshot_data(&self, scope: SnapshotScope) -> Result<SnapshotData, StateError> {
+        let mut data = SnapshotData { scope, ..SnapshotData::default() };
+        if scope.profiles {
+            data.profiles = self.profiles.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        }
+        if scope.products {
+            data.products = self.products.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        }
+        if scope.purchases {
+            data.purchases = self.purchases.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        }
+        if scope.posts {
+            data.posts = self.posts.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        }
+        if scope.subscriptions {
+            data.subscriptions = self.content_subscriptions.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        }
+        if scope.donations {
+            data.donations = self.donations.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        }
+        Ok(data)
+    }
+
+    pub async fn record_snapshot(&mut self, mut meta: SnapshotMeta) -> Result<u64, StateError> {
+        let id = self.snapshots.count().await? as u64 + 1;
+        meta.id = id;
+        self.snapshots.insert(&id, meta)?;
         Ok(id)
     }
 
-    pub async fn set_name(&mut self, owner: AccountOwner, name: String) -> Result<(), String> {
-        let mut p = self.profiles.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or(Profile { 
-            owner: owner.clone(), 
+    /// Purchases, subscriptions and donations with timestamps in [from, to), oldest first,
+    /// leaving out the first `skip` records at `from`. Reads the day indexes one day at a time
+    /// and stops once the cap is passed, so records from before the indexes are not exported.
+    /// Past the cap the window is cut at a timestamp boundary; only a single timestamp holding
+    /// more than the cap is split. Returns where the next export starts and how many to skip there.
+    pub async fn activity_window(&self, from: u64, to: u64, skip: u32) -> Result<(Vec<ActivityRecord>, Option<(u64, u32)>), StateError> {
+        let in_window = |ts: u64| ts >= from && ts < to;
+        let skip = skip as usize;
+        let mut records = Vec::new();
+        for day in day_of(from)..=day_of(to.saturating_sub(1)) {
+            if records.len() > skip + donations::MAX_EXPORT_RECORDS {
+                break;
+            }
+            for id in self.purchase_days.get(&day).await?.unwrap_or_default() {
+                if let Some(p) = self.purchases.get(&id).await?.filter(|p| in_window(p.timestamp)) {
+                    records.push(ActivityRecord::Purchase { id: p.id, product_id: p.product_id, buyer: p.buyer, seller: p.seller, amount: p.amount, timestamp: p.timestamp });
+                }
+            }
+            for id in self.subscription_days.get(&day).await?.unwrap_or_default() {
+                if let Some(s) = self.content_subscriptions.get(&id).await?.filter(|s| in_window(s.start_timestamp)) {
+                    records.push(ActivityRecord::Subscription { id: s.id, subscriber: s.subscriber, author: s.author, price: s.price, timestamp: s.start_timestamp });
+                }
+            }
+            for id in self.donation_days.get(&day).await?.unwrap_or_default() {
+                if let Some(d) = self.donations.get(&id).await?.filter(|d| in_window(d.timestamp)) {
+                    records.push(ActivityRecord::Donation { id: d.id, from: d.from, to: d.to, amount: d.amount, timestamp: d.timestamp });
+                }
+            }
+        }
+        records.sort_by_key(ActivityRecord::order_key);
+        let skipped = records.iter().take(skip).take_while(|r| r.timestamp() == from).count();
+        records.drain(..skipped);
+
+        if records.len() <= donations::MAX_EXPORT_RECORDS {
+            return Ok((records, None));
+        }
+        let cutoff = records[donations::MAX_EXPORT_RECORDS].timestamp();
+        let kept = records.iter().take_while(|r| r.timestamp() < cutoff).count();
+        if kept > 0 {
+            records.truncate(kept);
+            return Ok((records, Some((cutoff, 0))));
+        }
+        // A single timestamp holds more than the cap: resume inside it
+        records.truncate(donations::MAX_EXPORT_RECORDS);
+        let already = if cutoff == from { skipped } else { 0 };
+        Ok((records, Some((cutoff, (already + donations::MAX_EXPORT_RECORDS) as u32))))
+    }
+
+    pub async fn record_activity_export(&mut self, mut export: ActivityExport) -> Result<ActivityExport, StateError> {
+        export.id = self.activity_exports.count().await? as u64 + 1;
+        self.activity_exports.insert(&export.id, export.clone())?;
+        Ok(export)
+    }
+
+    /// Repopulate the views in the snapshot's scope. Refuses to run if any of them holds data.
+    pub async fn restore_snapshot(&mut self, data: SnapshotData) -> Result<(), StateError> {
+        let scope = data.scope;
+        let occupied = [
+            (scope.profiles, "profiles", self.profiles.count().await),
+            (scope.products, "products", self.products.count().await),
+            (scope.purchases, "purchases", self.purchases.count().await),
+            (scope.posts, "posts", self.posts.count().await),
+            (scope.subscriptions, "subscriptions", self.content_subscriptions.count().await),
+            (scope.donations, "donations", self.donations.count().await),
+        ];
+        for (selected, name, count) in occupied {
+            if selected && count? > 0 {
+                return Err(StateError::Validation(format!("Refusing to overwrite non-empty {}", name)));
+            }
+        }
+
+        for p in data.profiles {
+            self.profiles.insert(&p.owner.clone(), p)?;
+        }
+        for p in data.products {
+            self.create_product(p).await?;
+        }
+        for p in data.purchases {
+            self.record_purchase(p).await?;
+        }
+        for p in data.posts {
+            self.create_post(p).await?;
+        }
+        for s in data.subscriptions {
+            self.create_subscription(s).await?;
+        }
+        if self.day_index_start.get().is_none() && self.donations.count().await? == 0 {
+            self.day_index_start.set(Some(0));
+        }
+        let mut restored = std::collections::BTreeSet::new();
+        for rec in data.donations {
+            let (id, from, to, timestamp) = (rec.id, rec.from, rec.to, rec.timestamp);
+            restored.insert(id);
+            self.adjust_donation_totals(from, to, rec.amount, timestamp, true).await?;
+            self.add_to_donor_stats(from, to, rec.amount, timestamp).await?;
+            self.donations.insert(&id, rec)?;
+            let mut r = self.donations_by_recipient.get(&to).await?.unwrap_or_default();
+            r.push(id);
+            self.donations_by_recipient.insert(&to, r)?;
+            let mut d = self.donations_by_donor.get(&from).await?.unwrap_or_default();
+            d.push(id);
+            self.donations_by_donor.insert(&from, d)?;
+            self.index_donation_day(id, from, to, timestamp).await?;
+            if id > *self.donation_counter.get() {
+                self.donation_counter.set(id);
+            }
+        }
+        if !restored.is_empty() {
+            self.donation_id_gaps.set(*self.donation_counter.get() - restored.len() as u64);
+        }
+        Ok(())
+    }
+
+    /// Count one truncation/stripping of inbound content of the given kind
+    /// (e.g. "donation_message", "order_data") on the day of `timestamp`.
+    /// Handlers that adjust inbound text call this and set `content_modified` on the stored record.
+    pub async fn record_content_adjustment(&mut self, kind: &str, timestamp: u64) -> Result<(), StateError> {
+        let key = (kind.to_string(), (timestamp / MICROS_PER_DAY) as u32);
+        let count = self.content_adjustments.get(&key).await?.unwrap_or_default();
+        self.content_adjustments.insert(&key, count + 1).map_err(StateError::Storage)
+    }
+
+    /// Flag a stored donation whose message was adjusted on receipt
+    pub async fn mark_donation_modified(&mut self, id: u64) -> Result<(), StateError> {
+        if let Some(mut rec) = self.donations.get(&id).await? {
+            rec.content_modified = true;
+            self.donations.insert(&id, rec)?;
+        }
+        Ok(())
+    }
+
+    /// The name a profile shows: "anon" when left empty
+    pub fn normalize_name(name: String) -> String {
+        if name.is_empty() { "anon".to_string() } else { name }
+    }
+
+    /// Profile setters return whether anything changed, so callers can skip no-op events
+    pub async fn set_name(&mut self, owner: AccountOwner, name: String) -> Result<bool, StateError> {
+        let name = Self::normalize_name(name);
+        let existing = self.profiles.get(&owner).await?;
+        if existing.as_ref().is_some_and(|p| p.name == name) {
+            return Ok(false);
+        }
+        let mut p = existing.unwrap_or(Profile { 
+            owner, 
             name: "anon".to_string(), 
             bio: String::new(), 
             socials: Vec::new(),
             avatar_hash: None,
             header_hash: None,
         });
-        p.name = if name.is_empty() { "anon".to_string() } else { name };
-        self.profiles.insert(&owner, p).map_err(|e: ViewError| format!("{:?}", e))
+        p.name = name;
+        self.profiles.insert(&owner, p)?;
+        Ok(true)
     }
 
-    pub async fn set_bio(&mut self, owner: AccountOwner, bio: String) -> Result<(), String> {
-        let mut p = self.profiles.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or(Profile { 
-            owner: owner.clone(), 
+    pub async fn set_bio(&mut self, owner: AccountOwner, bio: String) -> Result<bool, StateError> {
+        let existing = self.profiles.get(&owner).await?;
+        if existing.as_ref().is_some_and(|p| p.bio == bio) {
+            return Ok(false);
+        }
+        let mut p = existing.unwrap_or(Profile { 
+            owner, 
             name: "anon".to_string(), 
             bio: String::new(), 
             socials: Vec::new(),
@@ -70,12 +720,17 @@ impl DonationsState {
             header_hash: None,
         });
         p.bio = bio;
-        self.profiles.insert(&owner, p).map_err(|e: ViewError| format!("{:?}", e))
+        self.profiles.insert(&owner, p)?;
+        Ok(true)
     }
 
-    pub async fn set_social(&mut self, owner: AccountOwner, name: String, url: String) -> Result<(), String> {
-        let mut p = self.profiles.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or(Profile { 
-            owner: owner.clone(), 
+    pub async fn set_social(&mut self, owner: AccountOwner, name: String, url: String) -> Result<bool, StateError> {
+        let existing = self.profiles.get(&owner).await?;
+        if existing.as_ref().is_some_and(|p| p.socials.iter().any(|s| s.name == name && s.url == url)) {
+            return Ok(false);
+        }
+        let mut p = existing.unwrap_or(Profile { 
+            owner, 
             name: "anon".to_string(), 
             bio: String::new(), 
             socials: Vec::new(),
@@ -85,12 +740,49 @@ impl DonationsState {
         let mut socials = p.socials;
         if let Some(s) = socials.iter_mut().find(|s| s.name == name) { s.url = url; } else { socials.push(SocialLink { name, url }); }
         p.socials = socials;
-        self.profiles.insert(&owner, p).map_err(|e: ViewError| format!("{:?}", e))
+        self.profiles.insert(&owner, p)?;
+        Ok(true)
+    }
+
+    /// Stamp a profile change, rejecting one within the cooldown of the previous change
+    pub async fn note_profile_update(&mut self, owner: AccountOwner, now: u64) -> Result<(), StateError> {
+        if let Some(last) = self.profile_updated_at.get(&owner).await? {
+            if now < last.saturating_add(PROFILE_UPDATE_COOLDOWN_MICROS) {
+                return Err(StateError::Validation(format!("Profile updated too recently; wait {} seconds between updates", PROFILE_UPDATE_COOLDOWN_MICROS / 1_000_000)));
+            }
+        }
+        self.profile_updated_at.insert(&owner, now).map_err(StateError::Storage)
+    }
+
+    /// Reject an operation from an owner who already used up the current window's event budget
+    pub async fn check_event_rate(&self, owner: AccountOwner, now: u64) -> Result<(), StateError> {
+        match self.event_rate.get(&owner).await? {
+            Some((start, count)) if now < start.saturating_add(EVENT_RATE_WINDOW_MICROS) && count >= MAX_EVENTS_PER_WINDOW => {
+                Err(StateError::Validation(format!("Too many events; at most {} every {} seconds", MAX_EVENTS_PER_WINDOW, EVENT_RATE_WINDOW_MICROS / 1_000_000)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Charge `emitted` events to the owner's current window, opening a new one once it ran out
+    pub async fn note_events(&mut self, owner: AccountOwner, now: u64, emitted: u32) -> Result<(), StateError> {
+        if emitted == 0 {
+            return Ok(());
+        }
+        let (start, count) = match self.event_rate.get(&owner).await? {
+            Some((start, count)) if now < start.saturating_add(EVENT_RATE_WINDOW_MICROS) => (start, count),
+            _ => (now, 0),
+        };
+        self.event_rate.insert(&owner, (start, count.saturating_add(emitted))).map_err(StateError::Storage)
     }
 
-    pub async fn set_avatar(&mut self, owner: AccountOwner, hash: String) -> Result<(), String> {
-        let mut p = self.profiles.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or(Profile { 
-            owner: owner.clone(), 
+    pub async fn set_avatar(&mut self, owner: AccountOwner, hash: String) -> Result<bool, StateError> {
+        let existing = self.profiles.get(&owner).await?;
+        if existing.as_ref().is_some_and(|p| p.avatar_hash.as_deref() == Some(hash.as_str())) {
+            return Ok(false);
+        }
+        let mut p = existing.unwrap_or(Profile { 
+            owner, 
             name: "anon".to_string(), 
             bio: String::new(), 
             socials: Vec::new(),
@@ -98,248 +790,1452 @@ impl DonationsState {
             header_hash: None,
         });
         p.avatar_hash = Some(hash);
-        self.profiles.insert(&owner, p).map_err(|e: ViewError| format!("{:?}", e))
+        self.profiles.insert(&owner, p)?;
+        Ok(true)
+    }
+
+    pub async fn set_header(&mut self, owner: AccountOwner, hash: String) -> Result<bool, StateError> {
+        let existing = self.profiles.get(&owner).await?;
+        if existing.as_ref().is_some_and(|p| p.header_hash.as_deref() == Some(hash.as_str())) {
+            return Ok(false);
+        }
+        let mut p = existing.unwrap_or(Profile { 
+            owner, 
+            name: "anon".to_string(), 
+            bio: String::new(), 
+            socials: Vec::new(),
+            avatar_hash: None,
+            header_hash: None,
+        });
+        p.header_hash = Some(hash);
+        self.profiles.insert(&owner, p)?;
+        Ok(true)
+    }
+
+    pub async fn is_closed(&self, owner: AccountOwner) -> bool {
+        self.closed_accounts.contains_key(&owner).await.unwrap_or(false)
+    }
+
+    /// Tombstone an account: drop its profile, products, featured list, subscription
+    /// offer and donation goal, keeping donation and purchase records. Returns the deleted product ids.
+    pub async fn close_account(&mut self, owner: AccountOwner, now: u64) -> Result<Vec<String>, StateError> {
+        if self.is_closed(owner).await {
+            return Err(StateError::Validation("Account already closed".to_string()));
+        }
+        let product_ids = self.products_by_author.get(&owner).await?.unwrap_or_default();
+        for product_id in &product_ids {
+            self.delete_product(product_id, owner).await?;
+        }
+        self.products_by_author.remove(&owner)?;
+        self.featured_products.remove(&owner)?;
+        self.product_order.remove(&owner)?;
+        self.subscription_prices.remove(&owner)?;
+        self.donation_goals.remove(&owner)?;
+        self.goal_received.remove(&owner)?;
+        self.profiles.remove(&owner)?;
+        self.profile_updated_at.remove(&owner)?;
+        self.closed_accounts.insert(&owner, now)?;
+        Ok(product_ids)
+    }
+
+    pub async fn get_profile(&self, owner: AccountOwner) -> Result<Option<Profile>, StateError> {
+        self.profiles.get(&owner).await.map_err(StateError::Storage)
+    }
+
+    /// Sum of all donations the owner received, saturating at `Amount::MAX`
+    pub async fn total_received(&self, owner: AccountOwner) -> Result<Amount, StateError> {
+        if let Some(total) = self.received_totals.get(&owner).await? {
+            return Ok(total);
+        }
+        let ids = self.donations_by_recipient.get(&owner).await?.unwrap_or_default();
+        let mut amounts = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(r) = self.donations.get(&id).await? { amounts.push(r.amount); }
+        }
+        Ok(donations::amount_math::sum_tracking_overflow(amounts).unwrap_or_else(|saturated| saturated))
+    }
+
+    /// Total given to `owner` by each donor; the aggregates are kept alongside `received_totals`
+    pub async fn donor_totals_for(&self, owner: AccountOwner) -> Result<std::collections::BTreeMap<AccountOwner, Amount>, StateError> {
+        if self.received_totals.contains_key(&owner).await? {
+            let Some(totals) = self.donor_totals.try_load_entry(&owner).await? else { return Ok(Default::default()) };
+            return Ok(totals.index_values().await?.into_iter().collect());
+        }
+        let mut totals = std::collections::BTreeMap::new();
+        for r in self.list_donations_by_recipient(owner).await? {
+            let entry = totals.entry(r.from).or_insert(Amount::ZERO);
+            *entry = donations::amount_math::add_tracking_overflow(*entry, r.amount).unwrap_or_else(|saturated| saturated);
+        }
+        Ok(totals)
+    }
+
+    /// The donor's running figures; donors whose donations predate them are seeded from their records
+    pub async fn donor_stats_for(&self, owner: AccountOwner) -> Result<DonorStats, StateError> {
+        if let Some(stats) = self.donor_stats.get(&owner).await? {
+            return Ok(stats);
+        }
+        let mut stats = DonorStats::default();
+        for r in self.list_donations_by_donor(owner).await? {
+            stats.add(r.amount, r.timestamp);
+        }
+        Ok(stats)
+    }
+
+    /// The donor's giving per recipient, kept alongside `donor_stats` and seeded the same way
+    pub async fn recipient_tallies_for(&self, owner: AccountOwner) -> Result<std::collections::BTreeMap<AccountOwner, RecipientTally>, StateError> {
+        if self.donor_stats.contains_key(&owner).await? {
+            let Some(tallies) = self.recipient_tallies.try_load_entry(&owner).await? else { return Ok(Default::default()) };
+            return Ok(tallies.index_values().await?.into_iter().collect());
+        }
+        let mut tallies = std::collections::BTreeMap::<AccountOwner, RecipientTally>::new();
+        for r in self.list_donations_by_donor(owner).await? {
+            tallies.entry(r.to).or_default().add(r.amount);
+        }
+        Ok(tallies)
+    }
+
+    /// Store the running figures of a donor whose donations predate them
+    async fn seed_donor_stats(&mut self, owner: AccountOwner) -> Result<(), StateError> {
+        if self.donor_stats.contains_key(&owner).await? {
+            return Ok(());
+        }
+        let stats = self.donor_stats_for(owner).await?;
+        let seeded = self.recipient_tallies_for(owner).await?;
+        let tallies = self.recipient_tallies.load_entry_mut(&owner).await?;
+        for (recipient, tally) in seeded {
+            tallies.insert(&recipient, tally)?;
+        }
+        self.donor_stats.insert(&owner, stats).map_err(StateError::Storage)
+    }
+
+    /// Count a donation in the donor's figures. Like the recipient aggregates, call it before
+    /// the record is indexed under `from`.
+    async fn add_to_donor_stats(&mut self, from: AccountOwner, to: AccountOwner, amount: Amount, timestamp: u64) -> Result<(), StateError> {
+        self.seed_donor_stats(from).await?;
+        let mut stats = self.donor_stats_for(from).await?;
+        stats.add(amount, timestamp);
+        self.donor_stats.insert(&from, stats)?;
+        let tallies = self.recipient_tallies.load_entry_mut(&from).await?;
+        let mut tally = tallies.get(&to).await?.unwrap_or_default();
+        tally.add(amount);
+        tallies.insert(&to, tally).map_err(StateError::Storage)
+    }
+
+    /// Add (or with `credit == false`, take back) a donation in the recipient's aggregates,
+    /// their goal's progress included when the donation was made after the goal was set.
+    /// Call before the record is indexed under `to`: recipients whose donations predate the
+    /// aggregates are seeded from their records on first use.
+    async fn adjust_donation_totals(&mut self, from: AccountOwner, to: AccountOwner, amount: Amount, timestamp: u64, credit: bool) -> Result<(), StateError> {
+        let received = self.total_received(to).await?;
+        if !self.received_totals.contains_key(&to).await? {
+            let seeded = self.donor_totals_for(to).await?;
+            let donors = self.donor_totals.load_entry_mut(&to).await?;
+            for (donor, given) in seeded {
+                donors.insert(&donor, given)?;
+            }
+        }
+        let donors = self.donor_totals.load_entry_mut(&to).await?;
+        let given = donors.get(&from).await?.unwrap_or(Amount::ZERO);
+        let step = if credit { donations::amount_math::add_tracking_overflow } else { donations::amount_math::sub_tracking_underflow };
+        let (received, given) = (step(received, amount).unwrap_or_else(|bound| bound), step(given, amount).unwrap_or_else(|bound| bound));
+        if given == Amount::ZERO {
+            donors.remove(&from)?;
+        } else {
+            donors.insert(&from, given)?;
+        }
+        self.received_totals.insert(&to, received)?;
+        if self.donation_goals.get(&to).await?.is_some_and(|goal| timestamp >= goal.created_at) {
+            let toward_goal = step(self.goal_received(to).await?, amount).unwrap_or_else(|bound| bound);
+            self.goal_received.insert(&to, toward_goal)?;
+        }
+        Ok(())
+    }
+
+    pub async fn list_donations_by_recipient(&self, owner: AccountOwner) -> Result<Vec<DonationRecord>, StateError> {
+        let ids = self.donations_by_recipient.get(&owner).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids { if let Some(r) = self.donations.get(&id).await? { res.push(r); } }
+        Ok(res)
+    }
+
+    pub async fn list_donations_by_donor(&self, owner: AccountOwner) -> Result<Vec<DonationRecord>, StateError> {
+        let ids = self.donations_by_donor.get(&owner).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids { if let Some(r) = self.donations.get(&id).await? { res.push(r); } }
+        Ok(res)
+    }
+
+    /// A page of a recipient's donations, hydrating only the requested ids, and the recipient's total count
+    pub async fn list_donations_by_recipient_page(&self, owner: AccountOwner, page: DonationPageQuery) -> Result<(Vec<DonationRecord>, u32), StateError> {
+        let ids = self.donations_by_recipient.get(&owner).await?.unwrap_or_default();
+        Ok((self.hydrate_donations(page.select(&ids).into_iter()).await?, ids.len() as u32))
+    }
+
+    /// A page of a donor's donations, hydrating only the requested ids, and the donor's total count
+    pub async fn list_donations_by_donor_page(&self, owner: AccountOwner, page: DonationPageQuery) -> Result<(Vec<DonationRecord>, u32), StateError> {
+        let ids = self.donations_by_donor.get(&owner).await?.unwrap_or_default();
+        Ok((self.hydrate_donations(page.select(&ids).into_iter()).await?, ids.len() as u32))
+    }
+
+    /// A page of all donations on this chain and their total count. Ids run from 1 to the counter, so the
+    /// page is read by walking them from the cursor instead of listing every key.
+    pub async fn list_all_donations_page(&self, page: DonationPageQuery) -> Result<(Vec<DonationRecord>, u32), StateError> {
+        let last = *self.donation_counter.get();
+        let ids: Box<dyn Iterator<Item = u64> + Send> = match (page.newest_first, page.after_id) {
+            (true, after) => Box::new((1..=after.map_or(last, |id| id.saturating_sub(1)).min(last)).rev()),
+            (false, after) => Box::new(after.map_or(1, |id| id.saturating_add(1)).max(1)..=last),
+        };
+        let (mut res, mut to_skip) = (Vec::new(), page.offset);
+        for id in ids {
+            if res.len() >= page.limit {
+                break;
+            }
+            let Some(r) = self.donations.get(&id).await? else { continue };
+            if to_skip > 0 {
+                to_skip -= 1;
+                continue;
+            }
+            res.push(r);
+        }
+        Ok((res, (last - *self.donation_id_gaps.get()) as u32))
+    }
+
+    /// One side's donations with `from <= timestamp <= to` (either bound optional), oldest first.
+    /// Reads only the days in range when the day indexes cover all of the side's donations
+    /// and the range is shorter than the list; otherwise falls back to scanning it.
+    pub async fn list_donations_between(&self, side: DonationSide, from: Option<u64>, to: Option<u64>) -> Result<Vec<DonationRecord>, StateError> {
+        let (from, to) = (from.unwrap_or(0), to.unwrap_or(u64::MAX));
+        if from > to {
+            return Ok(Vec::new());
+        }
+        let side_ids = match side {
+            DonationSide::Recipient(owner) => Some(self.donations_by_recipient.get(&owner).await?.unwrap_or_default()),
+            DonationSide::Donor(owner) => Some(self.donations_by_donor.get(&owner).await?.unwrap_or_default()),
+            // The chain's donations are the ids from 1 to the counter, so they need no listing
+            DonationSide::All => None,
+        };
+        let start = *self.day_index_start.get();
+        let (len, indexed) = match &side_ids {
+            Some(ids) => (ids.len() as u64, start.is_some_and(|start| ids.iter().all(|id| *id >= start))),
+            None => (*self.donation_counter.get(), start.is_some_and(|start| start <= 1)),
+        };
+        let (first_day, last_day) = (day_of(from), day_of(to));
+        let candidates = if indexed && ((last_day - first_day) as u64) < len {
+            let mut in_range = Vec::new();
+            for day in first_day..=last_day {
+                let day_ids = match side {
+                    DonationSide::Recipient(owner) => self.donation_days_by_recipient.get(&(owner, day)).await?,
+                    DonationSide::Donor(owner) => self.donation_days_by_donor.get(&(owner, day)).await?,
+                    DonationSide::All => self.donation_days.get(&day).await?,
+                };
+                in_range.extend(day_ids.unwrap_or_default());
+            }
+            in_range
+        } else {
+            side_ids.unwrap_or_else(|| (1..=len).collect())
+        };
+        let mut records = self.hydrate_donations(candidates.into_iter()).await?;
+        records.retain(|r| r.timestamp >= from && r.timestamp <= to);
+        records.sort_by_key(|r| (r.timestamp, r.id));
+        Ok(records)
+    }
+
+    /// A page of [`Self::list_donations_between`] and its total count.
+    /// `page.newest_first` is ignored and the cursor is a position in oldest-first order.
+    pub async fn list_donations_between_page(&self, side: DonationSide, from: Option<u64>, to: Option<u64>, page: DonationPageQuery) -> Result<(Vec<DonationRecord>, u32), StateError> {
+        let records = self.list_donations_between(side, from, to).await?;
+        let total = records.len() as u32;
+        let start = page.after_id.and_then(|after| records.iter().position(|r| r.id == after)).map_or(0, |i| i + 1);
+        Ok((records.into_iter().skip(start + page.offset).take(page.limit).collect(), total))
+    }
+
+    async fn hydrate_donations(&self, ids: impl Iterator<Item = u64>) -> Result<Vec<DonationRecord>, StateError> {
+        let mut res = Vec::new();
+        for id in ids { if let Some(r) = self.donations.get(&id).await? { res.push(r); } }
+        Ok(res)
+    }
+
+    pub async fn count_donations_by_recipient(&self, owner: AccountOwner) -> Result<u32, StateError> {
+        Ok(self.donations_by_recipient.get(&owner).await?.map_or(0, |ids| ids.len() as u32))
+    }
+
+    pub async fn count_donations_by_donor(&self, owner: AccountOwner) -> Result<u32, StateError> {
+        Ok(self.donations_by_donor.get(&owner).await?.map_or(0, |ids| ids.len() as u32))
+    }
+
+    pub async fn get_commitments(&self, owner: AccountOwner) -> Result<Vec<Commitment>, StateError> {
+        Ok(self.commitments.get(&owner).await?.unwrap_or_default())
+    }
+
+    pub async fn committed_total(&self, owner: AccountOwner) -> Result<Amount, StateError> {
+        let amounts = self.get_commitments(owner).await?.into_iter().map(|c| c.amount);
+        Ok(donations::amount_math::sum_tracking_overflow(amounts).unwrap_or_else(|saturated| saturated))
+    }
+
+    pub async fn add_commitment(&mut self, owner: AccountOwner, commitment: Commitment) -> Result<(), StateError> {
+        let mut list = self.get_commitments(owner).await?;
+        if list.iter().any(|c| c.kind == commitment.kind && c.reference_id == commitment.reference_id) {
+            return Err(StateError::Validation("Commitment already exists".to_string()));
+        }
+        list.push(commitment);
+        self.commitments.insert(&owner, list).map_err(StateError::Storage)
+    }
+
+    /// Remove a commitment, returning the amount it locked
+    pub async fn release_commitment(&mut self, owner: AccountOwner, kind: &str, reference_id: &str) -> Result<Option<Amount>, StateError> {
+        let mut list = self.get_commitments(owner).await?;
+        let Some(pos) = list.iter().position(|c| c.kind == kind && c.reference_id == reference_id) else {
+            return Ok(None);
+        };
+        let released = list.remove(pos);
+        if list.is_empty() {
+            self.commitments.remove(&owner)?;
+        } else {
+            self.commitments.insert(&owner, list)?;
+        }
+        Ok(Some(released.amount))
+    }
+
+    /// Total donated to an author per donor in a month, with the donor's last known chain
+    pub async fn monthly_donor_totals(&self, author: AccountOwner, month_key: u32) -> Result<Vec<(AccountOwner, Amount, Option<String>)>, StateError> {
+        let mut totals: std::collections::BTreeMap<AccountOwner, (Amount, Option<String>)> = std::collections::BTreeMap::new();
+        for r in self.list_donations_by_recipient(author).await? {
+            if donations::month_key_of(r.timestamp) != month_key {
+                continue;
+            }
+            let entry = totals.entry(r.from).or_insert((Amount::ZERO, None));
+            entry.0 = donations::amount_math::add_tracking_overflow(entry.0, r.amount).unwrap_or_else(|saturated| saturated);
+            if r.source_chain_id.is_some() {
+                entry.1 = r.source_chain_id;
+            }
+        }
+        Ok(totals.into_iter().map(|(donor, (amount, chain))| (donor, amount, chain)).collect())
+    }
+
+    pub async fn commit_supporter_draw(&mut self, author: AccountOwner, month_key: u32, commitment: String) -> Result<(), StateError> {
+        let key = (author, month_key);
+        if self.monthly_winners.contains_key(&key).await? {
+            return Err(StateError::Validation("Supporter of the month already drawn".to_string()));
+        }
+        if self.supporter_draw_commitments.contains_key(&key).await? {
+            return Err(StateError::Validation("Supporter draw already committed".to_string()));
+        }
+        self.supporter_draw_commitments.insert(&key, commitment).map_err(StateError::Storage)
+    }
+
+    /// Reveal the committed seed and draw a donor, each donated atto being one ticket.
+    /// Returns the winner and their last known chain.
+    pub async fn draw_supporter_of_month(&mut self, author: AccountOwner, month_key: u32, seed: String) -> Result<(AccountOwner, Option<String>), StateError> {
+        let key = (author, month_key);
+        if self.monthly_winners.contains_key(&key).await? {
+            return Err(StateError::Validation("Supporter of the month already drawn".to_string()));
+        }
+        let commitment = self.supporter_draw_commitments.get(&key).await?.ok_or_else(|| StateError::Validation("No commitment for this month".to_string()))?;
+        let draw_seed = donations::DrawSeed { author, month_key, seed };
+        if draw_seed.commitment() != commitment {
+            return Err(StateError::Validation("Seed does not match commitment".to_string()));
+        }
+        let totals = self.monthly_donor_totals(author, month_key).await?;
+        let weights: Vec<(usize, u128)> = totals.iter().enumerate().map(|(i, (_, amount, _))| (i, amount.to_attos())).collect();
+        let index = donations::pick_weighted(&weights, draw_seed.ticket()).ok_or_else(|| StateError::Validation("No donations in this month".to_string()))?;
+        let (winner, _, chain) = totals[index].clone();
+        self.monthly_winners.insert(&key, winner)?;
+        Ok((winner, chain))
+    }
+
+    // Validation methods for flexible products
+    pub fn validate_custom_fields(fields: &CustomFields) -> Result<(), StateError> {
+        if fields.len() > 20 {
+            return Err(StateError::Validation("Maximum 20 custom fields allowed".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn validate_order_form(form: &[OrderFormField]) -> Result<(), StateError> {
+        if form.len() > 20 {
+            return Err(StateError::Validation("Maximum 20 order form fields allowed".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Order data must answer the product's order form: nothing for an empty form,
+    /// otherwise only the form's keys, with every required field filled in
+    pub fn validate_order_data(form: &[OrderFormField], data: &OrderResponses) -> Result<(), StateError> {
+        if form.is_empty() {
+            if !data.is_empty() {
+                return Err(StateError::Validation("Product has no order form; order data must be empty".to_string()));
+            }
+            return Ok(());
+        }
+        if let Some(key) = data.keys().find(|key| !form.iter().any(|f| &f.key == *key)) {
+            return Err(StateError::Validation(format!("Unknown order form field: {}", key)));
+        }
+        Self::validate_order_responses(form, data).map_err(StateError::Validation)
+    }
+
+    /// Every required field of the form has a non-blank answer
+    pub fn validate_order_responses(form: &[OrderFormField], data: &OrderResponses) -> Result<(), String> {
+        match form.iter().find(|f| f.required && data.get(&f.key).is_none_or(|v| v.trim().is_empty())) {
+            Some(field) => Err(format!("Missing required order form field: {}", field.label)),
+            None => Ok(()),
+        }
+    }
+
+    /// Save a reusable order form under `name`, replacing any template of that name
+    pub async fn save_order_form_template(&mut self, owner: AccountOwner, name: String, fields: Vec<OrderFormField>) -> Result<(), StateError> {
+        if name.is_empty() || name.chars().count() > MAX_TEMPLATE_NAME_CHARS {
+            return Err(StateError::Validation(format!("Template name must be 1-{} characters", MAX_TEMPLATE_NAME_CHARS)));
+        }
+        Self::validate_order_form(&fields)?;
+        let mut templates = self.order_form_templates.get(&owner).await?.unwrap_or_default();
+        if !templates.contains_key(&name) && templates.len() >= MAX_ORDER_FORM_TEMPLATES {
+            return Err(StateError::Validation(format!("Maximum {} order form templates allowed", MAX_ORDER_FORM_TEMPLATES)));
+        }
+        templates.insert(name, fields);
+        self.order_form_templates.insert(&owner, templates).map_err(StateError::Storage)
+    }
+
+    pub async fn delete_order_form_template(&mut self, owner: AccountOwner, name: &str) -> Result<(), StateError> {
+        let mut templates = self.order_form_templates.get(&owner).await?.unwrap_or_default();
+        if templates.remove(name).is_none() {
+            return Err(StateError::NotFound("Order form template"));
+        }
+        if templates.is_empty() {
+            self.order_form_templates.remove(&owner).map_err(StateError::Storage)
+        } else {
+            self.order_form_templates.insert(&owner, templates).map_err(StateError::Storage)
+        }
+    }
+
+    pub async fn get_order_form_template(&self, owner: AccountOwner, name: &str) -> Result<Vec<OrderFormField>, StateError> {
+        let mut templates = self.order_form_templates.get(&owner).await?.unwrap_or_default();
+        templates.remove(name).ok_or(StateError::NotFound("Order form template"))
+    }
+
+    /// Slugs are lowercase `[a-z0-9-]`, 3 to 60 characters
+    pub fn validate_slug(slug: &str) -> Result<(), StateError> {
+        if slug.len() < 3 || slug.len() > 60 {
+            return Err(StateError::Validation("Slug must be 3-60 characters".to_string()));
+        }
+        if !slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            return Err(StateError::Validation("Slug may only contain a-z, 0-9 and '-'".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Categories are 1 to MAX_CATEGORY_CHARS characters with no surrounding whitespace
+    pub fn validate_category(category: &str) -> Result<(), StateError> {
+        if category.is_empty() || category.trim() != category || category.chars().count() > donations::MAX_CATEGORY_CHARS {
+            return Err(StateError::Validation(format!("Category must be 1-{} characters without surrounding spaces", donations::MAX_CATEGORY_CHARS)));
+        }
+        Ok(())
+    }
+
+    async fn index_category(&mut self, category: &str, product_id: &str) -> Result<(), StateError> {
+        let mut ids = self.products_by_category.get(category).await?.unwrap_or_default();
+        if !ids.iter().any(|id| id == product_id) {
+            ids.push(product_id.to_string());
+        }
+        self.products_by_category.insert(category, ids).map_err(StateError::Storage)
+    }
+
+    async fn unindex_category(&mut self, category: &str, product_id: &str) -> Result<(), StateError> {
+        let mut ids = self.products_by_category.get(category).await?.unwrap_or_default();
+        ids.retain(|id| id != product_id);
+        if ids.is_empty() {
+            self.products_by_category.remove(category).map_err(StateError::Storage)
+        } else {
+            self.products_by_category.insert(category, ids).map_err(StateError::Storage)
+        }
+    }
+
+    /// Set or clear (`None`) a product's category, moving it between index entries
+    pub async fn set_product_category(&mut self, product_id: &str, author: AccountOwner, category: Option<String>) -> Result<(), StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        if product.category == category {
+            return Ok(());
+        }
+        if let Some(new_category) = &category {
+            Self::validate_category(new_category)?;
+            self.index_category(new_category, product_id).await?;
+        }
+        if let Some(old_category) = product.category.take() {
+            self.unindex_category(&old_category, product_id).await?;
+        }
+        product.category = category;
+        self.products.insert(&product_id.to_string(), product).map_err(StateError::Storage)
+    }
+
+    pub async fn set_product_stock(&mut self, product_id: &str, author: AccountOwner, stock: Option<u32>) -> Result<(), StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        product.stock = stock;
+        self.products.insert(&product_id.to_string(), product).map_err(StateError::Storage)
+    }
+
+    /// Take one copy off a stocked product, returning it updated; sold-out products are refused
+    pub async fn decrement_stock(&mut self, product_id: &str) -> Result<Product, StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        match product.stock {
+            Some(0) => return Err(StateError::Validation("Product is sold out".to_string())),
+            Some(left) => product.stock = Some(left - 1),
+            None => return Ok(product),
+        }
+        self.products.insert(&product_id.to_string(), product.clone())?;
+        Ok(product)
+    }
+
+    /// Put one unit back, e.g. for a refunded purchase; products without stock tracking are unchanged
+    pub async fn increment_stock(&mut self, product_id: &str) -> Result<Product, StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        let Some(left) = product.stock else {
+            return Ok(product);
+        };
+        product.stock = Some(left.saturating_add(1));
+        self.products.insert(&product_id.to_string(), product.clone())?;
+        Ok(product)
+    }
+
+    pub async fn list_products_by_category(&self, category: &str) -> Result<Vec<Product>, StateError> {
+        let ids = self.products_by_category.get(category).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(p) = self.products.get(&id).await? {
+                res.push(p);
+            }
+        }
+        Ok(res)
+    }
+
+    fn slug_key(author: AccountOwner, slug: &str) -> String {
+        format!("{}:{}", author, slug)
+    }
+
+    /// Product currently holding a seller's slug, if any
+    pub async fn slug_owner(&self, author: AccountOwner, slug: &str) -> Result<Option<String>, StateError> {
+        self.product_slugs.get(&Self::slug_key(author, slug)).await.map_err(StateError::Storage)
+    }
+
+    /// Resolve a seller's slug to a product id, following unexpired redirects of old slugs
+    pub async fn resolve_slug(&self, author: AccountOwner, slug: &str, current_time: u64) -> Result<Option<String>, StateError> {
+        if let Some(product_id) = self.slug_owner(author, slug).await? {
+            return Ok(Some(product_id));
+        }
+        let redirect = self.slug_redirects.get(&Self::slug_key(author, slug)).await?;
+        Ok(redirect.filter(|r| r.expires_at >= current_time).map(|r| r.product_id))
+    }
+
+    /// Drop a slug from the index, optionally keeping it as a redirect until `redirect_until`
+    async fn release_slug(&mut self, author: AccountOwner, slug: &str, product_id: &str, redirect_until: Option<u64>) -> Result<(), StateError> {
+        let key = Self::slug_key(author, slug);
+        if self.product_slugs.get(&key).await?.as_deref() == Some(product_id) {
+            self.product_slugs.remove(&key)?;
+        }
+        if let Some(expires_at) = redirect_until {
+            self.slug_redirects.insert(&key, SlugRedirect { product_id: product_id.to_string(), expires_at })?;
+        }
+        Ok(())
+    }
+
+    /// Keep an old slug resolving to its product after it was changed
+    pub async fn add_slug_redirect(&mut self, author: AccountOwner, slug: &str, product_id: &str, current_time: u64) -> Result<(), StateError> {
+        self.release_slug(author, slug, product_id, Some(current_time + SLUG_REDIRECT_MICROS)).await
+    }
+
+    /// Check an edit's slug and category (empty meaning cleared) before any of it is written
+    pub async fn validate_product_edit(&self, product_id: &str, author: AccountOwner, slug: Option<&str>, category: Option<&str>) -> Result<(), StateError> {
+        let product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        if let Some(slug) = slug.filter(|s| !s.is_empty()) {
+            Self::validate_slug(slug)?;
+            if self.slug_owner(author, slug).await?.is_some_and(|id| id != product_id) {
+                return Err(StateError::Validation("Slug already taken".to_string()));
+            }
+        }
+        if let Some(category) = category.filter(|c| !c.is_empty()) {
+            Self::validate_category(category)?;
+        }
+        Ok(())
+    }
+
+    /// Set or clear (`None`) a product's slug; the old slug keeps redirecting for 30 days
+    pub async fn set_product_slug(&mut self, product_id: &str, author: AccountOwner, slug: Option<String>, current_time: u64) -> Result<(), StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        if product.slug == slug {
+            return Ok(());
+        }
+        if let Some(new_slug) = &slug {
+            Self::validate_slug(new_slug)?;
+            if self.slug_owner(author, new_slug).await?.is_some_and(|id| id != product_id) {
+                return Err(StateError::Validation("Slug already taken".to_string()));
+            }
+            self.product_slugs.insert(&Self::slug_key(author, new_slug), product_id.to_string())?;
+        }
+        if let Some(old_slug) = product.slug.take() {
+            self.add_slug_redirect(author, &old_slug, product_id, current_time).await?;
+        }
+        product.slug = slug;
+        self.products.insert(&product_id.to_string(), product).map_err(StateError::Storage)
+    }
+
+    /// Drop a product's slug without keeping a redirect (e.g. after a conflict on the main chain)
+    pub async fn clear_product_slug(&mut self, product_id: &str) -> Result<(), StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        if let Some(slug) = product.slug.take() {
+            self.release_slug(product.author, &slug, product_id, None).await?;
+            self.products.insert(&product_id.to_string(), product)?;
+        }
+        Ok(())
+    }
+
+    // Marketplace methods - updated for flexible structure
+    pub async fn create_product(&mut self, product: Product) -> Result<(), StateError> {
+        let product_id = product.id.clone();
+        let author = product.author;
+        let author_chain_id = product.author_chain_id.clone();  // Extract chain_id
+        
+        // Validate order form
+        Self::validate_order_form(&product.order_form)?;
+        donations::validate_entitlements(&product.entitlements).map_err(StateError::Validation)?;
+        
+        // Reserve slug (unique per seller)
+        if let Some(slug) = &product.slug {
+            Self::validate_slug(slug)?;
+            if self.slug_owner(author, slug).await?.is_some_and(|id| id != product_id) {
+                return Err(StateError::Validation("Slug already taken".to_string()));
+            }
+            self.product_slugs.insert(&Self::slug_key(author, slug), product_id.clone())?;
+        }
+
+        if let Some(category) = &product.category {
+            Self::validate_category(category)?;
+            self.index_category(category, &product_id).await?;
+        }
+
+        self.products.insert(&product_id, product)?;
+        // Add to author index
+        let mut author_products = self.products_by_author.get(&author).await?.unwrap_or_default();
+        author_products.push(product_id.clone());
+        self.products_by_author.insert(&author, author_products)?;
+        
+        // Add to chain index
+        let mut chain_products = self.products_by_chain.get(&author_chain_id).await?.unwrap_or_default();
+        chain_products.push(product_id.clone());
+        self.products_by_chain.insert(&author_chain_id, chain_products)?;
+        
+        Ok(())
+    }
+
+    // Updated to handle flexible product updates
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_product(&mut self, product_id: &str, author: AccountOwner, public_data: Option<CustomFields>, price: Option<Amount>, private_data: Option<CustomFields>, encrypted: Option<bool>, success_message: Option<String>, order_form: Option<Vec<OrderFormField>>, entitlements: Option<Vec<String>>) -> Result<(), StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        
+        let mut content_changed = false;
+        let mut form_changed = false;
+        if let Some(pd) = public_data { 
+            Self::validate_custom_fields(&pd)?;
+            content_changed |= product.public_data != pd;
+            product.public_data = pd; 
+        }
+        if let Some(pr) = price {
+            content_changed |= product.price != pr;
+            product.price = pr;
+        }
+        if let Some(pvd) = private_data { 
+            Self::validate_custom_fields(&pvd)?;
+            content_changed |= product.private_data != pvd;
+            product.private_data = pvd; 
+        }
+        if let Some(enc) = encrypted {
+            content_changed |= product.encrypted != enc;
+            product.encrypted = enc;
+        }
+        if let Some(ent) = entitlements {
+            donations::validate_entitlements(&ent).map_err(StateError::Validation)?;
+            content_changed |= product.entitlements != ent;
+            product.entitlements = ent;
+        }
+        if let Some(sm) = success_message {
+            form_changed |= product.success_message.as_ref() != Some(&sm);
+            product.success_message = Some(sm);
+        }
+        if let Some(of) = order_form { 
+            Self::validate_order_form(&of)?;
+            form_changed |= product.order_form != of;
+            product.order_form = of; 
+        }
+        if content_changed {
+            product.content_revision += 1;
+        }
+        if form_changed {
+            product.form_revision += 1;
+        }
+        
+        self.products.insert(&product_id.to_string(), product)?;
+        Ok(())
+    }
+
+    /// Move a product to another of its author's chains, re-bucketing it in the chain index
+    pub async fn set_product_chain(&mut self, product_id: &str, author: AccountOwner, new_chain_id: String) -> Result<Product, StateError> {
+        let mut product = self.products.get(&product_id.to_string()).await?.ok_or(StateError::NotFound("Product"))?;
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        if product.author_chain_id == new_chain_id {
+            return Ok(product);
+        }
+
+        let mut old_bucket = self.products_by_chain.get(&product.author_chain_id).await?.unwrap_or_default();
+        old_bucket.retain(|id| id != product_id);
+        self.products_by_chain.insert(&product.author_chain_id, old_bucket)?;
+
+        let mut new_bucket = self.products_by_chain.get(&new_chain_id).await?.unwrap_or_default();
+        new_bucket.push(product_id.to_string());
+        self.products_by_chain.insert(&new_chain_id, new_bucket)?;
+
+        product.author_chain_id = new_chain_id;
+        self.products.insert(&product_id.to_string(), product.clone())?;
+        Ok(product)
+    }
+
+    pub async fn delete_product(&mut self, product_id: &str, author: AccountOwner) -> Result<(), StateError> {
+        // Get product to extract chain_id before deletion
+        let product = self.products.get(product_id).await
+            ?
+            .ok_or(StateError::NotFound("Product"))?;
+        if product.author != author {
+            return Err(StateError::Unauthorized("not product owner"));
+        }
+        let chain_id = product.author_chain_id.clone();
+        
+        // Remove product
+        self.products.remove(product_id)?;
+        
+        // Free its slug
+        if let Some(slug) = &product.slug {
+            self.release_slug(product.author, slug, product_id, None).await?;
+        }
+
+        if let Some(category) = &product.category {
+            self.unindex_category(category, product_id).await?;
+        }
+
+        // Remove from author index
+        let mut author_products = self.products_by_author.get(&author).await?.unwrap_or_default();
+        author_products.retain(|id| id != product_id);
+        self.products_by_author.insert(&author, author_products)?;
+        
+        // Remove from chain index
+        let mut chain_products = self.products_by_chain.get(&chain_id).await?.unwrap_or_default();
+        chain_products.retain(|id| id != product_id);
+        self.products_by_chain.insert(&chain_id, chain_products)?;
+        
+        Ok(())
+    }
+
+    pub async fn get_product(&self, product_id: &str) -> Result<Option<Product>, StateError> {
+        self.products.get(&product_id.to_string()).await.map_err(StateError::Storage)
+    }
+
+    pub async fn list_products_by_author(&self, author: AccountOwner) -> Result<Vec<Product>, StateError> {
+        let ids = self.products_by_author.get(&author).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(p) = self.products.get(&id).await? {
+                res.push(p);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Check a featured list: at most 3 distinct products, all existing and owned by the author
+    pub async fn validate_featured_products(&self, author: AccountOwner, product_ids: &[String]) -> Result<(), StateError> {
+        if product_ids.len() > MAX_FEATURED_PRODUCTS {
+            return Err(StateError::Validation(format!("Maximum {} featured products allowed", MAX_FEATURED_PRODUCTS)));
+        }
+        for (i, id) in product_ids.iter().enumerate() {
+            if product_ids[..i].contains(id) {
+                return Err(StateError::Validation("Duplicate featured product".to_string()));
+            }
+            let product = self.products.get(id).await?.ok_or(StateError::NotFound("Product"))?;
+            if product.author != author {
+                return Err(StateError::Unauthorized("not product owner"));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn set_featured_products(&mut self, author: AccountOwner, product_ids: Vec<String>) -> Result<(), StateError> {
+        if product_ids.is_empty() {
+            self.featured_products.remove(&author).map_err(StateError::Storage)
+        } else {
+            self.featured_products.insert(&author, product_ids).map_err(StateError::Storage)
+        }
+    }
+
+    pub async fn get_featured_products(&self, author: AccountOwner) -> Result<Vec<String>, StateError> {
+        Ok(self.featured_products.get(&author).await?.unwrap_or_default())
+    }
+
+    /// Check a manual order: exactly the author's products, each once
+    pub async fn validate_product_order(&self, author: AccountOwner, ordered_ids: &[String]) -> Result<(), StateError> {
+        if ordered_ids.is_empty() {
+            return Ok(());
+        }
+        let mut owned = self.products_by_author.get(&author).await?.unwrap_or_default();
+        let mut given = ordered_ids.to_vec();
+        owned.sort();
+        given.sort();
+        if given.windows(2).any(|w| w[0] == w[1]) {
+            return Err(StateError::Validation("Duplicate product in order".to_string()));
+        }
+        if given != owned {
+            return Err(StateError::Validation("Order must list exactly the author's products".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn set_product_order(&mut self, author: AccountOwner, ordered_ids: Vec<String>) -> Result<(), StateError> {
+        if ordered_ids.is_empty() {
+            self.product_order.remove(&author).map_err(StateError::Storage)
+        } else {
+            self.product_order.insert(&author, ordered_ids).map_err(StateError::Storage)
+        }
+    }
+
+    pub async fn get_product_order(&self, author: AccountOwner) -> Result<Vec<String>, StateError> {
+        Ok(self.product_order.get(&author).await?.unwrap_or_default())
+    }
+
+    /// Drop a deleted product from its author's manual order
+    pub async fn unorder_product(&mut self, author: AccountOwner, product_id: &str) -> Result<(), StateError> {
+        let mut order = self.get_product_order(author).await?;
+        if order.iter().any(|id| id == product_id) {
+            order.retain(|id| id != product_id);
+            self.set_product_order(author, order).await?;
+        }
+        Ok(())
+    }
+
+    /// Drop a deleted product from its author's featured list
+    pub async fn unfeature_product(&mut self, author: AccountOwner, product_id: &str) -> Result<(), StateError> {
+        let mut featured = self.get_featured_products(author).await?;
+        if featured.iter().any(|id| id == product_id) {
+            featured.retain(|id| id != product_id);
+            self.set_featured_products(author, featured).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn record_purchase(&mut self, mut purchase: Purchase) -> Result<(), StateError> {
+        let purchase_id = purchase.id.clone();
+        let product_id = purchase.product_id.clone();
+        let buyer = purchase.buyer;
+        let seller = purchase.seller;
+        let day = day_of(purchase.timestamp);
+        let existing = self.purchases.get(&purchase_id).await?;
+        let known = existing.is_some();
+        // Keep whatever progress the order already made
+        if let Some(existing) = existing {
+            purchase.status = existing.status;
+        }
+        
+        self.purchases.insert(&purchase_id, purchase)?;
+        
+        // A purchase seen again (e.g. handled inline on one chain) is updated, not indexed twice
+        if known {
+            return Ok(());
+        }
+
+        // Index by buyer
+        let mut buyer_purchases = self.purchases_by_buyer.get(&buyer).await?.unwrap_or_default();
+        buyer_purchases.push(purchase_id.clone());
+        self.purchases_by_buyer.insert(&buyer, buyer_purchases)?;
+        
+        // Index by seller
+        let mut seller_purchases = self.purchases_by_seller.get(&seller).await?.unwrap_or_default();
+        seller_purchases.push(purchase_id.clone());
+        self.purchases_by_seller.insert(&seller, seller_purchases)?;
+        
+        // Index by product
+        let mut product_purchases = self.purchases_by_product.get(&product_id).await?.unwrap_or_default();
+        product_purchases.push(purchase_id.clone());
+        self.purchases_by_product.insert(&product_id, product_purchases)?;
+
+        // Index by day
+        let mut day_purchases = self.purchase_days.get(&day).await?.unwrap_or_default();
+        day_purchases.push(purchase_id);
+        self.purchase_days.insert(&day, day_purchases)?;
+
+        Ok(())
+    }
+
+    pub async fn get_purchase(&self, purchase_id: &str) -> Result<Option<Purchase>, StateError> {
+        self.purchases.get(&purchase_id.to_string()).await.map_err(StateError::Storage)
+    }
+
+    /// Append a note to a purchase; only its buyer and seller may write
+    pub async fn add_order_note(&mut self, purchase_id: &str, note: OrderNote) -> Result<Purchase, StateError> {
+        if note.text.is_empty() || note.text.chars().count() > MAX_ORDER_NOTE_CHARS {
+            return Err(StateError::Validation(format!("Order note must be 1-{} characters", MAX_ORDER_NOTE_CHARS)));
+        }
+        let purchase = self.get_purchase(purchase_id).await?.ok_or(StateError::NotFound("Purchase"))?;
+        if note.from != purchase.buyer && note.from != purchase.seller {
+            return Err(StateError::Unauthorized("not buyer or seller"));
+        }
+        let mut notes = self.order_notes.get(&purchase_id.to_string()).await?.unwrap_or_default();
+        notes.push(note);
+        self.order_notes.insert(&purchase_id.to_string(), notes)?;
+        Ok(purchase)
+    }
+
+    pub async fn mark_purchase_delivered(&mut self, purchase_id: &str, timestamp: u64) -> Result<(), StateError> {
+        self.delivered_purchases.insert(purchase_id, timestamp).map_err(StateError::Storage)
+    }
+
+    /// Ids of the buyer's purchases with no delivery confirmation from the buyer's chain
+    pub async fn undelivered_purchase_ids(&self, buyer: AccountOwner) -> Result<Vec<String>, StateError> {
+        let ids = self.purchases_by_buyer.get(&buyer).await?.unwrap_or_default();
+        let mut res = Vec::new();
+        for id in ids {
+            if !self.delivered_purchases.contains_key(&id).await? {
+                res.push(id);
+            }
+        }
+        Ok(res)
+    }
+
+    pub async fn has_purchased(&self, buyer: AccountOwner, product_id: &str) -> Result<bool, StateError> {
+        Ok(self.purchase_of(buyer, product_id).await?.is_some())
+    }
+
+    /// The buyer's first purchase of the product on record here
+    pub async fn purchase_of(&self, buyer: AccountOwner, product_id: &str) -> Result<Option<Purchase>, StateError> {
+        for id in self.purchases_by_buyer.get(&buyer).await?.unwrap_or_default() {
+            if let Some(purchase) = self.purchases.get(&id).await?.filter(|p| p.product_id == product_id) {
+                return Ok(Some(purchase));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Store a review, replacing the reviewer's earlier one for the product but keeping its creation time
+    pub async fn put_review(&mut self, mut review: Review) -> Result<Review, StateError> {
+        if !(1..=5).contains(&review.rating) {
+            return Err(StateError::Validation("Rating must be between 1 and 5".to_string()));
+        }
+        review.comment = review.comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+        if review.comment.as_ref().is_some_and(|c| c.chars().count() > donations::MAX_REVIEW_COMMENT_CHARS) {
+            return Err(StateError::Validation(format!("Review comment must be at most {} characters", donations::MAX_REVIEW_COMMENT_CHARS)));
+        }
+        let mut list = self.reviews.get(&review.product_id).await?.unwrap_or_default();
+        match list.iter_mut().find(|r| r.reviewer == review.reviewer) {
+            Some(existing) => {
+                review.created_at = existing.created_at;
+                *existing = review.clone();
+            }
+            None => list.push(review.clone()),
+        }
+        self.reviews.insert(&review.product_id, list)?;
+        Ok(review)
+    }
+
+    pub async fn list_reviews(&self, product_id: &str) -> Result<Vec<Review>, StateError> {
+        Ok(self.reviews.get(product_id).await?.unwrap_or_default())
+    }
+
+    /// (sum of ratings, number of reviews) for a product
+    pub async fn rating_totals(&self, product_id: &str) -> Result<(u32, u32), StateError> {
+        let list = self.list_reviews(product_id).await?;
+        Ok((list.iter().map(|r| r.rating as u32).sum(), list.len() as u32))
+    }
+
+    pub async fn list_purchases_by_buyer(&self, buyer: AccountOwner) -> Result<Vec<Purchase>, StateError> {
+        let ids = self.purchases_by_buyer.get(&buyer).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(p) = self.purchases.get(&id).await? {
+                res.push(p);
+            }
+        }
+        Ok(res)
+    }
+
+    pub async fn set_order_status(&mut self, purchase_id: &str, status: OrderStatus) -> Result<(), StateError> {
+        let mut purchase = self.purchases.get(purchase_id).await?.ok_or(StateError::NotFound("Purchase"))?;
+        purchase.status = status;
+        self.purchases.insert(purchase_id, purchase).map_err(StateError::Storage)
+    }
+
+    /// Check a seller-initiated status change of one order; closed orders and going back to Pending are refused
+    pub async fn check_order_status_change(&self, seller: AccountOwner, purchase_id: &str, status: OrderStatus) -> Result<Purchase, StateError> {
+        let purchase = self.purchases.get(purchase_id).await?.ok_or(StateError::NotFound("Purchase"))?;
+        if purchase.seller != seller {
+            return Err(StateError::Unauthorized("not the seller"));
+        }
+        if !purchase.status.is_open() {
+            return Err(StateError::Validation(format!("Order is already {:?}", purchase.status)));
+        }
+        if status == OrderStatus::Refunded {
+            return Err(StateError::Validation("Orders are refunded with RefundPurchase".to_string()));
+        }
+        if status == OrderStatus::Pending || status == purchase.status {
+            return Err(StateError::Validation(format!("Order can't be moved from {:?} to {:?}", purchase.status, status)));
+        }
+        Ok(purchase)
     }
 
-    pub async fn set_header(&mut self, owner: AccountOwner, hash: String) -> Result<(), String> {
-        let mut p = self.profiles.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or(Profile { 
-            owner: owner.clone(), 
-            name: "anon".to_string(), 
-            bio: String::new(), 
-            socials: Vec::new(),
-            avatar_hash: None,
-            header_hash: None,
-        });
-        p.header_hash = Some(hash);
-        self.profiles.insert(&owner, p).map_err(|e: ViewError| format!("{:?}", e))
+    /// Seller-initiated status change of one order, checked as in `check_order_status_change`
+    pub async fn update_order_status(&mut self, seller: AccountOwner, purchase_id: &str, status: OrderStatus) -> Result<Purchase, StateError> {
+        let mut purchase = self.check_order_status_change(seller, purchase_id, status).await?;
+        purchase.status = status;
+        self.purchases.insert(purchase_id, purchase.clone())?;
+        Ok(purchase)
     }
 
-    pub async fn get_profile(&self, owner: AccountOwner) -> Result<Option<Profile>, String> {
-        self.profiles.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))
+    /// Move the seller's Pending orders to Accepted, returning the orders that changed
+    pub async fn accept_pending_orders(&mut self, seller: AccountOwner) -> Result<Vec<Purchase>, StateError> {
+        // Snapshot of ids taken up front; accepting doesn't touch the index
+        let ids = self.purchases_by_seller.get(&seller).await?.unwrap_or_default();
+        let mut accepted = Vec::new();
+        for id in ids {
+            let Some(mut purchase) = self.purchases.get(&id).await? else {
+                continue;
+            };
+            if purchase.status != OrderStatus::Pending {
+                continue;
+            }
+            purchase.status = OrderStatus::Accepted;
+            self.purchases.insert(&id, purchase.clone())?;
+            accepted.push(purchase);
+        }
+        Ok(accepted)
     }
 
-    pub async fn list_donations_by_recipient(&self, owner: AccountOwner) -> Result<Vec<DonationRecord>, String> {
-        let ids = self.donations_by_recipient.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+    pub async fn list_purchases_by_seller(&self, seller: AccountOwner) -> Result<Vec<Purchase>, StateError> {
+        let ids = self.purchases_by_seller.get(&seller).await?.unwrap_or_default();
         let mut res = Vec::with_capacity(ids.len());
-        for id in ids { if let Some(r) = self.donations.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? { res.push(r); } }
+        for id in ids {
+            if let Some(p) = self.purchases.get(&id).await? {
+                res.push(p);
+            }
+        }
         Ok(res)
     }
+    
+    // Content subscription management
+    pub async fn set_subscription_price(&mut self, author: AccountOwner, price: Amount, description: Option<String>, grandfather_renewals: u8) -> Result<(), StateError> {
+        let (entitlements, grace_period_micros) = self.get_subscription_price(author).await?.map(|info| (info.entitlements, info.grace_period_micros)).unwrap_or_default();
+        let info = SubscriptionInfo { author, price, description, grandfather_renewals, pending_price: None, entitlements, grace_period_micros };
+        self.subscription_prices.insert(&author, info).map_err(StateError::Storage)
+    }
 
-    pub async fn list_donations_by_donor(&self, owner: AccountOwner) -> Result<Vec<DonationRecord>, String> {
-        let ids = self.donations_by_donor.get(&owner).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        let mut res = Vec::with_capacity(ids.len());
-        for id in ids { if let Some(r) = self.donations.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? { res.push(r); } }
-        Ok(res)
+    pub async fn set_subscription_entitlements(&mut self, author: AccountOwner, entitlements: Vec<String>) -> Result<(), StateError> {
+        donations::validate_entitlements(&entitlements).map_err(StateError::Validation)?;
+        let mut info = self.get_subscription_price(author).await?.ok_or_else(|| StateError::Validation("Author does not offer subscriptions".to_string()))?;
+        info.entitlements = entitlements;
+        self.subscription_prices.insert(&author, info).map_err(StateError::Storage)
     }
 
-    // Validation methods for flexible products
-    pub fn validate_custom_fields(fields: &CustomFields) -> Result<(), String> {
-        if fields.len() > 20 {
-            return Err("Maximum 20 custom fields allowed".to_string());
+    pub async fn set_subscription_grace_period(&mut self, author: AccountOwner, grace_period_micros: u64) -> Result<(), StateError> {
+        if grace_period_micros > donations::MAX_SUBSCRIPTION_GRACE_MICROS {
+            return Err(StateError::Validation(format!("Grace period may be at most {} days", donations::MAX_SUBSCRIPTION_GRACE_MICROS / MICROS_PER_DAY)));
         }
-        Ok(())
+        let mut info = self.get_subscription_price(author).await?.ok_or_else(|| StateError::Validation("Author does not offer subscriptions".to_string()))?;
+        info.grace_period_micros = grace_period_micros;
+        self.subscription_prices.insert(&author, info).map_err(StateError::Storage)
     }
 
-    pub fn validate_order_form(form: &Vec<OrderFormField>) -> Result<(), String> {
-        if form.len() > 20 {
-            return Err("Maximum 20 order form fields allowed".to_string());
-        }
-        Ok(())
+    /// The author's grace period, zero if they don't offer subscriptions
+    pub async fn subscription_grace_micros(&self, author: AccountOwner) -> u64 {
+        self.get_subscription_price(author).await.ok().flatten().map_or(0, |info| info.grace_period_micros)
     }
 
-    // Marketplace methods - updated for flexible structure
-    pub async fn create_product(&mut self, product: Product) -> Result<(), String> {
-        let product_id = product.id.clone();
-        let author = product.author.clone();
-        let author_chain_id = product.author_chain_id.clone();  // Extract chain_id
-        
-        // Validate order form
-        Self::validate_order_form(&product.order_form)?;
-        
-        self.products.insert(&product_id, product).map_err(|e: ViewError| format!("{:?}", e))?;
-        // Add to author index
-        let mut author_products = self.products_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        author_products.push(product_id.clone());
-        self.products_by_author.insert(&author, author_products).map_err(|e: ViewError| format!("{:?}", e))?;
-        
-        // Add to chain index
-        let mut chain_products = self.products_by_chain.get(&author_chain_id).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        chain_products.push(product_id.clone());
-        self.products_by_chain.insert(&author_chain_id, chain_products).map_err(|e: ViewError| format!("{:?}", e))?;
-        
+    /// Grants for a purchase of `product`; nothing is stored
+    pub fn purchase_grants(product: &Product, purchase_id: &str, now: u64) -> Vec<EntitlementGrant> {
+        product.entitlements.iter().map(|name| EntitlementGrant {
+            name: name.clone(),
+            purchase_id: purchase_id.to_string(),
+            granted_at: now,
+            expires_at: None,
+        }).collect()
+    }
+
+    /// Record a purchase and the entitlements it grants the buyer, stopping at the first failed write
+    pub async fn record_purchase_with_grants(&mut self, purchase: Purchase, grants: Vec<EntitlementGrant>) -> Result<(), StateError> {
+        let buyer = purchase.buyer;
+        self.record_purchase(purchase).await?;
+        self.store_entitlements(buyer, grants).await
+    }
+
+    /// Add a purchase's grants to the owner's; a grant delivered again is kept once
+    pub async fn store_entitlements(&mut self, owner: AccountOwner, grants: Vec<EntitlementGrant>) -> Result<(), StateError> {
+        if grants.is_empty() {
+            return Ok(());
+        }
+        let mut held = self.entitlements.get(&owner).await?.unwrap_or_default();
+        for grant in grants {
+            if !held.iter().any(|g| g.purchase_id == grant.purchase_id && g.name == grant.name) {
+                held.push(grant);
+            }
+        }
+        self.entitlements.insert(&owner, held)?;
         Ok(())
     }
 
-    // Updated to handle flexible product updates
-    pub async fn update_product(&mut self, product_id: &str, author: AccountOwner, public_data: Option<CustomFields>, price: Option<Amount>, private_data: Option<CustomFields>, success_message: Option<String>, order_form: Option<Vec<OrderFormField>>) -> Result<(), String> {
-        let mut product = self.products.get(&product_id.to_string()).await.map_err(|e: ViewError| format!("{:?}", e))?.ok_or("Product not found")?;
-        
-        if product.author != author {
-            return Err("Unauthorized: not product owner".to_string());
+    /// Remove the grants a purchase made to its buyer. Returns how many were removed.
+    pub async fn revoke_purchase_entitlements(&mut self, buyer: AccountOwner, purchase_id: &str) -> Result<usize, StateError> {
+        let mut held = self.entitlements.get(&buyer).await?.unwrap_or_default();
+        let before = held.len();
+        held.retain(|grant| grant.purchase_id != purchase_id);
+        let revoked = before - held.len();
+        if held.is_empty() {
+            self.entitlements.remove(&buyer)?;
+        } else if revoked > 0 {
+            self.entitlements.insert(&buyer, held)?;
         }
-        
-        if let Some(pd) = public_data { 
-            Self::validate_custom_fields(&pd)?;
-            product.public_data = pd; 
+        Ok(revoked)
+    }
+
+    /// Entitlements an owner holds at `now`: stored purchase grants that haven't expired, plus
+    /// those of authors they are actively subscribed to (when the author's terms are known here)
+    pub async fn entitlements_of(&self, owner: AccountOwner, now: u64) -> Result<Vec<EntitlementGrant>, StateError> {
+        let mut grants = self.entitlements.get(&owner).await?.unwrap_or_default();
+        grants.retain(|grant| grant.expires_at.is_none_or(|at| at > now));
+        for sub in self.list_subscriptions_by_subscriber(owner).await? {
+            if sub.end_timestamp <= now {
+                continue;
+            }
+            if let Some(info) = self.get_subscription_price(sub.author).await? {
+                for name in info.entitlements {
+                    grants.push(EntitlementGrant { name, purchase_id: sub.id.clone(), granted_at: sub.start_timestamp, expires_at: Some(sub.end_timestamp) });
+                }
+            }
         }
-        if let Some(pr) = price { product.price = pr; }
-        if let Some(pvd) = private_data { 
-            Self::validate_custom_fields(&pvd)?;
-            product.private_data = pvd; 
+        Ok(grants)
+    }
+
+    pub async fn has_entitlement(&self, owner: AccountOwner, name: &str, now: u64) -> Result<bool, StateError> {
+        Ok(self.entitlements_of(owner, now).await?.iter().any(|g| g.name == name))
+    }
+
+    /// Queue a price increase on the author's current terms
+    pub async fn schedule_subscription_price(&mut self, author: AccountOwner, pending: PendingPrice) -> Result<(), StateError> {
+        let mut info = self.get_subscription_price(author).await?.ok_or_else(|| StateError::Validation("Author does not offer subscriptions".to_string()))?;
+        info.pending_price = Some(pending);
+        self.subscription_prices.insert(&author, info).map_err(StateError::Storage)
+    }
+    
+    /// Apply a pending price whose notice period is over. Returns the new terms if it was applied.
+    pub async fn apply_pending_price(&mut self, author: AccountOwner, now: u64) -> Result<Option<SubscriptionInfo>, StateError> {
+        let Some(info) = self.get_subscription_price(author).await? else {
+            return Ok(None);
+        };
+        if info.pending_price.as_ref().is_none_or(|p| p.effective_at > now) {
+            return Ok(None);
         }
-        if let Some(sm) = success_message { product.success_message = Some(sm); }
-        if let Some(of) = order_form { 
-            Self::validate_order_form(&of)?;
-            product.order_form = of; 
+        let info = info.settled(now);
+        self.subscription_prices.insert(&author, info.clone())?;
+        Ok(Some(info))
+    }
+
+    /// Keep a purchase that couldn't be delivered; repeats of the same purchase are ignored
+    pub async fn record_failed_order(&mut self, buyer: AccountOwner, order: FailedOrder) -> Result<bool, StateError> {
+        let mut list = self.failed_orders.get(&buyer).await?.unwrap_or_default();
+        if list.iter().any(|o| o.purchase_id == order.purchase_id) {
+            return Ok(false);
         }
-        
-        self.products.insert(&product_id.to_string(), product).map_err(|e: ViewError| format!("{:?}", e))?;
+        list.push(order);
+        self.failed_orders.insert(&buyer, list)?;
+        Ok(true)
+    }
+
+    /// Owe a refused payment back until the payee next signs on this chain
+    pub async fn queue_refund(&mut self, payee: AccountOwner, refund: PendingRefund) -> Result<(), StateError> {
+        let mut list = self.pending_refunds.get(&payee).await?.unwrap_or_default();
+        list.push(refund);
+        self.pending_refunds.insert(&payee, list)?;
         Ok(())
     }
 
-    pub async fn delete_product(&mut self, product_id: &str, author: AccountOwner) -> Result<(), String> {
-        // Get product to extract chain_id before deletion
-        let product = self.products.get(product_id).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Product not found")?;
-        let chain_id = product.author_chain_id.clone();
-        
-        // Remove product
-        self.products.remove(product_id).map_err(|e: ViewError| format!("{:?}", e))?;
-        
-        // Remove from author index
-        let mut author_products = self.products_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        author_products.retain(|id| id != product_id);
-        self.products_by_author.insert(&author, author_products).map_err(|e: ViewError| format!("{:?}", e))?;
-        
-        // Remove from chain index
-        let mut chain_products = self.products_by_chain.get(&chain_id).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        chain_products.retain(|id| id != product_id);
-        self.products_by_chain.insert(&chain_id, chain_products).map_err(|e: ViewError| format!("{:?}", e))?;
-        
+    /// Refunds the payee still owes, oldest first
+    pub async fn get_pending_refunds(&self, payee: AccountOwner) -> Result<Vec<PendingRefund>, StateError> {
+        Ok(self.pending_refunds.get(&payee).await?.unwrap_or_default())
+    }
+
+    /// Replace the payee's owed refunds with the ones still unpaid
+    pub async fn set_pending_refunds(&mut self, payee: AccountOwner, refunds: Vec<PendingRefund>) -> Result<(), StateError> {
+        if refunds.is_empty() {
+            self.pending_refunds.remove(&payee)?;
+        } else {
+            self.pending_refunds.insert(&payee, refunds)?;
+        }
         Ok(())
     }
 
-    pub async fn get_product(&self, product_id: &str) -> Result<Option<Product>, String> {
-        self.products.get(&product_id.to_string()).await.map_err(|e: ViewError| format!("{:?}", e))
+    /// Keep a message being sent to `target_chain` until it is acked; returns its sequence to that chain
+    pub async fn queue_outbox(&mut self, target_chain: ChainId, message: Message, sender: Option<AccountOwner>, now: u64) -> Result<u64, StateError> {
+        let id = self.outbox_sequences.get(&target_chain).await?.unwrap_or(0);
+        self.outbox_sequences.insert(&target_chain, id + 1)?;
+        let entry = OutboxEntry { id, target_chain, message, sender, attempts: 1, next_retry: now.saturating_add(OutboxEntry::retry_delay(1)), created_at: now };
+        self.outbox_due.get_mut().insert((entry.next_retry, target_chain, id));
+        self.outbox.insert(&(target_chain, id), entry).map_err(StateError::Storage)?;
+        Ok(id)
     }
 
-    pub async fn list_products_by_author(&self, author: AccountOwner) -> Result<Vec<Product>, String> {
-        let ids = self.products_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        let mut res = Vec::with_capacity(ids.len());
-        for id in ids {
-            if let Some(p) = self.products.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? {
-                res.push(p);
+    /// Every entry still in the outbox, oldest first
+    pub async fn list_outbox(&self) -> Result<Vec<OutboxEntry>, StateError> {
+        let mut out: Vec<_> = self.outbox.index_values().await?.into_iter().map(|(_, v)| v).collect();
+        out.sort_by_key(|e| (e.created_at, e.target_chain, e.id));
+        Ok(out)
+    }
+
+    /// Entries whose next retry is at or before `now`, read through the due index
+    pub async fn due_outbox(&self, now: u64) -> Result<Vec<OutboxEntry>, StateError> {
+        let keys: Vec<_> = self.outbox_due.get().iter().take_while(|(due, _, _)| *due <= now).map(|(_, chain, id)| (*chain, *id)).collect();
+        let mut out = Vec::new();
+        for key in keys {
+            if let Some(entry) = self.outbox.get(&key).await? {
+                out.push(entry);
             }
         }
-        Ok(res)
+        Ok(out)
     }
 
-    pub async fn record_purchase(&mut self, purchase: Purchase) -> Result<(), String> {
-        let purchase_id = purchase.id.clone();
-        let buyer = purchase.buyer.clone();
-        let seller = purchase.seller.clone();
-        
-        self.purchases.insert(&purchase_id, purchase).map_err(|e: ViewError| format!("{:?}", e))?;
-        
-        // Index by buyer
-        let mut buyer_purchases = self.purchases_by_buyer.get(&buyer).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        buyer_purchases.push(purchase_id.clone());
-        self.purchases_by_buyer.insert(&buyer, buyer_purchases).map_err(|e: ViewError| format!("{:?}", e))?;
-        
-        // Index by seller
-        let mut seller_purchases = self.purchases_by_seller.get(&seller).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        seller_purchases.push(purchase_id);
-        self.purchases_by_seller.insert(&seller, seller_purchases).map_err(|e: ViewError| format!("{:?}", e))?;
-        
-        Ok(())
+    /// Count another send of an outbox entry and push its next retry back
+    pub async fn note_outbox_attempt(&mut self, target_chain: ChainId, id: u64, now: u64) -> Result<(), StateError> {
+        let mut entry = self.outbox.get(&(target_chain, id)).await?.ok_or(StateError::NotFound("Outbox entry"))?;
+        let due = self.outbox_due.get_mut();
+        due.remove(&(entry.next_retry, target_chain, id));
+        entry.attempts += 1;
+        entry.next_retry = now.saturating_add(OutboxEntry::retry_delay(entry.attempts));
+        due.insert((entry.next_retry, target_chain, id));
+        self.outbox.insert(&(target_chain, id), entry).map_err(StateError::Storage)
     }
 
-    pub async fn list_purchases_by_buyer(&self, buyer: AccountOwner) -> Result<Vec<Purchase>, String> {
-        let ids = self.purchases_by_buyer.get(&buyer).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        let mut res = Vec::with_capacity(ids.len());
-        for id in ids {
-            if let Some(p) = self.purchases.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? {
-                res.push(p);
-            }
+    /// Drop an entry, acked or given up on; false if it was already gone
+    pub async fn remove_outbox(&mut self, target_chain: ChainId, id: u64) -> Result<bool, StateError> {
+        let Some(entry) = self.outbox.get(&(target_chain, id)).await? else {
+            return Ok(false);
+        };
+        self.outbox_due.get_mut().remove(&(entry.next_retry, target_chain, id));
+        self.outbox.remove(&(target_chain, id))?;
+        Ok(true)
+    }
+
+    /// Remember a tracked message from `origin_chain`; false if it was already applied
+    pub async fn note_tracked_delivery(&mut self, origin_chain: ChainId, outbox_id: u64) -> Result<bool, StateError> {
+        let mut delivered = self.outbox_delivered.get(&origin_chain).await?.unwrap_or_default();
+        if !delivered.insert(outbox_id) {
+            return Ok(false);
         }
-        Ok(res)
+        self.outbox_delivered.insert(&origin_chain, delivered)?;
+        Ok(true)
     }
 
-    pub async fn list_purchases_by_seller(&self, seller: AccountOwner) -> Result<Vec<Purchase>, String> {
-        let ids = self.purchases_by_seller.get(&seller).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        let mut res = Vec::with_capacity(ids.len());
-        for id in ids {
-            if let Some(p) = self.purchases.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? {
-                res.push(p);
-            }
+    pub async fn push_notification(&mut self, owner: AccountOwner, notification: Notification) -> Result<(), StateError> {
+        let mut inbox = self.notifications.get(&owner).await?.unwrap_or_default();
+        inbox.push(notification);
+        if inbox.len() > MAX_NOTIFICATIONS {
+            inbox.drain(..inbox.len() - MAX_NOTIFICATIONS);
         }
-        Ok(res)
+        self.notifications.insert(&owner, inbox).map_err(StateError::Storage)
     }
-    
-    // Content subscription management
-    pub async fn set_subscription_price(&mut self, author: AccountOwner, price: Amount, description: Option<String>) -> Result<(), String> {
-        let info = SubscriptionInfo { author, price, description };
-        self.subscription_prices.insert(&author, info).map_err(|e: ViewError| format!("{:?}", e))
+
+    /// File an announcement in the subscriber's inbox and in their own store, where other
+    /// notifications don't push it out of the activity feed
+    pub async fn push_announcement(&mut self, subscriber: AccountOwner, announcement: Notification) -> Result<(), StateError> {
+        let mut received = self.announcements.get(&subscriber).await?.unwrap_or_default();
+        received.push(announcement.clone());
+        if received.len() > MAX_ANNOUNCEMENTS {
+            received.drain(..received.len() - MAX_ANNOUNCEMENTS);
+        }
+        self.announcements.insert(&subscriber, received)?;
+        self.push_notification(subscriber, announcement).await
     }
-    
-    pub async fn get_subscription_price(&self, author: AccountOwner) -> Result<Option<SubscriptionInfo>, String> {
-        self.subscription_prices.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))
+
+    pub async fn get_subscription_price(&self, author: AccountOwner) -> Result<Option<SubscriptionInfo>, StateError> {
+        self.subscription_prices.get(&author).await.map_err(StateError::Storage)
     }
     
-    pub async fn delete_subscription_info(&mut self, author: AccountOwner) -> Result<(), String> {
-        self.subscription_prices.remove(&author).map_err(|e: ViewError| format!("{:?}", e))
+    pub async fn delete_subscription_info(&mut self, author: AccountOwner) -> Result<(), StateError> {
+        self.subscription_prices.remove(&author).map_err(StateError::Storage)
     }
     
-    pub async fn create_subscription(&mut self, subscription: ContentSubscription) -> Result<(), String> {
+    /// Most recent subscription (active or expired) of a subscriber to an author
+    pub async fn latest_subscription(&self, author: AccountOwner, subscriber: AccountOwner) -> Result<Option<ContentSubscription>, StateError> {
+        let sub_ids = self.subscriptions_by_author.get(&author).await?.unwrap_or_default();
+        let mut latest: Option<ContentSubscription> = None;
+        for id in sub_ids {
+            if let Some(sub) = self.content_subscriptions.get(&id).await? {
+                if sub.subscriber == subscriber && latest.as_ref().is_none_or(|l| sub.start_timestamp > l.start_timestamp) {
+                    latest = Some(sub);
+                }
+            }
+        }
+        Ok(latest)
+    }
+
+    /// Locked price and remaining grandfathered renewals for a new subscription period.
+    /// While the previous period still has renewals left at a price below the current one,
+    /// the subscriber keeps paying the locked price; otherwise the current price is locked in.
+    /// Fails when the payment is below the applicable price or the author set no price.
+    pub async fn subscription_terms(&self, author: AccountOwner, subscriber: AccountOwner, amount: Amount, now: u64) -> Result<(Amount, u8), StateError> {
+        let info = self.get_subscription_price(author).await?.ok_or_else(|| StateError::Validation("Author does not offer subscriptions".to_string()))?.settled(now);
+        if let Some(previous) = self.latest_subscription(author, subscriber).await? {
+            if previous.renewals_remaining_at_locked_price > 0 && previous.locked_price < info.price {
+                if amount < previous.locked_price {
+                    return Err(StateError::Validation(format!("Insufficient subscription payment: {} paid, {} due", amount, previous.locked_price)));
+                }
+                return Ok((previous.locked_price, previous.renewals_remaining_at_locked_price - 1));
+            }
+        }
+        if amount < info.price {
+            return Err(StateError::Validation(format!("Insufficient subscription payment: {} paid, {} due", amount, info.price)));
+        }
+        Ok((info.price, info.grandfather_renewals))
+    }
+
+    /// Price the subscriber has to pay for their next renewal, if the author offers subscriptions
+    pub async fn effective_renewal_price(&self, author: AccountOwner, subscriber: AccountOwner, now: u64) -> Result<Option<Amount>, StateError> {
+        let info = match self.get_subscription_price(author).await? {
+            Some(info) => info.settled(now),
+            None => return Ok(None),
+        };
+        if let Some(previous) = self.latest_subscription(author, subscriber).await? {
+            if previous.renewals_remaining_at_locked_price > 0 && previous.locked_price < info.price {
+                return Ok(Some(previous.locked_price));
+            }
+        }
+        Ok(Some(info.price))
+    }
+
+    /// Apply the terms confirmed by the author's chain to the local copy of a subscription
+    pub async fn confirm_subscription(&mut self, sub_id: &str, author: AccountOwner, subscriber: AccountOwner, locked_price: Amount, renewals_remaining: u8, renewal_price: Amount) -> Result<(), StateError> {
+        if let Some(mut sub) = self.content_subscriptions.get(&sub_id.to_string()).await? {
+            sub.locked_price = locked_price;
+            sub.renewals_remaining_at_locked_price = renewals_remaining;
+            self.content_subscriptions.insert(&sub_id.to_string(), sub)?;
+        }
+        self.renewal_price_cache.insert(&format!("{}:{}", author, subscriber), renewal_price).map_err(StateError::Storage)
+    }
+
+    pub async fn create_subscription(&mut self, mut subscription: ContentSubscription) -> Result<(), StateError> {
+        subscription.subscriber_chain_id = donations::canonical_chain_id(&subscription.subscriber_chain_id).map_err(StateError::Validation)?;
+        subscription.author_chain_id = donations::canonical_chain_id(&subscription.author_chain_id).map_err(StateError::Validation)?;
         let sub_id = subscription.id.clone();
-        let author = subscription.author.clone();
+        let author = subscription.author;
         let author_chain_id = subscription.author_chain_id.clone();
-        let subscriber = subscription.subscriber.clone();
+        let subscriber = subscription.subscriber;
+        let day = day_of(subscription.start_timestamp);
         
-        self.content_subscriptions.insert(&sub_id, subscription).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.content_subscriptions.insert(&sub_id, subscription)?;
         
         // Add to author index
-        let mut author_subs = self.subscriptions_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut author_subs = self.subscriptions_by_author.get(&author).await?.unwrap_or_default();
         author_subs.push(sub_id.clone());
-        self.subscriptions_by_author.insert(&author, author_subs).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.subscriptions_by_author.insert(&author, author_subs)?;
         
         // Add to chain index
-        let mut chain_subs = self.subscriptions_by_chain.get(&author_chain_id).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut chain_subs = self.subscriptions_by_chain.get(&author_chain_id).await?.unwrap_or_default();
         chain_subs.push(sub_id.clone());
-        self.subscriptions_by_chain.insert(&author_chain_id, chain_subs).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.subscriptions_by_chain.insert(&author_chain_id, chain_subs)?;
         
         // Add to subscriber index
-        let mut subscriber_subs = self.subscriptions_by_subscriber.get(&subscriber).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
-        subscriber_subs.push(sub_id);
-        self.subscriptions_by_subscriber.insert(&subscriber, subscriber_subs).map_err(|e: ViewError| format!("{:?}", e))?;
+        let mut subscriber_subs = self.subscriptions_by_subscriber.get(&subscriber).await?.unwrap_or_default();
+        subscriber_subs.push(sub_id.clone());
+        self.subscriptions_by_subscriber.insert(&subscriber, subscriber_subs)?;
         
+        // Add to day index
+        let mut day_subs = self.subscription_days.get(&day).await?.unwrap_or_default();
+        day_subs.push(sub_id);
+        self.subscription_days.insert(&day, day_subs)?;
+
         Ok(())
     }
     
-    pub async fn remove_subscription(&mut self, sub_id: &str, author: AccountOwner, subscriber: AccountOwner) -> Result<(), String> {
-        self.content_subscriptions.remove(&sub_id.to_string()).map_err(|e: ViewError| format!("{:?}", e))?;
+    pub async fn set_auto_renew(&mut self, sub_id: &str, subscriber: AccountOwner, auto_renew: bool) -> Result<(), StateError> {
+        let mut sub = self.content_subscriptions.get(&sub_id.to_string()).await?
+            .ok_or(StateError::NotFound("Subscription"))?;
+        if sub.subscriber != subscriber {
+            return Err(StateError::Unauthorized("not the subscriber"));
+        }
+        sub.auto_renew = auto_renew;
+        sub.auto_renew_failures = 0;
+        self.content_subscriptions.insert(&sub_id.to_string(), sub).map_err(StateError::Storage)
+    }
+
+    /// Count a renewal that couldn't be paid; returns whether auto-renew was switched off
+    pub async fn note_auto_renew_failure(&mut self, sub_id: &str) -> Result<bool, StateError> {
+        let mut sub = self.content_subscriptions.get(&sub_id.to_string()).await?
+            .ok_or(StateError::NotFound("Subscription"))?;
+        sub.auto_renew_failures = sub.auto_renew_failures.saturating_add(1);
+        let disabled = sub.auto_renew_failures >= donations::MAX_AUTO_RENEW_FAILURES;
+        if disabled {
+            sub.auto_renew = false;
+        }
+        self.content_subscriptions.insert(&sub_id.to_string(), sub)?;
+        Ok(disabled)
+    }
+
+    /// Auto-renew subscriptions still running at `now` that end within `within_micros`, soonest first
+    pub async fn subscriptions_due_for_renewal(&self, subscriber: AccountOwner, now: u64, within_micros: u64) -> Result<Vec<ContentSubscription>, StateError> {
+        let until = now.saturating_add(within_micros);
+        let mut due: Vec<ContentSubscription> = self.list_subscriptions_by_subscriber(subscriber).await?
+            .into_iter()
+            .filter(|s| s.auto_renew && s.end_timestamp >= now && s.end_timestamp <= until)
+            .collect();
+        due.sort_by_key(|s| s.end_timestamp);
+        Ok(due)
+    }
+
+    pub async fn remove_subscription(&mut self, sub_id: &str, author: AccountOwner, subscriber: AccountOwner) -> Result<(), StateError> {
+        let existing = self.content_subscriptions.get(&sub_id.to_string()).await?;
+        self.content_subscriptions.remove(&sub_id.to_string())?;
         
+        // Remove from chain index
+        if let Some(sub) = existing {
+            let mut chain_subs = self.subscriptions_by_chain.get(&sub.author_chain_id).await?.unwrap_or_default();
+            chain_subs.retain(|id| id != sub_id);
+            self.subscriptions_by_chain.insert(&sub.author_chain_id, chain_subs)?;
+        }
+
         // Remove from author index
-        let mut author_subs = self.subscriptions_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut author_subs = self.subscriptions_by_author.get(&author).await?.unwrap_or_default();
         author_subs.retain(|id| id != sub_id);
-        self.subscriptions_by_author.insert(&author, author_subs).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.subscriptions_by_author.insert(&author, author_subs)?;
         
         // Remove from subscriber index  
-        let mut subscriber_subs = self.subscriptions_by_subscriber.get(&subscriber).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut subscriber_subs = self.subscriptions_by_subscriber.get(&subscriber).await?.unwrap_or_default();
         subscriber_subs.retain(|id| id != sub_id);
-        self.subscriptions_by_subscriber.insert(&subscriber, subscriber_subs).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.subscriptions_by_subscriber.insert(&subscriber, subscriber_subs)?;
         
         Ok(())
     }
     
-    pub async fn get_active_subscriptions(&self, author: AccountOwner, current_time: u64) -> Result<Vec<ContentSubscription>, String> {
-        let sub_ids = self.subscriptions_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+    pub async fn get_active_subscriptions(&self, author: AccountOwner, current_time: u64, access: SubscriptionAccess) -> Result<Vec<ContentSubscription>, StateError> {
+        let sub_ids = self.subscriptions_by_author.get(&author).await?.unwrap_or_default();
+        let grace = self.subscription_grace_micros(author).await;
         let mut active = Vec::new();
         
         for id in sub_ids {
-            if let Some(sub) = self.content_subscriptions.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? {
-                if sub.end_timestamp >= current_time {
+            if let Some(sub) = self.content_subscriptions.get(&id).await? {
+                if sub.allows(access, grace, current_time) {
                     active.push(sub);
                 }
             }
@@ -348,82 +2244,283 @@ impl DonationsState {
         Ok(active)
     }
     
-    pub async fn create_post(&mut self, post: Post) -> Result<(), String> {
+    pub async fn list_subscriptions_by_author(&self, author: AccountOwner) -> Result<Vec<ContentSubscription>, StateError> {
+        let sub_ids = self.subscriptions_by_author.get(&author).await?.unwrap_or_default();
+        let mut subs = Vec::new();
+        for id in sub_ids {
+            if let Some(sub) = self.content_subscriptions.get(&id).await? {
+                subs.push(sub);
+            }
+        }
+        Ok(subs)
+    }
+
+    pub async fn list_subscriptions_by_subscriber(&self, subscriber: AccountOwner) -> Result<Vec<ContentSubscription>, StateError> {
+        let sub_ids = self.subscriptions_by_subscriber.get(&subscriber).await?.unwrap_or_default();
+        let mut subs = Vec::new();
+        for id in sub_ids {
+            if let Some(sub) = self.content_subscriptions.get(&id).await? {
+                subs.push(sub);
+            }
+        }
+        Ok(subs)
+    }
+
+    /// Id for a new post on this chain: unique per chain thanks to the counter, and
+    /// sortable by creation time (fixed-width hex timestamp first)
+    pub fn next_post_id(&mut self, timestamp: u64, chain_id: &str) -> String {
+        let counter = *self.post_counter.get() + 1;
+        self.post_counter.set(counter);
+        format!("{:016x}-{:08x}-{}", timestamp, counter, chain_id)
+    }
+
+    pub async fn create_post(&mut self, mut post: Post) -> Result<(), StateError> {
+        post.author_chain_id = donations::canonical_chain_id(&post.author_chain_id).map_err(StateError::Validation)?;
         let post_id = post.id.clone();
-        let author = post.author.clone();
+        let author = post.author;
         let author_chain_id = post.author_chain_id.clone();
+        let known = self.posts.contains_key(&post_id).await?;
         
-        self.posts.insert(&post_id, post).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts.insert(&post_id, post)?;
         
+        // A post delivered again (event and message) is updated, not indexed twice
+        if known {
+            return Ok(());
+        }
+
         // Add to author index
-        let mut author_posts = self.posts_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut author_posts = self.posts_by_author.get(&author).await?.unwrap_or_default();
         author_posts.push(post_id.clone());
-        self.posts_by_author.insert(&author, author_posts).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts_by_author.insert(&author, author_posts)?;
         
         // Add to chain index
-        let mut chain_posts = self.posts_by_chain.get(&author_chain_id).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut chain_posts = self.posts_by_chain.get(&author_chain_id).await?.unwrap_or_default();
         chain_posts.push(post_id);
-        self.posts_by_chain.insert(&author_chain_id, chain_posts).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts_by_chain.insert(&author_chain_id, chain_posts)?;
         
         Ok(())
     }
     
-    pub async fn list_posts_by_author(&self, author: AccountOwner) -> Result<Vec<Post>, String> {
-        let ids = self.posts_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+    pub async fn list_posts_by_author(&self, author: AccountOwner) -> Result<Vec<Post>, StateError> {
+        let ids = self.posts_by_author.get(&author).await?.unwrap_or_default();
         let mut res = Vec::with_capacity(ids.len());
         for id in ids {
-            if let Some(p) = self.posts.get(&id).await.map_err(|e: ViewError| format!("{:?}", e))? {
+            if let Some(p) = self.posts.get(&id).await? {
                 res.push(p);
             }
         }
         Ok(res)
     }
     
-    pub async fn get_post(&self, post_id: &str) -> Result<Option<Post>, String> {
-        self.posts.get(&post_id.to_string()).await.map_err(|e: ViewError| format!("{:?}", e))
+    pub async fn get_post(&self, post_id: &str) -> Result<Option<Post>, StateError> {
+        self.posts.get(&post_id.to_string()).await.map_err(StateError::Storage)
     }
     
-    pub async fn update_post(&mut self, post_id: &str, title: Option<String>, content: Option<String>, image_hash: Option<String>) -> Result<(), String> {
+    /// `image_hash: None` keeps the current image unless `clear_image` removes it
+    pub async fn update_post(&mut self, post_id: &str, title: Option<String>, content: Option<String>, image_hash: Option<String>, clear_image: bool) -> Result<(), StateError> {
+        if clear_image && image_hash.is_some() {
+            return Err(StateError::Validation("Cannot both set and clear the image".to_string()));
+        }
         let mut post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
         if let Some(t) = title { post.title = t; }
         if let Some(c) = content { post.content = c; }
         if let Some(h) = image_hash { post.image_hash = Some(h); }
+        if clear_image { post.image_hash = None; }
         
-        self.posts.insert(&post_id.to_string(), post).map_err(|e: ViewError| format!("{:?}", e))
+        self.posts.insert(&post_id.to_string(), post).map_err(StateError::Storage)
     }
     
-    pub async fn delete_post(&mut self, post_id: &str, author: AccountOwner) -> Result<(), String> {
+    pub async fn delete_post(&mut self, post_id: &str, author: AccountOwner) -> Result<(), StateError> {
         let post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
         if post.author != author {
-            return Err("Unauthorized: not post author".to_string());
+            return Err(StateError::Unauthorized("not post author"));
         }
         
-        self.posts.remove(&post_id.to_string()).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts.remove(&post_id.to_string())?;
         
-        let mut author_posts = self.posts_by_author.get(&author).await.map_err(|e: ViewError| format!("{:?}", e))?.unwrap_or_default();
+        let mut author_posts = self.posts_by_author.get(&author).await?.unwrap_or_default();
         author_posts.retain(|id| id != post_id);
-        self.posts_by_author.insert(&author, author_posts).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts_by_author.insert(&author, author_posts)?;
         
         Ok(())
     }
     
+    /// Create a comment on a post. Replies to replies are re-parented to the
+    /// top-level comment, so threads are at most two levels deep.
+    /// Add a comment; `content_modified` says the caller already cut its text, and trimming it here counts too
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new_comment(&mut self, post_id: &str, author: AccountOwner, author_chain_id: String, parent_id: Option<String>, text: String, content_modified: bool, timestamp: u64) -> Result<Comment, StateError> {
+        if self.get_post(post_id).await?.is_none() {
+            return Err(StateError::NotFound("Post"));
+        }
+        let trimmed = text.trim();
+        let content_modified = content_modified || trimmed.len() != text.len();
+        let text = trimmed.to_string();
+        if text.is_empty() {
+            return Err(StateError::Validation("Comment is empty".to_string()));
+        }
+        let parent_id = match parent_id {
+            Some(pid) => {
+                let parent = self.get_comment(&pid).await?.ok_or(StateError::NotFound("Parent comment"))?;
+                if parent.post_id != post_id {
+                    return Err(StateError::Validation("Parent comment belongs to another post".to_string()));
+                }
+                Some(parent.parent_id.unwrap_or(parent.id))
+            }
+            None => None,
+        };
+
+        let seq = self.comment_seq.get(&post_id.to_string()).await?.unwrap_or(0) + 1;
+        self.comment_seq.insert(&post_id.to_string(), seq)?;
+
+        let comment = Comment {
+            id: format!("{}:{}", post_id, seq),
+            post_id: post_id.to_string(),
+            author,
+            author_chain_id,
+            parent_id,
+            text,
+            created_at: timestamp,
+            deleted: false,
+            hidden: false,
+            answered_at: None,
+            content_modified,
+        };
+        self.upsert_comment(comment.clone()).await?;
+        Ok(comment)
+    }
+
+    /// Store a new or changed comment, keeping indexes and the visible count in sync
+    pub async fn upsert_comment(&mut self, comment: Comment) -> Result<(), StateError> {
+        let previous = self.get_comment(&comment.id).await?;
+        if previous.is_none() {
+            self.track_comment_engagement(&comment).await?;
+            let (index, key) = match &comment.parent_id {
+                Some(parent_id) => (&mut self.comment_replies, parent_id.clone()),
+                None => (&mut self.comments_by_post, comment.post_id.clone()),
+            };
+            let mut ids = index.get(&key).await?.unwrap_or_default();
+            ids.push(comment.id.clone());
+            index.insert(&key, ids)?;
+        }
+
+        let was_visible = previous.as_ref().is_some_and(|c| !c.deleted && !c.hidden);
+        let is_visible = !comment.deleted && !comment.hidden;
+        if was_visible != is_visible {
+            let count = self.comment_count(&comment.post_id).await?;
+            let count = if is_visible { count + 1 } else { count.saturating_sub(1) };
+            self.comment_counts.insert(&comment.post_id, count)?;
+        }
+
+        self.comments.insert(&comment.id.clone(), comment).map_err(StateError::Storage)
+    }
+
+    /// Count a new comment towards the post author's engagement: top-level comments
+    /// from others are received on their day; the author's first reply answers them.
+    async fn track_comment_engagement(&mut self, comment: &Comment) -> Result<(), StateError> {
+        let Some(post) = self.get_post(&comment.post_id).await? else {
+            return Ok(());
+        };
+        let author = post.author;
+        let (day, response_micros) = match &comment.parent_id {
+            None if comment.author != author => (comment.created_at / MICROS_PER_DAY, None),
+            Some(parent_id) if comment.author == author => {
+                let Some(mut parent) = self.get_comment(parent_id).await? else {
+                    return Ok(());
+                };
+                if parent.author == author || parent.answered_at.is_some() {
+                    return Ok(());
+                }
+                parent.answered_at = Some(comment.created_at);
+                let day = parent.created_at / MICROS_PER_DAY;
+                let response = comment.created_at.saturating_sub(parent.created_at);
+                self.comments.insert(&parent.id.clone(), parent)?;
+                if response > donations::COMMENT_ANSWER_DEADLINE_MICROS {
+                    return Ok(());
+                }
+                (day, Some(response))
+            }
+            _ => return Ok(()),
+        };
+
+        let day = day as u32;
+        let mut days = self.comment_engagement.get(&author).await?.unwrap_or_default();
+        let entry = days.entry(day).or_default();
+        match response_micros {
+            None => entry.received += 1,
+            Some(micros) => {
+                entry.answered += 1;
+                *entry.response_hours.entry((micros / 3_600_000_000) as u32).or_default() += 1;
+            }
+        }
+        // Drop days that fell out of the window
+        let newest = days.keys().next_back().copied().unwrap_or(day);
+        days.retain(|d, _| d + donations::ENGAGEMENT_WINDOW_DAYS > newest);
+        self.comment_engagement.insert(&author, days).map_err(StateError::Storage)
+    }
+
+    /// Delete (comment author) or hide (post author) a comment. The entry is kept
+    /// so its replies stay attached to it.
+    pub async fn remove_comment(&mut self, comment_id: &str, requester: AccountOwner, hide: bool) -> Result<Comment, StateError> {
+        let mut comment = self.get_comment(comment_id).await?.ok_or(StateError::NotFound("Comment"))?;
+        if hide {
+            let post = self.get_post(&comment.post_id).await?.ok_or(StateError::NotFound("Post"))?;
+            if post.author != requester {
+                return Err(StateError::Unauthorized("not post author"));
+            }
+            comment.hidden = true;
+        } else {
+            if comment.author != requester {
+                return Err(StateError::Unauthorized("not comment author"));
+            }
+            comment.deleted = true;
+            comment.text = String::new();
+        }
+        self.upsert_comment(comment.clone()).await?;
+        Ok(comment)
+    }
+
+    pub async fn get_comment(&self, comment_id: &str) -> Result<Option<Comment>, StateError> {
+        self.comments.get(&comment_id.to_string()).await.map_err(StateError::Storage)
+    }
+
+    pub async fn comment_count(&self, post_id: &str) -> Result<u32, StateError> {
+        Ok(self.comment_counts.get(&post_id.to_string()).await?.unwrap_or(0))
+    }
+
+    /// Top-level comments of a post, oldest first, including removed ones
+    pub async fn list_comments(&self, post_id: &str) -> Result<Vec<Comment>, StateError> {
+        let ids = self.comments_by_post.get(&post_id.to_string()).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids { if let Some(c) = self.get_comment(&id).await? { res.push(c); } }
+        Ok(res)
+    }
+
+    /// Replies to a top-level comment, oldest first, including removed ones
+    pub async fn list_comment_replies(&self, comment_id: &str) -> Result<Vec<Comment>, StateError> {
+        let ids = self.comment_replies.get(&comment_id.to_string()).await?.unwrap_or_default();
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids { if let Some(c) = self.get_comment(&id).await? { res.push(c); } }
+        Ok(res)
+    }
+
     /// Cast a vote on a post's poll. Returns the updated Poll on success.
-    pub async fn cast_vote(&mut self, post_id: &str, voter_id: String, option_index: u32) -> Result<Poll, String> {
+    pub async fn cast_vote(&mut self, post_id: &str, voter_id: String, option_index: u32) -> Result<Poll, StateError> {
         let mut post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
-        let poll = post.poll.as_mut().ok_or("Post has no poll")?;
+        let poll = post.poll.as_mut().ok_or_else(|| StateError::Validation("Post has no poll".to_string()))?;
         
         // Check option index is valid
         if option_index as usize >= poll.options.len() {
-            return Err("Invalid option index".to_string());
+            return Err(StateError::Validation("Invalid option index".to_string()));
         }
         
         // If already voted - decrease old vote count
@@ -438,84 +2535,223 @@ impl DonationsState {
             opt.votes_count += 1;
             poll.voters.insert(voter_id, option_index);
         }
+        poll.revision += 1;
+        poll.votes_since_broadcast += 1;
         
         let updated_poll = poll.clone();
         
-        self.posts.insert(&post_id.to_string(), post).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts.insert(&post_id.to_string(), post)?;
         
         Ok(updated_poll)
     }
     
-    /// Update poll results from another chain (for subscribers)
-    pub async fn update_poll_results(&mut self, post_id: &str, poll: Poll) -> Result<(), String> {
+    /// Reset the coalescing counters after results were broadcast. Returns the updated Poll.
+    pub async fn mark_poll_broadcast(&mut self, post_id: &str, timestamp: u64) -> Result<Poll, StateError> {
+        let mut post = self.posts.get(&post_id.to_string()).await
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
+
+        let poll = post.poll.as_mut().ok_or_else(|| StateError::Validation("Post has no poll".to_string()))?;
+        poll.votes_since_broadcast = 0;
+        poll.last_broadcast_at = timestamp;
+        let updated_poll = poll.clone();
+
+        self.posts.insert(&post_id.to_string(), post)?;
+        Ok(updated_poll)
+    }
+
+    /// End a poll (author only). Returns the updated Poll.
+    pub async fn close_poll(&mut self, post_id: &str, author: AccountOwner, timestamp: u64) -> Result<Poll, StateError> {
+        let mut post = self.posts.get(&post_id.to_string()).await
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
+
+        if post.author != author {
+            return Err(StateError::Unauthorized("not post author"));
+        }
+        let poll = post.poll.as_mut().ok_or_else(|| StateError::Validation("Post has no poll".to_string()))?;
+        if poll.end_timestamp == 0 || poll.end_timestamp > timestamp {
+            poll.end_timestamp = timestamp;
+        }
+        poll.revision += 1;
+        let updated_poll = poll.clone();
+
+        self.posts.insert(&post_id.to_string(), post)?;
+        Ok(updated_poll)
+    }
+
+    /// Update poll results from another chain (for subscribers).
+    /// Broadcasts may arrive out of order, so results older than the stored ones are ignored.
+    pub async fn update_poll_results(&mut self, post_id: &str, poll: Poll) -> Result<(), StateError> {
         let mut post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
+        if post.poll.as_ref().is_some_and(|current| current.revision >= poll.revision) {
+            return Ok(());
+        }
         post.poll = Some(poll);
         
-        self.posts.insert(&post_id.to_string(), post).map_err(|e: ViewError| format!("{:?}", e))
+        self.posts.insert(&post_id.to_string(), post).map_err(StateError::Storage)
     }
     
     /// Add a participant to a giveaway
-    pub async fn add_giveaway_participant(&mut self, post_id: &str, participant: GiveawayParticipant) -> Result<Giveaway, String> {
+    pub async fn add_giveaway_participant(&mut self, post_id: &str, participant: GiveawayParticipant) -> Result<Giveaway, StateError> {
         let mut post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
-        let giveaway = post.giveaway.as_mut().ok_or("Post has no giveaway")?;
+        let giveaway = post.giveaway.as_mut().ok_or_else(|| StateError::Validation("Post has no giveaway".to_string()))?;
         
         // Check if already participating
         let owner_str = participant.owner.to_string();
         if giveaway.participants.iter().any(|p| p.owner.to_string() == owner_str) {
-            return Err("Already participating".to_string());
+            return Err(StateError::Validation("Already participating".to_string()));
+        }
+        if giveaway.remaining_slots() == Some(0) {
+            return Err(StateError::Validation("Giveaway is full".to_string()));
         }
         
         giveaway.participants.push(participant);
         
         let updated_giveaway = giveaway.clone();
         
-        self.posts.insert(&post_id.to_string(), post).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts.insert(&post_id.to_string(), post)?;
         
         Ok(updated_giveaway)
     }
     
     /// Resolve giveaway and set winner by index
-    pub async fn resolve_giveaway(&mut self, post_id: &str, winner_index: usize) -> Result<GiveawayParticipant, String> {
+    pub async fn resolve_giveaway(&mut self, post_id: &str, winner_index: usize) -> Result<GiveawayParticipant, StateError> {
         let mut post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
-        let giveaway = post.giveaway.as_mut().ok_or("Post has no giveaway")?;
+        let giveaway = post.giveaway.as_mut().ok_or_else(|| StateError::Validation("Post has no giveaway".to_string()))?;
         
         if giveaway.is_resolved {
-            return Err("Giveaway already resolved".to_string());
+            return Err(StateError::Validation("Giveaway already resolved".to_string()));
         }
         
         if giveaway.participants.is_empty() {
-            return Err("No participants".to_string());
+            return Err(StateError::Validation("No participants".to_string()));
         }
         
         let winner = giveaway.participants.get(winner_index % giveaway.participants.len())
             .cloned()
-            .ok_or("Invalid winner index")?;
+            .ok_or_else(|| StateError::Validation("Invalid winner index".to_string()))?;
         
         giveaway.winner = Some(winner.clone());
         giveaway.is_resolved = true;
         
-        self.posts.insert(&post_id.to_string(), post).map_err(|e: ViewError| format!("{:?}", e))?;
+        self.posts.insert(&post_id.to_string(), post)?;
         
         Ok(winner)
     }
     
     /// Update giveaway from another chain (for subscribers)
-    pub async fn update_giveaway(&mut self, post_id: &str, giveaway: Giveaway) -> Result<(), String> {
+    pub async fn update_giveaway(&mut self, post_id: &str, giveaway: Giveaway) -> Result<(), StateError> {
         let mut post = self.posts.get(&post_id.to_string()).await
-            .map_err(|e: ViewError| format!("{:?}", e))?
-            .ok_or("Post not found")?;
+            ?
+            .ok_or(StateError::NotFound("Post"))?;
         
         post.giveaway = Some(giveaway);
         
-        self.posts.insert(&post_id.to_string(), post).map_err(|e: ViewError| format!("{:?}", e))
+        self.posts.insert(&post_id.to_string(), post).map_err(StateError::Storage)
+    }
+
+    // Income redirect management
+    pub async fn set_income_redirect(&mut self, owner: AccountOwner, redirect: IncomeRedirect) -> Result<(), StateError> {
+        if redirect.basis_points == 0 || redirect.basis_points > 10_000 {
+            return Err(StateError::Validation("Redirect share must be between 1 and 10000 basis points".to_string()));
+        }
+        self.income_redirects.insert(&owner, redirect).map_err(StateError::Storage)
+    }
+
+    pub async fn set_donation_goal(&mut self, owner: AccountOwner, goal: DonationGoal) -> Result<(), StateError> {
+        if goal.target == Amount::ZERO {
+            return Err(StateError::Validation("Goal target must be positive".to_string()));
+        }
+        if goal.deadline_micros <= goal.created_at {
+            return Err(StateError::Validation("Goal deadline must be in the future".to_string()));
+        }
+        if goal.description.as_ref().is_some_and(|d| d.chars().count() > donations::MAX_GOAL_DESCRIPTION_CHARS) {
+            return Err(StateError::Validation(format!("Goal description must be at most {} characters", donations::MAX_GOAL_DESCRIPTION_CHARS)));
+        }
+        self.donation_goals.insert(&owner, goal)?;
+        // A new goal starts from nothing: only donations from here on count towards it
+        self.goal_received.remove(&owner).map_err(StateError::Storage)
+    }
+
+    pub async fn delete_donation_goal(&mut self, owner: AccountOwner) -> Result<(), StateError> {
+        if !self.donation_goals.contains_key(&owner).await? {
+            return Err(StateError::NotFound("Donation goal"));
+        }
+        self.donation_goals.remove(&owner)?;
+        self.goal_received.remove(&owner).map_err(StateError::Storage)
+    }
+
+    /// Donations received since the recipient's goal was set
+    pub async fn goal_received(&self, owner: AccountOwner) -> Result<Amount, StateError> {
+        Ok(self.goal_received.get(&owner).await?.unwrap_or_default())
+    }
+
+    pub async fn clear_income_redirect(&mut self, owner: AccountOwner) -> Result<(), StateError> {
+        self.income_redirects.remove(&owner).map_err(StateError::Storage)
+    }
+
+    pub async fn get_income_redirect(&self, owner: AccountOwner) -> Result<Option<IncomeRedirect>, StateError> {
+        self.income_redirects.get(&owner).await.map_err(StateError::Storage)
+    }
+
+    /// Add the pledged share of a gross credit to the owner's pending redirect. Returns the share,
+    /// and whether the pending total overflowed and was saturated.
+    /// The share is taken from the gross amount credited to the owner: this app charges no
+    /// platform fee, and anything split further downstream only sees what remains.
+    pub async fn accrue_income_redirect(&mut self, owner: AccountOwner, gross: Amount, chain_id: &str) -> Result<(Amount, bool), StateError> {
+        // Chains holding a mirrored copy of the pledge don't hold the income
+        let redirect = match self.get_income_redirect(owner).await? {
+            Some(r) if r.chain_id == chain_id => r,
+            _ => return Ok((Amount::ZERO, false)),
+        };
+        let share = donations::amount_math::mul_bps(gross, redirect.basis_points).map_err(StateError::Validation)?;
+        let mut overflowed = false;
+        if share > Amount::ZERO {
+            let pending = self.pending_redirects.get(&owner).await?.unwrap_or_default();
+            let pending = donations::amount_math::add_tracking_overflow(pending, share).unwrap_or_else(|saturated| {
+                overflowed = true;
+                saturated
+            });
+            self.pending_redirects.insert(&owner, pending)?;
+        }
+        Ok((share, overflowed))
     }
-}
\ No newline at end of file
+
+    pub async fn pending_redirect(&self, owner: AccountOwner) -> Result<Amount, StateError> {
+        Ok(self.pending_redirects.get(&owner).await?.unwrap_or_default())
+    }
+
+    /// Mark part of the pending redirect as paid out and add it to the lifetime total.
+    /// Returns whether the lifetime total overflowed and was saturated.
+    pub async fn settle_redirect(&mut self, owner: AccountOwner, amount: Amount) -> Result<bool, StateError> {
+        let pending = self.pending_redirect(owner).await?;
+        let remaining = pending.saturating_sub(amount);
+        if remaining == Amount::ZERO {
+            self.pending_redirects.remove(&owner)?;
+        } else {
+            self.pending_redirects.insert(&owner, remaining)?;
+        }
+        self.add_redirect_total(owner, amount).await
+    }
+
+    /// Add to the lifetime redirected total. Returns whether it overflowed and was saturated.
+    pub async fn add_redirect_total(&mut self, owner: AccountOwner, amount: Amount) -> Result<bool, StateError> {
+        let total = self.redirect_totals.get(&owner).await?.unwrap_or_default();
+        let (total, overflowed) = match donations::amount_math::add_tracking_overflow(total, amount) {
+            Ok(total) => (total, false),
+            Err(saturated) => (saturated, true),
+        };
+        self.redirect_totals.insert(&owner, total)?;
+        Ok(overflowed)
+    }
+}
@@ -172,18 +172,27 @@ async function syncProfiles() {
 
 async function syncDonations() {
     console.log('Syncing donations...');
-    const query = `query {
-        allDonations {
+    const PAGE_SIZE = 500;
+    const query = `query($afterId: Int) {
+        allDonations(afterId: $afterId, limit: ${PAGE_SIZE}) {
             id, from, to, amount, message, timestamp, sourceChainId, toChainId
         }
     }`;
     try {
-        const result = await fetchGraphQL(query);
-        if (!result.data) {
-            console.error('❌ [Donations] Skip sync: No data returned from chain');
-            return;
+        // allDonations is paginated newest first; follow the id cursor to the oldest
+        const donations = [];
+        let afterId = null;
+        for (;;) {
+            const result = await fetchGraphQL(query, { afterId });
+            if (!result.data) {
+                console.error('❌ [Donations] Skip sync: No data returned from chain');
+                return;
+            }
+            const page = result.data.allDonations || [];
+            donations.push(...page);
+            if (page.length < PAGE_SIZE) break;
+            afterId = page[page.length - 1].id;
         }
-        const donations = result.data.allDonations || [];
         console.log(`📊 [Donations] Found ${donations.length} donations on chain`);
 
         for (const d of donations) {